@@ -16,11 +16,13 @@ macro_rules! group {
 }
 
 mod storage_cmp;
+mod storage_perf;
 mod storage_sparse;
 
 pub use test::black_box;
 
 use storage_cmp::benches_storages;
+use storage_perf::benches_perf;
 use storage_sparse::benches_sparse;
 
-criterion_main!(benches_storages, benches_sparse);
+criterion_main!(benches_storages, benches_sparse, benches_perf);
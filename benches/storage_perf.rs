@@ -0,0 +1,110 @@
+use criterion::{Bencher, Criterion};
+use specs::prelude::*;
+
+use super::black_box;
+
+struct Dense(u64);
+
+impl Component for Dense {
+    type Storage = DenseVecStorage<Self>;
+}
+
+struct VecComp(u64);
+
+impl Component for VecComp {
+    type Storage = VecStorage<Self>;
+}
+
+/// Creates `num` entities, all having `VecComp` and every `gap`th having
+/// `Dense`.
+fn setup(num: usize, gap: usize) -> World {
+    let mut world = World::new();
+    world.register::<Dense>();
+    world.register::<VecComp>();
+
+    for i in 0..num {
+        let builder = world.create_entity().with(VecComp(i as u64));
+        if i % gap == 0 {
+            builder.with(Dense(i as u64)).build();
+        } else {
+            builder.build();
+        }
+    }
+
+    world
+}
+
+fn join(b: &mut Bencher, gap: usize) {
+    let world = setup(100_000, gap);
+    let dense = world.read_storage::<Dense>();
+    let vec = world.read_storage::<VecComp>();
+
+    b.iter(|| {
+        let sum: u64 = (&dense, &vec).join().map(|(d, v)| d.0 + v.0).sum();
+        black_box(sum)
+    });
+}
+
+fn dense_join(b: &mut Bencher, gap: usize) {
+    let world = setup(100_000, gap);
+    let dense = world.read_storage::<Dense>();
+    let vec = world.read_storage::<VecComp>();
+
+    b.iter(|| {
+        let sum: u64 = dense.dense_join(&vec).map(|(d, v)| d.0 + v.0).sum();
+        black_box(sum)
+    });
+}
+
+fn join_single(b: &mut Bencher, gap: usize) {
+    let world = setup(100_000, gap);
+    let entities = world.entities();
+    let dense = world.read_storage::<Dense>();
+
+    b.iter(|| {
+        let sum: u64 = (&entities, &dense)
+            .join()
+            .map(|(e, d)| e.id() as u64 + d.0)
+            .sum();
+        black_box(sum)
+    });
+}
+
+fn dense_join_single(b: &mut Bencher, gap: usize) {
+    let world = setup(100_000, gap);
+    let entities = world.entities();
+    let dense = world.read_storage::<Dense>();
+
+    b.iter(|| {
+        let sum: u64 = dense
+            .dense_join(&entities)
+            .map(|(d, e)| e.id() as u64 + d.0)
+            .sum();
+        black_box(sum)
+    });
+}
+
+#[rustfmt::skip]
+fn dense_join_benches(c: &mut Criterion) {
+    c.bench_function_over_inputs(
+        "join dense+vec/join",
+        |b, &&gap| join(b, gap),
+        &[1, 8, 64],
+    ).bench_function_over_inputs(
+        "join dense+vec/dense_join",
+        |b, &&gap| dense_join(b, gap),
+        &[1, 8, 64],
+    );
+
+    c.bench_function_over_inputs(
+        "join entities+dense/join",
+        |b, &&gap| join_single(b, gap),
+        &[1, 8, 64],
+    ).bench_function_over_inputs(
+        "join entities+dense/dense_join",
+        |b, &&gap| dense_join_single(b, gap),
+        &[1, 8, 64],
+    );
+}
+
+criterion_group!(benches_perf, dense_join_benches);
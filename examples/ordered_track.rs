@@ -53,7 +53,7 @@ impl<'a> System<'a> for SysA {
                         );
                     }
                 }
-                ComponentEvent::Inserted(id) => {
+                ComponentEvent::Inserted(id) | ComponentEvent::Loaded(id) => {
                     let entity = entities.entity(*id);
                     if let Some(component) = tracked.get(entity) {
                         self.cache.insert(*id, (entity, component.0));
@@ -70,6 +70,7 @@ impl<'a> System<'a> for SysA {
                     self.cache.remove(id);
                     println!("{:?} had its component removed", entity);
                 }
+                _ => {}
             }
         }
     }
@@ -39,12 +39,13 @@ impl<'a> System<'a> for SysA {
                 ComponentEvent::Modified(id) => {
                     self.modified.add(*id);
                 }
-                ComponentEvent::Inserted(id) => {
+                ComponentEvent::Inserted(id) | ComponentEvent::Loaded(id) => {
                     self.inserted.add(*id);
                 }
                 ComponentEvent::Removed(id) => {
                     self.removed.add(*id);
                 }
+                _ => {}
             }
         }
 
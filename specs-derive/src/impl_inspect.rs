@@ -0,0 +1,73 @@
+//! Contains the implementation of `#[derive(Inspect)]`.
+
+use proc_macro2::{Span, TokenStream};
+use syn::{Data, DeriveInput, Field, Fields, GenericParam, Index, Member, WherePredicate};
+
+pub fn impl_inspect(ast: &mut DeriveInput) -> TokenStream {
+    let fields = match ast.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => fields.named.iter().collect(),
+            Fields::Unnamed(ref fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => Vec::new(),
+        },
+        Data::Enum(_) => panic!("Enums cannot derive `Inspect`"),
+        Data::Union(_) => panic!("Unions cannot derive `Inspect`"),
+    };
+
+    // Fields are visited as `&dyn Any`, so type parameters have to be `'static`.
+    let static_bounds: Vec<WherePredicate> = ast
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(ty) => {
+                let ident = &ty.ident;
+                Some(parse_quote!(#ident: 'static))
+            }
+            _ => None,
+        })
+        .collect();
+    ast.generics
+        .make_where_clause()
+        .predicates
+        .extend(static_bounds);
+
+    let (names, members): (Vec<_>, Vec<_>) = fields
+        .into_iter()
+        .enumerate()
+        .filter(|(_, field)| !is_skipped(field))
+        .map(|(i, field)| match field.ident {
+            Some(ref ident) => (ident.to_string(), Member::Named(ident.clone())),
+            None => (
+                i.to_string(),
+                Member::Unnamed(Index {
+                    index: i as u32,
+                    span: Span::call_site(),
+                }),
+            ),
+        })
+        .unzip();
+
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics Inspect for #name #ty_generics #where_clause {
+            fn inspect(&self, visitor: &mut dyn InspectVisitor) {
+                #( visitor.field(#names, &self.#members); )*
+            }
+
+            fn inspect_mut(&mut self, visitor: &mut dyn InspectVisitorMut) {
+                #( visitor.field(#names, &mut self.#members); )*
+            }
+        }
+    }
+}
+
+/// Whether the field is marked with `#[inspect(skip)]`.
+fn is_skipped(field: &Field) -> bool {
+    field.attrs.iter().any(|attr| {
+        attr.path.is_ident("inspect")
+            && matches!(attr.parse_args::<syn::Ident>(), Ok(arg) if arg == "skip")
+    })
+}
@@ -7,8 +7,8 @@
 
 use proc_macro2::{Span, TokenStream};
 use syn::{
-    Attribute, DataEnum, DataStruct, DeriveInput, Field, GenericParam, Generics, Ident, Type,
-    WhereClause, WherePredicate,
+    Attribute, DataEnum, DataStruct, DeriveInput, Field, GenericParam, Generics, Ident, Path,
+    Type, WhereClause, WherePredicate,
 };
 
 /// Handy collection since tuples got unwieldy and
@@ -103,6 +103,8 @@ pub fn impl_saveload(ast: &mut DeriveInput) -> TokenStream {
 struct FieldMetaData {
     field: Field,
     skip_field: bool,
+    /// The module of `#[convert_save_load_with(module)]`.
+    with: Option<Path>,
 }
 
 /// Implements all elements of saveload common to structs of any type
@@ -190,6 +192,8 @@ fn saveload_named_struct(
 
         if field_meta.skip_field {
             quote! { #field_ident: self.#field_ident.clone() }
+        } else if let Some(ref with) = field_meta.with {
+            quote! { #field_ident: #with::convert_into(&self.#field_ident, &mut ids)? }
         } else {
             quote! { #field_ident: ConvertSaveload::convert_into(&self.#field_ident, &mut ids)? }
         }
@@ -209,6 +213,8 @@ fn saveload_named_struct(
 
             if field_meta.skip_field {
                 quote! { #field_ident: data.#field_ident }
+            } else if let Some(ref with) = field_meta.with {
+                quote! { #field_ident: #with::convert_from(data.#field_ident, &mut ids)? }
             } else {
                 quote! { #field_ident: ConvertSaveload::convert_from(data.#field_ident, &mut ids)? }
             }
@@ -282,6 +288,8 @@ fn saveload_tuple_struct(
         .map(|(field_meta, field_id)| {
             if field_meta.skip_field {
                 quote! { self.#field_id.clone() }
+            } else if let Some(ref with) = field_meta.with {
+                quote! { #with::convert_into(&self.#field_id, &mut ids)? }
             } else {
                 quote! { ConvertSaveload::convert_into(&self.#field_id, &mut ids)? }
             }
@@ -300,6 +308,8 @@ fn saveload_tuple_struct(
         .map(|(field_meta, field_id)| {
             if field_meta.skip_field {
                 quote! { data.#field_id }
+            } else if let Some(ref with) = field_meta.with {
+                quote! { #with::convert_from(data.#field_id, &mut ids)? }
             } else {
                 quote! { ConvertSaveload::convert_from(data.#field_id, &mut ids)? }
             }
@@ -329,6 +339,7 @@ where
             FieldMetaData {
                 field: resolved,
                 skip_field: field_should_skip(&f),
+                with: field_convert_with(f),
             }
         })
         .collect()
@@ -413,6 +424,8 @@ fn saveload_enum(data: &DataEnum, name: &Ident, generics: &Generics) -> Saveload
 
                     if field_meta.skip_field {
                         quote!{ #field_ident: #field_ident.clone() }
+                    } else if let Some(ref with) = field_meta.with {
+                        quote!{ #field_ident: #with::convert_into(#field_ident, &mut ids)? }
                     } else {
                         quote!{ #field_ident: ConvertSaveload::convert_into(#field_ident, &mut ids)? }
                     }
@@ -434,6 +447,8 @@ fn saveload_enum(data: &DataEnum, name: &Ident, generics: &Generics) -> Saveload
 
                     if field_meta.skip_field {
                         quote!{ #field_ident: #field_ident }
+                    } else if let Some(ref with) = field_meta.with {
+                        quote!{ #field_ident: #with::convert_from(#field_ident, &mut ids)? }
                     } else {
                         quote!{ #field_ident: ConvertSaveload::convert_from(#field_ident, &mut ids)? }
                     }
@@ -460,6 +475,8 @@ fn saveload_enum(data: &DataEnum, name: &Ident, generics: &Generics) -> Saveload
                     .map(|(field_meta, field_ident)| {
                         if field_meta.skip_field {
                             quote! { #field_ident.clone() }
+                        } else if let Some(ref with) = field_meta.with {
+                            quote! { #with::convert_into(#field_ident, &mut ids)? }
                         } else {
                             quote! { ConvertSaveload::convert_into(#field_ident, &mut ids)? }
                         }
@@ -477,6 +494,8 @@ fn saveload_enum(data: &DataEnum, name: &Ident, generics: &Generics) -> Saveload
                     .map(|(field_meta, field_ident)| {
                         if field_meta.skip_field {
                             quote! { #field_ident.clone() }
+                        } else if let Some(ref with) = field_meta.with {
+                            quote! { #with::convert_from(#field_ident, &mut ids)? }
                         } else {
                             quote! { ConvertSaveload::convert_from(#field_ident, &mut ids)? }
                         }
@@ -553,8 +572,28 @@ fn field_should_skip(field: &Field) -> bool {
     field.attrs.iter().any(attribute_is_skip)
 }
 
+/// Returns the module of `#[convert_save_load_with(module)]`, whose
+/// `convert_into` and `convert_from` functions convert the field instead of
+/// `ConvertSaveload`, and whose `Data<MA>` type is the converted field.
+fn field_convert_with(field: &Field) -> Option<Path> {
+    let attr = field
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("convert_save_load_with"))?;
+    if field_should_skip(field) {
+        panic!("`convert_save_load_with` cannot be combined with `convert_save_load_skip_convert`");
+    }
+
+    match attr.parse_args::<Path>() {
+        Ok(with) => Some(with),
+        Err(err) => panic!("Unparseable module path in convert_save_load_with: {}", err),
+    }
+}
+
 fn replace_field(field: &mut Field) {
-    if !field_should_skip(field) {
+    if let Some(with) = field_convert_with(field) {
+        field.ty = parse_quote!(#with::Data<MA>);
+    } else if !field_should_skip(field) {
         replace_entity_type(&mut field.ty);
     }
 
@@ -609,7 +648,9 @@ fn replace_attributes(attrs: &mut Vec<Attribute>) {
     let output_attrs = attrs
         .iter()
         .filter_map(|attr| {
-            if attr.path.is_ident("convert_save_load_skip_convert") {
+            if attr.path.is_ident("convert_save_load_skip_convert")
+                || attr.path.is_ident("convert_save_load_with")
+            {
                 None
             } else if attr.path.is_ident("convert_save_load_attr") {
                 match attr.parse_args_with(single_parse_outer_from_args) {
@@ -0,0 +1,145 @@
+//! Contains the implementation of `#[derive(SoAComponent)]`.
+
+use proc_macro2::TokenStream;
+use syn::{parse_quote, Data, DeriveInput, Error, Fields, GenericParam, Ident};
+
+pub fn impl_soa(ast: &DeriveInput) -> TokenStream {
+    let fields = match ast.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Error::new_spanned(
+                    &ast.ident,
+                    "only structs with named fields can derive `SoAComponent`",
+                )
+                .to_compile_error();
+            }
+        },
+        _ => {
+            return Error::new_spanned(&ast.ident, "only structs can derive `SoAComponent`")
+                .to_compile_error();
+        }
+    };
+
+    let name = &ast.ident;
+    let vis = &ast.vis;
+    let columns = Ident::new(&format!("{}Columns", name), name.span());
+    let ref_name = Ident::new(&format!("{}Ref", name), name.span());
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let mut ref_generics = ast.generics.clone();
+    ref_generics
+        .params
+        .insert(0, GenericParam::Lifetime(parse_quote!('a)));
+    let (ref_impl_generics, ref_ty_generics, _) = ref_generics.split_for_impl();
+
+    let idents: Vec<_> = fields.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let types: Vec<_> = fields.iter().map(|f| &f.ty).collect();
+    let idents_mut: Vec<_> = idents
+        .iter()
+        .map(|ident| Ident::new(&format!("{}_mut", ident), ident.span()))
+        .collect();
+    let docs: Vec<_> = idents
+        .iter()
+        .map(|ident| format!("Returns the `{}` column.", ident))
+        .collect();
+    let docs_mut: Vec<_> = idents
+        .iter()
+        .map(|ident| format!("Returns the `{}` column mutably.", ident))
+        .collect();
+    let ref_docs: Vec<_> = idents
+        .iter()
+        .map(|ident| format!("The `{}` field.", ident))
+        .collect();
+    let columns_doc = format!("The columns of `{}`, see `SoAComponent`.", name);
+    let ref_doc = format!(
+        "Borrows the fields of a `{}` in its columns, see `SoAComponent`.",
+        name
+    );
+
+    quote! {
+        #[doc = #columns_doc]
+        #vis struct #columns #impl_generics #where_clause {
+            #( #idents: Vec<#types>, )*
+        }
+
+        impl #impl_generics Default for #columns #ty_generics #where_clause {
+            fn default() -> Self {
+                #columns {
+                    #( #idents: Vec::new(), )*
+                }
+            }
+        }
+
+        impl #impl_generics #columns #ty_generics #where_clause {
+            #(
+                #[doc = #docs]
+                #vis fn #idents(&self) -> &[#types] {
+                    &self.#idents
+                }
+
+                #[doc = #docs_mut]
+                #vis fn #idents_mut(&mut self) -> &mut [#types] {
+                    &mut self.#idents
+                }
+            )*
+        }
+
+        #[doc = #ref_doc]
+        #vis struct #ref_name #ref_impl_generics #where_clause {
+            #(
+                #[doc = #ref_docs]
+                #vis #idents: &'a #types,
+            )*
+        }
+
+        impl #ref_impl_generics Clone for #ref_name #ref_ty_generics #where_clause {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl #ref_impl_generics Copy for #ref_name #ref_ty_generics #where_clause {}
+
+        impl #impl_generics SoAColumns<#name #ty_generics> for #columns #ty_generics #where_clause {
+            type Ref<'a> = #ref_name #ref_ty_generics where Self: 'a;
+
+            fn get(&self, index: usize) -> Self::Ref<'_> {
+                #ref_name {
+                    #( #idents: &self.#idents[index], )*
+                }
+            }
+
+            fn push(&mut self, row: #name #ty_generics) {
+                #( self.#idents.push(row.#idents); )*
+            }
+
+            fn swap_remove(&mut self, index: usize) -> #name #ty_generics {
+                #name {
+                    #( #idents: self.#idents.swap_remove(index), )*
+                }
+            }
+
+            fn row(&self, index: usize) -> #name #ty_generics {
+                #name {
+                    #( #idents: Clone::clone(&self.#idents[index]), )*
+                }
+            }
+
+            fn set_row(&mut self, index: usize, row: #name #ty_generics) {
+                #( self.#idents[index] = row.#idents; )*
+            }
+
+            fn clear(&mut self) {
+                #( self.#idents.clear(); )*
+            }
+        }
+
+        impl #impl_generics SoAComponent for #name #ty_generics #where_clause {
+            type Columns = #columns #ty_generics;
+        }
+
+        impl #impl_generics Component for #name #ty_generics #where_clause {
+            type Storage = SoAStorage<Self>;
+        }
+    }
+}
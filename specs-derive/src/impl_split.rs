@@ -0,0 +1,183 @@
+//! Contains the implementation of `#[derive(SplitComponent)]`.
+
+use proc_macro2::TokenStream;
+use syn::{Data, DeriveInput, Error, Field, Fields, Ident, Result};
+
+pub fn impl_split(ast: &DeriveInput) -> TokenStream {
+    match split(ast) {
+        Ok(tokens) => tokens,
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+fn split(ast: &DeriveInput) -> Result<TokenStream> {
+    let fields = match ast.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(Error::new_spanned(
+                    &ast.ident,
+                    "only structs with named fields can derive `SplitComponent`",
+                ));
+            }
+        },
+        _ => {
+            return Err(Error::new_spanned(
+                &ast.ident,
+                "only structs can derive `SplitComponent`",
+            ));
+        }
+    };
+    if !ast.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &ast.generics,
+            "generic structs cannot derive `SplitComponent`",
+        ));
+    }
+
+    let mut hot = Vec::new();
+    let mut cold = Vec::new();
+    for field in fields {
+        if is_cold(field)? {
+            cold.push(field);
+        } else {
+            hot.push(field);
+        }
+    }
+
+    let name = &ast.ident;
+    let vis = &ast.vis;
+    let hot_name = Ident::new(&format!("{}Hot", name), name.span());
+    let cold_name = Ident::new(&format!("{}Cold", name), name.span());
+    let ref_name = Ident::new(&format!("{}Ref", name), name.span());
+
+    let hot_fields = declare(&hot);
+    let cold_fields = declare(&cold);
+    let hot_idents: Vec<_> = hot.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let cold_idents: Vec<_> = cold.iter().map(|f| f.ident.as_ref().unwrap()).collect();
+    let hot_types: Vec<_> = hot.iter().map(|f| &f.ty).collect();
+    let cold_types: Vec<_> = cold.iter().map(|f| &f.ty).collect();
+
+    let hot_doc = format!(
+        "The frequently accessed fields of `{}`, see `SplitComponent`.",
+        name
+    );
+    let cold_doc = format!(
+        "The rarely accessed fields of `{}`, see `SplitComponent`.",
+        name
+    );
+    let ref_doc = format!(
+        "Borrows both parts of `{}` to access its fields, see `SplitComponent`.",
+        name
+    );
+    let field_docs = |idents: &[&Ident]| -> Vec<String> {
+        idents
+            .iter()
+            .map(|ident| format!("Returns the `{}` field.", ident))
+            .collect()
+    };
+    let hot_docs = field_docs(&hot_idents);
+    let cold_docs = field_docs(&cold_idents);
+
+    Ok(quote! {
+        #[doc = #hot_doc]
+        #vis struct #hot_name {
+            #( #hot_fields, )*
+        }
+
+        impl Component for #hot_name {
+            type Storage = DenseVecStorage<Self>;
+        }
+
+        #[doc = #cold_doc]
+        #vis struct #cold_name {
+            #( #cold_fields, )*
+        }
+
+        impl Component for #cold_name {
+            type Storage = DenseVecStorage<Self>;
+        }
+
+        #[doc = #ref_doc]
+        #[derive(Clone, Copy)]
+        #vis struct #ref_name<'a> {
+            /// The frequently accessed fields.
+            pub hot: &'a #hot_name,
+            /// The rarely accessed fields.
+            pub cold: &'a #cold_name,
+        }
+
+        impl<'a> #ref_name<'a> {
+            /// Borrows both parts.
+            #vis fn new(hot: &'a #hot_name, cold: &'a #cold_name) -> Self {
+                #ref_name { hot, cold }
+            }
+
+            #(
+                #[doc = #hot_docs]
+                #vis fn #hot_idents(&self) -> &'a #hot_types {
+                    &self.hot.#hot_idents
+                }
+            )*
+
+            #(
+                #[doc = #cold_docs]
+                #vis fn #cold_idents(&self) -> &'a #cold_types {
+                    &self.cold.#cold_idents
+                }
+            )*
+        }
+
+        impl SplitComponent for #name {
+            type Hot = #hot_name;
+            type Cold = #cold_name;
+
+            fn split(self) -> (#hot_name, #cold_name) {
+                (
+                    #hot_name { #( #hot_idents: self.#hot_idents, )* },
+                    #cold_name { #( #cold_idents: self.#cold_idents, )* },
+                )
+            }
+
+            fn unsplit(hot: #hot_name, cold: #cold_name) -> Self {
+                #name {
+                    #( #hot_idents: hot.#hot_idents, )*
+                    #( #cold_idents: cold.#cold_idents, )*
+                }
+            }
+        }
+    })
+}
+
+/// Declares the fields in a part, without the `#[split(...)]` attributes.
+fn declare(fields: &[&Field]) -> Vec<Field> {
+    fields
+        .iter()
+        .map(|field| {
+            let mut field = (*field).clone();
+            field.attrs.retain(|attr| !attr.path.is_ident("split"));
+            field
+        })
+        .collect()
+}
+
+/// Whether the field is marked with `#[split(cold)]`, rather than
+/// `#[split(hot)]` or nothing.
+fn is_cold(field: &Field) -> Result<bool> {
+    let mut cold = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("split")) {
+        let arg = attr.parse_args::<Ident>()?;
+        if arg == "cold" {
+            cold = true;
+        } else if arg == "hot" {
+            cold = false;
+        } else {
+            return Err(Error::new_spanned(
+                arg,
+                "expected `#[split(hot)]` or `#[split(cold)]`",
+            ));
+        }
+    }
+
+    Ok(cold)
+}
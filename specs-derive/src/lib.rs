@@ -1,5 +1,7 @@
-//! Implements the `#[derive(Component)]`, `#[derive(Saveload)]` macro and
-//! `#[component]` attribute for [Specs][sp].
+//! Implements the `#[derive(Component)]`, `#[derive(Saveload)]`,
+//! `#[derive(Inspect)]`, `#[derive(SoAComponent)]`,
+//! `#[derive(SplitComponent)]` macro and `#[component]` attribute for
+//! [Specs][sp].
 //!
 //! [sp]: https://slide-rs.github.io/specs-website/
 
@@ -15,10 +17,13 @@ extern crate syn;
 use proc_macro::TokenStream;
 use syn::{
     parse::{Parse, ParseStream, Result},
-    DeriveInput, Path, PathArguments,
+    DeriveInput, Error, PathArguments, Type, TypePath,
 };
 
+mod impl_inspect;
 mod impl_saveload;
+mod impl_soa;
+mod impl_split;
 
 /// Custom derive macro for the `Component` trait.
 ///
@@ -41,6 +46,11 @@ mod impl_saveload;
 /// #[storage(VecStorage)] // Equals to #[storage(VecStorage<Self>)]
 /// struct Pos(f32, f32, f32);
 /// ```
+///
+/// Any type can be used as storage, including paths and const generic
+/// arguments like `#[storage(crate::storage::ChunkStorage<Self, 4>)]`. If it
+/// doesn't implement `UnprotectedStorage<Self>` the compile error points at
+/// the attribute.
 #[proc_macro_derive(Component, attributes(storage))]
 pub fn component(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
@@ -48,8 +58,9 @@ pub fn component(input: TokenStream) -> TokenStream {
     gen.into()
 }
 
+/// Parses the storage type of `#[storage(...)]`.
 struct StorageAttribute {
-    storage: Path,
+    storage: Type,
 }
 
 impl Parse for StorageAttribute {
@@ -64,30 +75,61 @@ impl Parse for StorageAttribute {
 }
 
 fn impl_component(ast: &DeriveInput) -> proc_macro2::TokenStream {
-    let name = &ast.ident;
-    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    match component_storage(ast) {
+        Ok(storage) => {
+            let name = &ast.ident;
+            let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
-    let storage = ast
+            quote! {
+                impl #impl_generics Component for #name #ty_generics #where_clause {
+                    type Storage = #storage;
+                }
+            }
+        }
+        Err(err) => err.to_compile_error(),
+    }
+}
+
+/// Returns the storage type of the component, defaulting to
+/// `DenseVecStorage<Self>`.
+///
+/// The type keeps the spans of the attribute, so if it doesn't implement
+/// `UnprotectedStorage<Self>` the error points at `#[storage(...)]` rather
+/// than at the derive.
+fn component_storage(ast: &DeriveInput) -> Result<Type> {
+    let mut attrs = ast
         .attrs
         .iter()
-        .find(|attr| attr.path.segments[0].ident == "storage")
-        .map(|attr| {
-            syn::parse2::<StorageAttribute>(attr.tokens.clone())
-                .unwrap()
-                .storage
-        })
-        .unwrap_or_else(|| parse_quote!(DenseVecStorage));
-
-    let additional_generics = match storage.segments.last().unwrap().arguments {
-        PathArguments::AngleBracketed(_) => quote!(),
-        _ => quote!(<Self>),
+        .filter(|attr| attr.path.is_ident("storage"));
+    let attr = match attrs.next() {
+        Some(attr) => attr,
+        None => return Ok(parse_quote!(DenseVecStorage<Self>)),
     };
+    if let Some(duplicate) = attrs.next() {
+        return Err(Error::new_spanned(
+            duplicate,
+            "duplicate `#[storage(...)]` attribute",
+        ));
+    }
 
-    quote! {
-        impl #impl_generics Component for #name #ty_generics #where_clause {
-            type Storage = #storage #additional_generics;
+    let mut storage = syn::parse2::<StorageAttribute>(attr.tokens.clone())
+        .map_err(|err| {
+            Error::new_spanned(
+                attr,
+                format!("expected `#[storage(MyStorage<Self>)]`: {}", err),
+            )
+        })?
+        .storage;
+    // When the type parameter is `<Self>` it can be omitted.
+    if let Type::Path(TypePath { qself: None, path }) = &mut storage {
+        let last = path.segments.last_mut().unwrap();
+        if let PathArguments::None = last.arguments {
+            let span = last.ident.span();
+            last.arguments = PathArguments::AngleBracketed(parse_quote_spanned!(span=> <Self>));
         }
     }
+
+    Ok(storage)
 }
 
 /// Custom derive macro for the `ConvertSaveload` trait.
@@ -102,9 +144,28 @@ fn impl_component(ast: &DeriveInput) -> proc_macro2::TokenStream {
 /// #[derive(ConvertSaveload)]
 /// struct Target(Entity);
 /// ```
+///
+/// Like serde's `with` attribute, `#[convert_save_load_with(module)]` converts
+/// a field with the functions of `module` instead of `ConvertSaveload`. The
+/// module has to provide a `Data<MA>` type, generic over the marker, and the
+/// functions:
+///
+/// ```rust,ignore
+/// pub fn convert_into<MA, F>(field: &Field, ids: F) -> Result<Data<MA>, Infallible>
+/// where
+///     F: FnMut(Entity) -> Option<MA>;
+///
+/// pub fn convert_from<MA, F>(data: Data<MA>, ids: F) -> Result<Field, Infallible>
+/// where
+///     F: FnMut(MA) -> Option<Entity>;
+/// ```
 #[proc_macro_derive(
     ConvertSaveload,
-    attributes(convert_save_load_attr, convert_save_load_skip_convert)
+    attributes(
+        convert_save_load_attr,
+        convert_save_load_skip_convert,
+        convert_save_load_with
+    )
 )]
 pub fn saveload(input: TokenStream) -> TokenStream {
     use impl_saveload::impl_saveload;
@@ -113,3 +174,102 @@ pub fn saveload(input: TokenStream) -> TokenStream {
     let gen = impl_saveload(&mut ast);
     gen.into()
 }
+
+/// Custom derive macro for the `Inspect` trait.
+///
+/// Visits the fields of a struct in order; tuple fields are named by their
+/// index. Fields marked with `#[inspect(skip)]` are left out.
+///
+/// Requires `Inspect`, `InspectVisitor`, `InspectVisitorMut` to be in a scope
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// use specs::world::{Inspect, InspectVisitor, InspectVisitorMut};
+///
+/// #[derive(Inspect)]
+/// struct Health {
+///     current: u32,
+///     #[inspect(skip)]
+///     regen_timer: f32,
+/// }
+/// ```
+#[proc_macro_derive(Inspect, attributes(inspect))]
+pub fn inspect(input: TokenStream) -> TokenStream {
+    use impl_inspect::impl_inspect;
+    let mut ast = syn::parse(input).unwrap();
+
+    let gen = impl_inspect(&mut ast);
+    gen.into()
+}
+
+/// Custom derive macro for the `SoAComponent` trait.
+///
+/// Implements `Component` with `SoAStorage<Self>` as storage and generates a
+/// `<Name>Columns` type storing each field in its own `Vec`, with accessors
+/// for the slices of the columns, and a `<Name>Ref<'a>` proxy with a
+/// reference to each field of a row. Only structs with named fields whose
+/// types implement `Clone` are supported.
+///
+/// Requires `Component`, `SoAColumns`, `SoAComponent`, `SoAStorage` to be in a
+/// scope
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// use specs::{storage::{SoAColumns, SoAComponent, SoAStorage}, Component};
+///
+/// #[derive(Clone, SoAComponent)]
+/// struct Pos {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// // Generates `PosColumns` with `x()`, `x_mut()`, `y()` and `y_mut()`, and
+/// // `PosRef<'a>` with the fields `x: &'a f32` and `y: &'a f32`.
+/// ```
+#[proc_macro_derive(SoAComponent)]
+pub fn soa_component(input: TokenStream) -> TokenStream {
+    use impl_soa::impl_soa;
+    let ast = syn::parse(input).unwrap();
+
+    let gen = impl_soa(&ast);
+    gen.into()
+}
+
+/// Custom derive macro for the `SplitComponent` trait.
+///
+/// Splits a struct into a `<Name>Hot` and a `<Name>Cold` component, both
+/// using `DenseVecStorage`. Fields marked with `#[split(cold)]` go to the
+/// cold part, the others, optionally marked with `#[split(hot)]`, to the hot
+/// part. Also generates a `<Name>Ref<'a>` facade borrowing both parts, with
+/// an accessor for each field. Only non-generic structs with named fields
+/// are supported.
+///
+/// Requires `Component`, `DenseVecStorage`, `SplitComponent` to be in a
+/// scope
+///
+/// ## Example
+///
+/// ```rust,ignore
+/// use specs::{world::SplitComponent, Component, DenseVecStorage};
+///
+/// #[derive(SplitComponent)]
+/// struct Body {
+///     pos: [f32; 3],
+///     vel: [f32; 3],
+///     #[split(cold)]
+///     mesh: Vec<u8>,
+/// }
+///
+/// // Generates the components `BodyHot { pos, vel }` and `BodyCold { mesh }`,
+/// // and `BodyRef` with `pos()`, `vel()` and `mesh()`.
+/// ```
+#[proc_macro_derive(SplitComponent, attributes(split))]
+pub fn split_component(input: TokenStream) -> TokenStream {
+    use impl_split::impl_split;
+    let ast = syn::parse(input).unwrap();
+
+    let gen = impl_split(&ast);
+    gen.into()
+}
@@ -4,6 +4,8 @@
 
 #![cfg_attr(rustfmt, rustfmt::skip)]
 
+use std::ops::Range;
+
 use hibitset::{AtomicBitSet, BitSet, BitSetAnd, BitSetLike, BitSetNot, BitSetOr, BitSetXor};
 
 #[nougat::gat(Type)]
@@ -93,3 +95,297 @@ define_bit_join!(impl<()(A, B)> for BitSetOr<A, B>);
 define_bit_join!(impl<('a)(A, B)> for &'a BitSetOr<A, B>);
 define_bit_join!(impl<()(A, B)> for BitSetXor<A, B>);
 define_bit_join!(impl<('a)()> for &'a dyn BitSetLike);
+define_bit_join!(impl<()(A)> for BitSetRange<A>);
+define_bit_join!(impl<('a)(A)> for &'a BitSetRange<A>);
+
+/// The indices of a bitset within a range of indices, see
+/// `LendJoin::in_index_range`.
+///
+/// The words of every layer are masked to the range, so iterating skips
+/// everything outside of it without visiting the indices, which makes
+/// iterating a small range of a large bitset cheap.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{hibitset::BitSetLike, BitSet, BitSetLen, BitSetRange};
+///
+/// let set: BitSet = [1, 7, 4096, 70000].iter().copied().collect();
+/// let range = BitSetRange::new(&set, 5..5000);
+/// assert_eq!(range.len(), 2);
+/// assert_eq!(range.iter().collect::<Vec<_>>(), [7, 4096]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BitSetRange<M> {
+    mask: M,
+    range: Range<Index>,
+}
+
+impl<M: BitSetLike> BitSetRange<M> {
+    /// Restricts `mask` to the indices in `range`.
+    pub fn new(mask: M, range: Range<Index>) -> Self {
+        BitSetRange { mask, range }
+    }
+
+    /// Returns the bits of the word `i` of `layer` whose indices intersect
+    /// the range.
+    #[inline]
+    fn clip(&self, layer: u32, i: usize) -> usize {
+        if self.range.is_empty() {
+            return 0;
+        }
+
+        // The range of bits of the whole layer covering the range.
+        let shift = BITS * layer;
+        let first = (self.range.start as usize) >> shift;
+        let last = (self.range.end as usize - 1) >> shift;
+        // The range of bits of the word `i`.
+        let word_first = i << BITS;
+        let word_last = word_first + usize::BITS as usize - 1;
+
+        let lo = first.max(word_first);
+        let hi = last.min(word_last);
+        if lo > hi {
+            0
+        } else {
+            let lo = lo - word_first;
+            let hi = hi - word_first;
+            (usize::MAX >> (usize::BITS as usize - 1 - hi)) & (usize::MAX << lo)
+        }
+    }
+}
+
+impl<M: BitSetLike> BitSetLike for BitSetRange<M> {
+    #[inline]
+    fn layer3(&self) -> usize {
+        self.mask.layer3() & self.clip(3, 0)
+    }
+
+    #[inline]
+    fn layer2(&self, i: usize) -> usize {
+        self.mask.layer2(i) & self.clip(2, i)
+    }
+
+    #[inline]
+    fn layer1(&self, i: usize) -> usize {
+        self.mask.layer1(i) & self.clip(1, i)
+    }
+
+    #[inline]
+    fn layer0(&self, i: usize) -> usize {
+        self.mask.layer0(i) & self.clip(0, i)
+    }
+
+    #[inline]
+    fn contains(&self, i: Index) -> bool {
+        self.range.contains(&i) && self.mask.contains(i)
+    }
+}
+
+/// Counts the indices contained in a bitset.
+///
+/// This is implemented for every `BitSetLike`, using the popcounts of the
+/// hierarchical layers rather than iterating every index: only the words of
+/// layer 0 that have a bit set in the layers above are counted.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{BitSet, BitSetLen};
+///
+/// let set: BitSet = [1, 7, 4096].iter().copied().collect();
+/// assert_eq!(set.len(), 3);
+/// ```
+pub trait BitSetLen: BitSetLike {
+    /// Returns the number of indices contained in `self`.
+    fn len(&self) -> usize {
+        popcount(self)
+    }
+}
+
+impl<B: BitSetLike> BitSetLen for B {}
+
+/// A `BitSet` which keeps track of the number of indices it contains, so
+/// counting them is constant time.
+#[derive(Debug, Default)]
+pub(crate) struct CountedBitSet {
+    set: BitSet,
+    len: usize,
+}
+
+impl CountedBitSet {
+    /// Adds `id`, returning `true` if it was already contained.
+    pub(crate) fn add(&mut self, id: Index) -> bool {
+        let contained = self.set.add(id);
+        if !contained {
+            self.len += 1;
+        }
+
+        contained
+    }
+
+    /// Removes `id`, returning `true` if it was contained.
+    pub(crate) fn remove(&mut self, id: Index) -> bool {
+        let contained = self.set.remove(id);
+        if contained {
+            self.len -= 1;
+        }
+
+        contained
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.set.clear();
+        self.len = 0;
+    }
+
+    /// Returns the number of indices contained in the set.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl std::ops::Deref for CountedBitSet {
+    type Target = BitSet;
+
+    fn deref(&self) -> &BitSet {
+        &self.set
+    }
+}
+
+/// Number of bits addressed by a single `usize` of a bitset layer, as a shift.
+const BITS: u32 = usize::BITS.trailing_zeros();
+
+/// Number of layers of a hierarchical bitset.
+const LAYERS: usize = 4;
+
+/// An iterator over the indices of a bitset, like hibitset's `BitIter`, which
+/// can also count the indices it has left.
+///
+/// It walks the layers the same way, keeping the words it still has to visit
+/// of every layer.
+pub(crate) struct MaskIter<B> {
+    set: B,
+    /// The bits of every layer that are left to visit.
+    masks: [usize; LAYERS],
+    /// The first index of the current word of every layer but the top one.
+    prefix: [u32; LAYERS - 1],
+}
+
+impl<B: BitSetLike> MaskIter<B> {
+    pub(crate) fn new(set: B) -> Self {
+        let layer3 = set.layer3();
+
+        MaskIter {
+            set,
+            masks: [0, 0, 0, layer3],
+            prefix: [0; LAYERS - 1],
+        }
+    }
+
+    /// Counts the indices left to iterate, from the popcounts of the words
+    /// left to visit.
+    ///
+    /// Words below the current ones are only read once they are visited, so
+    /// indices added to the set in the meantime may be iterated as well.
+    pub(crate) fn remaining(&self) -> usize {
+        (0..LAYERS)
+            .map(|layer| {
+                let prefix = self.prefix.get(layer).map_or(0, |&p| p >> BITS);
+                count_word(&self.set, layer, self.masks[layer], prefix as usize)
+            })
+            .sum()
+    }
+}
+
+impl<B: BitSetLike> Iterator for MaskIter<B> {
+    type Item = Index;
+
+    fn next(&mut self) -> Option<Index> {
+        'find: loop {
+            for layer in 0..LAYERS {
+                let mask = &mut self.masks[layer];
+                if *mask == 0 {
+                    continue;
+                }
+
+                let bit = mask.trailing_zeros();
+                *mask &= *mask - 1;
+                let idx = self.prefix.get(layer).copied().unwrap_or(0) | bit;
+                if layer == 0 {
+                    return Some(idx);
+                }
+
+                // Descend into the word of the layer below.
+                self.masks[layer - 1] = self.set.get_from_layer(layer - 1, idx as usize);
+                self.prefix[layer - 1] = idx << BITS;
+                continue 'find;
+            }
+
+            return None;
+        }
+    }
+}
+
+/// Counts the indices below the bits set in `word`, a word of `layer` whose
+/// index within its layer is `prefix`.
+fn count_word<B: BitSetLike>(set: &B, layer: usize, word: usize, prefix: usize) -> usize {
+    if layer == 0 {
+        return word.count_ones() as usize;
+    }
+
+    let mut count = 0;
+    for_each_bit(word, prefix, |i| {
+        count += count_word(set, layer - 1, set.get_from_layer(layer - 1, i), i);
+    });
+
+    count
+}
+
+/// Counts the indices contained in `set`.
+///
+/// Instead of iterating every index, this walks the upper layers of the
+/// hierarchical bitset and sums up the popcounts of the non-empty words of
+/// layer 0.
+pub(crate) fn popcount<B: BitSetLike + ?Sized>(set: &B) -> usize {
+    let mut count = 0;
+    for_each_bit(set.layer3(), 0, |i2| {
+        for_each_bit(set.layer2(i2), i2, |i1| {
+            for_each_bit(set.layer1(i1), i1, |i0| {
+                count += set.layer0(i0).count_ones() as usize;
+            });
+        });
+    });
+
+    count
+}
+
+/// Returns the highest index contained in `set`.
+///
+/// Follows the highest bit of each layer down to layer 0.
+pub(crate) fn last_index<B: BitSetLike>(set: &B) -> Option<Index> {
+    let highest = |word: usize, prefix: usize| {
+        (prefix << BITS) | (usize::BITS - 1 - word.leading_zeros()) as usize
+    };
+
+    let layer3 = set.layer3();
+    if layer3 == 0 {
+        return None;
+    }
+    let i2 = highest(layer3, 0);
+    let i1 = highest(set.layer2(i2), i2);
+    let i0 = highest(set.layer1(i1), i1);
+
+    Some(highest(set.layer0(i0), i0) as Index)
+}
+
+/// Calls `f` with the index in the layer below for every bit set in `word`,
+/// where `prefix` is the index of `word` in its own layer.
+#[inline]
+pub(crate) fn for_each_bit<F: FnMut(usize)>(mut word: usize, prefix: usize, mut f: F) {
+    while word != 0 {
+        let bit = word.trailing_zeros() as usize;
+        word &= word - 1;
+        f((prefix << BITS) | bit);
+    }
+}
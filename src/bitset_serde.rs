@@ -0,0 +1,96 @@
+//! Serde support for `BitSet`, for use with `#[serde(with = "...")]`.
+//!
+//! `BitSet` is defined by `hibitset`, so it can't implement `Serialize` and
+//! `Deserialize` itself. This module (de)serializes it as a sequence of
+//! `(block, bits)` pairs, one for every block of 64 consecutive indices
+//! containing at least one index, which is compact for dense sets and
+//! independent of the pointer width.
+//!
+//! A mask only stores entity indices, not their generations, so it refers to
+//! the same entities only as long as they are alive; e.g. use
+//! `Storage::mask_owned` to save which entities were selected in an editor
+//! together with the rest of the world, or to send an interest mask over the
+//! network.
+//!
+//! ## Examples
+//!
+//! ```
+//! use serde::{Deserialize, Serialize};
+//! use specs::prelude::*;
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Selection {
+//!     #[serde(with = "specs::bitset_serde")]
+//!     entities: BitSet,
+//! }
+//!
+//! let entities: BitSet = [1, 5, 64, 1000].iter().copied().collect();
+//! let json = serde_json::to_string(&Selection { entities }).unwrap();
+//! assert_eq!(json, r#"{"entities":[[0,34],[1,1],[15,1099511627776]]}"#);
+//!
+//! let selection: Selection = serde_json::from_str(&json).unwrap();
+//! assert_eq!(
+//!     (&selection.entities).join().collect::<Vec<_>>(),
+//!     [1, 5, 64, 1000]
+//! );
+//! ```
+
+use hibitset::{BitSet, BitSetLike};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{bitset::for_each_bit, world::Index};
+
+/// The number of blocks of 64 indices a `BitSet` can hold.
+const BLOCK_LIMIT: u32 =
+    (BitSet::LAYER2_GRANULARITY * BitSet::BITS_PER_USIZE * BitSet::BITS_PER_USIZE / 64) as u32;
+
+/// Serializes `set` as `(block, bits)` pairs.
+pub fn serialize<S>(set: &BitSet, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut blocks: Vec<(u32, u64)> = Vec::new();
+    // Copies the words of layer 0, which are 32 bits wide on 32 bit targets.
+    for_each_bit(set.layer3(), 0, |i2| {
+        for_each_bit(set.layer2(i2), i2, |i1| {
+            for_each_bit(set.layer1(i1), i1, |i0| {
+                let first = i0 * usize::BITS as usize;
+                let block = (first / 64) as u32;
+                let bits = (set.layer0(i0) as u64) << (first % 64);
+                match blocks.last_mut() {
+                    Some((last, last_bits)) if *last == block => *last_bits |= bits,
+                    _ => blocks.push((block, bits)),
+                }
+            });
+        });
+    });
+
+    blocks.serialize(serializer)
+}
+
+/// Deserializes a `BitSet` from `(block, bits)` pairs.
+///
+/// Fails if a block is beyond the capacity of a `BitSet`.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<BitSet, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let blocks = Vec::<(u32, u64)>::deserialize(deserializer)?;
+    let mut set = BitSet::new();
+    for (block, mut bits) in blocks {
+        if block >= BLOCK_LIMIT && bits != 0 {
+            return Err(D::Error::custom(format_args!(
+                "block {} exceeds the capacity of a BitSet of {} blocks",
+                block, BLOCK_LIMIT
+            )));
+        }
+        // Can't overflow since the block is within the capacity.
+        while bits != 0 {
+            let bit = bits.trailing_zeros();
+            bits &= bits - 1;
+            set.add(block * 64 + bit as Index);
+        }
+    }
+
+    Ok(set)
+}
@@ -8,6 +8,7 @@ use std::{
     convert::Infallible,
     error::Error as StdError,
     fmt::{Debug, Display, Formatter, Result as FmtResult},
+    panic::Location,
 };
 
 use crate::world::{Entity, Generation};
@@ -55,6 +56,20 @@ pub enum Error {
     Custom(BoxedErr),
     /// Wrong generation error.
     WrongGeneration(WrongGeneration),
+    /// Slice too short error.
+    SliceTooShort(SliceTooShort),
+    /// Wrong shard error.
+    WrongShard(WrongShard),
+    /// Borrow conflict error.
+    BorrowConflict(BorrowConflict),
+    /// Transaction error.
+    Transaction(TransactionError),
+    /// Lease conflict error.
+    LeaseConflict(LeaseConflict),
+    /// Not registered error.
+    NotRegistered(NotRegistered),
+    /// Invalid component error.
+    InvalidComponent(InvalidComponent),
 }
 
 impl Display for Error {
@@ -62,6 +77,13 @@ impl Display for Error {
         match *self {
             Error::Custom(ref e) => write!(f, "Custom: {}", e),
             Error::WrongGeneration(ref e) => write!(f, "Wrong generation: {}", e),
+            Error::SliceTooShort(ref e) => write!(f, "Slice too short: {}", e),
+            Error::WrongShard(ref e) => write!(f, "Wrong shard: {}", e),
+            Error::BorrowConflict(ref e) => write!(f, "Borrow conflict: {}", e),
+            Error::Transaction(ref e) => write!(f, "Transaction: {}", e),
+            Error::LeaseConflict(ref e) => write!(f, "Lease conflict: {}", e),
+            Error::NotRegistered(ref e) => write!(f, "Not registered: {}", e),
+            Error::InvalidComponent(ref e) => write!(f, "Invalid component: {}", e),
         }
     }
 }
@@ -78,11 +100,60 @@ impl From<WrongGeneration> for Error {
     }
 }
 
+impl From<SliceTooShort> for Error {
+    fn from(e: SliceTooShort) -> Self {
+        Error::SliceTooShort(e)
+    }
+}
+
+impl From<WrongShard> for Error {
+    fn from(e: WrongShard) -> Self {
+        Error::WrongShard(e)
+    }
+}
+
+impl From<BorrowConflict> for Error {
+    fn from(e: BorrowConflict) -> Self {
+        Error::BorrowConflict(e)
+    }
+}
+
+impl From<TransactionError> for Error {
+    fn from(e: TransactionError) -> Self {
+        Error::Transaction(e)
+    }
+}
+
+impl From<LeaseConflict> for Error {
+    fn from(e: LeaseConflict) -> Self {
+        Error::LeaseConflict(e)
+    }
+}
+
+impl From<NotRegistered> for Error {
+    fn from(e: NotRegistered) -> Self {
+        Error::NotRegistered(e)
+    }
+}
+
+impl From<InvalidComponent> for Error {
+    fn from(e: InvalidComponent) -> Self {
+        Error::InvalidComponent(e)
+    }
+}
+
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         let e = match *self {
             Error::Custom(ref e) => e.as_ref(),
             Error::WrongGeneration(ref e) => e,
+            Error::SliceTooShort(ref e) => e,
+            Error::WrongShard(ref e) => e,
+            Error::BorrowConflict(ref e) => e,
+            Error::Transaction(ref e) => e,
+            Error::LeaseConflict(ref e) => e,
+            Error::NotRegistered(ref e) => e,
+            Error::InvalidComponent(ref e) => e,
         };
 
         Some(e)
@@ -113,6 +184,319 @@ impl Display for WrongGeneration {
 
 impl StdError for WrongGeneration {}
 
+/// Slice too short error.
+///
+/// Returned when a slice indexed by entity ids doesn't cover all entities
+/// having a component.
+#[derive(Debug, PartialEq, Eq)]
+pub struct SliceTooShort {
+    /// The length of the passed slice.
+    pub len: usize,
+    /// The minimum length required to cover all components.
+    pub required: usize,
+}
+
+impl Display for SliceTooShort {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "The slice has a length of {}, but indices up to {} need to be covered",
+            self.len,
+            self.required - 1
+        )
+    }
+}
+
+impl StdError for SliceTooShort {}
+
+/// Insert error.
+///
+/// Returned by `Storage::insert_new` together with the component that could
+/// not be inserted.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InsertError<T> {
+    /// The entity already has a component.
+    AlreadyPresent {
+        /// The entity that has been passed.
+        entity: Entity,
+        /// The component that has been passed.
+        component: T,
+    },
+    /// The entity is not alive.
+    WrongGeneration {
+        /// The wrong generation error.
+        error: WrongGeneration,
+        /// The component that has been passed.
+        component: T,
+    },
+    /// A validator rejected the component.
+    Invalid {
+        /// The invalid component error.
+        error: InvalidComponent,
+        /// The component that has been passed.
+        component: T,
+    },
+}
+
+impl<T> InsertError<T> {
+    /// Returns the component that could not be inserted.
+    pub fn into_component(self) -> T {
+        match self {
+            InsertError::AlreadyPresent { component, .. }
+            | InsertError::WrongGeneration { component, .. }
+            | InsertError::Invalid { component, .. } => component,
+        }
+    }
+}
+
+impl<T> Display for InsertError<T> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            InsertError::AlreadyPresent { entity, .. } => write!(
+                f,
+                "Tried to insert a new component for entity {:?}, but it already has one",
+                entity
+            ),
+            InsertError::WrongGeneration { ref error, .. } => write!(f, "{}", error),
+            InsertError::Invalid { ref error, .. } => write!(f, "{}", error),
+        }
+    }
+}
+
+impl<T: Debug> StdError for InsertError<T> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            InsertError::AlreadyPresent { .. } => None,
+            InsertError::WrongGeneration { ref error, .. } => Some(error),
+            InsertError::Invalid { ref error, .. } => Some(error),
+        }
+    }
+}
+
+/// Invalid entity bits error.
+///
+/// Returned when converting a `u64` which wasn't produced by
+/// `Entity::to_bits` into an `Entity`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidEntityBits {
+    /// The bits that have been passed.
+    pub bits: u64,
+}
+
+impl Display for InvalidEntityBits {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "{:#018x} is not a valid entity, its generation is zero",
+            self.bits
+        )
+    }
+}
+
+impl StdError for InvalidEntityBits {}
+
+/// Wrong shard error.
+///
+/// Returned when inserting a component through a `ShardedWrite` of a shard
+/// the entity doesn't belong to.
+#[derive(Debug, PartialEq, Eq)]
+pub struct WrongShard {
+    /// The entity that has been passed.
+    pub entity: Entity,
+    /// The shard that was written.
+    pub shard: usize,
+    /// The shard the entity belongs to.
+    pub expected: usize,
+}
+
+impl Display for WrongShard {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "Tried to insert a component for entity {:?} into shard {}, but it belongs to shard {}",
+            self.entity, self.shard, self.expected
+        )
+    }
+}
+
+impl StdError for WrongShard {}
+
+/// Borrow conflict error.
+///
+/// Returned, wrapped in `Error`, by `WorldExt::try_exec` if the system data
+/// would borrow a resource mutably more than once, or both mutably and
+/// immutably, and by `WorldExt::try_exec` and the fallible fetches of
+/// `WorldExt`, like `WorldExt::try_write_storage`, if the resource is already
+/// borrowed in a conflicting way.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BorrowConflict {
+    /// The type name of the system data, or of the fetched storage or
+    /// resource.
+    pub system_data: &'static str,
+    /// The conflicting resource, named by its component for registered
+    /// storages.
+    pub resource: String,
+}
+
+impl Display for BorrowConflict {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "`{}` can't borrow {}, it is already borrowed",
+            self.system_data, self.resource
+        )
+    }
+}
+
+impl StdError for BorrowConflict {}
+
+/// Not registered error.
+///
+/// Returned by the fallible fetch methods of `WorldExt`, like
+/// `WorldExt::try_read_storage`, if the component has not been registered or
+/// the resource has not been added, and by `WorldExt::try_exec` if a
+/// resource of its system data is missing.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NotRegistered {
+    /// The type name of the component or resource, or of the system data
+    /// passed to `WorldExt::try_exec`.
+    pub type_name: &'static str,
+}
+
+impl NotRegistered {
+    /// Creates the error for the component or resource `T`.
+    pub fn new<T: ?Sized>() -> Self {
+        NotRegistered {
+            type_name: std::any::type_name::<T>(),
+        }
+    }
+}
+
+impl Display for NotRegistered {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "Tried to fetch `{}`, but it has not been registered",
+            self.type_name
+        )
+    }
+}
+
+impl StdError for NotRegistered {}
+
+/// Invalid component error.
+///
+/// Returned by `Storage::insert` in debug builds if a validator registered
+/// with `WorldExt::register_validator` rejects the component.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidComponent {
+    /// The entity the component was inserted for.
+    pub entity: Entity,
+    /// The type name of the component.
+    pub type_name: &'static str,
+    /// Why the validator rejected the component.
+    pub reason: String,
+}
+
+impl Display for InvalidComponent {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "Rejected `{}` for entity {:?}: {}",
+            self.type_name, self.entity, self.reason
+        )
+    }
+}
+
+impl StdError for InvalidComponent {}
+
+/// Transaction error.
+///
+/// Returned by `WorldExt::transaction` for the first invalid operation, in
+/// which case none of the operations are applied.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransactionError {
+    /// The entity is dead, or deleted by an earlier operation of the
+    /// transaction.
+    DeadEntity {
+        /// The invalid operation, like `"insert"`.
+        action: &'static str,
+        /// The dead entity.
+        entity: Entity,
+    },
+    /// The component has not been registered.
+    Unregistered {
+        /// The invalid operation, like `"insert"`.
+        action: &'static str,
+        /// The type name of the component.
+        component: &'static str,
+    },
+    /// A validator rejected an inserted component.
+    Invalid(InvalidComponent),
+    /// The storage of a component is leased, so it can't be written.
+    LeaseConflict(LeaseConflict),
+}
+
+impl Display for TransactionError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match *self {
+            TransactionError::DeadEntity { action, entity } => {
+                write!(f, "Tried to {} dead entity {:?}", action, entity)
+            }
+            TransactionError::Unregistered { action, component } => write!(
+                f,
+                "Tried to {} component `{}`, which is not registered",
+                action, component
+            ),
+            TransactionError::Invalid(ref e) => write!(f, "Tried to insert {}", e),
+            TransactionError::LeaseConflict(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl StdError for TransactionError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            TransactionError::Invalid(ref e) => Some(e),
+            TransactionError::LeaseConflict(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Lease conflict error.
+///
+/// Returned by `WorldExt::try_write_storage`, wrapped in `Error`, and the
+/// message of the panic of fetching a `WriteStorage`, while the storage is
+/// leased with `Storage::lease`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct LeaseConflict {
+    /// The type name of the component.
+    pub component: &'static str,
+    /// Where the active leases were taken.
+    pub leases: Vec<&'static Location<'static>>,
+}
+
+impl Display for LeaseConflict {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "Tried to write the storage of `{}` while it is leased, by the leases taken at ",
+            self.component
+        )?;
+        for (i, location) in self.leases.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl StdError for LeaseConflict {}
+
 /// Reexport of `Infallible` for a smoother transition.
 #[deprecated = "Use std::convert::Infallible instead"]
 pub type NoError = Infallible;
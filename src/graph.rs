@@ -0,0 +1,640 @@
+//! Inspecting the system graph of a dispatcher.
+//!
+//! Shred's `DispatcherBuilder` doesn't expose the systems it schedules, so
+//! `DebugGraph::debug_graph` turns it into a `GraphBuilder`, which records
+//! every system added through it. `GraphBuilder::graph` then returns a
+//! `DispatcherGraph` with the names, dependencies, resource accesses and
+//! stages of the systems, as well as the resource conflicts between them,
+//! which can be written as DOT with `DispatcherGraph::to_dot` or serialized
+//! with `serde` (with the `serde` feature).
+
+use std::{any::type_name, fmt};
+
+use ahash::AHashMap as HashMap;
+use shred::{Accessor, Dispatcher, DispatcherBuilder, Resource, ResourceId, RunNow, System};
+
+use crate::{
+    storage::MaskedStorage,
+    world::{Component, EntitiesRes, ExclusiveDispatcherBuilder, ExclusiveSystem, LazyUpdate},
+};
+
+/// Extension trait for shred's `DispatcherBuilder` to record its system
+/// graph.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{graph::DebugGraph, prelude::*};
+///
+/// struct Pos;
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Move;
+///
+/// impl<'a> System<'a> for Move {
+///     type SystemData = WriteStorage<'a, Pos>;
+///
+///     fn run(&mut self, _: Self::SystemData) {}
+/// }
+///
+/// struct Render;
+///
+/// impl<'a> System<'a> for Render {
+///     type SystemData = ReadStorage<'a, Pos>;
+///
+///     fn run(&mut self, _: Self::SystemData) {}
+/// }
+///
+/// let builder = DispatcherBuilder::new()
+///     .debug_graph()
+///     .name_component::<Pos>()
+///     .with(Move, "move", &[])
+///     .with(Render, "render", &[]);
+///
+/// let graph = builder.graph();
+/// assert_eq!(graph.systems[1].stage, Some(1));
+/// assert_eq!(graph.conflicts[0].resources, vec!["Pos"]);
+/// println!("{}", graph.to_dot());
+///
+/// let dispatcher = builder.build();
+/// ```
+pub trait DebugGraph<'a, 'b> {
+    /// Returns a `GraphBuilder` recording the systems added from now on.
+    fn debug_graph(self) -> GraphBuilder<'a, 'b>;
+}
+
+impl<'a, 'b> DebugGraph<'a, 'b> for DispatcherBuilder<'a, 'b> {
+    fn debug_graph(self) -> GraphBuilder<'a, 'b> {
+        GraphBuilder {
+            builder: self,
+            systems: Vec::new(),
+            ids: HashMap::new(),
+            names: HashMap::new(),
+            barrier: 0,
+        }
+        .name_resource::<EntitiesRes>()
+        .name_resource::<LazyUpdate>()
+    }
+}
+
+/// Wrapper around a `DispatcherBuilder` that records the systems added to
+/// it, created with `DebugGraph::debug_graph`.
+///
+/// The methods for adding systems mirror the ones of `DispatcherBuilder`.
+pub struct GraphBuilder<'a, 'b> {
+    builder: DispatcherBuilder<'a, 'b>,
+    systems: Vec<RecordedSystem>,
+    ids: HashMap<String, usize>,
+    names: HashMap<ResourceId, String>,
+    barrier: usize,
+}
+
+/// A system as recorded by `GraphBuilder`.
+struct RecordedSystem {
+    name: String,
+    dependencies: Vec<usize>,
+    reads: Vec<ResourceId>,
+    writes: Vec<ResourceId>,
+    /// Minimum stage, set by barriers; `None` for thread local systems.
+    barrier: Option<usize>,
+}
+
+impl<'a, 'b> GraphBuilder<'a, 'b> {
+    /// Names the resource `R` after its type in the graph.
+    ///
+    /// Resources without a name are labeled by the order in which they are
+    /// first accessed, e.g. `resource #2`.
+    pub fn name_resource<R: Resource>(mut self) -> Self {
+        let name = short_type_name::<R>();
+        self.names.insert(ResourceId::new::<R>(), name);
+
+        self
+    }
+
+    /// Names the storage of the component `C` after its type in the graph.
+    pub fn name_component<C: Component>(mut self) -> Self {
+        let name = short_type_name::<C>();
+        self.names
+            .insert(ResourceId::new::<MaskedStorage<C>>(), name);
+
+        self
+    }
+
+    /// Adds a new system with a given name and a list of dependencies, see
+    /// `DispatcherBuilder::with`.
+    pub fn with<T>(mut self, system: T, name: &str, dep: &[&str]) -> Self
+    where
+        T: for<'c> System<'c> + Send + 'a,
+    {
+        self.add(system, name, dep);
+
+        self
+    }
+
+    /// Adds a new system with a given name and a list of dependencies, see
+    /// `DispatcherBuilder::add`.
+    ///
+    /// Dependencies on systems added before `DebugGraph::debug_graph` was
+    /// called are left out of the graph, since they weren't recorded.
+    ///
+    /// ## Panics
+    ///
+    /// Panics in the same cases as `DispatcherBuilder::add`.
+    pub fn add<T>(&mut self, system: T, name: &str, dep: &[&str])
+    where
+        T: for<'c> System<'c> + Send + 'a,
+    {
+        let accessor = system.accessor();
+        let mut reads = accessor.reads();
+        let writes = accessor.writes();
+        reads.sort();
+        reads.dedup();
+        reads.retain(|id| !writes.contains(id));
+
+        // Let the builder check the dependencies and the name first.
+        self.builder.add(system, name, dep);
+
+        let index = self.systems.len();
+        if !name.is_empty() {
+            self.ids.insert(name.to_owned(), index);
+        }
+        self.systems.push(RecordedSystem {
+            name: name.to_owned(),
+            dependencies: dep
+                .iter()
+                .filter_map(|dep| self.ids.get(*dep).copied())
+                .collect(),
+            reads,
+            writes,
+            barrier: Some(self.barrier),
+        });
+    }
+
+    /// Adds a new thread local system, see
+    /// `DispatcherBuilder::with_thread_local`.
+    pub fn with_thread_local<T>(mut self, system: T) -> Self
+    where
+        T: for<'c> RunNow<'c> + 'b,
+    {
+        self.add_thread_local(system);
+
+        self
+    }
+
+    /// Adds a new thread local system, see
+    /// `DispatcherBuilder::add_thread_local`.
+    ///
+    /// Thread local systems don't report their resource accesses, so they
+    /// are recorded without any.
+    pub fn add_thread_local<T>(&mut self, system: T)
+    where
+        T: for<'c> RunNow<'c> + 'b,
+    {
+        self.builder.add_thread_local(system);
+        self.record_thread_local();
+    }
+
+    /// Inserts a barrier, see `DispatcherBuilder::with_barrier`.
+    pub fn with_barrier(mut self) -> Self {
+        self.add_barrier();
+
+        self
+    }
+
+    /// Inserts a barrier, see `DispatcherBuilder::add_barrier`.
+    pub fn add_barrier(&mut self) {
+        self.builder.add_barrier();
+        self.barrier = self
+            .stages()
+            .into_iter()
+            .flatten()
+            .map(|s| s + 1)
+            .max()
+            .unwrap_or(0);
+    }
+
+    /// Returns the graph of the systems added so far.
+    pub fn graph(&self) -> DispatcherGraph {
+        let mut labels = self.names.clone();
+        let mut unnamed = 0;
+        let mut label = |ids: &mut dyn Iterator<Item = &ResourceId>| {
+            let mut names: Vec<_> = ids
+                .map(|id| {
+                    labels
+                        .entry(id.clone())
+                        .or_insert_with(|| {
+                            unnamed += 1;
+                            format!("resource #{}", unnamed)
+                        })
+                        .clone()
+                })
+                .collect();
+            names.sort();
+
+            names
+        };
+
+        let systems = self
+            .systems
+            .iter()
+            .zip(self.stages())
+            .map(|(system, stage)| SystemNode {
+                name: system.name.clone(),
+                dependencies: system.dependencies.clone(),
+                reads: label(&mut system.reads.iter()),
+                writes: label(&mut system.writes.iter()),
+                stage,
+            })
+            .collect();
+
+        let mut conflicts = Vec::new();
+        for (second, b) in self.systems.iter().enumerate() {
+            for (first, a) in self.systems[..second].iter().enumerate() {
+                let resources = label(
+                    &mut a
+                        .writes
+                        .iter()
+                        .filter(|id| b.reads.contains(id) || b.writes.contains(id))
+                        .chain(a.reads.iter().filter(|id| b.writes.contains(id))),
+                );
+                if !resources.is_empty() {
+                    conflicts.push(Conflict {
+                        first,
+                        second,
+                        resources,
+                    });
+                }
+            }
+        }
+
+        DispatcherGraph { systems, conflicts }
+    }
+
+    /// Builds the `Dispatcher`.
+    pub fn build(self) -> Dispatcher<'a, 'b> {
+        self.builder.build()
+    }
+
+    /// Builds the `Dispatcher`, also returning the graph of its systems.
+    pub fn build_with_graph(self) -> (Dispatcher<'a, 'b>, DispatcherGraph) {
+        let graph = self.graph();
+
+        (self.build(), graph)
+    }
+
+    /// Returns the wrapped `DispatcherBuilder`, which stops recording.
+    pub fn into_inner(self) -> DispatcherBuilder<'a, 'b> {
+        self.builder
+    }
+
+    fn record_thread_local(&mut self) {
+        self.systems.push(RecordedSystem {
+            name: String::new(),
+            dependencies: Vec::new(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            barrier: None,
+        });
+    }
+
+    /// Computes the stage of every system.
+    fn stages(&self) -> Vec<Option<usize>> {
+        let mut stages: Vec<Option<usize>> = Vec::with_capacity(self.systems.len());
+        for (index, system) in self.systems.iter().enumerate() {
+            let stage = system.barrier.map(|barrier| {
+                let after_deps = system.dependencies.iter().filter_map(|&dep| stages[dep]);
+                let after_conflicts = self.systems[..index]
+                    .iter()
+                    .zip(&stages)
+                    .filter(|(other, _)| conflicting(system, other))
+                    .filter_map(|(_, stage)| *stage);
+
+                after_deps
+                    .chain(after_conflicts)
+                    .map(|stage| stage + 1)
+                    .fold(barrier, usize::max)
+            });
+            stages.push(stage);
+        }
+
+        stages
+    }
+}
+
+impl<'a, 'b> ExclusiveDispatcherBuilder for GraphBuilder<'a, 'b> {
+    fn with_exclusive<S: ExclusiveSystem>(mut self, system: S, name: &str, dep: &[&str]) -> Self {
+        self.add_exclusive(system, name, dep);
+
+        self
+    }
+
+    /// Adds a new exclusive system, which is recorded like a thread local
+    /// system with its name and dependencies.
+    fn add_exclusive<S: ExclusiveSystem>(&mut self, system: S, name: &str, dep: &[&str]) {
+        self.builder.add_exclusive(system, name, dep);
+        self.record_thread_local();
+        let index = self.systems.len() - 1;
+        if !name.is_empty() {
+            self.ids.insert(name.to_owned(), index);
+        }
+        let system = &mut self.systems[index];
+        system.name = name.to_owned();
+        system.dependencies = dep
+            .iter()
+            .filter_map(|dep| self.ids.get(*dep).copied())
+            .collect();
+    }
+}
+
+fn conflicting(a: &RecordedSystem, b: &RecordedSystem) -> bool {
+    a.writes
+        .iter()
+        .any(|id| b.reads.contains(id) || b.writes.contains(id))
+        || a.reads.iter().any(|id| b.writes.contains(id))
+}
+
+/// Returns the name of `T` without module paths, e.g. `Vec<Pos>`.
+fn short_type_name<T: ?Sized>() -> String {
+    let mut name = String::new();
+    let mut segment = String::new();
+    for c in type_name::<T>().chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            segment.push(c);
+        } else {
+            name.push_str(segment.rsplit("::").next().unwrap_or_default());
+            segment.clear();
+            name.push(c);
+        }
+    }
+    name.push_str(segment.rsplit("::").next().unwrap_or_default());
+
+    name
+}
+
+/// The systems of a dispatcher and the resource conflicts between them, as
+/// returned by `GraphBuilder::graph`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DispatcherGraph {
+    /// The systems, in the order they were added.
+    pub systems: Vec<SystemNode>,
+    /// Every pair of systems accessing a resource in a conflicting way.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// A system of a `DispatcherGraph`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SystemNode {
+    /// The name of the system, empty for unnamed and thread local systems.
+    pub name: String,
+    /// The indices of the systems this system depends on.
+    pub dependencies: Vec<usize>,
+    /// The resources read by the system, sorted by name.
+    pub reads: Vec<String>,
+    /// The resources written by the system, sorted by name.
+    pub writes: Vec<String>,
+    /// The stage of the system, `None` for thread local systems, which run
+    /// after all others.
+    ///
+    /// A system can't run before all systems of lower stages it depends on
+    /// or conflicts with have run, while systems of the same stage can run in
+    /// parallel. This is the earliest possible schedule; the dispatcher may
+    /// still run conflicting systems in the same stage one after another.
+    pub stage: Option<usize>,
+}
+
+/// Two systems writing a resource the other one reads or writes.
+///
+/// Such systems can't run in parallel, the one added first always runs
+/// before the other.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Conflict {
+    /// The index of the system added first.
+    pub first: usize,
+    /// The index of the system added second.
+    pub second: usize,
+    /// The resources the systems conflict on, sorted by name.
+    pub resources: Vec<String>,
+}
+
+impl DispatcherGraph {
+    /// Writes the graph in the DOT format of Graphviz.
+    ///
+    /// Dependencies are drawn as solid edges, conflicts as dashed edges
+    /// labeled with the conflicting resources. Systems of the same stage are
+    /// aligned.
+    pub fn write_dot<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        writeln!(w, "digraph dispatcher {{")?;
+        writeln!(w, "    rankdir=LR;")?;
+        for (index, system) in self.systems.iter().enumerate() {
+            let name = match (system.name.is_empty(), system.stage) {
+                (false, _) => system.name.clone(),
+                (true, Some(_)) => format!("<unnamed #{}>", index),
+                (true, None) => format!("<thread local #{}>", index),
+            };
+            let stage = match system.stage {
+                Some(stage) => format!("stage {}", stage),
+                None => "thread local".to_owned(),
+            };
+            writeln!(
+                w,
+                "    s{} [label=\"{}\\n{}\"];",
+                index,
+                escape(&name),
+                stage
+            )?;
+        }
+
+        let mut stages: Vec<Vec<usize>> = Vec::new();
+        for (index, system) in self.systems.iter().enumerate() {
+            if let Some(stage) = system.stage {
+                stages.resize_with(stages.len().max(stage + 1), Vec::new);
+                stages[stage].push(index);
+            }
+        }
+        for systems in stages.iter().filter(|systems| systems.len() > 1) {
+            write!(w, "    {{ rank=same;")?;
+            for index in systems {
+                write!(w, " s{};", index)?;
+            }
+            writeln!(w, " }}")?;
+        }
+
+        for (index, system) in self.systems.iter().enumerate() {
+            for dep in &system.dependencies {
+                writeln!(w, "    s{} -> s{};", dep, index)?;
+            }
+        }
+        for conflict in &self.conflicts {
+            writeln!(
+                w,
+                "    s{} -> s{} [style=dashed, color=red, label=\"{}\"];",
+                conflict.first,
+                conflict.second,
+                escape(&conflict.resources.join(", "))
+            )?;
+        }
+
+        writeln!(w, "}}")
+    }
+
+    /// Returns the graph in the DOT format of Graphviz, see
+    /// `DispatcherGraph::write_dot`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        self.write_dot(&mut dot)
+            .expect("Writing to a `String` can't fail");
+
+        dot
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        storage::{ReadStorage, VecStorage, WriteStorage},
+        world::Entities,
+    };
+    use shred::{Read, Write};
+
+    struct Pos;
+
+    impl Component for Pos {
+        type Storage = VecStorage<Self>;
+    }
+
+    struct Vel;
+
+    impl Component for Vel {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Default)]
+    struct Time;
+
+    struct Physics;
+
+    impl<'a> System<'a> for Physics {
+        type SystemData = (Read<'a, Time>, ReadStorage<'a, Vel>, WriteStorage<'a, Pos>);
+
+        fn run(&mut self, _: Self::SystemData) {}
+    }
+
+    struct Render;
+
+    impl<'a> System<'a> for Render {
+        type SystemData = (Entities<'a>, ReadStorage<'a, Pos>);
+
+        fn run(&mut self, _: Self::SystemData) {}
+    }
+
+    struct Clock;
+
+    impl<'a> System<'a> for Clock {
+        type SystemData = Write<'a, Time>;
+
+        fn run(&mut self, _: Self::SystemData) {}
+    }
+
+    struct Idle;
+
+    impl<'a> System<'a> for Idle {
+        type SystemData = ReadStorage<'a, Vel>;
+
+        fn run(&mut self, _: Self::SystemData) {}
+    }
+
+    fn builder() -> GraphBuilder<'static, 'static> {
+        DispatcherBuilder::new()
+            .debug_graph()
+            .name_component::<Pos>()
+            .name_component::<Vel>()
+            .with(Clock, "clock", &[])
+            .with(Idle, "idle", &[])
+            .with(Physics, "physics", &[])
+            .with(Render, "render", &["idle"])
+    }
+
+    #[test]
+    fn stages_and_conflicts() {
+        let graph = builder().with_barrier().with(Idle, "", &[]).graph();
+
+        let stages: Vec<_> = graph.systems.iter().map(|s| s.stage).collect();
+        assert_eq!(stages, vec![Some(0), Some(0), Some(1), Some(2), Some(3)]);
+        assert_eq!(graph.systems[3].dependencies, vec![1]);
+        assert_eq!(
+            graph.systems[2].reads,
+            vec!["EntitiesRes", "Vel", "resource #1"]
+        );
+        assert_eq!(graph.systems[2].writes, vec!["Pos"]);
+        assert_eq!(graph.systems[3].reads, vec!["EntitiesRes", "Pos"]);
+
+        assert_eq!(
+            graph.conflicts,
+            vec![
+                Conflict {
+                    first: 0,
+                    second: 2,
+                    resources: vec!["resource #1".to_owned()],
+                },
+                Conflict {
+                    first: 2,
+                    second: 3,
+                    resources: vec!["Pos".to_owned()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn unrecorded_dependency() {
+        let graph = DispatcherBuilder::new()
+            .with(Clock, "clock", &[])
+            .debug_graph()
+            .with(Physics, "physics", &["clock"])
+            .with(Render, "render", &["clock", "physics"])
+            .graph();
+
+        assert!(graph.systems[0].dependencies.is_empty());
+        assert_eq!(graph.systems[1].dependencies, vec![0]);
+    }
+
+    #[test]
+    fn thread_local() {
+        let graph = builder().with_thread_local(Idle).graph();
+
+        assert_eq!(graph.systems[4].stage, None);
+        assert!(graph.systems[4].reads.is_empty());
+    }
+
+    #[test]
+    fn dot() {
+        let dot = builder().graph().to_dot();
+
+        assert!(dot.starts_with("digraph dispatcher {\n"));
+        assert!(dot.contains("    s2 [label=\"physics\\nstage 1\"];\n"));
+        assert!(dot.contains("    { rank=same; s0; s1; }\n"));
+        assert!(dot.contains("    s1 -> s3;\n"));
+        assert!(dot.contains("    s2 -> s3 [style=dashed, color=red, label=\"Pos\"];\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serialize() {
+        let json = serde_json::to_value(builder().graph()).unwrap();
+
+        assert_eq!(json["systems"][2]["name"], "physics");
+        assert_eq!(json["conflicts"][1]["resources"][0], "Pos");
+    }
+}
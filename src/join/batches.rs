@@ -0,0 +1,284 @@
+use std::{
+    ops::{Deref, DerefMut, Range},
+    slice,
+};
+
+use hibitset::{BitSet, BitSetLike};
+use rayon::iter::{plumbing::UnindexedConsumer, IntoParallelIterator, ParallelIterator};
+
+use super::BitAnd;
+use crate::{
+    storage::{IdSliceAccess, MaskedStorage, SliceAccess, Storage},
+    world::{Component, Index},
+};
+
+/// Parallel joins over batches of consecutive ids, yielding slices of the
+/// components instead of single components.
+///
+/// Numeric systems can run vectorizable inner loops over the slices of each
+/// batch, instead of calling a closure per entity. This is implemented for
+/// `&Storage` and `&mut Storage` of storages whose slices are indexed by
+/// entity id (see `IdSliceAccess`), like `VecStorage`, `DefaultVecStorage`
+/// and `AlignedVecStorage`, and for tuples of them. Other storages are joined
+/// per entity with `ParJoin::par_join`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::prelude::*;
+///
+/// #[derive(Clone, Copy, Default)]
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = DefaultVecStorage<Self>;
+/// }
+///
+/// #[derive(Clone, Copy, Default)]
+/// struct Vel(f32);
+///
+/// impl Component for Vel {
+///     type Storage = DefaultVecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// world.register::<Vel>();
+/// for i in 0..100 {
+///     world
+///         .create_entity()
+///         .with(Pos(0.0))
+///         .with(Vel(i as f32))
+///         .build();
+/// }
+///
+/// let mut pos = world.write_storage::<Pos>();
+/// let vel = world.read_storage::<Vel>();
+/// (&mut pos, &vel)
+///     .par_join_batches(16)
+///     .for_each(|(ids, (pos, vel))| {
+///         assert_eq!(pos.len(), ids.len());
+///         for (pos, vel) in pos.iter_mut().zip(vel) {
+///             pos.0 += vel.0;
+///         }
+///     });
+/// assert_eq!((&pos).join().map(|p| p.0).sum::<f32>(), 4950.0);
+/// ```
+///
+/// # Safety
+///
+/// The `Mask` must only contain ids `slices` may be called with. The slices
+/// returned for disjoint ranges must not alias mutably, and `slices` must be
+/// safe to call from multiple threads at once.
+pub unsafe trait BatchJoin {
+    /// Type of the mask of the join.
+    type Mask: BitSetLike;
+    /// Type of the slices of one batch.
+    type Slices;
+    /// Type of the opened join.
+    type Value;
+
+    /// Creates a parallel iterator over batches of at most `batch_size`
+    /// consecutive ids, yielding the range of ids and the slices of their
+    /// components.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `batch_size` is zero.
+    fn par_join_batches(self, batch_size: usize) -> JoinParBatches<Self>
+    where
+        Self: Sized,
+    {
+        assert!(batch_size > 0, "`batch_size` must be greater than zero");
+
+        // SAFETY: The value is only accessed with the ids of the mask.
+        let (mask, value) = unsafe { self.open() };
+        let mut runs: Vec<Range<Index>> = Vec::new();
+        for id in mask.iter() {
+            match runs.last_mut() {
+                Some(run) if run.end == id && ((id - run.start) as usize) < batch_size => {
+                    run.end += 1;
+                }
+                _ => runs.push(id..id + 1),
+            }
+        }
+
+        JoinParBatches { runs, value }
+    }
+
+    /// Opens this join by returning the mask and the storages.
+    ///
+    /// # Safety
+    ///
+    /// The `Value` must only be accessed with the ids of the `Mask`.
+    unsafe fn open(self) -> (Self::Mask, Self::Value);
+
+    /// Returns the slices of the components of the ids in `range`.
+    ///
+    /// # Safety
+    ///
+    /// All ids in `range` must be in the mask, and the slices returned for
+    /// overlapping ranges must not be alive at the same time.
+    unsafe fn slices(value: &Self::Value, range: Range<usize>) -> Self::Slices;
+}
+
+/// Parallel iterator over batches of a `BatchJoin`, see
+/// `BatchJoin::par_join_batches`.
+#[must_use]
+pub struct JoinParBatches<J: BatchJoin> {
+    runs: Vec<Range<Index>>,
+    value: J::Value,
+}
+
+impl<J> ParallelIterator for JoinParBatches<J>
+where
+    J: BatchJoin,
+    J::Value: Send + Sync,
+    J::Slices: Send,
+{
+    type Item = (Range<Index>, J::Slices);
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let JoinParBatches { runs, value } = self;
+        let value = &value;
+        runs.into_par_iter()
+            .map(move |ids| {
+                // SAFETY: The runs only contain ids of the mask and don't
+                // overlap.
+                let slices = unsafe { J::slices(value, ids.start as usize..ids.end as usize) };
+                (ids, slices)
+            })
+            .drive_unindexed(consumer)
+    }
+}
+
+/// Pointer to the elements of an `IdSliceAccess` storage.
+pub struct SlicePtr<T> {
+    ptr: *mut T,
+    len: usize,
+}
+
+// SAFETY: `SlicePtr` is a slice which is shared with `BatchJoin::slices`.
+unsafe impl<T: Send + Sync> Send for SlicePtr<T> {}
+
+// SAFETY: `BatchJoin::slices` only hands out mutable references to disjoint
+// ranges.
+unsafe impl<T: Send + Sync> Sync for SlicePtr<T> {}
+
+impl<T> SlicePtr<T> {
+    /// Returns a pointer to the start of `range`.
+    fn get(&self, range: Range<usize>) -> *mut T {
+        assert!(range.start <= range.end && range.end <= self.len);
+        // SAFETY: `range.start` is in bounds.
+        unsafe { self.ptr.add(range.start) }
+    }
+}
+
+// SAFETY: The elements of the ids of the mask are components, and shared
+// slices may alias.
+unsafe impl<'a, 'e, T, D> BatchJoin for &'a Storage<'e, T, D>
+where
+    T: Component + Send + Sync,
+    T::Storage: IdSliceAccess<T>,
+    D: Deref<Target = MaskedStorage<T>>,
+{
+    type Mask = &'a BitSet;
+    type Slices = &'a [T];
+    type Value = SlicePtr<T>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        let (mask, storage) = self.masked_storage().open();
+        let slice = storage.as_slice();
+        let ptr = SlicePtr {
+            ptr: slice.as_ptr() as *mut T,
+            len: slice.len(),
+        };
+
+        (mask, ptr)
+    }
+
+    unsafe fn slices(value: &Self::Value, range: Range<usize>) -> &'a [T] {
+        let len = range.len();
+        // SAFETY: The caller ensures the ids are in the mask, so the range is
+        // in bounds and holds initialized components.
+        unsafe { slice::from_raw_parts(value.get(range), len) }
+    }
+}
+
+// SAFETY: The elements of the ids of the mask are components, and the slices
+// of disjoint ranges don't alias.
+unsafe impl<'a, 'e, T, D> BatchJoin for &'a mut Storage<'e, T, D>
+where
+    T: Component + Send + Sync,
+    T::Storage: IdSliceAccess<T>,
+    D: DerefMut<Target = MaskedStorage<T>>,
+{
+    type Mask = &'a BitSet;
+    type Slices = &'a mut [T];
+    type Value = SlicePtr<T>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: The ids of the storage are not changed while the join is
+        // alive, since it borrows the storage mutably.
+        let (mask, storage) = unsafe { self.masked_storage_mut().open_mut() };
+        let slice = storage.as_mut_slice();
+        let ptr = SlicePtr {
+            ptr: slice.as_mut_ptr() as *mut T,
+            len: slice.len(),
+        };
+
+        (mask, ptr)
+    }
+
+    unsafe fn slices(value: &Self::Value, range: Range<usize>) -> &'a mut [T] {
+        let len = range.len();
+        // SAFETY: The caller ensures the ids are in the mask, so the range is
+        // in bounds and holds initialized components, and that the slices
+        // don't overlap.
+        unsafe { slice::from_raw_parts_mut(value.get(range), len) }
+    }
+}
+
+macro_rules! define_batch_join {
+    ($($from:ident),*) => {
+        // SAFETY: The mask is the intersection of the masks of all joins, so
+        // their contracts are upheld for every id of it.
+        unsafe impl<$($from,)*> BatchJoin for ($($from),*,)
+        where
+            $($from: BatchJoin),*,
+            ($(<$from as BatchJoin>::Mask,)*): BitAnd,
+        {
+            type Mask = <($($from::Mask,)*) as BitAnd>::Value;
+            type Slices = ($($from::Slices),*,);
+            type Value = ($($from::Value),*,);
+
+            #[allow(non_snake_case)]
+            unsafe fn open(self) -> (Self::Mask, Self::Value) {
+                let ($($from,)*) = self;
+                // SAFETY: The caller upholds the contract of each join.
+                let ($($from,)*) = unsafe { ($($from.open(),)*) };
+
+                (($($from.0),*,).and(), ($($from.1),*,))
+            }
+
+            #[allow(non_snake_case)]
+            unsafe fn slices(value: &Self::Value, range: Range<usize>) -> Self::Slices {
+                let ($(ref $from,)*) = *value;
+                // SAFETY: The ids are in the masks of all joins.
+                unsafe { ($($from::slices($from, range.clone()),)*) }
+            }
+        }
+    };
+}
+
+define_batch_join!(A);
+define_batch_join!(A, B);
+define_batch_join!(A, B, C);
+define_batch_join!(A, B, C, D);
+define_batch_join!(A, B, C, D, E);
+define_batch_join!(A, B, C, D, E, F);
+define_batch_join!(A, B, C, D, E, F, G);
+define_batch_join!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,86 @@
+use std::ops::Deref;
+
+use hibitset::{BitIter, BitSet, BitSetLike, BitSetOr};
+
+use crate::{
+    storage::{MaskedStorage, Storage, UnprotectedStorage},
+    world::{Component, Index},
+};
+
+/// Iterates over the components of two storages of the same type, e.g. from
+/// two different worlds, yielding the index together with the component of
+/// each storage for every index at least one of them has a component for.
+///
+/// This supports tools comparing worlds, like diffs, test assertions or an
+/// editor comparing a scene to its saved version. Note that the storages are
+/// matched by index only, the generations of the entities are not compared.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{join::cross_join, prelude::*};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Pos(u32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut saved = World::new();
+/// saved.register::<Pos>();
+/// saved.create_entity().with(Pos(1)).build();
+/// saved.create_entity().build();
+///
+/// let mut scene = World::new();
+/// scene.register::<Pos>();
+/// scene.create_entity().with(Pos(2)).build();
+/// scene.create_entity().with(Pos(3)).build();
+///
+/// let saved = saved.read_storage::<Pos>();
+/// let scene = scene.read_storage::<Pos>();
+/// let diff: Vec<_> = cross_join(&saved, &scene).collect();
+/// assert_eq!(
+///     diff,
+///     [(0, Some(&Pos(1)), Some(&Pos(2))), (1, None, Some(&Pos(3)))]
+/// );
+/// ```
+pub fn cross_join<'a, T, D1, D2>(
+    a: &'a Storage<'_, T, D1>,
+    b: &'a Storage<'_, T, D2>,
+) -> CrossJoin<'a, T>
+where
+    T: Component,
+    D1: Deref<Target = MaskedStorage<T>>,
+    D2: Deref<Target = MaskedStorage<T>>,
+{
+    let a = a.masked_storage().open();
+    let b = b.masked_storage().open();
+
+    CrossJoin {
+        iter: BitSetOr(a.0, b.0).iter(),
+        a,
+        b,
+    }
+}
+
+/// Iterator over the components of two storages, see `cross_join`.
+pub struct CrossJoin<'a, T: Component> {
+    iter: BitIter<BitSetOr<&'a BitSet, &'a BitSet>>,
+    a: (&'a BitSet, &'a T::Storage),
+    b: (&'a BitSet, &'a T::Storage),
+}
+
+impl<'a, T: Component> Iterator for CrossJoin<'a, T> {
+    type Item = (Index, Option<&'a T>, Option<&'a T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let get = |(mask, storage): (&'a BitSet, &'a T::Storage), id| {
+            // SAFETY: We checked the mask the storage came with.
+            mask.contains(id).then(|| unsafe { storage.get(id) })
+        };
+        let id = self.iter.next()?;
+
+        Some((id, get(self.a, id), get(self.b, id)))
+    }
+}
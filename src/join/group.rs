@@ -0,0 +1,188 @@
+use std::{
+    collections::{btree_map, BTreeMap},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use ahash::AHashMap as HashMap;
+use hibitset::{BitSet, BitSetLike};
+use shred::World;
+
+use super::{Join, JoinIter};
+use crate::{
+    storage::StorageHooks,
+    world::{Component, Entity, Index, WorldExt},
+};
+
+/// An index of the entities having a component, grouped by the value of the
+/// component, e.g. by team, chunk or material.
+///
+/// The index is kept up to date with `StorageHooks` on the storage of `K`,
+/// so systems processing entities group by group don't have to sort them
+/// every frame. Since hooks only observe insertions and removals, the key of
+/// an entity has to be changed by inserting a new component, not by mutating
+/// it in place.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{join::GroupIndex, prelude::*};
+///
+/// #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// struct Team(u8);
+///
+/// impl Component for Team {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Score(u32);
+///
+/// impl Component for Score {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Team>();
+/// world.register::<Score>();
+/// GroupIndex::<Team>::attach(&mut world);
+///
+/// world.create_entity().with(Team(1)).with(Score(3)).build();
+/// world.create_entity().with(Team(0)).with(Score(1)).build();
+/// let e = world.create_entity().with(Team(0)).with(Score(2)).build();
+/// world.write_storage::<Team>().insert(e, Team(1)).unwrap();
+///
+/// let index = world.read_resource::<GroupIndex<Team>>();
+/// let scores = world.read_storage::<Score>();
+/// let totals: Vec<_> = index
+///     .lock()
+///     .group_by(&scores)
+///     .map(|(team, members)| (team.0, members.map(|score| score.0).sum::<u32>()))
+///     .collect();
+/// assert_eq!(totals, [(0, 1), (1, 5)]);
+/// ```
+pub struct GroupIndex<K> {
+    groups: Arc<Mutex<GroupMasks<K>>>,
+}
+
+struct GroupMasks<K> {
+    masks: BTreeMap<K, BitSet>,
+    keys: HashMap<Index, K>,
+}
+
+impl<K> GroupIndex<K>
+where
+    K: Component + Ord + Clone + Send,
+{
+    /// Inserts the index of `K` into `world`, adding hooks to the storage of
+    /// `K`, which has to be registered already, to maintain it.
+    ///
+    /// Hooks added with `Storage::observe` before stay in place. Only the
+    /// components inserted afterwards are indexed.
+    pub fn attach(world: &mut World) {
+        let groups = Arc::new(Mutex::new(GroupMasks {
+            masks: BTreeMap::new(),
+            keys: HashMap::new(),
+        }));
+        let (inserted, removed) = (groups.clone(), groups.clone());
+        world.write_storage::<K>().observe(
+            StorageHooks::new()
+                .on_insert(move |e, key: &K| lock(&inserted).insert(e.id(), key))
+                .on_remove(move |e, _: &K| lock(&removed).remove(e.id())),
+        );
+        world.insert(GroupIndex { groups });
+    }
+
+    /// Returns the key of the group of `e`.
+    pub fn key(&self, e: Entity) -> Option<K> {
+        lock(&self.groups).keys.get(&e.id()).cloned()
+    }
+
+    /// Returns the number of groups.
+    pub fn len(&self) -> usize {
+        lock(&self.groups).masks.len()
+    }
+
+    /// Returns `true` if there are no groups.
+    pub fn is_empty(&self) -> bool {
+        lock(&self.groups).masks.is_empty()
+    }
+
+    /// Locks the index to iterate over its groups.
+    ///
+    /// Inserting or removing components of `K` blocks until the returned
+    /// `Groups` are dropped.
+    pub fn lock(&self) -> Groups<'_, K> {
+        Groups(lock(&self.groups))
+    }
+}
+
+/// The locked groups of a `GroupIndex`, see `GroupIndex::lock`.
+pub struct Groups<'a, K>(MutexGuard<'a, GroupMasks<K>>);
+
+impl<'a, K> Groups<'a, K> {
+    /// Returns an iterator over the groups in the order of their keys,
+    /// yielding the key of each group and an iterator over the items of
+    /// `join` of its entities.
+    pub fn group_by<J>(&self, join: J) -> GroupBy<'_, K, J>
+    where
+        J: Join + Clone,
+    {
+        GroupBy {
+            groups: self.0.masks.iter(),
+            join,
+        }
+    }
+}
+
+impl<K: Ord + Clone> GroupMasks<K> {
+    fn insert(&mut self, id: Index, key: &K) {
+        self.remove(id);
+        self.masks.entry(key.clone()).or_default().add(id);
+        self.keys.insert(id, key.clone());
+    }
+
+    fn remove(&mut self, id: Index) {
+        if let Some(key) = self.keys.remove(&id) {
+            if let btree_map::Entry::Occupied(mut mask) = self.masks.entry(key) {
+                mask.get_mut().remove(id);
+                if mask.get().is_empty() {
+                    mask.remove();
+                }
+            }
+        }
+    }
+}
+
+fn lock<K>(groups: &Mutex<GroupMasks<K>>) -> MutexGuard<'_, GroupMasks<K>> {
+    groups.lock().expect("`GroupIndex` hook panicked")
+}
+
+/// Iterator over the groups of a `GroupIndex`, see `Groups::group_by`.
+pub struct GroupBy<'a, K, J> {
+    groups: btree_map::Iter<'a, K, BitSet>,
+    join: J,
+}
+
+impl<'a, K, J> Iterator for GroupBy<'a, K, J>
+where
+    J: Join + Clone,
+{
+    type Item = (&'a K, Group<'a, J>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, mask) = self.groups.next()?;
+
+        Some((key, Group((mask, self.join.clone()).join())))
+    }
+}
+
+/// Iterator over the items of a join for the entities of one group, see
+/// `Groups::group_by`.
+pub struct Group<'a, J: Join>(JoinIter<(&'a BitSet, J)>);
+
+impl<'a, J: Join> Iterator for Group<'a, J> {
+    type Item = J::Type;
+
+    fn next(&mut self) -> Option<J::Type> {
+        self.0.next().map(|(_, item)| item)
+    }
+}
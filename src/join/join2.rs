@@ -0,0 +1,203 @@
+use std::ops::{Deref, DerefMut};
+
+use hibitset::{BitIter, BitSet, BitSetAnd, BitSetLike};
+
+use crate::{
+    storage::{MaskedStorage, SharedGetMutOnly, SharedGetMutStorage, Storage, UnprotectedStorage},
+    world::{Component, Index},
+};
+
+/// How much larger the mask of one storage has to be for iterating the
+/// smaller one and checking the larger one to be faster than iterating the
+/// intersection of both masks.
+const SPARSE_RATIO: usize = 8;
+
+/// Joins two storages, like `(&a, &b).join()`.
+///
+/// If one storage has far fewer components than the other, only the
+/// indices of its mask are iterated and looked up in the mask of the other
+/// storage, which is faster than iterating the intersection of both masks.
+/// Otherwise, this falls back to iterating the intersection.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{join::join2, prelude::*};
+///
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Target;
+///
+/// impl Component for Target {
+///     type Storage = HashMapStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// world.register::<Target>();
+/// for i in 0..100 {
+///     world.create_entity().with(Pos(i as f32)).build();
+/// }
+/// world.create_entity().with(Pos(7.0)).with(Target).build();
+///
+/// let pos = world.read_storage::<Pos>();
+/// let targets = world.read_storage::<Target>();
+/// let sum: f32 = join2(&pos, &targets).map(|(pos, _)| pos.0).sum();
+/// assert_eq!(sum, 7.0);
+/// ```
+pub fn join2<'a, A, B, DA, DB>(
+    a: &'a Storage<'_, A, DA>,
+    b: &'a Storage<'_, B, DB>,
+) -> Join2<'a, A, B>
+where
+    A: Component,
+    B: Component,
+    DA: Deref<Target = MaskedStorage<A>>,
+    DB: Deref<Target = MaskedStorage<B>>,
+{
+    let counts = (a.count(), b.count());
+    let (mask_a, a) = a.masked_storage().open();
+    let (mask_b, b) = b.masked_storage().open();
+
+    Join2 {
+        iter: Join2Iter::new((mask_a, mask_b), counts),
+        a,
+        b,
+    }
+}
+
+/// Joins two storages, the first one mutably, like `(&mut a, &b).join()`.
+///
+/// See `join2` for how the storages are iterated.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{join::join2_mut, prelude::*};
+///
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Vel(f32);
+///
+/// impl Component for Vel {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// world.register::<Vel>();
+/// let e = world.create_entity().with(Pos(1.0)).with(Vel(2.0)).build();
+///
+/// let mut pos = world.write_storage::<Pos>();
+/// let vel = world.read_storage::<Vel>();
+/// for (pos, vel) in join2_mut(&mut pos, &vel) {
+///     pos.0 += vel.0;
+/// }
+/// assert_eq!(pos.get(e).unwrap().0, 3.0);
+/// ```
+pub fn join2_mut<'a, A, B, DA, DB>(
+    a: &'a mut Storage<'_, A, DA>,
+    b: &'a Storage<'_, B, DB>,
+) -> Join2Mut<'a, A, B>
+where
+    A: Component,
+    A::Storage: SharedGetMutStorage<A>,
+    B: Component,
+    DA: DerefMut<Target = MaskedStorage<A>>,
+    DB: Deref<Target = MaskedStorage<B>>,
+{
+    let counts = (a.count(), b.count());
+    let (mask_a, a) = a.masked_storage_mut().open_shared_mut();
+    let (mask_b, b) = b.masked_storage().open();
+
+    Join2Mut {
+        iter: Join2Iter::new((mask_a, mask_b), counts),
+        a,
+        b,
+    }
+}
+
+/// Iterator over two storages, see `join2`.
+pub struct Join2<'a, A: Component, B: Component> {
+    iter: Join2Iter<'a>,
+    a: &'a A::Storage,
+    b: &'a B::Storage,
+}
+
+impl<'a, A, B> Iterator for Join2<'a, A, B>
+where
+    A: Component,
+    B: Component,
+{
+    type Item = (&'a A, &'a B);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.iter.next()?;
+
+        // SAFETY: `id` is in both masks.
+        Some(unsafe { (self.a.get(id), self.b.get(id)) })
+    }
+}
+
+/// Iterator over two storages, the first one mutably, see `join2_mut`.
+pub struct Join2Mut<'a, A: Component, B: Component> {
+    iter: Join2Iter<'a>,
+    a: SharedGetMutOnly<'a, A, A::Storage>,
+    b: &'a B::Storage,
+}
+
+impl<'a, A, B> Iterator for Join2Mut<'a, A, B>
+where
+    A: Component,
+    A::Storage: SharedGetMutStorage<A>,
+    B: Component,
+{
+    type Item = (<A::Storage as UnprotectedStorage<A>>::AccessMut<'a>, &'a B);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.iter.next()?;
+
+        // SAFETY: `id` is in both masks and the iterator doesn't repeat
+        // indices, so there are no aliasing references to the component.
+        Some(unsafe { (SharedGetMutOnly::get_mut(&self.a, id), self.b.get(id)) })
+    }
+}
+
+/// The indices contained in two masks.
+enum Join2Iter<'a> {
+    /// Iterates the sparser mask, checking the other one.
+    Sparse(BitIter<&'a BitSet>, &'a BitSet),
+    /// Iterates the intersection of both masks.
+    Both(BitIter<BitSetAnd<&'a BitSet, &'a BitSet>>),
+}
+
+impl<'a> Join2Iter<'a> {
+    fn new((a, b): (&'a BitSet, &'a BitSet), (count_a, count_b): (usize, usize)) -> Self {
+        if count_a.saturating_mul(SPARSE_RATIO) <= count_b {
+            Join2Iter::Sparse(a.iter(), b)
+        } else if count_b.saturating_mul(SPARSE_RATIO) <= count_a {
+            Join2Iter::Sparse(b.iter(), a)
+        } else {
+            Join2Iter::Both(BitSetAnd(a, b).iter())
+        }
+    }
+}
+
+impl<'a> Iterator for Join2Iter<'a> {
+    type Item = Index;
+
+    fn next(&mut self) -> Option<Index> {
+        match self {
+            Join2Iter::Sparse(iter, other) => iter.find(|&id| other.contains(id)),
+            Join2Iter::Both(iter) => iter.next(),
+        }
+    }
+}
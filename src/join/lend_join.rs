@@ -1,5 +1,6 @@
-use super::MaybeJoin;
+use super::{InIndexRange, MaybeJoin};
 use hibitset::{BitIter, BitSetLike};
+use std::ops::Range;
 
 use crate::world::{Entities, Entity, Index};
 
@@ -34,6 +35,48 @@ use crate::world::{Entities, Entity, Index};
 ///
 /// Also see the `lend_join` example.
 ///
+/// ## Joining with entities
+///
+/// Joining `Entities` with a `&mut Storage` lends each component together with
+/// its entity, as `(Entity, AccessMut)`. Unlike holding on to a `JoinIter` and
+/// calling `JoinIter::get` with other entities, this can't hand out two
+/// mutable references to the same component, since the lending iterator stays
+/// borrowed while an item is alive.
+///
+/// ```
+/// use specs::prelude::*;
+///
+/// struct Health(u32);
+///
+/// impl Component for Health {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Health>();
+/// let a = world.create_entity().with(Health(10)).build();
+/// let b = world.create_entity().with(Health(1)).build();
+///
+/// let entities = world.entities();
+/// let mut health = world.write_storage::<Health>();
+///
+/// let mut dying = Vec::new();
+/// let mut lending = (&entities, &mut health).lend_join();
+/// while let Some((entity, health)) = lending.next() {
+///     health.0 -= 1;
+///     if health.0 == 0 {
+///         dying.push(entity);
+///     }
+/// }
+/// assert_eq!(dying, [b]);
+///
+/// // Random access to the joined components of an entity.
+/// if let Some((_, health)) = lending.get(a, &entities) {
+///     health.0 += 5;
+/// }
+/// assert_eq!(health.get(a).unwrap().0, 14);
+/// ```
+///
 /// # Safety
 ///
 /// The `Self::Mask` value returned with the `Self::Value` must correspond such
@@ -132,6 +175,41 @@ pub unsafe trait LendJoin {
         MaybeJoin(self)
     }
 
+    /// Returns a structure that implements `Join`/`LendJoin`/`ParJoin` if the
+    /// contained `T` does and that only yields the entities whose index is in
+    /// `range`.
+    ///
+    /// The joined mask is restricted to the range layer by layer, so the
+    /// indices outside of it are skipped without being visited. This suits
+    /// worlds that allocate a range of entity indices per region, processing
+    /// each region on its own.
+    ///
+    /// ```
+    /// # use specs::prelude::*;
+    /// # #[derive(Debug, PartialEq)]
+    /// # struct Pos(u32); impl Component for Pos { type Storage = VecStorage<Self>; }
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// for i in 0..100 {
+    ///     world.create_entity().with(Pos(i)).build();
+    /// }
+    ///
+    /// let entities = world.entities();
+    /// let pos = world.read_storage::<Pos>();
+    /// let region: Vec<_> = (&entities, &pos)
+    ///     .in_index_range(10..13)
+    ///     .join()
+    ///     .map(|(e, pos)| (e.id(), pos.0))
+    ///     .collect();
+    /// assert_eq!(region, [(10, 10), (11, 11), (12, 12)]);
+    /// ```
+    fn in_index_range(self, range: Range<Index>) -> InIndexRange<Self>
+    where
+        Self: Sized,
+    {
+        InIndexRange::new(self, range)
+    }
+
     /// Open this join by returning the mask and the storages.
     ///
     /// # Safety
@@ -294,6 +372,14 @@ impl<J: LendJoin> JoinLendIter<J> {
         }
     }
 
+    /// Returns `true` if `entity` is alive and part of the join, i.e. if
+    /// `get` returns its joined values.
+    ///
+    /// Unlike `get`, this doesn't require `J: RepeatableLendGet`.
+    pub fn contains(&self, entity: Entity, entities: &Entities) -> bool {
+        self.keys.contains(entity.id()) && entities.is_alive(entity)
+    }
+
     /// Allows getting joined values for specific raw index.
     ///
     /// The raw index for an `Entity` can be retrieved using `Entity::id`
@@ -1,24 +1,49 @@
 //! Joining of components for iteration over entities with specific components.
 
-use hibitset::{BitIter, BitSetLike};
+use hibitset::BitSetLike;
 use shred::{Fetch, FetchMut, Read, ReadExpect, Resource, Write, WriteExpect};
-use std::ops::{Deref, DerefMut};
+use std::{
+    iter::Take,
+    ops::{Deref, DerefMut},
+};
 
-use crate::world::Index;
+use crate::{
+    bitset::{popcount, MaskIter},
+    storage::{MaskedStorage, Storage},
+    world::{Component, Index},
+};
 
+#[cfg(feature = "parallel")]
+mod batches;
 mod bit_and;
+mod cross;
+mod group;
+mod join2;
 mod lend_join;
 mod maybe;
+mod overlay;
 #[cfg(feature = "parallel")]
 mod par_join;
+mod previous;
+mod range;
+mod sorted;
 
+#[cfg(feature = "parallel")]
+pub use batches::{BatchJoin, JoinParBatches};
 pub use bit_and::BitAnd;
+pub use cross::{cross_join, CrossJoin};
+pub use group::{Group, GroupBy, GroupIndex, Groups};
+pub use join2::{join2, join2_mut, Join2, Join2Mut};
 #[nougat::gat(Type)]
 pub use lend_join::LendJoin;
 pub use lend_join::{JoinLendIter, LendJoinType, RepeatableLendGet};
 pub use maybe::MaybeJoin;
+pub use overlay::Overlay;
 #[cfg(feature = "parallel")]
 pub use par_join::{JoinParIter, ParJoin};
+pub use previous::{Snapshot, WithPrevious};
+pub use range::InIndexRange;
+pub use sorted::{SortBuffer, SortedJoinIter};
 
 /// The purpose of the `Join` trait is to provide a way
 /// to access multiple storages at the same time with
@@ -112,6 +137,64 @@ pub unsafe trait Join {
         JoinIter::new(self)
     }
 
+    /// Creates an iterator over the contents in the order of a key read from
+    /// the component of each match in `keys`, e.g. to render by depth or to
+    /// process entities by priority.
+    ///
+    /// Only entities having a component in `keys` are yielded. The keys are
+    /// read directly from the storage and sorted in `buffer` together with
+    /// the indices of the matches, so passing the same buffer every frame
+    /// avoids allocations. Matches with equal keys are yielded in the order
+    /// of their indices.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{join::SortBuffer, prelude::*};
+    ///
+    /// struct Depth(i32);
+    ///
+    /// impl Component for Depth {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// struct Name(&'static str);
+    ///
+    /// impl Component for Name {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Depth>();
+    /// world.register::<Name>();
+    /// world.create_entity().with(Depth(2)).with(Name("back")).build();
+    /// world.create_entity().with(Depth(-1)).with(Name("front")).build();
+    /// world.create_entity().with(Depth(0)).with(Name("middle")).build();
+    ///
+    /// let mut buffer = SortBuffer::new();
+    /// let (depths, names) = (world.read_storage::<Depth>(), world.read_storage::<Name>());
+    /// let order: Vec<_> = (&names)
+    ///     .join_sorted_by_key(&depths, &mut buffer, |depth| depth.0)
+    ///     .map(|name| name.0)
+    ///     .collect();
+    /// assert_eq!(order, ["front", "middle", "back"]);
+    /// ```
+    fn join_sorted_by_key<'b, C, D, K, F>(
+        self,
+        keys: &Storage<C, D>,
+        buffer: &'b mut SortBuffer<K>,
+        key: F,
+    ) -> SortedJoinIter<'b, Self, K>
+    where
+        Self: Sized,
+        C: Component,
+        D: Deref<Target = MaskedStorage<C>>,
+        K: Ord,
+        F: FnMut(&C) -> K,
+    {
+        SortedJoinIter::new(self, keys, buffer, key)
+    }
+
     /// Open this join by returning the mask and the storages.
     ///
     /// # Safety
@@ -144,10 +227,18 @@ pub unsafe trait Join {
 }
 
 /// `JoinIter` is an `Iterator` over a group of storages.
+///
+/// The matches are counted from the popcount of the joined mask when the
+/// iterator is created, for joins that are not unconstrained (see
+/// `Join::is_unconstrained`), and `size_hint` returns the ones left. This is
+/// only a lower bound, as the mask of `Entities` can grow while iterating,
+/// when entities are created.
 #[must_use]
 pub struct JoinIter<J: Join> {
-    keys: BitIter<J::Mask>,
+    keys: MaskIter<J::Mask>,
     values: J::Value,
+    /// The matches left, as counted when the iterator was created.
+    remaining: usize,
 }
 
 impl<J: Join> JoinIter<J> {
@@ -164,11 +255,73 @@ impl<J: Join> JoinIter<J> {
         // SAFETY: We do not swap out the mask or the values, nor do we allow it
         // by exposing them.
         let (keys, values) = unsafe { j.open() };
+        let remaining = if <J as Join>::is_unconstrained() {
+            0
+        } else {
+            popcount(&keys)
+        };
         JoinIter {
-            keys: keys.iter(),
+            keys: MaskIter::new(keys),
             values,
+            remaining,
         }
     }
+
+    /// Returns the number of remaining matches without retrieving the
+    /// components, using the popcount of the joined mask.
+    ///
+    /// Unlike `Iterator::count`, this does not call `Join::get`, so e.g.
+    /// joining a `FlaggedStorage` mutably won't flag any components.
+    /// For unconstrained joins this falls back to iterating.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use specs::prelude::*;
+    /// # struct Pos; impl Component for Pos { type Storage = VecStorage<Self>; }
+    /// # struct Vel; impl Component for Vel { type Storage = VecStorage<Self>; }
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// world.register::<Vel>();
+    /// world.create_entity().with(Pos).with(Vel).build();
+    /// world.create_entity().with(Pos).build();
+    ///
+    /// let (pos, vel) = (world.read_storage::<Pos>(), world.read_storage::<Vel>());
+    /// assert_eq!((&pos, &vel).join().count_matched(), 1);
+    /// assert_eq!((&pos).join().count_matched(), 2);
+    /// ```
+    pub fn count_matched(self) -> usize {
+        if <J as Join>::is_unconstrained() {
+            self.count()
+        } else {
+            self.keys.remaining()
+        }
+    }
+
+    /// Yields at most `n` matches, stopping the iteration of the mask as soon
+    /// as `n` matches were found.
+    ///
+    /// The returned iterator has a `size_hint` of at least
+    /// `min(n, count_matched())` if this join is not unconstrained.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use specs::prelude::*;
+    /// # struct Pos; impl Component for Pos { type Storage = VecStorage<Self>; }
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// for _ in 0..10 {
+    ///     world.create_entity().with(Pos).build();
+    /// }
+    ///
+    /// let pos = world.read_storage::<Pos>();
+    /// let first: Vec<_> = (&pos).join().take_matched(3).collect();
+    /// assert_eq!(first.len(), 3);
+    /// ```
+    pub fn take_matched(self, n: usize) -> Take<Self> {
+        self.take(n)
+    }
 }
 
 impl<J: Join> std::iter::Iterator for JoinIter<J> {
@@ -178,9 +331,21 @@ impl<J: Join> std::iter::Iterator for JoinIter<J> {
         // SAFETY: Since `idx` is yielded from `keys` (the mask), it is
         // necessarily a part of it. `Join` requires that the iterator doesn't
         // repeat indices and we advance the iterator for each `get` call.
-        self.keys
+        let item = self
+            .keys
             .next()
-            .map(|idx| unsafe { J::get(&mut self.values, idx) })
+            .map(|idx| unsafe { J::get(&mut self.values, idx) });
+        if item.is_some() {
+            // NOTE: Masks of atomically created entities can grow while
+            // iterating, so more matches than counted may be yielded.
+            self.remaining = self.remaining.saturating_sub(1);
+        }
+
+        item
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, None)
     }
 }
 
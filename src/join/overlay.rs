@@ -0,0 +1,135 @@
+use std::ops::Deref;
+
+use hibitset::{BitSet, BitSetOr};
+
+use super::Join;
+#[cfg(feature = "parallel")]
+use super::ParJoin;
+use crate::{
+    storage::{MaskedStorage, Storage, UnprotectedStorage},
+    world::{Component, EntitiesRes, Entity, Index},
+};
+
+/// Reads the components of two storages of the same type, where the
+/// components of the override storage take precedence over the ones of the
+/// base storage.
+///
+/// This supports per-instance overrides on top of shared template values,
+/// e.g. a storage of tuned values next to the defaults of every entity,
+/// without copying the defaults or branching in every system. Joining an
+/// `Overlay` yields the component of each entity having one in either
+/// storage.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{join::Overlay, prelude::*, storage::MaskedStorage, world::EntitiesRes};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Speed(f32);
+///
+/// impl Component for Speed {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Speed>();
+/// let a = world.create_entity().with(Speed(1.0)).build();
+/// let b = world.create_entity().with(Speed(1.0)).build();
+///
+/// // A second storage of `Speed`, next to the one of the `World`.
+/// let mut overrides = MaskedStorage::<Speed>::default();
+/// Storage::new(world.fetch::<EntitiesRes>(), &mut overrides)
+///     .insert(b, Speed(2.0))
+///     .unwrap();
+///
+/// let overrides = Storage::new(world.fetch::<EntitiesRes>(), &overrides);
+/// let base = world.read_storage::<Speed>();
+/// let speed = Overlay::new(&overrides, &base);
+/// assert_eq!(speed.get(a), Some(&Speed(1.0)));
+/// assert_eq!(speed.get(b), Some(&Speed(2.0)));
+/// assert_eq!(speed.join().collect::<Vec<_>>(), [&Speed(1.0), &Speed(2.0)]);
+/// ```
+pub struct Overlay<'a, T: Component> {
+    over: (&'a BitSet, &'a T::Storage),
+    base: (&'a BitSet, &'a T::Storage),
+    entities: &'a EntitiesRes,
+}
+
+impl<'a, T: Component> Overlay<'a, T> {
+    /// Creates an overlay of `over` on top of `base`.
+    ///
+    /// Entities are checked for being alive with the entities of `over`.
+    pub fn new<D1, D2>(over: &'a Storage<'_, T, D1>, base: &'a Storage<'_, T, D2>) -> Self
+    where
+        D1: Deref<Target = MaskedStorage<T>>,
+        D2: Deref<Target = MaskedStorage<T>>,
+    {
+        Overlay {
+            over: over.masked_storage().open(),
+            base: base.masked_storage().open(),
+            entities: over.fetched_entities(),
+        }
+    }
+
+    /// Returns the component of `e` in the override storage if there is one,
+    /// the one in the base storage otherwise.
+    pub fn get(&self, e: Entity) -> Option<&'a T> {
+        if self.entities.is_alive(e) {
+            get(self.over, self.base, e.id())
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the component of `e` comes from the override
+    /// storage.
+    pub fn is_overridden(&self, e: Entity) -> bool {
+        self.entities.is_alive(e) && self.over.0.contains(e.id())
+    }
+}
+
+type Layer<'a, T> = (&'a BitSet, &'a <T as Component>::Storage);
+
+fn get<'a, T: Component>(over: Layer<'a, T>, base: Layer<'a, T>, id: Index) -> Option<&'a T> {
+    let (mask, storage) = if over.0.contains(id) { over } else { base };
+    // SAFETY: We checked the mask the storage came with.
+    mask.contains(id).then(|| unsafe { storage.get(id) })
+}
+
+// SAFETY: `get` only reads the storages, checking their masks.
+unsafe impl<'a, T: Component> Join for Overlay<'a, T> {
+    type Mask = BitSetOr<&'a BitSet, &'a BitSet>;
+    type Type = &'a T;
+    type Value = (Layer<'a, T>, Layer<'a, T>);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (BitSetOr(self.over.0, self.base.0), (self.over, self.base))
+    }
+
+    unsafe fn get(&mut (over, base): &mut Self::Value, id: Index) -> &'a T {
+        // The mask contains `id`, so one of the storages has a component.
+        get(over, base, id).unwrap()
+    }
+}
+
+// SAFETY: `get` only reads the storages, so it is safe to call concurrently.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, T> ParJoin for Overlay<'a, T>
+where
+    T: Component,
+    T::Storage: Sync,
+{
+    type Mask = BitSetOr<&'a BitSet, &'a BitSet>;
+    type Type = &'a T;
+    type Value = (Layer<'a, T>, Layer<'a, T>);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (BitSetOr(self.over.0, self.base.0), (self.over, self.base))
+    }
+
+    unsafe fn get(&(over, base): &Self::Value, id: Index) -> &'a T {
+        // The mask contains `id`, so one of the storages has a component.
+        get(over, base, id).unwrap()
+    }
+}
@@ -1,3 +1,5 @@
+use std::iter::Sum;
+
 use hibitset::{BitProducer, BitSetLike};
 use rayon::iter::{
     plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer},
@@ -73,9 +75,155 @@ pub unsafe trait ParJoin {
 }
 
 /// `JoinParIter` is a `ParallelIterator` over a group of storages.
+///
+/// Fallible work can use `ParallelIterator::try_for_each`, which stops at
+/// the first error and returns it, or `JoinParIter::try_for_each_collect`,
+/// which processes every item and returns all errors.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::prelude::*;
+///
+/// struct Script(&'static str);
+///
+/// impl Component for Script {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Script>();
+/// world.create_entity().with(Script("ok")).build();
+/// world.create_entity().with(Script("bad")).build();
+///
+/// let scripts = world.read_storage::<Script>();
+/// let run = |script: &Script| match script.0 {
+///     "ok" => Ok(()),
+///     err => Err(err),
+/// };
+///
+/// let first = (&scripts).par_join().try_for_each(run);
+/// assert_eq!(first, Err("bad"));
+///
+/// let all = (&scripts).par_join().try_for_each_collect(run);
+/// assert_eq!(all, Err(vec!["bad"]));
+/// ```
 #[must_use]
 pub struct JoinParIter<J>(J);
 
+impl<J> JoinParIter<J>
+where
+    J: ParJoin + Send,
+    J::Mask: Send + Sync,
+    J::Type: Send,
+    J::Value: Send + Sync,
+{
+    /// Calls the fallible `f` for every item in parallel, returning the
+    /// errors of all failed calls in the order of their entities.
+    ///
+    /// Unlike `ParallelIterator::try_for_each`, this doesn't stop at the
+    /// first error.
+    pub fn try_for_each_collect<F, E>(self, f: F) -> Result<(), Vec<E>>
+    where
+        F: Fn(J::Type) -> Result<(), E> + Send + Sync,
+        E: Send,
+    {
+        let errors: Vec<E> = self.filter_map(|item| f(item).err()).collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Maps every item with `map` in parallel and combines the results with
+    /// `reduce`, e.g. to compute a bounding box.
+    ///
+    /// `identity` creates the initial value of every parallel chunk, so
+    /// `reduce(identity(), x)` must return `x`. `reduce` must be associative,
+    /// since the chunks are combined in no particular grouping. Returns
+    /// `identity()` if there are no items.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// for x in [3.0, -1.0, 2.0] {
+    ///     world.create_entity().with(Pos(x)).build();
+    /// }
+    ///
+    /// let positions = world.read_storage::<Pos>();
+    /// let (min, max) = (&positions).par_join().map_reduce(
+    ///     |pos| (pos.0, pos.0),
+    ///     || (f32::INFINITY, f32::NEG_INFINITY),
+    ///     |a, b| (a.0.min(b.0), a.1.max(b.1)),
+    /// );
+    /// assert_eq!((min, max), (-1.0, 3.0));
+    /// ```
+    pub fn map_reduce<T, M, ID, R>(self, map: M, identity: ID, reduce: R) -> T
+    where
+        M: Fn(J::Type) -> T + Send + Sync,
+        ID: Fn() -> T + Send + Sync,
+        R: Fn(T, T) -> T + Send + Sync,
+        T: Send,
+    {
+        self.map(map).reduce(identity, reduce)
+    }
+
+    /// Sums the values `f` returns for every item in parallel, e.g. the total
+    /// damage dealt.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Damage(u32);
+    ///
+    /// impl Component for Damage {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Damage>();
+    /// for damage in [5, 7, 11] {
+    ///     world.create_entity().with(Damage(damage)).build();
+    /// }
+    ///
+    /// let damage = world.read_storage::<Damage>();
+    /// assert_eq!((&damage).par_join().sum_by(|d| d.0), 23);
+    /// ```
+    pub fn sum_by<S, F>(self, f: F) -> S
+    where
+        F: Fn(J::Type) -> S + Send + Sync,
+        S: Send + Sum,
+    {
+        self.map(f).sum()
+    }
+
+    /// Returns the item with the minimum key computed by `f`, in parallel.
+    ///
+    /// Like `ParallelIterator::min_by_key`, of equally minimal items the one
+    /// of the entity with the lowest id is returned, so the result doesn't
+    /// depend on how the work is split. Returns `None` if there are no items.
+    pub fn min_by_key<K, F>(self, f: F) -> Option<J::Type>
+    where
+        F: Fn(&J::Type) -> K + Send + Sync,
+        K: Ord + Send,
+    {
+        ParallelIterator::min_by_key(self, f)
+    }
+}
+
 impl<J> ParallelIterator for JoinParIter<J>
 where
     J: ParJoin + Send,
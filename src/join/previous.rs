@@ -0,0 +1,189 @@
+use std::ops::Deref;
+
+use hibitset::BitSet;
+
+use super::Join;
+#[cfg(feature = "parallel")]
+use super::ParJoin;
+use crate::{
+    storage::{MaskedStorage, Storage, UnprotectedStorage},
+    world::{Component, EntitiesRes, Entity, Generation, Index},
+};
+
+/// A copy of the components of a storage, taken with `Storage::snapshot`,
+/// which remembers the entity each component belonged to.
+///
+/// Components of entities deleted since the snapshot are not returned, even
+/// if their index has been reused by a new entity.
+pub struct Snapshot<T: Component> {
+    components: MaskedStorage<T>,
+    generations: Vec<Generation>,
+}
+
+impl<T: Component> Snapshot<T> {
+    pub(crate) fn new(components: MaskedStorage<T>, generations: Vec<Generation>) -> Self {
+        Snapshot {
+            components,
+            generations,
+        }
+    }
+
+    /// Returns the component `e` had when the snapshot was taken.
+    pub fn get(&self, e: Entity) -> Option<&T> {
+        if self.contains(e) {
+            // SAFETY: We checked the mask.
+            Some(unsafe { self.components.open().1.get(e.id()) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if `e` had a component when the snapshot was taken.
+    pub fn contains(&self, e: Entity) -> bool {
+        self.components.mask().contains(e.id())
+            && self.generations.get(e.id() as usize) == Some(&e.gen())
+    }
+
+    fn open(&self) -> Previous<'_, T> {
+        let (mask, storage) = self.components.open();
+        (mask, storage, &self.generations)
+    }
+}
+
+/// Reads the components of a storage together with their values in a
+/// `Snapshot` of it, taken e.g. with `Storage::snapshot` at the end of the
+/// previous run of a system.
+///
+/// Joining a `WithPrevious` yields `(new, old)` for each component of the
+/// current storage, where `old` is `None` if the entity had no component in
+/// the snapshot. An entity created at the index of one deleted since the
+/// snapshot has no previous value. This serves systems computing deltas, like movement deltas
+/// or delta encoding for the network, without looking up the snapshot for
+/// every entity.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     join::{Snapshot, WithPrevious},
+///     prelude::*,
+/// };
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// let a = world.create_entity().with(Pos(1.0)).build();
+///
+/// let previous: Snapshot<Pos> = world.read_storage::<Pos>().snapshot();
+/// world.write_storage::<Pos>().get_mut(a).unwrap().0 = 3.0;
+/// world.create_entity().with(Pos(5.0)).build();
+///
+/// let pos = world.read_storage::<Pos>();
+/// let deltas: Vec<f32> = WithPrevious::new(&pos, &previous)
+///     .join()
+///     .map(|(new, old)| new.0 - old.map_or(new.0, |old| old.0))
+///     .collect();
+/// assert_eq!(deltas, [2.0, 0.0]);
+/// ```
+pub struct WithPrevious<'a, T: Component> {
+    current: Layer<'a, T>,
+    previous: Previous<'a, T>,
+    entities: &'a EntitiesRes,
+}
+
+impl<'a, T: Component> WithPrevious<'a, T> {
+    /// Joins the components of `current` with the ones of `previous`.
+    ///
+    /// Entities are checked for being alive with the entities of `current`.
+    pub fn new<D>(current: &'a Storage<'_, T, D>, previous: &'a Snapshot<T>) -> Self
+    where
+        D: Deref<Target = MaskedStorage<T>>,
+    {
+        WithPrevious {
+            current: current.masked_storage().open(),
+            previous: previous.open(),
+            entities: current.fetched_entities(),
+        }
+    }
+
+    /// Returns the component of `e` and its previous value, if `e` has a
+    /// component.
+    pub fn get(&self, e: Entity) -> Option<(&'a T, Option<&'a T>)> {
+        if self.entities.is_alive(e) && self.current.0.contains(e.id()) {
+            // SAFETY: We checked the mask of the current storage.
+            Some(unsafe { get(self.current, self.previous, e) })
+        } else {
+            None
+        }
+    }
+}
+
+type Layer<'a, T> = (&'a BitSet, &'a <T as Component>::Storage);
+
+/// A `Layer` of a `Snapshot` with the generations of its entities.
+type Previous<'a, T> = (&'a BitSet, &'a <T as Component>::Storage, &'a [Generation]);
+
+/// Returns the components of `e`.
+///
+/// # Safety
+///
+/// The mask of `current` must contain the index of `e`.
+unsafe fn get<'a, T: Component>(
+    current: Layer<'a, T>,
+    (mask, previous, generations): Previous<'a, T>,
+    e: Entity,
+) -> (&'a T, Option<&'a T>) {
+    let id = e.id();
+    let same_entity = generations.get(id as usize) == Some(&e.gen());
+    // SAFETY: The caller checked the mask of `current`, and we check the one
+    // of `previous`.
+    unsafe {
+        (
+            current.1.get(id),
+            (same_entity && mask.contains(id)).then(|| previous.get(id)),
+        )
+    }
+}
+
+// SAFETY: `get` only reads the storages, checking their masks.
+unsafe impl<'a, T: Component> Join for WithPrevious<'a, T> {
+    type Mask = &'a BitSet;
+    type Type = (&'a T, Option<&'a T>);
+    type Value = (Layer<'a, T>, Previous<'a, T>, &'a EntitiesRes);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.current.0, (self.current, self.previous, self.entities))
+    }
+
+    unsafe fn get(&mut (current, previous, entities): &mut Self::Value, id: Index) -> Self::Type {
+        // SAFETY: The mask of `current` contains `id`.
+        unsafe { get(current, previous, entities.entity(id)) }
+    }
+}
+
+// SAFETY: `get` only reads the storages, so it is safe to call concurrently.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, T> ParJoin for WithPrevious<'a, T>
+where
+    T: Component,
+    T::Storage: Sync,
+{
+    type Mask = &'a BitSet;
+    type Type = (&'a T, Option<&'a T>);
+    type Value = (Layer<'a, T>, Previous<'a, T>, &'a EntitiesRes);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.current.0, (self.current, self.previous, self.entities))
+    }
+
+    unsafe fn get(&(current, previous, entities): &Self::Value, id: Index) -> Self::Type {
+        // SAFETY: The mask of `current` contains `id`.
+        unsafe { get(current, previous, entities.entity(id)) }
+    }
+}
@@ -0,0 +1,108 @@
+use std::ops::Range;
+
+#[nougat::gat(Type)]
+use super::LendJoin;
+#[cfg(feature = "parallel")]
+use super::ParJoin;
+use super::{Join, RepeatableLendGet};
+use crate::{bitset::BitSetRange, world::Index};
+
+/// Returns a structure that implements `Join`/`LendJoin`/`ParJoin` if the
+/// contained `J` does and that only yields the indices in a range.
+///
+/// For usage see [`LendJoin::in_index_range()`](LendJoin::in_index_range).
+pub struct InIndexRange<J> {
+    join: J,
+    range: Range<Index>,
+}
+
+impl<J> InIndexRange<J> {
+    /// Restricts `join` to the indices in `range`.
+    pub fn new(join: J, range: Range<Index>) -> Self {
+        InIndexRange { join, range }
+    }
+}
+
+// SAFETY: The returned mask is a subset of the mask of `J`, so it is safe to
+// retrieve the items of `J` whose presence it indicates. Iterating the mask
+// does not repeat indices.
+#[nougat::gat]
+unsafe impl<J> LendJoin for InIndexRange<J>
+where
+    J: LendJoin,
+{
+    type Mask = BitSetRange<<J as LendJoin>::Mask>;
+    type Type<'next> = <J as LendJoin>::Type<'next>;
+    type Value = <J as LendJoin>::Value;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: While we do expose the mask and the values and therefore
+        // would allow swapping them, this method is `unsafe` and relies on the
+        // same invariants.
+        let (mask, value) = unsafe { self.join.open() };
+        (BitSetRange::new(mask, self.range), value)
+    }
+
+    unsafe fn get<'next>(value: &'next mut Self::Value, id: Index) -> Self::Type<'next> {
+        // SAFETY: `id` is in our mask, so it is in the mask of `J`. The other
+        // requirements are passed to the caller.
+        unsafe { <J as LendJoin>::get(value, id) }
+    }
+}
+
+// SAFETY: <InIndexRange as LendJoin>::get does not rely on only being called
+// once with a particular ID as long as `J` does not.
+unsafe impl<J> RepeatableLendGet for InIndexRange<J> where J: RepeatableLendGet {}
+
+// SAFETY: The returned mask is a subset of the mask of `J`, so it is safe to
+// retrieve the items of `J` whose presence it indicates. Iterating the mask
+// does not repeat indices.
+unsafe impl<J> Join for InIndexRange<J>
+where
+    J: Join,
+{
+    type Mask = BitSetRange<<J as Join>::Mask>;
+    type Type = <J as Join>::Type;
+    type Value = <J as Join>::Value;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: While we do expose the mask and the values and therefore
+        // would allow swapping them, this method is `unsafe` and relies on the
+        // same invariants.
+        let (mask, value) = unsafe { self.join.open() };
+        (BitSetRange::new(mask, self.range), value)
+    }
+
+    unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
+        // SAFETY: `id` is in our mask, so it is in the mask of `J`. The other
+        // requirements are passed to the caller.
+        unsafe { <J as Join>::get(value, id) }
+    }
+}
+
+// SAFETY: This is safe as long as `J` implements `ParJoin` safely. The
+// returned mask is a subset of the mask of `J`, and iterating it does not
+// repeat indices.
+#[cfg(feature = "parallel")]
+unsafe impl<J> ParJoin for InIndexRange<J>
+where
+    J: ParJoin,
+{
+    type Mask = BitSetRange<<J as ParJoin>::Mask>;
+    type Type = <J as ParJoin>::Type;
+    type Value = <J as ParJoin>::Value;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: While we do expose the mask and the values and therefore
+        // would allow swapping them, this method is `unsafe` and relies on the
+        // same invariants.
+        let (mask, value) = unsafe { self.join.open() };
+        (BitSetRange::new(mask, self.range), value)
+    }
+
+    unsafe fn get(value: &Self::Value, id: Index) -> Self::Type {
+        // SAFETY: `id` is in our mask, so it is in the mask of `J`. The other
+        // requirements are passed to the caller.
+        unsafe { <J as ParJoin>::get(value, id) }
+    }
+}
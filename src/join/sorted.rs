@@ -0,0 +1,107 @@
+use std::{ops::Deref, vec::Drain};
+
+use hibitset::{BitSetAnd, BitSetLike};
+
+use super::Join;
+use crate::{
+    storage::{MaskedStorage, Storage, UnprotectedStorage},
+    world::{Component, Index},
+};
+
+/// A reusable buffer for `Join::join_sorted_by_key`.
+///
+/// Keep it around, e.g. in the system struct, so sorting the matches every
+/// frame doesn't allocate once the buffer grew large enough.
+pub struct SortBuffer<K> {
+    entries: Vec<(K, Index)>,
+}
+
+impl<K> SortBuffer<K> {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        SortBuffer {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Creates an empty buffer with space for `capacity` matches.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SortBuffer {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of matches the buffer can hold without
+    /// reallocating.
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+}
+
+impl<K> Default for SortBuffer<K> {
+    fn default() -> Self {
+        SortBuffer::new()
+    }
+}
+
+/// Iterator over the items of a join in the order of their keys, see
+/// `Join::join_sorted_by_key`.
+#[must_use]
+pub struct SortedJoinIter<'b, J: Join, K> {
+    entries: Drain<'b, (K, Index)>,
+    values: J::Value,
+}
+
+impl<'b, J: Join, K: Ord> SortedJoinIter<'b, J, K> {
+    pub(crate) fn new<C, D, F>(
+        join: J,
+        keys: &Storage<C, D>,
+        buffer: &'b mut SortBuffer<K>,
+        mut key: F,
+    ) -> Self
+    where
+        C: Component,
+        D: Deref<Target = MaskedStorage<C>>,
+        F: FnMut(&C) -> K,
+    {
+        // SAFETY: We only call `get` with the indices of the mask, each one
+        // once, and don't expose the values.
+        let (mask, values) = unsafe { join.open() };
+        let storage = keys.unprotected_storage();
+        buffer.entries.clear();
+        buffer.entries.extend(
+            BitSetAnd(&mask, keys.mask())
+                .iter()
+                // SAFETY: The index is in the mask of `keys`.
+                .map(|id| (key(unsafe { storage.get(id) }), id)),
+        );
+        // Ties are broken by the index to keep the order deterministic,
+        // unlike a stable sort this doesn't allocate.
+        buffer
+            .entries
+            .sort_unstable_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        SortedJoinIter {
+            entries: buffer.entries.drain(..),
+            values,
+        }
+    }
+}
+
+impl<'b, J: Join, K> Iterator for SortedJoinIter<'b, J, K> {
+    type Item = J::Type;
+
+    fn next(&mut self) -> Option<J::Type> {
+        // SAFETY: The indices are a subset of the mask of `J` and unique, and
+        // each one is drained, so `get` is called at most once per index.
+        self.entries
+            .next()
+            .map(|(_, id)| unsafe { J::get(&mut self.values, id) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<'b, J: Join, K> ExactSizeIterator for SortedJoinIter<'b, J, K> {}
@@ -191,6 +191,7 @@ pub extern crate hibitset;
 #[cfg(feature = "parallel")]
 pub extern crate rayon;
 pub extern crate shred;
+#[cfg(feature = "tracking")]
 pub extern crate shrev;
 #[cfg(feature = "uuid_entity")]
 pub extern crate uuid;
@@ -199,11 +200,16 @@ pub extern crate uuid;
 pub mod saveload;
 
 mod bitset;
+#[cfg(feature = "serde")]
+pub mod bitset_serde;
 pub mod changeset;
 pub mod error;
+pub mod graph;
 pub mod join;
 pub mod prelude;
 pub mod storage;
+#[cfg(feature = "test-support")]
+pub mod test_utils;
 pub mod world;
 
 pub use hibitset::BitSet;
@@ -212,24 +218,31 @@ pub use shred::{
     DispatcherBuilder, Read, ReadExpect, RunNow, RunningTime, SendDispatcher, StaticAccessor,
     System, SystemData, World, Write, WriteExpect,
 };
+#[cfg(feature = "tracking")]
 pub use shrev::ReaderId;
 
 #[cfg(feature = "parallel")]
 pub use shred::AsyncDispatcher;
 
 #[cfg(feature = "specs-derive")]
-pub use specs_derive::{Component, ConvertSaveload};
+pub use specs_derive::{Component, ConvertSaveload, Inspect, SoAComponent, SplitComponent};
 
 #[cfg(feature = "parallel")]
-pub use crate::join::ParJoin;
+pub use crate::join::{BatchJoin, ParJoin};
 pub use crate::{
+    bitset::{BitSetLen, BitSetRange},
     changeset::ChangeSet,
     join::{Join, LendJoin},
     storage::{
-        DefaultVecStorage, DenseVecStorage, FlaggedStorage, HashMapStorage, NullStorage,
-        ReadStorage, Storage, Tracked, VecStorage, WriteStorage,
+        DefaultVecStorage, DenseVecStorage, FlagBoolStorage, FlaggedStorage, HashMapStorage,
+        NullStorage, ReadStorage, Storage, Tracked, VecStorage, WriteStorage,
+    },
+    world::{
+        Builder, Component, Entities, Entity, EntityBuilder, ExclusiveDispatcher,
+        ExclusiveDispatcherBuilder, ExclusiveSystem, LazyUpdate, SetupDispatcher,
+        SetupDispatcherBuilder, WorldExt, WorldPlugin,
     },
-    world::{Builder, Component, Entities, Entity, EntityBuilder, LazyUpdate, WorldExt},
 };
 
+#[cfg(feature = "tracking")]
 pub use crate::storage::DerefFlaggedStorage;
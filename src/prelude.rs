@@ -6,12 +6,13 @@ pub use crate::join::Join;
 #[nougat::gat(Type)]
 pub use crate::join::LendJoin;
 #[cfg(feature = "parallel")]
-pub use crate::join::ParJoin;
+pub use crate::join::{BatchJoin, ParJoin};
 pub use hibitset::BitSet;
 pub use shred::{
     Accessor, Dispatcher, DispatcherBuilder, Read, ReadExpect, Resource, ResourceId, RunNow,
     StaticAccessor, System, SystemData, World, Write, WriteExpect,
 };
+#[cfg(feature = "tracking")]
 pub use shrev::ReaderId;
 
 #[cfg(feature = "parallel")]
@@ -20,10 +21,15 @@ pub use rayon::iter::ParallelIterator;
 pub use shred::AsyncDispatcher;
 
 pub use crate::{
+    bitset::BitSetLen,
     changeset::ChangeSet,
     storage::{
-        ComponentEvent, DefaultVecStorage, DenseVecStorage, FlaggedStorage, HashMapStorage,
-        NullStorage, ReadStorage, Storage, Tracked, VecStorage, WriteStorage,
+        ComponentEvent, DefaultVecStorage, DenseVecStorage, FlagBoolStorage, FlaggedStorage,
+        HashMapStorage, NullStorage, ReadStorage, Storage, Tracked, VecStorage, WriteStorage,
+    },
+    world::{
+        Builder, Component, Entities, Entity, EntityBuilder, ExclusiveDispatcher,
+        ExclusiveDispatcherBuilder, ExclusiveSystem, GroupDispatcherBuilder, LazyUpdate,
+        SetupDispatcher, SetupDispatcherBuilder, TickDispatcherBuilder, WorldExt,
     },
-    world::{Builder, Component, Entities, Entity, EntityBuilder, LazyUpdate, WorldExt},
 };
@@ -1,22 +1,203 @@
 use std::{
+    any::type_name,
     fmt::{self, Display, Formatter},
     marker::PhantomData,
 };
 
 use serde::de::{
-    self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, SeqAccess, Visitor,
+    self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, IgnoredAny, MapAccess,
+    SeqAccess, Visitor,
 };
 
+use ahash::AHashMap as HashMap;
+
 use super::ConvertSaveload;
 use crate::{
     saveload::{
         marker::{Marker, MarkerAllocator},
-        EntityData,
+        ComponentRegistry, EntityData, LoadHooks, UnknownComponentPolicy,
     },
-    storage::{GenericWriteStorage, WriteStorage},
-    world::{Component, EntitiesRes, Entity},
+    storage::{GenericReadStorage, GenericWriteStorage, WriteStorage},
+    world::{Component, EntitiesRes, Entity, LazyUpdate},
 };
 
+/// What to do when a deserialized marker already belongs to a live entity,
+/// for example when the same save file is loaded twice or a partial network
+/// snapshot is applied on top of the current state.
+///
+/// Markers that don't belong to a live entity always get a new entity.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MarkerConflictPolicy {
+    /// Load into the existing entity, replacing its components of the
+    /// deserialized group and removing those absent from the data.
+    #[default]
+    ReuseAndOverwrite,
+    /// Load into the existing entity, only inserting the components it
+    /// doesn't have yet. Components absent from the data are kept.
+    ReuseAndMerge,
+    /// Load into a new entity with a newly allocated marker. References to
+    /// the marker from entities loaded after it resolve to the new entity.
+    SpawnNew,
+    /// Fail the deserialization.
+    Error,
+}
+
+/// The state of a snapshot loaded in batches with
+/// `DeserializeComponents::deserialize_batch`, e.g. as the packets of a
+/// network snapshot arrive over several frames.
+///
+/// It keeps the markers of the batches consistent, so an entity referencing
+/// a marker of a later batch gets the entity that batch is loaded into, and
+/// records the entities created by the load so an incomplete snapshot can be
+/// rolled back with `abort`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     error::Error,
+///     prelude::*,
+///     saveload::{
+///         BatchLoad, DeserializeComponents, EntityData, SimpleMarker, SimpleMarkerAllocator,
+///     },
+/// };
+///
+/// #[derive(Clone, serde::Serialize, serde::Deserialize)]
+/// struct Pos(i32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Net;
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// world.register::<SimpleMarker<Net>>();
+/// world.insert(SimpleMarkerAllocator::<Net>::new());
+///
+/// let packets = [
+///     r#"[{"marker": [0], "components": [1]}]"#,
+///     r#"[{"marker": [1], "components": [2]}, {"marker": [2], "components": [3]}]"#,
+/// ];
+/// let mut load = BatchLoad::new();
+/// for packet in packets {
+///     let batch: Vec<EntityData<SimpleMarker<Net>, (Option<Pos>,)>> =
+///         serde_json::from_str(packet).unwrap();
+///     world.exec(
+///         |(entities, pos, mut markers, mut allocator): (
+///             Entities,
+///             WriteStorage<Pos>,
+///             WriteStorage<SimpleMarker<Net>>,
+///             Write<SimpleMarkerAllocator<Net>>,
+///         )| {
+///             DeserializeComponents::<Error, _>::deserialize_batch(
+///                 &mut (pos,),
+///                 &entities,
+///                 &mut markers,
+///                 &mut allocator,
+///                 &mut load,
+///                 batch,
+///             )
+///             .unwrap();
+///         },
+///     );
+/// }
+/// assert_eq!(world.read_storage::<Pos>().count(), 3);
+///
+/// // The connection dropped, discard the snapshot.
+/// load.abort(&world.entities());
+/// world.maintain();
+/// assert_eq!(world.read_storage::<Pos>().count(), 0);
+/// ```
+pub struct BatchLoad<M> {
+    created: Vec<Entity>,
+    /// The new entities of the markers of the data, if loading into new
+    /// entities only, see `BatchLoad::fresh`.
+    fresh: Option<HashMap<M, Entity>>,
+}
+
+impl<M: Marker> BatchLoad<M> {
+    /// Starts loading a snapshot according to markers, like
+    /// `DeserializeComponents::deserialize`.
+    ///
+    /// Entities whose markers already belong to a live entity are loaded
+    /// into that entity. `abort` can't restore their previous components.
+    pub fn new() -> Self {
+        BatchLoad {
+            created: Vec::new(),
+            fresh: None,
+        }
+    }
+
+    /// Starts loading a snapshot into new entities with newly allocated
+    /// markers, like `DeserializeComponents::deserialize_fresh`, so `abort`
+    /// rolls back the whole load.
+    pub fn fresh() -> Self {
+        BatchLoad {
+            created: Vec::new(),
+            fresh: Some(HashMap::new()),
+        }
+    }
+
+    /// Returns the entities created by the load so far.
+    pub fn created(&self) -> &[Entity] {
+        &self.created
+    }
+
+    /// Rolls back the load by deleting the entities it created, e.g. when a
+    /// snapshot is only partially received.
+    ///
+    /// As with `EntitiesRes::delete`, their components are removed during
+    /// the next `World::maintain`.
+    pub fn abort(self, entities: &EntitiesRes) {
+        for entity in self.created {
+            // Entities deleted since are skipped.
+            let _ = entities.delete(entity);
+        }
+    }
+
+    /// Returns the entity of `marker`, creating it if the load didn't
+    /// retrieve it before and no live entity has it.
+    fn retrieve(
+        &mut self,
+        marker: M,
+        entities: &EntitiesRes,
+        markers: &mut WriteStorage<M>,
+        allocator: &mut M::Allocator,
+    ) -> Entity {
+        if let Some(fresh) = &mut self.fresh {
+            if let Some(&entity) = fresh.get(&marker) {
+                return entity;
+            }
+            let entity = entities.create();
+            let new_marker = allocator.allocate(entity, None);
+            // A freshly created entity can't be dead.
+            markers.insert(entity, new_marker).unwrap();
+            fresh.insert(marker, entity);
+            self.created.push(entity);
+
+            return entity;
+        }
+
+        let existing = allocator
+            .retrieve_entity_internal(marker.id())
+            .filter(|&entity| markers.contains(entity));
+        let entity = allocator.retrieve_entity(marker, markers, entities);
+        if existing.is_none() {
+            self.created.push(entity);
+        }
+
+        entity
+    }
+}
+
+impl<M: Marker> Default for BatchLoad<M> {
+    fn default() -> Self {
+        BatchLoad::new()
+    }
+}
+
 /// A trait which allows to deserialize entities and their components.
 pub trait DeserializeComponents<E, M>
 where
@@ -28,6 +209,10 @@ where
     type Data: DeserializeOwned;
 
     /// Loads `Component`s to entity from `Data` deserializable representation
+    ///
+    /// Components are inserted with `GenericWriteStorage::insert_loaded`, so
+    /// tracked storages emit `ComponentEvent::Loaded` for them instead of
+    /// `ComponentEvent::Inserted`.
     fn deserialize_entity<F>(
         &mut self,
         entity: Entity,
@@ -37,6 +222,92 @@ where
     where
         F: FnMut(M) -> Option<Entity>;
 
+    /// Loads `Component`s to entity from `Data` like `deserialize_entity`,
+    /// passing each converted component to its hook in `hooks` before
+    /// inserting it.
+    ///
+    /// The default implementation ignores the hooks, it is overridden for all
+    /// tuples of storages.
+    fn deserialize_entity_with_hooks<F>(
+        &mut self,
+        entity: Entity,
+        components: Self::Data,
+        ids: F,
+        hooks: &LoadHooks,
+        lazy: &LazyUpdate,
+    ) -> Result<(), E>
+    where
+        F: FnMut(M) -> Option<Entity>,
+    {
+        let _ = (hooks, lazy);
+
+        self.deserialize_entity(entity, components, ids)
+    }
+
+    /// Loads `Component`s to entity from `Data` like `deserialize_entity`,
+    /// but skips the components the entity already has and keeps the ones
+    /// missing from `Data`. Used for `MarkerConflictPolicy::ReuseAndMerge`.
+    ///
+    /// The default implementation overwrites like `deserialize_entity`, it is
+    /// overridden for all tuples of storages.
+    fn deserialize_entity_merged<F>(
+        &mut self,
+        entity: Entity,
+        components: Self::Data,
+        ids: F,
+    ) -> Result<(), E>
+    where
+        F: FnMut(M) -> Option<Entity>,
+    {
+        self.deserialize_entity(entity, components, ids)
+    }
+
+    /// Reads the components of a single entity from `map`, keyed by the
+    /// names registered in `registry`. Components missing from the map are
+    /// `None`, unknown names are handled according to the registry's
+    /// `UnknownComponentPolicy`.
+    ///
+    /// The default implementation fails, it is implemented for all tuples of
+    /// storages.
+    fn deserialize_named_data<'de, A>(
+        registry: &ComponentRegistry,
+        map: A,
+    ) -> Result<Self::Data, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let _ = (registry, map);
+
+        Err(de::Error::custom(format_args!(
+            "`{}` does not support named components",
+            type_name::<Self>()
+        )))
+    }
+
+    /// Loads a batch of already deserialized entities as part of `load`,
+    /// e.g. the entities of one network packet, so a snapshot can be applied
+    /// over several frames as it arrives.
+    ///
+    /// If this fails, the entities of the batch before the failing one stay
+    /// loaded; use `BatchLoad::abort` to roll back the whole load.
+    fn deserialize_batch<'a>(
+        &mut self,
+        entities: &EntitiesRes,
+        markers: &mut WriteStorage<'a, M>,
+        allocator: &mut M::Allocator,
+        load: &mut BatchLoad<M>,
+        batch: Vec<EntityData<M, Self::Data>>,
+    ) -> Result<(), E> {
+        for data in batch {
+            let entity = load.retrieve(data.marker, entities, markers, allocator);
+            self.deserialize_entity(entity, data.components, |marker| {
+                Some(load.retrieve(marker, entities, markers, allocator))
+            })?;
+        }
+
+        Ok(())
+    }
+
     /// Deserialize entities according to markers.
     fn deserialize<'a: 'b, 'b, 'de, D>(
         &'b mut self,
@@ -53,6 +324,162 @@ where
             entities,
             markers,
             storages: self,
+            registry: None,
+            hooks: None,
+            policy: MarkerConflictPolicy::default(),
+            respawned: HashMap::new(),
+            fresh: None,
+            pd: PhantomData,
+        })
+    }
+
+    /// Deserialize entities into new entities with newly allocated markers,
+    /// like data written by `SerializeComponents::serialize_selection`.
+    ///
+    /// The markers of the data are only used to resolve the references
+    /// between the loaded entities, so loading the same data again creates
+    /// new copies of the entities.
+    fn deserialize_fresh<'a: 'b, 'b, 'de, D>(
+        &'b mut self,
+        entities: &'b EntitiesRes,
+        markers: &'b mut WriteStorage<'a, M>,
+        allocator: &'b mut M::Allocator,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VisitEntities::<E, M, Self> {
+            allocator,
+            entities,
+            markers,
+            storages: self,
+            registry: None,
+            hooks: None,
+            policy: MarkerConflictPolicy::default(),
+            respawned: HashMap::new(),
+            fresh: Some(HashMap::new()),
+            pd: PhantomData,
+        })
+    }
+
+    /// Deserialize entities according to markers, like `deserialize`,
+    /// handling markers that already belong to a live entity according to
+    /// `policy`.
+    ///
+    /// `deserialize` uses `MarkerConflictPolicy::ReuseAndOverwrite`.
+    fn deserialize_with_policy<'a: 'b, 'b, 'de, D>(
+        &'b mut self,
+        entities: &'b EntitiesRes,
+        markers: &'b mut WriteStorage<'a, M>,
+        allocator: &'b mut M::Allocator,
+        policy: MarkerConflictPolicy,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VisitEntities::<E, M, Self> {
+            allocator,
+            entities,
+            markers,
+            storages: self,
+            registry: None,
+            hooks: None,
+            policy,
+            respawned: HashMap::new(),
+            fresh: None,
+            pd: PhantomData,
+        })
+    }
+
+    /// Deserialize entities according to markers, like `deserialize`, from
+    /// data written by `SerializeComponents::serialize_named`.
+    ///
+    /// Fails if one of the components has no registered name.
+    fn deserialize_named<'a: 'b, 'b, 'de, D>(
+        &'b mut self,
+        entities: &'b EntitiesRes,
+        markers: &'b mut WriteStorage<'a, M>,
+        allocator: &'b mut M::Allocator,
+        registry: &'b ComponentRegistry,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VisitEntities::<E, M, Self> {
+            allocator,
+            entities,
+            markers,
+            storages: self,
+            registry: Some(registry),
+            hooks: None,
+            policy: MarkerConflictPolicy::default(),
+            respawned: HashMap::new(),
+            fresh: None,
+            pd: PhantomData,
+        })
+    }
+
+    /// Deserialize entities according to markers, like `deserialize`, and
+    /// run the hooks registered in `hooks` for the loaded components.
+    ///
+    /// See `LoadHooks` for details.
+    fn deserialize_with_hooks<'a: 'b, 'b, 'de, D>(
+        &'b mut self,
+        entities: &'b EntitiesRes,
+        markers: &'b mut WriteStorage<'a, M>,
+        allocator: &'b mut M::Allocator,
+        hooks: &'b LoadHooks,
+        lazy: &'b LazyUpdate,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VisitEntities::<E, M, Self> {
+            allocator,
+            entities,
+            markers,
+            storages: self,
+            registry: None,
+            hooks: Some((hooks, lazy)),
+            policy: MarkerConflictPolicy::default(),
+            respawned: HashMap::new(),
+            fresh: None,
+            pd: PhantomData,
+        })
+    }
+
+    /// Deserialize entities according to markers, like `deserialize_named`,
+    /// and run the hooks registered in `hooks` for the loaded components.
+    ///
+    /// See `LoadHooks` for details.
+    #[allow(clippy::too_many_arguments)]
+    fn deserialize_named_with_hooks<'a: 'b, 'b, 'de, D>(
+        &'b mut self,
+        entities: &'b EntitiesRes,
+        markers: &'b mut WriteStorage<'a, M>,
+        allocator: &'b mut M::Allocator,
+        registry: &'b ComponentRegistry,
+        hooks: &'b LoadHooks,
+        lazy: &'b LazyUpdate,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(VisitEntities::<E, M, Self> {
+            allocator,
+            entities,
+            markers,
+            storages: self,
+            registry: Some(registry),
+            hooks: Some((hooks, lazy)),
+            policy: MarkerConflictPolicy::default(),
+            respawned: HashMap::new(),
+            fresh: None,
             pd: PhantomData,
         })
     }
@@ -65,6 +492,13 @@ struct DeserializeEntity<'a: 'b, 'b, E, M: Marker, S: 'b> {
     entities: &'b EntitiesRes,
     storages: &'b mut S,
     markers: &'b mut WriteStorage<'a, M>,
+    registry: Option<&'b ComponentRegistry>,
+    hooks: Option<(&'b LoadHooks, &'b LazyUpdate)>,
+    policy: MarkerConflictPolicy,
+    respawned: &'b mut HashMap<Entity, Entity>,
+    /// The new entities of the markers of the data, if loading into new
+    /// entities only, see `DeserializeComponents::deserialize_fresh`.
+    fresh: Option<&'b mut HashMap<M, Entity>>,
     pd: PhantomData<E>,
 }
 
@@ -85,15 +519,75 @@ where
             storages,
             markers,
             allocator,
+            registry,
+            hooks,
+            policy,
+            respawned,
+            fresh,
             ..
         } = self;
-        let data = EntityData::<M, S::Data>::deserialize(deserializer)?;
-        let entity = allocator.retrieve_entity(data.marker, markers, entities);
-        let ids = |marker: M| Some(allocator.retrieve_entity(marker, markers, entities));
+        let data = match registry {
+            Some(registry) => deserializer.deserialize_struct(
+                "EntityData",
+                &["marker", "components"],
+                VisitNamedEntity::<E, M, S> {
+                    registry,
+                    pd: PhantomData,
+                },
+            )?,
+            None => EntityData::<M, S::Data>::deserialize(deserializer)?,
+        };
+        if let Some(fresh) = fresh {
+            let mut spawn = |marker: M| {
+                *fresh.entry(marker).or_insert_with(|| {
+                    let entity = entities.create();
+                    let marker = allocator.allocate(entity, None);
+                    // A freshly created entity can't be dead.
+                    markers.insert(entity, marker).unwrap();
+                    entity
+                })
+            };
+            let entity = spawn(data.marker);
 
-        storages
-            .deserialize_entity(entity, data.components, ids)
-            .map_err(de::Error::custom)
+            return storages
+                .deserialize_entity(entity, data.components, |marker| Some(spawn(marker)))
+                .map_err(de::Error::custom);
+        }
+
+        let existing = allocator
+            .retrieve_entity_internal(data.marker.id())
+            .filter(|&entity| markers.contains(entity));
+        let entity = match (existing, policy) {
+            (Some(existing), MarkerConflictPolicy::SpawnNew) => {
+                let entity = entities.create();
+                let marker = allocator.allocate(entity, None);
+                // A freshly created entity can't be dead.
+                markers.insert(entity, marker).unwrap();
+                respawned.insert(existing, entity);
+                entity
+            }
+            (Some(_), MarkerConflictPolicy::Error) => {
+                return Err(de::Error::custom(format_args!(
+                    "marker {:?} already belongs to a live entity",
+                    data.marker
+                )));
+            }
+            _ => allocator.retrieve_entity(data.marker, markers, entities),
+        };
+        let merge = existing.is_some() && policy == MarkerConflictPolicy::ReuseAndMerge;
+        let ids = |marker: M| {
+            let entity = allocator.retrieve_entity(marker, markers, entities);
+            Some(respawned.get(&entity).copied().unwrap_or(entity))
+        };
+
+        match hooks {
+            Some((hooks, lazy)) => {
+                storages.deserialize_entity_with_hooks(entity, data.components, ids, hooks, lazy)
+            }
+            None if merge => storages.deserialize_entity_merged(entity, data.components, ids),
+            None => storages.deserialize_entity(entity, data.components, ids),
+        }
+        .map_err(de::Error::custom)
     }
 }
 
@@ -104,6 +598,11 @@ struct VisitEntities<'a: 'b, 'b, E, M: Marker, S: 'b> {
     entities: &'b EntitiesRes,
     markers: &'b mut WriteStorage<'a, M>,
     storages: &'b mut S,
+    registry: Option<&'b ComponentRegistry>,
+    hooks: Option<(&'b LoadHooks, &'b LazyUpdate)>,
+    policy: MarkerConflictPolicy,
+    respawned: HashMap<Entity, Entity>,
+    fresh: Option<HashMap<M, Entity>>,
     pd: PhantomData<E>,
 }
 
@@ -119,7 +618,7 @@ where
         write!(formatter, "Sequence of serialized entities")
     }
 
-    fn visit_seq<SEQ>(self, mut seq: SEQ) -> Result<(), SEQ::Error>
+    fn visit_seq<SEQ>(mut self, mut seq: SEQ) -> Result<(), SEQ::Error>
     where
         SEQ: SeqAccess<'de>,
     {
@@ -129,6 +628,11 @@ where
                 storages: self.storages,
                 markers: self.markers,
                 allocator: self.allocator,
+                registry: self.registry,
+                hooks: self.hooks,
+                policy: self.policy,
+                respawned: &mut self.respawned,
+                fresh: self.fresh.as_mut(),
                 pd: self.pd,
             })?;
 
@@ -139,6 +643,131 @@ where
     }
 }
 
+/// Field names of `EntityData`.
+#[derive(serde::Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum EntityField {
+    Marker,
+    Components,
+    #[serde(other)]
+    Other,
+}
+
+/// `serde::de::Visitor` for `EntityData` whose components are a map keyed
+/// by component names.
+struct VisitNamedEntity<'r, E, M, S> {
+    registry: &'r ComponentRegistry,
+    pd: PhantomData<(E, M, S)>,
+}
+
+impl<'de, 'r, E, M, S> Visitor<'de> for VisitNamedEntity<'r, E, M, S>
+where
+    E: Display,
+    M: Marker,
+    S: DeserializeComponents<E, M>,
+{
+    type Value = EntityData<M, S::Data>;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "Serialized entity with named components")
+    }
+
+    fn visit_seq<SEQ>(self, mut seq: SEQ) -> Result<Self::Value, SEQ::Error>
+    where
+        SEQ: SeqAccess<'de>,
+    {
+        let marker = seq
+            .next_element()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let components = seq
+            .next_element_seed(VisitNamedComponents::<E, M, S> {
+                registry: self.registry,
+                pd: PhantomData,
+            })?
+            .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+
+        Ok(EntityData { marker, components })
+    }
+
+    fn visit_map<MAP>(self, mut map: MAP) -> Result<Self::Value, MAP::Error>
+    where
+        MAP: MapAccess<'de>,
+    {
+        let mut marker = None;
+        let mut components = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                EntityField::Marker => {
+                    if marker.is_some() {
+                        return Err(de::Error::duplicate_field("marker"));
+                    }
+                    marker = Some(map.next_value()?);
+                }
+                EntityField::Components => {
+                    if components.is_some() {
+                        return Err(de::Error::duplicate_field("components"));
+                    }
+                    components = Some(map.next_value_seed(VisitNamedComponents::<E, M, S> {
+                        registry: self.registry,
+                        pd: PhantomData,
+                    })?);
+                }
+                EntityField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(EntityData {
+            marker: marker.ok_or_else(|| de::Error::missing_field("marker"))?,
+            components: components.ok_or_else(|| de::Error::missing_field("components"))?,
+        })
+    }
+}
+
+/// `serde::de::DeserializeSeed` for a map of components keyed by component
+/// names.
+struct VisitNamedComponents<'r, E, M, S> {
+    registry: &'r ComponentRegistry,
+    pd: PhantomData<(E, M, S)>,
+}
+
+impl<'de, 'r, E, M, S> DeserializeSeed<'de> for VisitNamedComponents<'r, E, M, S>
+where
+    E: Display,
+    M: Marker,
+    S: DeserializeComponents<E, M>,
+{
+    type Value = S::Data;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<S::Data, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'r, E, M, S> Visitor<'de> for VisitNamedComponents<'r, E, M, S>
+where
+    E: Display,
+    M: Marker,
+    S: DeserializeComponents<E, M>,
+{
+    type Value = S::Data;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "Map of components keyed by their names")
+    }
+
+    fn visit_map<MAP>(self, map: MAP) -> Result<S::Data, MAP::Error>
+    where
+        MAP: MapAccess<'de>,
+    {
+        S::deserialize_named_data(self.registry, map)
+    }
+}
+
 macro_rules! deserialize_components {
     ($($comp:ident => $sto:ident,)*) => {
         impl<'b, E, M, $($sto,)*> DeserializeComponents<E, M> for ($($sto,)*)
@@ -147,15 +776,15 @@ macro_rules! deserialize_components {
             M: Marker,
             $(
                 $sto: GenericWriteStorage,
-                <$sto as GenericWriteStorage>::Component: ConvertSaveload<M> + Component,
+                <$sto as GenericReadStorage>::Component: ConvertSaveload<M> + Component,
                 E: From<<
-                    <$sto as GenericWriteStorage>::Component as ConvertSaveload<M>
+                    <$sto as GenericReadStorage>::Component as ConvertSaveload<M>
                 >::Error>,
             )*
         {
             type Data = ($(
                 Option<
-                    <<$sto as GenericWriteStorage>::Component as ConvertSaveload<M>>::Data
+                    <<$sto as GenericReadStorage>::Component as ConvertSaveload<M>>::Data
                 >,)*
             );
 
@@ -175,13 +804,116 @@ macro_rules! deserialize_components {
                 let ($($comp,)*) = components;
                 $(
                     if let Some(component) = $comp {
-                        $sto.insert(entity, ConvertSaveload::<M>::convert_from(component, &mut ids)?);
+                        $sto.insert_loaded(
+                            entity,
+                            ConvertSaveload::<M>::convert_from(component, &mut ids)?,
+                        );
+                    } else {
+                        $sto.remove(entity);
+                    }
+                )*
+                Ok(())
+            }
+
+            #[allow(unused)]
+            fn deserialize_entity_with_hooks<F>(
+                &mut self,
+                entity: Entity,
+                components: Self::Data,
+                mut ids: F,
+                hooks: &LoadHooks,
+                lazy: &LazyUpdate,
+            ) -> Result<(), E>
+            where
+                F: FnMut(M) -> Option<Entity>
+            {
+                #[allow(bad_style)]
+                let ($(ref mut $sto,)*) = *self;
+                #[allow(bad_style)]
+                let ($($comp,)*) = components;
+                $(
+                    if let Some(component) = $comp {
+                        let mut component: <$sto as GenericReadStorage>::Component =
+                            ConvertSaveload::<M>::convert_from(component, &mut ids)?;
+                        hooks.run(&mut component, entity, lazy);
+                        $sto.insert_loaded(entity, component);
                     } else {
                         $sto.remove(entity);
                     }
                 )*
                 Ok(())
             }
+
+            #[allow(unused)]
+            fn deserialize_entity_merged<F>(
+                &mut self,
+                entity: Entity,
+                components: Self::Data,
+                mut ids: F,
+            ) -> Result<(), E>
+            where
+                F: FnMut(M) -> Option<Entity>
+            {
+                #[allow(bad_style)]
+                let ($(ref mut $sto,)*) = *self;
+                #[allow(bad_style)]
+                let ($($comp,)*) = components;
+                $(
+                    if let Some(component) = $comp {
+                        if !$sto.contains(entity) {
+                            $sto.insert_loaded(
+                                entity,
+                                ConvertSaveload::<M>::convert_from(component, &mut ids)?,
+                            );
+                        }
+                    }
+                )*
+                Ok(())
+            }
+
+            #[allow(unused)]
+            fn deserialize_named_data<'de, A>(
+                registry: &ComponentRegistry,
+                mut map: A,
+            ) -> Result<Self::Data, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let names: &[&'static str] = &[$(
+                    registry
+                        .expect_name::<<$sto as GenericReadStorage>::Component>()
+                        .map_err(<A::Error as de::Error>::custom)?,
+                )*];
+                $(
+                    #[allow(bad_style)]
+                    let mut $comp = None;
+                )*
+                'entries: while let Some(key) = map.next_key::<String>()? {
+                    let mut index = 0;
+                    $(
+                        if key == names[index] {
+                            if $comp.is_some() {
+                                return Err(de::Error::duplicate_field(names[index]));
+                            }
+                            $comp = Some(map.next_value()?);
+                            continue 'entries;
+                        }
+                        index += 1;
+                    )*
+                    match registry.unknown_policy() {
+                        UnknownComponentPolicy::Ignore => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                        UnknownComponentPolicy::Error => {
+                            return Err(de::Error::custom(format_args!(
+                                "unknown component `{}`",
+                                key
+                            )));
+                        }
+                    }
+                }
+                Ok(($($comp,)*))
+            }
         }
 
         deserialize_components!(@pop $($comp => $sto,)*);
@@ -0,0 +1,531 @@
+//! Provides the `StateHashRegistry` for hashing the state of a `World`.
+
+use std::{
+    any::TypeId,
+    fmt::{Display, Formatter, Result as FmtResult},
+    hash::{Hash, Hasher},
+};
+
+use serde::ser::{self, Serialize};
+
+use crate::{
+    error::{BoxedErr, Error},
+    join::Join,
+    storage::ReadStorage,
+    world::{Component, Entity, WorldExt},
+};
+use shred::World;
+
+use super::Marker;
+
+/// The components and entities included in `WorldExt::hash_state`.
+///
+/// The digest of a `World` is computed by visiting its entities in the order
+/// of their ids, hashing the id and generation of each entity followed by
+/// its registered components in the order in which they were registered.
+/// Comparing the digests of two simulations, e.g. the peers of a lockstep
+/// multiplayer game, detects when they desync.
+///
+/// Components can be hashed either with their `Hash` implementation, or by
+/// hashing their `Serialize` output, which allows including components
+/// containing floats. Using `StateHashRegistry::only_marked`, only entities
+/// with a `Marker` are visited, and the marker replaces the entity in the
+/// digest, so it doesn't depend on how entities are allocated.
+///
+/// To compare the digests of different processes, they have to use the same
+/// `Hasher`, and `H::default()` must not be randomly seeded. `DefaultHasher`
+/// is deterministic, but its algorithm may change between Rust versions.
+///
+/// ## Examples
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+///
+/// use serde::Serialize;
+/// use specs::{prelude::*, saveload::StateHashRegistry};
+///
+/// #[derive(Hash)]
+/// struct Health(u32);
+///
+/// impl Component for Health {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Pos(f32, f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Health>();
+/// world.register::<Pos>();
+/// let e = world.create_entity().with(Health(3)).with(Pos(0.0, 1.0)).build();
+///
+/// let registry = StateHashRegistry::new()
+///     .with_hash::<Health>()
+///     .with_serialize::<Pos>();
+/// let before = world.hash_state::<DefaultHasher>(&registry).unwrap();
+///
+/// world.write_storage::<Pos>().get_mut(e).unwrap().0 = 2.0;
+/// let after = world.hash_state::<DefaultHasher>(&registry).unwrap();
+/// assert_ne!(before, after);
+/// ```
+#[derive(Default)]
+pub struct StateHashRegistry {
+    components: Vec<(TypeId, HashComponents)>,
+    marker: Option<HashEntities>,
+}
+
+/// Fetches the storage of a component.
+type HashComponents = for<'a> fn(&'a World) -> Box<dyn HashStorage + 'a>;
+
+/// Visits the entities to hash, writing their identity to the hasher.
+type HashEntities = fn(&World, &mut dyn Hasher, &mut HashEntity) -> Result<(), Error>;
+
+/// Hashes the components of an entity.
+type HashEntity<'a> = dyn FnMut(Entity, &mut dyn Hasher) -> Result<(), Error> + 'a;
+
+impl StateHashRegistry {
+    /// Creates an empty registry which visits all entities.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Includes a component, hashed with its `Hash` implementation.
+    ///
+    /// Registering a component again moves it to the end.
+    pub fn register_hash<T>(&mut self)
+    where
+        T: Component + Hash,
+    {
+        self.register::<T>(|world| Box::new(ByHash(world.read_storage::<T>())));
+    }
+
+    /// Includes a component, hashed with its `Hash` implementation.
+    ///
+    /// Same as `StateHashRegistry::register_hash`, but returns `self` to
+    /// enable method chaining.
+    pub fn with_hash<T>(mut self) -> Self
+    where
+        T: Component + Hash,
+    {
+        self.register_hash::<T>();
+
+        self
+    }
+
+    /// Includes a component, hashed by feeding its `Serialize` output into
+    /// the hasher.
+    ///
+    /// Floats are hashed by their bits. Registering a component again moves
+    /// it to the end.
+    pub fn register_serialize<T>(&mut self)
+    where
+        T: Component + Serialize,
+    {
+        self.register::<T>(|world| Box::new(BySerialize(world.read_storage::<T>())));
+    }
+
+    /// Includes a component, hashed by its `Serialize` output.
+    ///
+    /// Same as `StateHashRegistry::register_serialize`, but returns `self` to
+    /// enable method chaining.
+    pub fn with_serialize<T>(mut self) -> Self
+    where
+        T: Component + Serialize,
+    {
+        self.register_serialize::<T>();
+
+        self
+    }
+
+    fn register<T: Component>(&mut self, fetch: HashComponents) {
+        self.exclude::<T>();
+        self.components.push((TypeId::of::<T>(), fetch));
+    }
+
+    /// Removes a component from the digest.
+    ///
+    /// Returns `true` if it was registered.
+    pub fn exclude<T: Component>(&mut self) -> bool {
+        let len = self.components.len();
+        self.components.retain(|(ty, _)| *ty != TypeId::of::<T>());
+
+        self.components.len() != len
+    }
+
+    /// Returns whether a component is included in the digest.
+    pub fn contains<T: Component>(&self) -> bool {
+        self.components
+            .iter()
+            .any(|(ty, _)| *ty == TypeId::of::<T>())
+    }
+
+    /// Only visits entities marked with `M`, identifying them by their
+    /// marker instead of their id and generation.
+    pub fn only_marked<M: Marker>(mut self) -> Self {
+        self.marker = Some(hash_marked::<M>);
+
+        self
+    }
+
+    /// Computes the digest of `world`, see `WorldExt::hash_state`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if one of the registered components, or the marker, is not
+    /// registered in the `World`.
+    pub fn hash<H>(&self, world: &World) -> Result<u64, Error>
+    where
+        H: Hasher + Default,
+    {
+        let storages: Vec<_> = self
+            .components
+            .iter()
+            .map(|(_, fetch)| fetch(world))
+            .collect();
+        let mut hash_components = |entity: Entity, state: &mut dyn Hasher| {
+            storages
+                .iter()
+                .try_for_each(|storage| storage.hash_component(entity, state))
+        };
+
+        let mut state = H::default();
+        match self.marker {
+            Some(hash_entities) => hash_entities(world, &mut state, &mut hash_components)?,
+            None => {
+                for entity in world.entities().join() {
+                    entity.hash(&mut state);
+                    hash_components(entity, &mut state)?;
+                }
+            }
+        }
+
+        Ok(state.finish())
+    }
+}
+
+fn hash_marked<M: Marker>(
+    world: &World,
+    state: &mut dyn Hasher,
+    hash_components: &mut HashEntity,
+) -> Result<(), Error> {
+    let entities = world.entities();
+    let markers = world.read_storage::<M>();
+    for (entity, marker) in (&entities, &markers).join() {
+        marker.hash(&mut &mut *state);
+        hash_components(entity, state)?;
+    }
+
+    Ok(())
+}
+
+/// A fetched storage of a component included in the digest.
+trait HashStorage {
+    /// Hashes whether the entity has the component and its value.
+    fn hash_component(&self, entity: Entity, state: &mut dyn Hasher) -> Result<(), Error>;
+}
+
+struct ByHash<'a, T: Component>(ReadStorage<'a, T>);
+
+impl<'a, T> HashStorage for ByHash<'a, T>
+where
+    T: Component + Hash,
+{
+    fn hash_component(&self, entity: Entity, mut state: &mut dyn Hasher) -> Result<(), Error> {
+        self.0.get(entity).hash(&mut state);
+
+        Ok(())
+    }
+}
+
+struct BySerialize<'a, T: Component>(ReadStorage<'a, T>);
+
+impl<'a, T> HashStorage for BySerialize<'a, T>
+where
+    T: Component + Serialize,
+{
+    fn hash_component(&self, entity: Entity, state: &mut dyn Hasher) -> Result<(), Error> {
+        self.0
+            .get(entity)
+            .serialize(&mut HashSerializer(state))
+            .map_err(|e| Error::Custom(BoxedErr::new(e)))
+    }
+}
+
+/// Error of a component's `Serialize` implementation while hashing.
+#[derive(Debug)]
+struct HashError(String);
+
+impl Display for HashError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl ser::Error for HashError {
+    fn custom<T: Display>(msg: T) -> Self {
+        HashError(msg.to_string())
+    }
+}
+
+/// A `Serializer` writing everything into a `Hasher`.
+///
+/// Variants are hashed by their index and sequences of unknown length are
+/// terminated, so different values don't produce the same stream of writes.
+struct HashSerializer<'a>(&'a mut dyn Hasher);
+
+impl<'a, 'b> ser::Serializer for &'a mut HashSerializer<'b> {
+    type Error = HashError;
+    type Ok = ();
+    type SerializeMap = Self;
+    type SerializeSeq = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), HashError> {
+        self.0.write_u8(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), HashError> {
+        self.0.write_i8(v);
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), HashError> {
+        self.0.write_i16(v);
+        Ok(())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), HashError> {
+        self.0.write_i32(v);
+        Ok(())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), HashError> {
+        self.0.write_i64(v);
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), HashError> {
+        self.0.write_i128(v);
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), HashError> {
+        self.0.write_u8(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), HashError> {
+        self.0.write_u16(v);
+        Ok(())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), HashError> {
+        self.0.write_u32(v);
+        Ok(())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), HashError> {
+        self.0.write_u64(v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), HashError> {
+        self.0.write_u128(v);
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), HashError> {
+        self.0.write_u32(v.to_bits());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), HashError> {
+        self.0.write_u64(v.to_bits());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), HashError> {
+        self.0.write_u32(v as u32);
+        Ok(())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), HashError> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), HashError> {
+        self.0.write_u64(v.len() as u64);
+        self.0.write(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), HashError> {
+        self.0.write_u8(0);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), HashError> {
+        self.0.write_u8(1);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), HashError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), HashError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+    ) -> Result<(), HashError> {
+        self.0.write_u32(variant_index);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.0.write_u32(variant_index);
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, HashError> {
+        self.0.write_u8(len.is_some() as u8);
+        if let Some(len) = len {
+            self.0.write_u64(len as u64);
+        }
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self, HashError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self, HashError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, HashError> {
+        self.0.write_u32(variant_index);
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, HashError> {
+        self.serialize_seq(len)
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, HashError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        variant_index: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self, HashError> {
+        self.0.write_u32(variant_index);
+        Ok(self)
+    }
+}
+
+impl<'a, 'b> ser::SerializeSeq for &'a mut HashSerializer<'b> {
+    type Error = HashError;
+    type Ok = ();
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        // Distinguishes the elements from the end of the sequence.
+        self.0.write_u8(1);
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.0.write_u8(0);
+        Ok(())
+    }
+}
+
+impl<'a, 'b> ser::SerializeMap for &'a mut HashSerializer<'b> {
+    type Error = HashError;
+    type Ok = ();
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), HashError> {
+        self.0.write_u8(1);
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.0.write_u8(0);
+        Ok(())
+    }
+}
+
+macro_rules! hash_fields {
+    ($($trait:ident => $method:ident($($key:ty)?),)*) => {
+        $(
+            impl<'a, 'b> ser::$trait for &'a mut HashSerializer<'b> {
+                type Error = HashError;
+                type Ok = ();
+
+                fn $method<T: ?Sized + Serialize>(
+                    &mut self,
+                    $(_: $key,)?
+                    value: &T,
+                ) -> Result<(), HashError> {
+                    value.serialize(&mut **self)
+                }
+
+                fn end(self) -> Result<(), HashError> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+hash_fields! {
+    SerializeTuple => serialize_element(),
+    SerializeTupleStruct => serialize_field(),
+    SerializeTupleVariant => serialize_field(),
+    SerializeStruct => serialize_field(&'static str),
+    SerializeStructVariant => serialize_field(&'static str),
+}
@@ -0,0 +1,79 @@
+//! Provides `LoadHooks` for fixing up components after loading.
+
+use std::any::{Any, TypeId};
+
+use ahash::AHashMap as HashMap;
+
+use crate::world::{Component, Entity, LazyUpdate};
+
+/// Fixups run for every component loaded by
+/// `DeserializeComponents::deserialize_with_hooks`.
+///
+/// A hook is a `fn(&mut T, Entity, &LazyUpdate)` registered per component
+/// type. It is called with each loaded component once it has been converted
+/// from its serialized form, right before it is inserted into its storage,
+/// so tracked storages still only emit `ComponentEvent::Loaded`. Use the
+/// `LazyUpdate` to defer work that needs the `World`, like registering the
+/// entity with other resources.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, saveload::LoadHooks};
+///
+/// struct Mesh {
+///     path: String,
+///     // Not serialized, rebuilt after loading.
+///     vertices: Vec<f32>,
+/// }
+///
+/// impl Component for Mesh {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let hooks = LoadHooks::new().with::<Mesh>(|mesh, _entity, _lazy| {
+///     mesh.vertices = vec![0.0; mesh.path.len()];
+/// });
+/// ```
+#[derive(Default)]
+pub struct LoadHooks {
+    hooks: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl LoadHooks {
+    /// Creates an empty set of hooks.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers the hook of a component, replacing the previous one.
+    pub fn register<T: Component>(&mut self, hook: fn(&mut T, Entity, &LazyUpdate)) {
+        self.hooks.insert(TypeId::of::<T>(), Box::new(hook));
+    }
+
+    /// Registers the hook of a component.
+    ///
+    /// Same as `LoadHooks::register`, but returns `self` to enable method
+    /// chaining.
+    pub fn with<T: Component>(mut self, hook: fn(&mut T, Entity, &LazyUpdate)) -> Self {
+        self.register(hook);
+
+        self
+    }
+
+    /// Returns whether a hook is registered for a component.
+    pub fn contains<T: Component>(&self) -> bool {
+        self.hooks.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Calls the hook registered for `T`, if any.
+    pub(crate) fn run<T: Component>(&self, component: &mut T, entity: Entity, lazy: &LazyUpdate) {
+        let hook = self
+            .hooks
+            .get(&TypeId::of::<T>())
+            .and_then(|hook| hook.downcast_ref::<fn(&mut T, Entity, &LazyUpdate)>());
+        if let Some(hook) = hook {
+            hook(component, entity, lazy);
+        }
+    }
+}
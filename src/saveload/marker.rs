@@ -5,6 +5,7 @@ use std::{
     fmt::{self, Debug},
     hash::{Hash, Hasher},
     marker::PhantomData,
+    ops::Range,
 };
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
@@ -423,8 +424,36 @@ where
 }
 
 /// Basic marker allocator, uses `u64` as identifier
+///
+/// By default, ids are allocated from `0` upwards. To merge saves made by
+/// different sources, e.g. several servers or a server and its clients, give
+/// each source a disjoint range of ids with `with_range`. Loaded markers
+/// outside of the range of the allocator keep their id, but don't advance it.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     saveload::{MarkedBuilder, Marker, SimpleMarker, SimpleMarkerAllocator},
+/// };
+///
+/// struct NetworkSync;
+///
+/// let mut world = World::new();
+/// world.register::<SimpleMarker<NetworkSync>>();
+/// world.insert(SimpleMarkerAllocator::<NetworkSync>::with_range(1000..2000));
+///
+/// let e = world
+///     .create_entity()
+///     .marked::<SimpleMarker<NetworkSync>>()
+///     .build();
+/// let storage = world.read_storage::<SimpleMarker<NetworkSync>>();
+/// assert_eq!(storage.get(e).unwrap().id(), 1000);
+/// ```
 pub struct SimpleMarkerAllocator<T: ?Sized> {
     index: u64,
+    range: Range<u64>,
     mapping: HashMap<u64, Entity>,
     _phantom_data: PhantomData<T>,
 }
@@ -433,6 +462,7 @@ impl<T: ?Sized> Debug for SimpleMarkerAllocator<T> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("SimpleMarkerAllocator")
             .field("index", &self.index)
+            .field("range", &self.range)
             .field("mapping", &self.mapping)
             .field("_phantom_data", &self._phantom_data)
             .finish()
@@ -443,6 +473,7 @@ impl<T: ?Sized> Clone for SimpleMarkerAllocator<T> {
     fn clone(&self) -> Self {
         Self {
             index: self.index,
+            range: self.range.clone(),
             mapping: self.mapping.clone(),
             _phantom_data: PhantomData,
         }
@@ -459,12 +490,45 @@ impl<T: ?Sized> SimpleMarkerAllocator<T> {
     /// Create new `SimpleMarkerAllocator` which will yield `SimpleMarker`s
     /// starting with `0`
     pub fn new() -> Self {
+        Self::with_range(0..u64::MAX)
+    }
+
+    /// Create new `SimpleMarkerAllocator` which will yield `SimpleMarker`s
+    /// from `range` only, starting with its start.
+    ///
+    /// Allocating more markers than the range holds panics.
+    pub fn with_range(range: Range<u64>) -> Self {
         Self {
-            index: 0,
+            index: range.start,
+            range,
             mapping: HashMap::new(),
             _phantom_data: PhantomData,
         }
     }
+
+    /// Returns the range of ids this allocator yields.
+    pub fn range(&self) -> Range<u64> {
+        self.range.clone()
+    }
+
+    /// Returns the id of the next allocated marker.
+    pub fn next_id(&self) -> u64 {
+        self.index
+    }
+
+    /// Moves the next id past every marker of the range that is known to the
+    /// allocator.
+    ///
+    /// Markers loaded through the allocator already advance it. Call this
+    /// after `maintain` when markers have been inserted into the storage
+    /// directly, e.g. by a custom loader, so new markers don't collide with
+    /// them.
+    pub fn reseed(&mut self) {
+        let range = &self.range;
+        if let Some(max) = self.mapping.keys().filter(|id| range.contains(id)).max() {
+            self.index = self.index.max(max + 1);
+        }
+    }
 }
 
 impl<T> MarkerAllocator<SimpleMarker<T>> for SimpleMarkerAllocator<T>
@@ -473,11 +537,16 @@ where
 {
     fn allocate(&mut self, entity: Entity, id: Option<u64>) -> SimpleMarker<T> {
         let marker = if let Some(id) = id {
-            if id >= self.index {
+            if id >= self.index && self.range.contains(&id) {
                 self.index = id + 1;
             }
             SimpleMarker(id, PhantomData)
         } else {
+            assert!(
+                self.index < self.range.end,
+                "marker range {:?} is exhausted",
+                self.range
+            );
             self.index += 1;
             SimpleMarker(self.index - 1, PhantomData)
         };
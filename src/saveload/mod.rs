@@ -27,10 +27,13 @@ use std::convert::Infallible;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use crate::world::Entity;
+use crate::world::{Entity, WeakEntity};
 
 mod de;
+mod hash;
+mod hooks;
 mod marker;
+mod registry;
 mod ser;
 #[cfg(test)]
 mod tests;
@@ -40,8 +43,11 @@ mod uuid;
 #[cfg(feature = "uuid_entity")]
 pub use self::uuid::{UuidMarker, UuidMarkerAllocator};
 pub use self::{
-    de::DeserializeComponents,
+    de::{BatchLoad, DeserializeComponents, MarkerConflictPolicy},
+    hash::StateHashRegistry,
+    hooks::LoadHooks,
     marker::{MarkedBuilder, Marker, MarkerAllocator, SimpleMarker, SimpleMarkerAllocator},
+    registry::{ComponentRegistry, UnknownComponentPolicy},
     ser::SerializeComponents,
 };
 
@@ -189,3 +195,29 @@ where
         Ok(func(data).unwrap())
     }
 }
+
+impl<M> ConvertSaveload<M> for WeakEntity
+where
+    M: Serialize + DeserializeOwned,
+{
+    type Data = Option<M>;
+    type Error = Infallible;
+
+    fn convert_into<F>(&self, func: F) -> Result<Self::Data, Self::Error>
+    where
+        F: FnMut(Entity) -> Option<M>,
+    {
+        // An entity without a marker can't be referenced, which is fine for
+        // a reference that may dangle anyway.
+        Ok(self.entity().and_then(func))
+    }
+
+    fn convert_from<F>(data: Self::Data, func: F) -> Result<Self, Self::Error>
+    where
+        F: FnMut(M) -> Option<Entity>,
+    {
+        Ok(data
+            .and_then(func)
+            .map_or_else(WeakEntity::dangling, WeakEntity::from))
+    }
+}
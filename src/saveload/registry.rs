@@ -0,0 +1,130 @@
+//! Provides the `ComponentRegistry` for human-readable saves.
+
+use std::any::{type_name, TypeId};
+
+use ahash::AHashMap as HashMap;
+
+use crate::world::Component;
+
+/// What to do with component names that are not part of the component group
+/// being deserialized.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum UnknownComponentPolicy {
+    /// Skip the component data.
+    #[default]
+    Ignore,
+    /// Fail the deserialization.
+    Error,
+}
+
+/// Maps saveload-able components to stable string names.
+///
+/// With a registry, `SerializeComponents::serialize_named` writes the
+/// components of each entity as a map keyed by their names instead of a
+/// positional tuple, e.g. `{"pos": (x: 1.0, y: 2.0)}` in RON. This keeps save
+/// files readable and editable by hand, and independent of the order of the
+/// components in the serialized group.
+///
+/// `DeserializeComponents::deserialize_named` reads these maps back. Names
+/// that are missing from an entity's map are treated like absent components;
+/// names that don't belong to the deserialized group are handled according
+/// to the registry's `UnknownComponentPolicy`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, saveload::ComponentRegistry};
+///
+/// struct Pos(f32, f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let registry = ComponentRegistry::new().with::<Pos>("pos");
+///
+/// assert_eq!(registry.name::<Pos>(), Some("pos"));
+/// ```
+#[derive(Debug, Default)]
+pub struct ComponentRegistry {
+    names: HashMap<TypeId, &'static str>,
+    types: HashMap<&'static str, &'static str>,
+    unknown: UnknownComponentPolicy,
+}
+
+impl ComponentRegistry {
+    /// Creates an empty registry which ignores unknown components.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers the name of a component.
+    ///
+    /// Registering a component again replaces its previous name.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `name` is already used by another component.
+    pub fn register<T: Component>(&mut self, name: &'static str) {
+        let type_name = type_name::<T>();
+        if let Some(other) = self.types.get(name) {
+            assert!(
+                *other == type_name,
+                "Component name `{}` is already used by `{}`",
+                name,
+                other
+            );
+        }
+
+        if let Some(old) = self.names.insert(TypeId::of::<T>(), name) {
+            self.types.remove(old);
+        }
+        self.types.insert(name, type_name);
+    }
+
+    /// Registers the name of a component.
+    ///
+    /// Same as `ComponentRegistry::register`, but returns `self` to enable
+    /// method chaining.
+    pub fn with<T: Component>(mut self, name: &'static str) -> Self {
+        self.register::<T>(name);
+
+        self
+    }
+
+    /// Returns the registered name of a component.
+    pub fn name<T: Component>(&self) -> Option<&'static str> {
+        self.names.get(&TypeId::of::<T>()).copied()
+    }
+
+    /// Returns the policy for unknown component names.
+    pub fn unknown_policy(&self) -> UnknownComponentPolicy {
+        self.unknown
+    }
+
+    /// Sets the policy for unknown component names.
+    pub fn set_unknown_policy(&mut self, policy: UnknownComponentPolicy) {
+        self.unknown = policy;
+    }
+
+    /// Sets the policy for unknown component names.
+    ///
+    /// Same as `ComponentRegistry::set_unknown_policy`, but returns `self` to
+    /// enable method chaining.
+    pub fn with_unknown_policy(mut self, policy: UnknownComponentPolicy) -> Self {
+        self.set_unknown_policy(policy);
+
+        self
+    }
+
+    /// Returns the registered name of a component, or an error message
+    /// suitable for `serde`'s `custom` errors.
+    pub(crate) fn expect_name<T: Component>(&self) -> Result<&'static str, String> {
+        self.name::<T>().ok_or_else(|| {
+            format!(
+                "Component `{}` has no name in the `ComponentRegistry`",
+                type_name::<T>()
+            )
+        })
+    }
+}
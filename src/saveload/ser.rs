@@ -1,19 +1,26 @@
-use std::fmt::Display;
+use std::{any::type_name, fmt::Display, marker::PhantomData};
 
-use serde::ser::{self, Serialize, SerializeSeq, Serializer};
+use ahash::AHashMap as HashMap;
+use hibitset::BitSet;
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
 
 use super::ConvertSaveload;
 use crate::{
     join::Join,
     saveload::{
         marker::{Marker, MarkerAllocator},
-        EntityData,
+        ComponentRegistry, EntityData,
     },
     storage::{GenericReadStorage, ReadStorage, WriteStorage},
     world::{Component, EntitiesRes, Entity},
 };
 
 /// A trait which allows to serialize entities and their components.
+///
+/// Entities are serialized in ascending order of their index, whatever the
+/// storages of their components, so serializing the same entities twice
+/// produces the same output. `serialize_recursive` appends the entities
+/// marked while serializing in the order they are referenced.
 pub trait SerializeComponents<E, M>
 where
     M: Marker,
@@ -27,6 +34,28 @@ where
     where
         F: FnMut(Entity) -> Option<M>;
 
+    /// Serialize the components of a single entity as entries of `map`,
+    /// keyed by the names registered in `registry`. Absent components are
+    /// skipped.
+    ///
+    /// The default implementation fails, it is implemented for all tuples of
+    /// storages.
+    fn serialize_named_data<S>(
+        registry: &ComponentRegistry,
+        data: &Self::Data,
+        map: &mut S,
+    ) -> Result<(), S::Error>
+    where
+        S: SerializeMap,
+    {
+        let _ = (registry, data, map);
+
+        Err(ser::Error::custom(format_args!(
+            "`{}` does not support named components",
+            type_name::<Self>()
+        )))
+    }
+
     /// Serialize components from specified storages
     /// of all marked entities with provided serializer.
     /// When the component gets serialized the closure passed
@@ -58,6 +87,79 @@ where
         serseq.end()
     }
 
+    /// Serialize components from specified storages
+    /// of all marked entities with provided serializer, like `serialize`.
+    /// The components of each entity are written as a map keyed by the names
+    /// registered in `registry` instead of a positional tuple, see
+    /// `ComponentRegistry`.
+    ///
+    /// Fails if one of the components has no registered name.
+    fn serialize_named<S>(
+        &self,
+        entities: &EntitiesRes,
+        markers: &ReadStorage<M>,
+        registry: &ComponentRegistry,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        E: Display,
+        S: Serializer,
+    {
+        let count = (entities, markers).join().count();
+        let mut serseq = serializer.serialize_seq(Some(count))?;
+        let ids = |entity| -> Option<M> { markers.get(entity).cloned() };
+        for (entity, marker) in (entities, markers).join() {
+            serseq.serialize_element(&EntityData::<M, NamedComponents<E, M, Self>> {
+                marker: marker.clone(),
+                components: NamedComponents {
+                    registry,
+                    data: self
+                        .serialize_entity(entity, &ids)
+                        .map_err(ser::Error::custom)?,
+                    pd: PhantomData,
+                },
+            })?;
+        }
+        serseq.end()
+    }
+
+    /// Serialize components from specified storages of the entities in
+    /// `selection`, without a marker storage.
+    ///
+    /// Every selected entity gets a temporary marker from a new allocator,
+    /// references to entities outside of the selection are treated like
+    /// references to unmarked entities by `serialize`. This allows ad-hoc
+    /// exports, like copying entities in an editor, which are then loaded
+    /// with `DeserializeComponents::deserialize_fresh`.
+    fn serialize_selection<S>(
+        &self,
+        entities: &EntitiesRes,
+        selection: &BitSet,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        E: Display,
+        S: Serializer,
+        M::Allocator: Default,
+    {
+        let mut allocator = M::Allocator::default();
+        let markers: HashMap<Entity, M> = (entities, selection)
+            .join()
+            .map(|(entity, _)| (entity, allocator.allocate(entity, None)))
+            .collect();
+        let mut serseq = serializer.serialize_seq(Some(markers.len()))?;
+        let ids = |entity| -> Option<M> { markers.get(&entity).cloned() };
+        for (entity, _) in (entities, selection).join() {
+            serseq.serialize_element(&EntityData::<M, Self::Data> {
+                marker: markers[&entity].clone(),
+                components: self
+                    .serialize_entity(entity, &ids)
+                    .map_err(ser::Error::custom)?,
+            })?;
+        }
+        serseq.end()
+    }
+
     /// Serialize components from specified storages
     /// of all marked entities with provided serializer.
     /// When the component gets serialized the closure passed
@@ -109,6 +211,33 @@ where
     }
 }
 
+/// Serializes the data of a component group as a map keyed by component
+/// names.
+struct NamedComponents<'r, E, M, S: ?Sized>
+where
+    M: Marker,
+    S: SerializeComponents<E, M>,
+{
+    registry: &'r ComponentRegistry,
+    data: S::Data,
+    pd: PhantomData<(E, M)>,
+}
+
+impl<'r, E, M, S> Serialize for NamedComponents<'r, E, M, S>
+where
+    M: Marker,
+    S: SerializeComponents<E, M> + ?Sized,
+{
+    fn serialize<SER>(&self, serializer: SER) -> Result<SER::Ok, SER::Error>
+    where
+        SER: Serializer,
+    {
+        let mut map = serializer.serialize_map(None)?;
+        S::serialize_named_data(self.registry, &self.data, &mut map)?;
+        map.end()
+    }
+}
+
 macro_rules! serialize_components {
     ($($comp:ident => $sto:ident,)*) => {
         impl<'a, E, M, $($comp,)* $($sto,)*> SerializeComponents<E, M> for ($($sto,)*)
@@ -134,6 +263,26 @@ macro_rules! serialize_components {
                     $comp.get(entity).map(|c| c.convert_into(&mut ids).map(Some)).unwrap_or(Ok(None))?,
                 )*))
             }
+
+            #[allow(unused)]
+            fn serialize_named_data<S>(
+                registry: &ComponentRegistry,
+                data: &Self::Data,
+                map: &mut S,
+            ) -> Result<(), S::Error>
+            where
+                S: SerializeMap,
+            {
+                #[allow(bad_style)]
+                let ($(ref $comp,)*) = *data;
+                $(
+                    if let Some(data) = $comp {
+                        let name = registry.expect_name::<$comp>().map_err(ser::Error::custom)?;
+                        map.serialize_entry(name, data)?;
+                    }
+                )*
+                Ok(())
+            }
         }
 
         serialize_components!(@pop $($comp => $sto,)*);
@@ -159,6 +159,668 @@ mod marker_test {
         assert_markers_are_unique::<M>(&mut world);
     }
 
+    #[cfg(feature = "tracking")]
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    struct Flagged(u32);
+
+    #[cfg(feature = "tracking")]
+    impl Component for Flagged {
+        type Storage = FlaggedStorage<Self>;
+    }
+
+    /// Ensure that components inserted by deserialization emit `Loaded`
+    /// events instead of `Inserted` events.
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn deserialize_emits_loaded_events() {
+        let mut world = World::new();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        world.register::<Flagged>();
+        world.register::<SimpleMarker<NetworkSync>>();
+
+        world
+            .create_entity()
+            .with(Flagged(1))
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+        world
+            .create_entity()
+            .with(Flagged(2))
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+
+        let serial = world.exec(
+            |(ents, comps, markers): (
+                Entities,
+                ReadStorage<Flagged>,
+                ReadStorage<SimpleMarker<NetworkSync>>,
+            )| {
+                let mut buf = Vec::new();
+                let mut ser = ron::ser::Serializer::new(&mut buf, None).unwrap();
+                SerializeComponents::<Infallible, SimpleMarker<NetworkSync>>::serialize(
+                    &(&comps,),
+                    &ents,
+                    &markers,
+                    &mut ser,
+                )
+                .unwrap();
+                String::from_utf8(buf).expect("Ron should be utf-8")
+            },
+        );
+
+        let mut world = World::new();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        world.register::<Flagged>();
+        world.register::<SimpleMarker<NetworkSync>>();
+        let mut reader = world.write_storage::<Flagged>().register_reader();
+
+        let mut de = ron::de::Deserializer::from_str(&serial).unwrap();
+        world.exec(
+            |(ents, comps, mut markers, mut alloc): (
+                Entities,
+                WriteStorage<Flagged>,
+                WriteStorage<SimpleMarker<NetworkSync>>,
+                Write<SimpleMarkerAllocator<NetworkSync>>,
+            )| {
+                DeserializeComponents::<Error, _>::deserialize(
+                    &mut (comps,),
+                    &ents,
+                    &mut markers,
+                    &mut alloc,
+                    &mut de,
+                )
+                .unwrap();
+            },
+        );
+
+        let comps = world.read_storage::<Flagged>();
+        let events: Vec<_> = comps.channel().read(&mut reader).collect();
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, ComponentEvent::Loaded(_))));
+    }
+
+    /// Ensure that load hooks see every loaded component before it's inserted.
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn deserialize_runs_load_hooks() {
+        let mut world = World::new();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        world.register::<Flagged>();
+        world.register::<B>();
+        world.register::<SimpleMarker<NetworkSync>>();
+
+        let mut reader = world.write_storage::<Flagged>().register_reader();
+        let serial = "[(marker: (1), components: (Some((1)),)), \
+                      (marker: (2), components: (Some((2)),))]";
+        let hooks = LoadHooks::new().with::<Flagged>(|flagged, entity, lazy| {
+            flagged.0 *= 10;
+            lazy.insert(entity, B(true));
+        });
+
+        let mut de = ron::de::Deserializer::from_str(serial).unwrap();
+        world.exec(
+            |(ents, comps, mut markers, mut alloc, lazy): (
+                Entities,
+                WriteStorage<Flagged>,
+                WriteStorage<SimpleMarker<NetworkSync>>,
+                Write<SimpleMarkerAllocator<NetworkSync>>,
+                Read<LazyUpdate>,
+            )| {
+                DeserializeComponents::<Error, _>::deserialize_with_hooks(
+                    &mut (comps,),
+                    &ents,
+                    &mut markers,
+                    &mut alloc,
+                    &hooks,
+                    &lazy,
+                    &mut de,
+                )
+                .unwrap();
+            },
+        );
+        world.maintain();
+
+        let (ents, comps, bs) =
+            world.system_data::<(Entities, ReadStorage<Flagged>, ReadStorage<B>)>();
+        let loaded: Vec<_> = (&ents, &comps, &bs)
+            .join()
+            .map(|(_, flagged, b)| (flagged.clone(), b.clone()))
+            .collect();
+        assert_eq!(loaded, vec![(Flagged(10), B(true)), (Flagged(20), B(true))]);
+        assert!(comps
+            .channel()
+            .read(&mut reader)
+            .all(|event| matches!(event, ComponentEvent::Loaded(_))));
+    }
+
+    /// Ensure that markers of live entities are handled according to the
+    /// `MarkerConflictPolicy`.
+    #[test]
+    fn deserialize_marker_conflict_policy() {
+        fn load(world: &mut World, serial: &str, policy: MarkerConflictPolicy) -> bool {
+            let mut de = ron::de::Deserializer::from_str(serial).unwrap();
+            world.exec(
+                |(ents, a, b, mut markers, mut alloc): (
+                    Entities,
+                    WriteStorage<A>,
+                    WriteStorage<B>,
+                    WriteStorage<SimpleMarker<NetworkSync>>,
+                    Write<SimpleMarkerAllocator<NetworkSync>>,
+                )| {
+                    DeserializeComponents::<Error, _>::deserialize_with_policy(
+                        &mut (a, b),
+                        &ents,
+                        &mut markers,
+                        &mut alloc,
+                        policy,
+                        &mut de,
+                    )
+                    .is_ok()
+                },
+            )
+        }
+
+        fn loaded(world: &mut World) -> Vec<(Option<A>, Option<B>)> {
+            let (ents, a, b) = world.system_data::<(Entities, ReadStorage<A>, ReadStorage<B>)>();
+            (&ents, a.maybe(), b.maybe())
+                .join()
+                .map(|(_, a, b)| (a.cloned(), b.cloned()))
+                .collect()
+        }
+
+        let save = "[(marker: (1), components: (Some((1)), Some((false))))]";
+        let snapshot = "[(marker: (1), components: (Some((2)), None))]";
+        let policies = [
+            (
+                MarkerConflictPolicy::ReuseAndOverwrite,
+                vec![(Some(A(2)), None)],
+            ),
+            (
+                MarkerConflictPolicy::ReuseAndMerge,
+                vec![(Some(A(1)), Some(B(false)))],
+            ),
+            (
+                MarkerConflictPolicy::SpawnNew,
+                vec![(Some(A(1)), Some(B(false))), (Some(A(2)), None)],
+            ),
+            (
+                MarkerConflictPolicy::Error,
+                vec![(Some(A(1)), Some(B(false)))],
+            ),
+        ];
+
+        for (policy, expected) in policies {
+            let mut world = World::new();
+            world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+            world.register::<A>();
+            world.register::<B>();
+            world.register::<SimpleMarker<NetworkSync>>();
+
+            assert!(load(&mut world, save, policy));
+            assert_eq!(
+                load(&mut world, snapshot, policy),
+                policy != MarkerConflictPolicy::Error
+            );
+            assert_eq!(loaded(&mut world), expected, "{:?}", policy);
+            assert_markers_are_unique::<SimpleMarker<NetworkSync>>(&mut world);
+        }
+    }
+
+    /// Ensure that components can be saved and loaded as maps keyed by their
+    /// registered names.
+    #[test]
+    fn named_components_round_trip() {
+        let registry = ComponentRegistry::new().with::<A>("a").with::<B>("b");
+
+        let mut world = World::new();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        world.register::<A>();
+        world.register::<B>();
+        world.register::<SimpleMarker<NetworkSync>>();
+
+        world
+            .create_entity()
+            .with(A(1))
+            .with(B(true))
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+        world
+            .create_entity()
+            .with(A(2))
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+
+        let serial = world.exec(
+            |(ents, comp_a, comp_b, markers): (
+                Entities,
+                ReadStorage<A>,
+                ReadStorage<B>,
+                ReadStorage<SimpleMarker<NetworkSync>>,
+            )| {
+                let mut buf = Vec::new();
+                let mut ser = ron::ser::Serializer::new(&mut buf, None).unwrap();
+                SerializeComponents::<Infallible, SimpleMarker<NetworkSync>>::serialize_named(
+                    &(&comp_a, &comp_b),
+                    &ents,
+                    &markers,
+                    &registry,
+                    &mut ser,
+                )
+                .unwrap();
+                String::from_utf8(buf).expect("Ron should be utf-8")
+            },
+        );
+        assert_eq!(
+            serial,
+            r#"[(marker:(0),components:{"a":(1),"b":(true)}),(marker:(1),components:{"a":(2)})]"#
+        );
+
+        // The order of the components doesn't matter, missing ones are absent
+        // and unknown ones are skipped.
+        let edited =
+            r#"[(marker:(0),components:{"b":(false),"c":(3)}),(marker:(1),components:{"a":(2)})]"#;
+        let load = |registry: &ComponentRegistry| {
+            let mut world = World::new();
+            world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+            world.register::<A>();
+            world.register::<B>();
+            world.register::<SimpleMarker<NetworkSync>>();
+
+            let mut de = ron::de::Deserializer::from_str(edited).unwrap();
+            let result = world.exec(
+                |(ents, comp_a, comp_b, mut markers, mut alloc): (
+                    Entities,
+                    WriteStorage<A>,
+                    WriteStorage<B>,
+                    WriteStorage<SimpleMarker<NetworkSync>>,
+                    Write<SimpleMarkerAllocator<NetworkSync>>,
+                )| {
+                    DeserializeComponents::<Error, _>::deserialize_named(
+                        &mut (comp_b, comp_a),
+                        &ents,
+                        &mut markers,
+                        &mut alloc,
+                        registry,
+                        &mut de,
+                    )
+                },
+            );
+
+            result.map(|()| {
+                let (ents, comp_a, comp_b) =
+                    world.system_data::<(Entities, ReadStorage<A>, ReadStorage<B>)>();
+                (&ents, comp_a.maybe(), comp_b.maybe())
+                    .join()
+                    .map(|(_, a, b)| (a.cloned(), b.cloned()))
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            load(&registry).unwrap(),
+            vec![(None, Some(B(false))), (Some(A(2)), None)]
+        );
+
+        let strict = ComponentRegistry::new()
+            .with::<A>("a")
+            .with::<B>("b")
+            .with_unknown_policy(UnknownComponentPolicy::Error);
+        assert!(load(&strict).is_err());
+
+        let incomplete = ComponentRegistry::new().with::<A>("a");
+        assert!(load(&incomplete).is_err());
+    }
+
+    /// Ensure that the state hash covers the registered components and, with
+    /// a marker, doesn't depend on the entity ids.
+    #[test]
+    fn hash_state() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn world(spare: usize, a: i32) -> World {
+            let mut world = World::new();
+            world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+            world.register::<A>();
+            world.register::<B>();
+            world.register::<SimpleMarker<NetworkSync>>();
+
+            let spare: Vec<_> = world.create_iter().take(spare).collect();
+            world
+                .create_entity()
+                .with(A(a))
+                .marked::<SimpleMarker<NetworkSync>>()
+                .build();
+            world
+                .create_entity()
+                .with(B(true))
+                .marked::<SimpleMarker<NetworkSync>>()
+                .build();
+            world.create_entity().with(A(7)).build();
+            world.delete_entities(&spare).unwrap();
+
+            world
+        }
+
+        let hash = |world: &World, registry: &StateHashRegistry| {
+            world.hash_state::<DefaultHasher>(registry).unwrap()
+        };
+        let mut registry = StateHashRegistry::new()
+            .with_serialize::<A>()
+            .with_serialize::<B>();
+
+        assert_eq!(hash(&world(0, 1), &registry), hash(&world(0, 1), &registry));
+        assert_ne!(hash(&world(0, 1), &registry), hash(&world(0, 2), &registry));
+        assert_ne!(hash(&world(0, 1), &registry), hash(&world(2, 1), &registry));
+
+        assert!(registry.exclude::<A>());
+        assert!(!registry.contains::<A>());
+        assert_eq!(hash(&world(0, 1), &registry), hash(&world(0, 2), &registry));
+
+        let marked = StateHashRegistry::new()
+            .with_serialize::<A>()
+            .only_marked::<SimpleMarker<NetworkSync>>();
+        assert_eq!(hash(&world(0, 1), &marked), hash(&world(2, 1), &marked));
+        assert_ne!(hash(&world(0, 1), &marked), hash(&world(2, 2), &marked));
+    }
+
+    /// Ensure that an allocator with a range only advances on ids of its
+    /// range, and that `reseed` skips markers inserted behind its back.
+    #[test]
+    fn marker_range_partitioning() {
+        type Alloc = SimpleMarkerAllocator<NetworkSync>;
+
+        let mut world = World::new();
+        world.insert(Alloc::with_range(100..200));
+        world.register::<SimpleMarker<NetworkSync>>();
+
+        let mut other = Alloc::new();
+        world.exec(
+            |(ents, mut markers, mut alloc): (
+                Entities,
+                WriteStorage<SimpleMarker<NetworkSync>>,
+                Write<Alloc>,
+            )| {
+                alloc.allocate(ents.create(), Some(5));
+                assert_eq!(alloc.next_id(), 100);
+                alloc.allocate(ents.create(), Some(150));
+                assert_eq!(alloc.next_id(), 151);
+                assert_eq!(alloc.allocate(ents.create(), None).id(), 151);
+
+                let e = ents.create();
+                markers.insert(e, other.allocate(e, Some(180))).unwrap();
+            },
+        );
+
+        world.exec(
+            |(ents, markers, mut alloc): (
+                Entities,
+                ReadStorage<SimpleMarker<NetworkSync>>,
+                Write<Alloc>,
+            )| {
+                alloc.maintain(&ents, &markers);
+                alloc.reseed();
+                assert_eq!(alloc.next_id(), 181);
+                assert_eq!(alloc.range(), 100..200);
+            },
+        );
+    }
+
+    /// Ensure that a selection of entities can be exported without markers and
+    /// imported as new entities, any number of times.
+    #[test]
+    fn selection_round_trip() {
+        let mut world = World::new();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        world.register::<A>();
+        world.register::<B>();
+        world.register::<SimpleMarker<NetworkSync>>();
+
+        let a = world.create_entity().with(A(1)).build();
+        world.create_entity().with(A(2)).build();
+        let c = world.create_entity().with(A(3)).with(B(true)).build();
+        let mut selection = BitSet::new();
+        selection.add(a.id());
+        selection.add(c.id());
+
+        let mut buf = Vec::new();
+        let mut ser = ron::ser::Serializer::new(&mut buf, None).unwrap();
+        world.exec(|(ents, a, b): (Entities, ReadStorage<A>, ReadStorage<B>)| {
+            SerializeComponents::<Infallible, SimpleMarker<NetworkSync>>::serialize_selection(
+                &(&a, &b),
+                &ents,
+                &selection,
+                &mut ser,
+            )
+            .unwrap();
+        });
+        let serial = String::from_utf8(buf).unwrap();
+
+        for _ in 0..2 {
+            let mut de = ron::de::Deserializer::from_str(&serial).unwrap();
+            world.exec(
+                |(ents, a, b, mut markers, mut alloc): (
+                    Entities,
+                    WriteStorage<A>,
+                    WriteStorage<B>,
+                    WriteStorage<SimpleMarker<NetworkSync>>,
+                    Write<SimpleMarkerAllocator<NetworkSync>>,
+                )| {
+                    DeserializeComponents::<Error, _>::deserialize_fresh(
+                        &mut (a, b),
+                        &ents,
+                        &mut markers,
+                        &mut alloc,
+                        &mut de,
+                    )
+                    .unwrap();
+                },
+            );
+        }
+
+        let (ents, a, b) = world.system_data::<(Entities, ReadStorage<A>, ReadStorage<B>)>();
+        let loaded: Vec<_> = (&ents, &a).join().map(|(_, a)| a.0).collect();
+        assert_eq!(loaded, [1, 2, 3, 1, 3, 1, 3]);
+        assert_eq!(b.count(), 3);
+        drop((ents, a, b));
+        assert_marked_entity_count::<SimpleMarker<NetworkSync>>(&mut world, 4);
+        assert_markers_are_unique::<SimpleMarker<NetworkSync>>(&mut world);
+    }
+
+    /// Ensure that components in a `HashMapStorage` are serialized in
+    /// ascending entity order, regardless of the order they were inserted in.
+    #[test]
+    fn hash_map_storage_byte_stable() {
+        #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+        struct C(u32);
+
+        impl Component for C {
+            type Storage = HashMapStorage<Self>;
+        }
+
+        let serialize = |reverse: bool| {
+            let mut world = World::new();
+            world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+            world.register::<C>();
+            world.register::<SimpleMarker<NetworkSync>>();
+            let entities: Vec<_> = (0..64)
+                .map(|_| {
+                    world
+                        .create_entity()
+                        .marked::<SimpleMarker<NetworkSync>>()
+                        .build()
+                })
+                .collect();
+            let mut order: Vec<_> = entities.iter().enumerate().collect();
+            if reverse {
+                order.reverse();
+            }
+            for (i, &e) in order {
+                world.write_storage::<C>().insert(e, C(i as u32)).unwrap();
+            }
+
+            let mut buf = Vec::new();
+            let mut ser = serde_json::Serializer::new(&mut buf);
+            world.exec(
+                |(ents, c, markers): (
+                    Entities,
+                    ReadStorage<C>,
+                    ReadStorage<SimpleMarker<NetworkSync>>,
+                )| {
+                    SerializeComponents::<Infallible, SimpleMarker<NetworkSync>>::serialize(
+                        &(&c,),
+                        &ents,
+                        &markers,
+                        &mut ser,
+                    )
+                    .unwrap();
+                },
+            );
+            buf
+        };
+
+        let forward = serialize(false);
+        assert_eq!(forward, serialize(true));
+        let data: Vec<EntityData<SimpleMarker<NetworkSync>, (Option<C>,)>> =
+            serde_json::from_slice(&forward).unwrap();
+        let values: Vec<_> = data
+            .iter()
+            .map(|d| d.components.0.clone().unwrap().0)
+            .collect();
+        assert_eq!(values, (0..64).collect::<Vec<_>>());
+    }
+
+    /// Ensure that a `WeakEntity` to an unmarked entity is saved as dangling
+    /// instead of failing.
+    #[test]
+    fn weak_entity_dangling() {
+        let mut world = World::new();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        world.register::<SimpleMarker<NetworkSync>>();
+
+        let marked = world
+            .create_entity()
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+        let unmarked = world.create_entity().build();
+
+        let markers = world.read_storage::<SimpleMarker<NetworkSync>>();
+        let ids = |e| markers.get(e).copied();
+        let weak = WeakEntity::from(marked);
+        let data = ConvertSaveload::<SimpleMarker<NetworkSync>>::convert_into(&weak, ids).unwrap();
+        assert_eq!(data, markers.get(marked).copied());
+        let data = ConvertSaveload::convert_into(&WeakEntity::from(unmarked), ids).unwrap();
+        assert_eq!(data, None);
+
+        let weak: WeakEntity = ConvertSaveload::convert_from(data, |_| Some(marked)).unwrap();
+        assert_eq!(weak.upgrade(&world.entities()), None);
+    }
+
+    /// Ensure that a snapshot loaded in batches resolves references to
+    /// entities of later batches, and that aborting the load deletes the
+    /// entities it created.
+    #[test]
+    fn batch_load() {
+        struct Link(Entity);
+
+        impl Component for Link {
+            type Storage = VecStorage<Self>;
+        }
+
+        impl<M: Marker> ConvertSaveload<M> for Link {
+            type Data = M;
+            type Error = Infallible;
+
+            fn convert_into<F>(&self, mut ids: F) -> Result<M, Infallible>
+            where
+                F: FnMut(Entity) -> Option<M>,
+            {
+                Ok(ids(self.0).unwrap())
+            }
+
+            fn convert_from<F>(data: M, mut ids: F) -> Result<Self, Infallible>
+            where
+                F: FnMut(M) -> Option<Entity>,
+            {
+                Ok(Link(ids(data).unwrap()))
+            }
+        }
+
+        type Net = SimpleMarker<NetworkSync>;
+        type Batch = Vec<EntityData<Net, (Option<A>, Option<Net>)>>;
+
+        fn load(world: &mut World, load: &mut BatchLoad<Net>, batch: &str) {
+            let batch: Batch = serde_json::from_str(batch).unwrap();
+            world.exec(
+                |(ents, a, links, mut markers, mut alloc): (
+                    Entities,
+                    WriteStorage<A>,
+                    WriteStorage<Link>,
+                    WriteStorage<SimpleMarker<NetworkSync>>,
+                    Write<SimpleMarkerAllocator<NetworkSync>>,
+                )| {
+                    DeserializeComponents::<Error, _>::deserialize_batch(
+                        &mut (a, links),
+                        &ents,
+                        &mut markers,
+                        &mut alloc,
+                        load,
+                        batch,
+                    )
+                    .unwrap();
+                },
+            );
+        }
+
+        let mut world = World::new();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        world.register::<A>();
+        world.register::<Link>();
+        world.register::<SimpleMarker<NetworkSync>>();
+        let existing = world
+            .create_entity()
+            .with(A(0))
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+        let existing_marker = world
+            .read_storage::<SimpleMarker<NetworkSync>>()
+            .get(existing)
+            .unwrap()
+            .id();
+        assert_eq!(existing_marker, 0);
+
+        for (mut batch_load, fresh) in [(BatchLoad::new(), false), (BatchLoad::fresh(), true)] {
+            load(
+                &mut world,
+                &mut batch_load,
+                r#"[{"marker": [7], "components": [1, [8]]}]"#,
+            );
+            load(
+                &mut world,
+                &mut batch_load,
+                r#"[{"marker": [8], "components": [2, null]}, {"marker": [0], "components": [3, null]}]"#,
+            );
+
+            let (a, links) = (world.read_storage::<A>(), world.read_storage::<Link>());
+            let (&first, created) = batch_load.created().split_first().unwrap();
+            let target = links.get(first).unwrap().0;
+            assert_eq!(a.get(first), Some(&A(1)));
+            assert_eq!(a.get(target), Some(&A(2)));
+            assert_eq!(created.len(), if fresh { 2 } else { 1 });
+            assert!(!created.contains(&existing));
+            drop((a, links));
+
+            batch_load.abort(&world.entities());
+            world.maintain();
+            assert!(world.is_alive(existing));
+            assert!(!world.is_alive(first) && !world.is_alive(target));
+        }
+        // The fresh load didn't touch the existing entity.
+        assert_eq!(world.read_storage::<A>().get(existing), Some(&A(3)));
+        assert_marked_entity_count::<SimpleMarker<NetworkSync>>(&mut world, 1);
+    }
+
     /// Assert that the number of entities marked with `SimpleMarker` is equal
     /// to `count`
     fn assert_marked_entity_count<M: Marker>(world: &mut World, count: usize) {
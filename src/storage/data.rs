@@ -1,7 +1,7 @@
-use shred::{Fetch, FetchMut, MetaTable, ResourceId, SystemData, World};
+use shred::{Fetch, FetchMut, ResourceId, SystemData, World};
 
 use crate::{
-    storage::{AnyStorage, MaskedStorage, Storage, TryDefault},
+    storage::{setup_storage, MaskedStorage, Storage},
     world::{Component, EntitiesRes},
 };
 
@@ -121,10 +121,7 @@ where
     T: Component,
 {
     fn setup(res: &mut World) {
-        res.entry::<MaskedStorage<T>>()
-            .or_insert_with(|| MaskedStorage::new(<T::Storage as TryDefault>::unwrap_default()));
-        res.fetch_mut::<MetaTable<dyn AnyStorage>>()
-            .register::<MaskedStorage<T>>();
+        setup_storage::<T, _>(res, ());
     }
 
     fn fetch(res: &'a World) -> Self {
@@ -208,14 +205,16 @@ where
     T: Component,
 {
     fn setup(res: &mut World) {
-        res.entry::<MaskedStorage<T>>()
-            .or_insert_with(|| MaskedStorage::new(<T::Storage as TryDefault>::unwrap_default()));
-        res.fetch_mut::<MetaTable<dyn AnyStorage>>()
-            .register::<MaskedStorage<T>>();
+        setup_storage::<T, _>(res, ());
     }
 
     fn fetch(res: &'a World) -> Self {
-        Storage::new(res.fetch(), res.fetch_mut())
+        let data: FetchMut<MaskedStorage<T>> = res.fetch_mut();
+        if let Err(e) = data.check_leases() {
+            panic!("{}", e);
+        }
+
+        Storage::new(res.fetch(), data)
     }
 
     fn reads() -> Vec<ResourceId> {
@@ -1,8 +1,16 @@
+#[cfg(feature = "mutation-audit")]
+use std::{
+    any::type_name,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
 use std::{
     marker::PhantomData,
     ops::{Deref, DerefMut},
 };
 
+#[cfg(feature = "mutation-audit")]
+use ahash::AHashMap as HashMap;
 use hibitset::BitSetLike;
 
 use crate::{
@@ -22,14 +30,76 @@ use shrev::EventChannel;
 /// accesses that only emits modification events when the component is actually
 /// used mutably. This means that simply performing a mutable join or calling
 /// `WriteStorage::get_mut` will not, by itself, trigger a modification event.
+///
+/// With the `mutation-audit` feature,
+/// `DerefFlaggedStorage::with_mutation_audit` helps finding mutable accesses
+/// that emit events without changing the component.
 pub struct DerefFlaggedStorage<C, T = DenseVecStorage<C>> {
     channel: EventChannel<ComponentEvent>,
     storage: T,
     #[cfg(feature = "storage-event-control")]
     event_emission: bool,
+    #[cfg(feature = "mutation-audit")]
+    audit: Option<MutationAudit<C>>,
     phantom: PhantomData<C>,
 }
 
+/// Hashes of the components modified since the last maintain, taken before
+/// their first modification.
+#[cfg(feature = "mutation-audit")]
+struct MutationAudit<C> {
+    hash: fn(&C) -> u64,
+    modified: HashMap<Index, u64>,
+}
+
+#[cfg(feature = "mutation-audit")]
+fn hash_component<C: Hash>(component: &C) -> u64 {
+    let mut state = DefaultHasher::new();
+    component.hash(&mut state);
+    state.finish()
+}
+
+#[cfg(feature = "mutation-audit")]
+impl<C, T> DerefFlaggedStorage<C, T>
+where
+    C: Hash,
+{
+    /// Enables auditing of the mutable accesses to this storage.
+    ///
+    /// Every component is hashed before it is first accessed mutably through
+    /// `FlaggedAccessMut`. If its hash didn't change by the next
+    /// `World::maintain`, a warning is logged, since the
+    /// `ComponentEvent::Modified` emitted for it was most likely unwanted;
+    /// e.g. an `access_mut` or `DerefMut` in a code path that doesn't end up
+    /// changing anything. Use `Deref` to read components instead.
+    ///
+    /// This is meant for debugging and slows down mutable accesses.
+    ///
+    /// ```
+    /// use specs::{prelude::*, storage::DerefFlaggedStorage};
+    ///
+    /// #[derive(Hash)]
+    /// struct Pos(i32, i32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = DerefFlaggedStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_with_storage::<_, Pos>(|| {
+    ///     DerefFlaggedStorage::default().with_mutation_audit()
+    /// });
+    /// ```
+    pub fn with_mutation_audit(mut self) -> Self {
+        self.audit = Some(MutationAudit {
+            hash: hash_component::<C>,
+            modified: HashMap::new(),
+        });
+
+        self
+    }
+}
+
 impl<C, T> DerefFlaggedStorage<C, T> {
     #[cfg(feature = "storage-event-control")]
     fn emit_event(&self) -> bool {
@@ -52,12 +122,18 @@ where
             storage: T::unwrap_default(),
             #[cfg(feature = "storage-event-control")]
             event_emission: true,
+            #[cfg(feature = "mutation-audit")]
+            audit: None,
             phantom: PhantomData,
         }
     }
 }
 
-impl<C: Component, T: UnprotectedStorage<C>> UnprotectedStorage<C> for DerefFlaggedStorage<C, T> {
+impl<C, T> UnprotectedStorage<C> for DerefFlaggedStorage<C, T>
+where
+    C: Component,
+    T: UnprotectedStorage<C> + TryDefault,
+{
     type AccessMut<'a> = FlaggedAccessMut<'a, <T as UnprotectedStorage<C>>::AccessMut<'a>, C>
         where T: 'a;
 
@@ -67,6 +143,10 @@ impl<C: Component, T: UnprotectedStorage<C>> UnprotectedStorage<C> for DerefFlag
     {
         // SAFETY: Requirements passed to caller.
         unsafe { self.storage.clean(has) };
+        #[cfg(feature = "mutation-audit")]
+        if let Some(audit) = &mut self.audit {
+            audit.modified.clear();
+        }
     }
 
     unsafe fn get(&self, id: Index) -> &C {
@@ -82,6 +162,8 @@ impl<C: Component, T: UnprotectedStorage<C>> UnprotectedStorage<C> for DerefFlag
             id,
             // SAFETY: Requirements passed to caller.
             access: unsafe { self.storage.get_mut(id) },
+            #[cfg(feature = "mutation-audit")]
+            audit: self.audit.as_mut(),
             phantom: PhantomData,
         }
     }
@@ -94,13 +176,50 @@ impl<C: Component, T: UnprotectedStorage<C>> UnprotectedStorage<C> for DerefFlag
         unsafe { self.storage.insert(id, comp) };
     }
 
+    unsafe fn insert_loaded(&mut self, id: Index, comp: C) {
+        if self.emit_event() {
+            self.channel.single_write(ComponentEvent::Loaded(id));
+        }
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.storage.insert_loaded(id, comp) };
+    }
+
     unsafe fn remove(&mut self, id: Index) -> C {
         if self.emit_event() {
             self.channel.single_write(ComponentEvent::Removed(id));
         }
+        #[cfg(feature = "mutation-audit")]
+        if let Some(audit) = &mut self.audit {
+            audit.modified.remove(&id);
+        }
         // SAFETY: Requirements passed to caller.
         unsafe { self.storage.remove(id) }
     }
+
+    fn maintain(&mut self) {
+        #[cfg(feature = "mutation-audit")]
+        if let Some(audit) = &mut self.audit {
+            for (id, before) in audit.modified.drain() {
+                // SAFETY: Components are only recorded while they are present,
+                // `remove` and `clean` drop them from `modified`.
+                let component = unsafe { self.storage.get(id) };
+                if (audit.hash)(component) == before {
+                    log::warn!(
+                        "Component `{}` with index {} was accessed mutably, emitting \
+                         `ComponentEvent::Modified`, but it didn't change",
+                        type_name::<C>(),
+                        id
+                    );
+                }
+            }
+        }
+
+        self.storage.maintain();
+    }
+
+    fn is_thread_local(&self) -> bool {
+        self.storage.is_thread_local()
+    }
 }
 
 impl<C, T> Tracked for DerefFlaggedStorage<C, T> {
@@ -130,6 +249,8 @@ pub struct FlaggedAccessMut<'a, A, C> {
     emit: bool,
     id: Index,
     access: A,
+    #[cfg(feature = "mutation-audit")]
+    audit: Option<&'a mut MutationAudit<C>>,
     phantom: PhantomData<C>,
 }
 
@@ -151,6 +272,15 @@ where
     fn deref_mut(&mut self) -> &mut Self::Target {
         if self.emit {
             self.channel.single_write(ComponentEvent::Modified(self.id));
+            #[cfg(feature = "mutation-audit")]
+            if let Some(audit) = &mut self.audit {
+                let hash = audit.hash;
+                let access = &self.access;
+                audit
+                    .modified
+                    .entry(self.id)
+                    .or_insert_with(|| hash(access));
+            }
         }
         self.access.access_mut()
     }
@@ -0,0 +1,459 @@
+use std::{
+    any::{Any, TypeId},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::OnceLock,
+};
+
+use ahash::AHashMap as HashMap;
+use hibitset::{BitSet, BitSetLike};
+
+#[cfg(feature = "parallel")]
+use crate::join::ParJoin;
+use crate::{
+    join::Join,
+    storage::{
+        sync_unsafe_cell::SyncUnsafeCell, DenseVecStorage, MaskedStorage, SharedGetMutOnly,
+        SharedGetMutStorage, Storage, UnprotectedStorage,
+    },
+    world::{Component, Entity, Index},
+};
+
+/// Gives access to a value as `dyn Any`, so trait objects can be downcast
+/// to their concrete type.
+///
+/// This is implemented for every `'static` type. Make it a supertrait of the
+/// traits whose trait objects are stored in a `DynStorage`.
+pub trait AsAny: Any {
+    /// Returns `self` as `&dyn Any`.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns `self` as `&mut dyn Any`.
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Returns the type id of the concrete type behind `value`.
+fn concrete_type<B: ?Sized + AsAny>(value: &B) -> TypeId {
+    value.as_any().type_id()
+}
+
+/// Storage for boxed trait objects, like `Box<dyn Behavior>`, which keeps a
+/// mask of the components of every concrete type.
+///
+/// This allows storing polymorphic components of different concrete types
+/// in a single storage. Joining it calls the trait methods as usual, while
+/// `Storage::downcast_join` and `Storage::downcast_join_mut` join only the
+/// components of a concrete type, without checking every component.
+///
+/// The trait needs `AsAny` as a supertrait. The masks of the concrete types
+/// are updated on insertion and removal, and when a component is replaced
+/// through mutable access.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     storage::{AsAny, DynStorage},
+/// };
+///
+/// trait Behavior: AsAny + Send + Sync {
+///     fn act(&mut self) -> u32;
+/// }
+///
+/// impl Component for Box<dyn Behavior> {
+///     type Storage = DynStorage<dyn Behavior>;
+/// }
+///
+/// struct Patrol(u32);
+///
+/// impl Behavior for Patrol {
+///     fn act(&mut self) -> u32 {
+///         self.0 += 1;
+///         self.0
+///     }
+/// }
+///
+/// struct Idle;
+///
+/// impl Behavior for Idle {
+///     fn act(&mut self) -> u32 {
+///         0
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Box<dyn Behavior>>();
+/// world
+///     .create_entity()
+///     .with(Box::new(Patrol(0)) as Box<dyn Behavior>)
+///     .build();
+/// world
+///     .create_entity()
+///     .with(Box::new(Idle) as Box<dyn Behavior>)
+///     .build();
+///
+/// let mut behaviors = world.write_storage::<Box<dyn Behavior>>();
+/// let acted: Vec<_> = (&mut behaviors).join().map(|mut b| b.act()).collect();
+/// assert_eq!(acted, [1, 0]);
+///
+/// let patrols: Vec<_> = behaviors.downcast_join::<Patrol>().join().map(|p| p.0).collect();
+/// assert_eq!(patrols, [1]);
+/// ```
+pub struct DynStorage<B: ?Sized> {
+    storage: DenseVecStorage<Box<B>>,
+    /// The ids of the components of every concrete type.
+    ///
+    /// Only mutated with exclusive access or by the `DynAccessMut` returned
+    /// from `shared_get_mut`, which is not `Send`. This storage doesn't
+    /// implement `DistinctStorage`, so those are only created and dropped on
+    /// one thread at a time.
+    types: SyncUnsafeCell<HashMap<TypeId, BitSet>>,
+}
+
+impl<B: ?Sized> Default for DynStorage<B> {
+    fn default() -> Self {
+        DynStorage {
+            storage: Default::default(),
+            types: Default::default(),
+        }
+    }
+}
+
+impl<B: ?Sized + AsAny> DynStorage<B> {
+    /// Returns an empty mask, for the concrete types without components.
+    fn empty_mask() -> &'static BitSet {
+        static EMPTY: OnceLock<BitSet> = OnceLock::new();
+
+        EMPTY.get_or_init(BitSet::new)
+    }
+
+    /// Returns the ids of the components whose concrete type is `C`.
+    fn type_mask<C: Any>(&self) -> &BitSet {
+        // SAFETY: The map is only mutated with exclusive access, or by
+        // `DynAccessMut`s, which can't be alive while this storage is borrowed
+        // immutably (see `SharedGetMutStorage::shared_get_mut`).
+        let types = unsafe { &*self.types.get() };
+        types
+            .get(&TypeId::of::<C>())
+            .unwrap_or_else(|| Self::empty_mask())
+    }
+}
+
+impl<B: ?Sized + AsAny> UnprotectedStorage<Box<B>> for DynStorage<B> {
+    type AccessMut<'a> = DynAccessMut<'a, B> where B: 'a;
+
+    unsafe fn clean<M>(&mut self, has: M)
+    where
+        M: BitSetLike,
+    {
+        self.types.get_mut().clear();
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.storage.clean(has) };
+    }
+
+    unsafe fn get(&self, id: Index) -> &Box<B> {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.storage.get(id) }
+    }
+
+    unsafe fn get_mut(&mut self, id: Index) -> DynAccessMut<'_, B> {
+        // SAFETY: Requirements passed to caller.
+        let value = unsafe { self.storage.get_mut(id) };
+        DynAccessMut::new(value, id, &self.types)
+    }
+
+    unsafe fn insert(&mut self, id: Index, value: Box<B>) {
+        let ty = concrete_type(&*value);
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.storage.insert(id, value) };
+        self.types.get_mut().entry(ty).or_default().add(id);
+    }
+
+    unsafe fn remove(&mut self, id: Index) -> Box<B> {
+        // SAFETY: Requirements passed to caller.
+        let value = unsafe { self.storage.remove(id) };
+        if let Some(mask) = self.types.get_mut().get_mut(&concrete_type(&*value)) {
+            mask.remove(id);
+        }
+
+        value
+    }
+}
+
+impl<B: ?Sized + AsAny> SharedGetMutStorage<Box<B>> for DynStorage<B> {
+    unsafe fn shared_get_mut(&self, id: Index) -> DynAccessMut<'_, B> {
+        // SAFETY: Requirements passed to caller.
+        let value = unsafe { self.storage.shared_get_mut(id) };
+        DynAccessMut::new(value, id, &self.types)
+    }
+}
+
+/// Mutable access to a component of a `DynStorage`, updating the masks of
+/// the concrete types when it is dropped if the component was replaced by
+/// one of another type.
+pub struct DynAccessMut<'a, B: ?Sized + AsAny> {
+    value: &'a mut Box<B>,
+    id: Index,
+    ty: TypeId,
+    types: &'a SyncUnsafeCell<HashMap<TypeId, BitSet>>,
+    // Dropping this mutates `types`, see `DynStorage::types`.
+    not_send: PhantomData<*mut ()>,
+}
+
+impl<'a, B: ?Sized + AsAny> DynAccessMut<'a, B> {
+    fn new(
+        value: &'a mut Box<B>,
+        id: Index,
+        types: &'a SyncUnsafeCell<HashMap<TypeId, BitSet>>,
+    ) -> Self {
+        DynAccessMut {
+            ty: concrete_type(&**value),
+            value,
+            id,
+            types,
+            not_send: PhantomData,
+        }
+    }
+}
+
+impl<'a, B: ?Sized + AsAny> Deref for DynAccessMut<'a, B> {
+    type Target = Box<B>;
+
+    fn deref(&self) -> &Box<B> {
+        self.value
+    }
+}
+
+impl<'a, B: ?Sized + AsAny> DerefMut for DynAccessMut<'a, B> {
+    fn deref_mut(&mut self) -> &mut Box<B> {
+        self.value
+    }
+}
+
+impl<'a, B: ?Sized + AsAny> Drop for DynAccessMut<'a, B> {
+    fn drop(&mut self) {
+        let ty = concrete_type(&**self.value);
+        if ty != self.ty {
+            // SAFETY: See `DynStorage::types`, no other reference to the map
+            // is alive while this one is.
+            let types = unsafe { &mut *self.types.get() };
+            if let Some(mask) = types.get_mut(&self.ty) {
+                mask.remove(self.id);
+            }
+            types.entry(ty).or_default().add(self.id);
+        }
+    }
+}
+
+impl<'e, B, D> Storage<'e, Box<B>, D>
+where
+    B: ?Sized + AsAny,
+    Box<B>: Component<Storage = DynStorage<B>>,
+    D: Deref<Target = MaskedStorage<Box<B>>>,
+{
+    /// Returns the component of `e` if its concrete type is `C`.
+    pub fn downcast<C: Any>(&self, e: Entity) -> Option<&C> {
+        self.get(e)
+            .and_then(|component| (**component).as_any().downcast_ref())
+    }
+
+    /// Joins the components whose concrete type is `C`, yielding `&C`.
+    pub fn downcast_join<C: Any>(&self) -> DowncastJoin<'_, B, C> {
+        DowncastJoin {
+            mask: self.data.inner.type_mask::<C>(),
+            storage: &self.data.inner.storage,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'e, B, D> Storage<'e, Box<B>, D>
+where
+    B: ?Sized + AsAny,
+    Box<B>: Component<Storage = DynStorage<B>>,
+    D: DerefMut<Target = MaskedStorage<Box<B>>>,
+{
+    /// Returns the component of `e` mutably if its concrete type is `C`.
+    pub fn downcast_mut<C: Any>(&mut self, e: Entity) -> Option<&mut C> {
+        if self.contains(e) {
+            // SAFETY: We checked the mask. Downcasting can't replace the
+            // component, so the masks of the concrete types stay valid.
+            let component = unsafe { self.data.inner.storage.get_mut(e.id()) };
+            (**component).as_any_mut().downcast_mut()
+        } else {
+            None
+        }
+    }
+
+    /// Joins the components whose concrete type is `C`, yielding `&mut C`.
+    pub fn downcast_join_mut<C: Any>(&mut self) -> DowncastJoinMut<'_, B, C> {
+        let DynStorage { storage, types } = &mut self.data.inner;
+        let types = &*types;
+        let mask = {
+            // SAFETY: We have exclusive access to the storage.
+            let types = unsafe { &*types.get() };
+            types.get(&TypeId::of::<C>())
+        };
+
+        DowncastJoinMut {
+            mask: mask.unwrap_or_else(|| DynStorage::<B>::empty_mask()),
+            storage: SharedGetMutOnly::new(storage),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// Joins the components of a `DynStorage` whose concrete type is `C`,
+/// returned by `Storage::downcast_join`.
+pub struct DowncastJoin<'a, B: ?Sized, C> {
+    mask: &'a BitSet,
+    storage: &'a DenseVecStorage<Box<B>>,
+    phantom: PhantomData<fn() -> C>,
+}
+
+/// Returns the component `id`, which is of type `C`.
+///
+/// # Safety
+///
+/// The storage must have a component `id` of type `C`.
+unsafe fn get<B, C>(storage: &DenseVecStorage<Box<B>>, id: Index) -> &C
+where
+    B: ?Sized + AsAny,
+    C: Any,
+{
+    // SAFETY: The caller checked that there is a component.
+    let component = unsafe { storage.get(id) };
+    (**component)
+        .as_any()
+        .downcast_ref()
+        .expect("The masks of the concrete types are kept up to date")
+}
+
+// SAFETY: The mask of `C` is a subset of the mask of the storage, and `get`
+// only reads the storage.
+unsafe impl<'a, B, C> Join for DowncastJoin<'a, B, C>
+where
+    B: ?Sized + AsAny,
+    C: Any,
+{
+    type Mask = &'a BitSet;
+    type Type = &'a C;
+    type Value = &'a DenseVecStorage<Box<B>>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, self.storage)
+    }
+
+    unsafe fn get(storage: &mut Self::Value, id: Index) -> &'a C {
+        // SAFETY: `id` is in the mask of `C`.
+        unsafe { get(storage, id) }
+    }
+}
+
+// SAFETY: `get` only reads the storage, so it is safe to call concurrently.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, B, C> ParJoin for DowncastJoin<'a, B, C>
+where
+    B: ?Sized + AsAny + Sync,
+    C: Any + Sync,
+{
+    type Mask = &'a BitSet;
+    type Type = &'a C;
+    type Value = &'a DenseVecStorage<Box<B>>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, self.storage)
+    }
+
+    unsafe fn get(storage: &Self::Value, id: Index) -> &'a C {
+        // SAFETY: `id` is in the mask of `C`.
+        unsafe { get(storage, id) }
+    }
+}
+
+/// Joins the components of a `DynStorage` whose concrete type is `C`
+/// mutably, returned by `Storage::downcast_join_mut`.
+pub struct DowncastJoinMut<'a, B: ?Sized, C> {
+    mask: &'a BitSet,
+    storage: SharedGetMutOnly<'a, Box<B>, DenseVecStorage<Box<B>>>,
+    phantom: PhantomData<fn() -> C>,
+}
+
+/// Returns the component `id` mutably, which is of type `C`.
+///
+/// # Safety
+///
+/// The storage must have a component `id` of type `C`, and no other
+/// reference to it may be alive.
+unsafe fn get_mut<'a, B, C>(
+    storage: &SharedGetMutOnly<'a, Box<B>, DenseVecStorage<Box<B>>>,
+    id: Index,
+) -> &'a mut C
+where
+    B: ?Sized + AsAny,
+    C: Any,
+{
+    // SAFETY: Requirements passed to the caller.
+    let component = unsafe { SharedGetMutOnly::get_mut(storage, id) };
+    (**component)
+        .as_any_mut()
+        .downcast_mut()
+        .expect("The masks of the concrete types are kept up to date")
+}
+
+// SAFETY: The mask of `C` is a subset of the mask of the storage, and iterating
+// it does not repeat indices, so the mutable references don't alias. Those
+// can't replace the boxed components, which keeps the masks valid.
+unsafe impl<'a, B, C> Join for DowncastJoinMut<'a, B, C>
+where
+    B: ?Sized + AsAny,
+    C: Any,
+{
+    type Mask = &'a BitSet;
+    type Type = &'a mut C;
+    type Value = SharedGetMutOnly<'a, Box<B>, DenseVecStorage<Box<B>>>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, self.storage)
+    }
+
+    unsafe fn get(storage: &mut Self::Value, id: Index) -> &'a mut C {
+        // SAFETY: `id` is in the mask of `C`, and the caller only calls this
+        // once per `id`.
+        unsafe { get_mut(storage, id) }
+    }
+}
+
+// SAFETY: `DenseVecStorage` is a `DistinctStorage`, so `get` can be called
+// concurrently for distinct ids.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, B, C> ParJoin for DowncastJoinMut<'a, B, C>
+where
+    B: ?Sized + AsAny + Send + Sync,
+    C: Any + Send + Sync,
+{
+    type Mask = &'a BitSet;
+    type Type = &'a mut C;
+    type Value = SharedGetMutOnly<'a, Box<B>, DenseVecStorage<Box<B>>>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, self.storage)
+    }
+
+    unsafe fn get(storage: &Self::Value, id: Index) -> &'a mut C {
+        // SAFETY: `id` is in the mask of `C`, and the caller doesn't call this
+        // with the same `id` while the returned reference is alive.
+        unsafe { get_mut(storage, id) }
+    }
+}
@@ -212,12 +212,15 @@ where
     /// Inserts a value into the storage and returns the old one.
     pub fn insert(&mut self, mut component: T) -> T {
         core::mem::swap(&mut component, self.get_mut().access_mut());
+        let e = self.storage.entities.entity(self.id);
+        self.storage.data.inserted(e);
         component
     }
 
     /// Removes the component from the storage and returns it.
     pub fn remove(self) -> T {
-        self.storage.data.remove(self.id).unwrap()
+        let e = self.storage.entities.entity(self.id);
+        self.storage.data.remove_observed(e).unwrap()
     }
 }
 
@@ -234,11 +237,25 @@ where
     D: DerefMut<Target = MaskedStorage<T>>,
 {
     /// Inserts a value into the storage.
+    ///
+    /// ## Panics
+    ///
+    /// Panics in debug builds if a validator registered with
+    /// `WorldExt::register_validator` rejects the component, since there's no
+    /// way to return the error. Use `Storage::insert_new` to handle it.
     pub fn insert(self, component: T) -> AccessMutReturn<'a, T> {
+        let e = self.storage.entities.entity(self.id);
+        if let Err(err) = self.storage.data.validate(e, &component) {
+            panic!("{}", err);
+        }
+
         // Note, this method adds `id` to the mask.
         // SAFETY: `VacantEntry` is only constructed after checking that `id` is
         // not present in the mask and we consume `VacantEntry` here.
-        unsafe { self.storage.not_present_insert(self.id, component) };
+        unsafe {
+            self.storage
+                .not_present_insert(self.id, component, UnprotectedStorage::insert)
+        };
         // TODO (perf): We could potentially have an insert method that directly
         // produces a reference to the just inserted value.
         // SAFETY: We just inserted the component above.
@@ -290,4 +307,21 @@ where
             StorageEntry::Vacant(vacant) => vacant.insert(default()),
         }
     }
+
+    /// Inserts a component created by the constructor registered with
+    /// `WorldExt::register_with_ctor` if the entity does not have it already.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if no constructor is registered for `T`.
+    pub fn or_construct(self) -> AccessMutReturn<'a, T> {
+        match self {
+            StorageEntry::Occupied(occupied) => occupied.into_mut(),
+            StorageEntry::Vacant(vacant) => {
+                let entity = vacant.storage.entities.entity(vacant.id);
+                let component = vacant.storage.construct(entity);
+                vacant.insert(component)
+            }
+        }
+    }
 }
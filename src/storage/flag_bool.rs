@@ -0,0 +1,262 @@
+use std::ops::{Deref, DerefMut};
+
+use hibitset::{BitSet, BitSetAll, BitSetLike};
+
+#[nougat::gat(Type)]
+use crate::join::LendJoin;
+#[cfg(feature = "parallel")]
+use crate::join::ParJoin;
+use crate::{
+    error::{Error, WrongGeneration},
+    join::{Join, RepeatableLendGet},
+    storage::{
+        DistinctStorage, MaskedStorage, NullStorage, SharedGetMutStorage, Storage,
+        UnprotectedStorage,
+    },
+    world::{Component, Entity, Index},
+};
+
+/// A storage for boolean flags, where having the component means `true` and
+/// not having it means `false`.
+///
+/// Like `NullStorage`, this can only be used with zero sized components and
+/// stores nothing but the mask. On top of the usual storage API it adds
+/// methods to treat the component as a `bool`, like `Storage::value`,
+/// `Storage::set` and `Storage::toggle`, and a join yielding the flag by
+/// value for every entity via `Storage::values`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, storage::FlagBoolStorage};
+///
+/// #[derive(Default)]
+/// struct Visible;
+///
+/// impl Component for Visible {
+///     type Storage = FlagBoolStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Visible>();
+/// let a = world.create_entity().build();
+/// let b = world.create_entity().with(Visible).build();
+///
+/// let mut visible = world.write_storage::<Visible>();
+/// assert_eq!(visible.value(a), Some(false));
+/// visible.toggle(a).unwrap();
+/// visible.set(b, false).unwrap();
+///
+/// let entities = world.entities();
+/// let flags: Vec<_> = (&entities, visible.values())
+///     .join()
+///     .map(|(_, v)| v)
+///     .collect();
+/// assert_eq!(flags, vec![true, false]);
+/// ```
+pub struct FlagBoolStorage<T>(NullStorage<T>);
+
+impl<T> Default for FlagBoolStorage<T> {
+    fn default() -> Self {
+        FlagBoolStorage(NullStorage::default())
+    }
+}
+
+impl<T> UnprotectedStorage<T> for FlagBoolStorage<T> {
+    type AccessMut<'a> = &'a mut T where T: 'a;
+
+    unsafe fn clean<B>(&mut self, has: B)
+    where
+        B: BitSetLike,
+    {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.clean(has) };
+    }
+
+    unsafe fn get(&self, id: Index) -> &T {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.get(id) }
+    }
+
+    unsafe fn get_mut(&mut self, id: Index) -> &mut T {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.get_mut(id) }
+    }
+
+    unsafe fn insert(&mut self, id: Index, v: T) {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.insert(id, v) };
+    }
+
+    unsafe fn remove(&mut self, id: Index) -> T {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.remove(id) }
+    }
+
+    fn maintain(&mut self) {
+        self.0.maintain();
+    }
+}
+
+impl<T> SharedGetMutStorage<T> for FlagBoolStorage<T> {
+    unsafe fn shared_get_mut(&self, id: Index) -> &mut T {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.shared_get_mut(id) }
+    }
+}
+
+// SAFETY: `shared_get_mut` is forwarded to `NullStorage` which implements
+// `DistinctStorage`.
+unsafe impl<T> DistinctStorage for FlagBoolStorage<T> {}
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component<Storage = FlagBoolStorage<T>>,
+    D: Deref<Target = MaskedStorage<T>>,
+{
+    /// Returns the flag of an `Entity`, or `None` if the entity is dead.
+    pub fn value(&self, e: Entity) -> Option<bool> {
+        if self.fetched_entities().is_alive(e) {
+            Some(self.mask().contains(e.id()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a joinable structure that yields the flag of every index by
+    /// value, `true` for entities having the component and `false` for all
+    /// others.
+    ///
+    /// WARNING: Like `MaybeJoin`, this yields every single index when joined
+    /// on its own. Add an `EntitiesRes` to the join to bound it to all
+    /// entities that are alive.
+    pub fn values(&self) -> FlagValues<'_> {
+        FlagValues(self.mask())
+    }
+}
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component<Storage = FlagBoolStorage<T>> + Default,
+    D: DerefMut<Target = MaskedStorage<T>>,
+{
+    /// Sets the flag of an `Entity`, inserting or removing the component.
+    ///
+    /// Returns the previous flag.
+    pub fn set(&mut self, e: Entity, value: bool) -> Result<bool, Error> {
+        if !self.fetched_entities().is_alive(e) {
+            return Err(Error::WrongGeneration(WrongGeneration {
+                action: "set flag for entity",
+                actual_gen: self.fetched_entities().entity(e.id()).gen(),
+                entity: e,
+            }));
+        }
+
+        let previous = self.mask().contains(e.id());
+        if value && !previous {
+            self.insert(e, T::default())?;
+        } else if !value && previous {
+            self.remove(e);
+        }
+
+        Ok(previous)
+    }
+
+    /// Flips the flag of an `Entity`.
+    ///
+    /// Returns the new flag.
+    pub fn toggle(&mut self, e: Entity) -> Result<bool, Error> {
+        let previous = self.mask().contains(e.id());
+        self.set(e, !previous).map(|_| !previous)
+    }
+
+    /// Sets the flag of all alive entities in `mask`.
+    ///
+    /// Indices in `mask` that don't belong to an alive entity are ignored.
+    pub fn set_all<B>(&mut self, mask: B, value: bool)
+    where
+        B: BitSetLike,
+    {
+        for id in mask.iter() {
+            let e = self.fetched_entities().entity(id);
+            if e.gen().is_alive() && self.fetched_entities().is_alive(e) {
+                // Can't fail, we just checked that `e` is alive.
+                let _ = self.set(e, value);
+            }
+        }
+    }
+}
+
+/// Joinable structure yielding the flag of a `FlagBoolStorage` by value.
+///
+/// Returned from `Storage::values`.
+pub struct FlagValues<'a>(&'a BitSet);
+
+// SAFETY: We return a mask containing all items, and `get` only reads the
+// original mask. Iterating the mask does not repeat indices.
+#[nougat::gat]
+unsafe impl<'a> LendJoin for FlagValues<'a> {
+    type Mask = BitSetAll;
+    type Type<'next> = bool;
+    type Value = &'a BitSet;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (BitSetAll, self.0)
+    }
+
+    unsafe fn get(mask: &mut Self::Value, id: Index) -> bool {
+        mask.contains(id)
+    }
+
+    #[inline]
+    fn is_unconstrained() -> bool {
+        true
+    }
+}
+
+// SAFETY: <FlagValues as LendJoin>::get only reads the mask.
+unsafe impl RepeatableLendGet for FlagValues<'_> {}
+
+// SAFETY: We return a mask containing all items, and `get` only reads the
+// original mask. Iterating the mask does not repeat indices.
+unsafe impl<'a> Join for FlagValues<'a> {
+    type Mask = BitSetAll;
+    type Type = bool;
+    type Value = &'a BitSet;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (BitSetAll, self.0)
+    }
+
+    unsafe fn get(mask: &mut Self::Value, id: Index) -> bool {
+        mask.contains(id)
+    }
+
+    #[inline]
+    fn is_unconstrained() -> bool {
+        true
+    }
+}
+
+// SAFETY: `get` only reads the mask so it is safe to call concurrently.
+// We return a mask containing all items. Iterating the mask does not repeat
+// indices.
+#[cfg(feature = "parallel")]
+unsafe impl<'a> ParJoin for FlagValues<'a> {
+    type Mask = BitSetAll;
+    type Type = bool;
+    type Value = &'a BitSet;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (BitSetAll, self.0)
+    }
+
+    unsafe fn get(mask: &Self::Value, id: Index) -> bool {
+        mask.contains(id)
+    }
+
+    #[inline]
+    fn is_unconstrained() -> bool {
+        true
+    }
+}
@@ -4,17 +4,22 @@ use hibitset::BitSetLike;
 
 use crate::{
     storage::{
-        ComponentEvent, DenseVecStorage, SharedGetMutStorage, SyncUnsafeCell, Tracked, TryDefault,
-        UnprotectedStorage,
+        ComponentEvent, DenseVecStorage, MaskClearing, SharedGetMutStorage, SyncUnsafeCell,
+        Tracked, TrackedMasks, TryDefault, UnprotectedStorage,
     },
     world::{Component, Index},
 };
 
+#[cfg(feature = "tracking")]
 use shrev::EventChannel;
 
 /// Wrapper storage that tracks modifications, insertions, and removals of
 /// components through an `EventChannel`.
 ///
+/// Without the `tracking` feature, there is no event channel and the storage
+/// maintains its `TrackedMasks` by default instead, see
+/// `FlaggedStorage::with_masks`.
+///
 /// **Note:** Joining over all components of a `FlaggedStorage`
 /// mutably will flag all components.
 ///
@@ -24,7 +29,8 @@ use shrev::EventChannel;
 ///
 /// # Examples
 ///
-/// ```
+#[cfg_attr(feature = "tracking", doc = "```")]
+#[cfg_attr(not(feature = "tracking"), doc = "```ignore")]
 /// extern crate specs;
 ///
 /// use specs::prelude::*;
@@ -168,7 +174,10 @@ use shrev::EventChannel;
 /// }
 /// ```
 pub struct FlaggedStorage<C, T = DenseVecStorage<C>> {
+    #[cfg(feature = "tracking")]
     channel: SyncUnsafeCell<EventChannel<ComponentEvent>>,
+    masks: SyncUnsafeCell<Option<TrackedMasks>>,
+    mask_clearing: MaskClearing,
     storage: T,
     #[cfg(feature = "storage-event-control")]
     event_emission: bool,
@@ -176,6 +185,83 @@ pub struct FlaggedStorage<C, T = DenseVecStorage<C>> {
 }
 
 impl<C, T> FlaggedStorage<C, T> {
+    /// Makes this storage maintain `TrackedMasks` of the inserted, modified,
+    /// removed and loaded components next to the event channel, cleared as
+    /// specified by `clearing`.
+    ///
+    /// This allows simple systems to join against the masks returned by
+    /// `Storage::inserted_mask` and friends instead of reading the channel.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{prelude::*, storage::MaskClearing};
+    ///
+    /// pub struct Comp(u32);
+    ///
+    /// impl Component for Comp {
+    ///     type Storage = FlaggedStorage<Self, VecStorage<Self>>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_with_storage::<_, Comp>(|| {
+    ///     FlaggedStorage::default().with_masks(MaskClearing::Maintain)
+    /// });
+    ///
+    /// let a = world.create_entity().with(Comp(1)).build();
+    /// world.create_entity().with(Comp(2)).build();
+    /// world.maintain();
+    ///
+    /// let mut comps = world.write_storage::<Comp>();
+    /// comps.get_mut(a).unwrap().0 += 1;
+    ///
+    /// let modified: Vec<_> = (&world.entities(), comps.modified_mask())
+    ///     .join()
+    ///     .map(|(e, _)| e)
+    ///     .collect();
+    /// assert_eq!(modified, vec![a]);
+    /// ```
+    pub fn with_masks(mut self, clearing: MaskClearing) -> Self {
+        self.set_masks(Some(clearing));
+
+        self
+    }
+
+    /// Enables maintaining `TrackedMasks`, see `FlaggedStorage::with_masks`,
+    /// or disables it by passing `None`.
+    ///
+    /// Enabling the masks again keeps their current contents.
+    pub fn set_masks(&mut self, clearing: Option<MaskClearing>) {
+        let masks = self.masks.get_mut();
+        match clearing {
+            Some(clearing) => {
+                masks.get_or_insert_with(Default::default);
+                self.mask_clearing = clearing;
+            }
+            None => *masks = None,
+        }
+    }
+
+    /// Returns the wrapped storage, e.g. to run kernels over the slices of its
+    /// components in read-only systems.
+    ///
+    /// There's no mutable counterpart, since changes made through it couldn't
+    /// be tracked.
+    pub fn inner(&self) -> &T {
+        &self.storage
+    }
+
+    /// Writes `event` to the channel and records it in the masks.
+    fn emit(&mut self, event: ComponentEvent) {
+        if self.emit_event() {
+            #[cfg(feature = "tracking")]
+            self.channel.get_mut().single_write(event);
+            if let Some(masks) = self.masks.get_mut() {
+                masks.record(event);
+            }
+        }
+    }
+
     #[cfg(feature = "storage-event-control")]
     fn emit_event(&self) -> bool {
         self.event_emission
@@ -193,7 +279,14 @@ where
 {
     fn default() -> Self {
         FlaggedStorage {
+            #[cfg(feature = "tracking")]
             channel: SyncUnsafeCell::new(EventChannel::<ComponentEvent>::default()),
+            #[cfg(feature = "tracking")]
+            masks: SyncUnsafeCell::new(None),
+            // The masks are the only way to observe changes without a channel.
+            #[cfg(not(feature = "tracking"))]
+            masks: SyncUnsafeCell::new(Some(TrackedMasks::default())),
+            mask_clearing: MaskClearing::Maintain,
             storage: T::unwrap_default(),
             #[cfg(feature = "storage-event-control")]
             event_emission: true,
@@ -202,7 +295,11 @@ where
     }
 }
 
-impl<C: Component, T: UnprotectedStorage<C>> UnprotectedStorage<C> for FlaggedStorage<C, T> {
+impl<C, T> UnprotectedStorage<C> for FlaggedStorage<C, T>
+where
+    C: Component,
+    T: UnprotectedStorage<C> + TryDefault,
+{
     type AccessMut<'a> = <T as UnprotectedStorage<C>>::AccessMut<'a> where T: 'a;
 
     unsafe fn clean<B>(&mut self, has: B)
@@ -219,44 +316,66 @@ impl<C: Component, T: UnprotectedStorage<C>> UnprotectedStorage<C> for FlaggedSt
     }
 
     unsafe fn get_mut(&mut self, id: Index) -> <T as UnprotectedStorage<C>>::AccessMut<'_> {
-        if self.emit_event() {
-            self.channel
-                .get_mut()
-                .single_write(ComponentEvent::Modified(id));
-        }
+        self.emit(ComponentEvent::Modified(id));
         // SAFETY: Requirements passed to caller.
         unsafe { self.storage.get_mut(id) }
     }
 
     unsafe fn insert(&mut self, id: Index, comp: C) {
-        if self.emit_event() {
-            self.channel
-                .get_mut()
-                .single_write(ComponentEvent::Inserted(id));
-        }
+        self.emit(ComponentEvent::Inserted(id));
         // SAFETY: Requirements passed to caller.
         unsafe { self.storage.insert(id, comp) };
     }
 
+    unsafe fn insert_loaded(&mut self, id: Index, comp: C) {
+        self.emit(ComponentEvent::Loaded(id));
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.storage.insert_loaded(id, comp) };
+    }
+
     unsafe fn remove(&mut self, id: Index) -> C {
-        if self.emit_event() {
-            self.channel
-                .get_mut()
-                .single_write(ComponentEvent::Removed(id));
-        }
+        self.emit(ComponentEvent::Removed(id));
         // SAFETY: Requirements passed to caller.
         unsafe { self.storage.remove(id) }
     }
+
+    fn maintain(&mut self) {
+        if self.mask_clearing == MaskClearing::Maintain {
+            if let Some(masks) = self.masks.get_mut() {
+                masks.clear();
+            }
+        }
+        self.storage.maintain();
+    }
+
+    fn is_thread_local(&self) -> bool {
+        self.storage.is_thread_local()
+    }
 }
 
-impl<C: Component, T: SharedGetMutStorage<C>> SharedGetMutStorage<C> for FlaggedStorage<C, T> {
+impl<C, T> SharedGetMutStorage<C> for FlaggedStorage<C, T>
+where
+    C: Component,
+    T: SharedGetMutStorage<C> + TryDefault,
+{
     unsafe fn shared_get_mut(&self, id: Index) -> <T as UnprotectedStorage<C>>::AccessMut<'_> {
         if self.emit_event() {
-            let channel_ptr = self.channel.get();
+            #[cfg(feature = "tracking")]
+            {
+                let channel_ptr = self.channel.get();
+                // SAFETY: Caller required to ensure references returned from
+                // other safe methods such as Tracked::channel are no longer
+                // alive. This storage is not marked with a `DistinctStorage`
+                // impl.
+                unsafe { &mut *channel_ptr }.single_write(ComponentEvent::Modified(id));
+            }
+            let masks_ptr = self.masks.get();
             // SAFETY: Caller required to ensure references returned from other
-            // safe methods such as Tracked::channel are no longer alive. This
+            // safe methods such as `Tracked::masks` are no longer alive. This
             // storage is not marked with a `DistinctStorage` impl.
-            unsafe { &mut *channel_ptr }.single_write(ComponentEvent::Modified(id));
+            if let Some(masks) = unsafe { &mut *masks_ptr } {
+                masks.record(ComponentEvent::Modified(id));
+            }
         }
         // SAFETY: Requirements passed to caller.
         unsafe { self.storage.shared_get_mut(id) }
@@ -264,6 +383,7 @@ impl<C: Component, T: SharedGetMutStorage<C>> SharedGetMutStorage<C> for Flagged
 }
 
 impl<C, T> Tracked for FlaggedStorage<C, T> {
+    #[cfg(feature = "tracking")]
     fn channel(&self) -> &EventChannel<ComponentEvent> {
         let channel_ptr = self.channel.get();
         // SAFETY: The only place that mutably accesses the channel via a shared
@@ -274,10 +394,23 @@ impl<C, T> Tracked for FlaggedStorage<C, T> {
         unsafe { &*channel_ptr }
     }
 
+    #[cfg(feature = "tracking")]
     fn channel_mut(&mut self) -> &mut EventChannel<ComponentEvent> {
         self.channel.get_mut()
     }
 
+    fn masks(&self) -> Option<&TrackedMasks> {
+        let masks_ptr = self.masks.get();
+        // SAFETY: The only place that mutably accesses the masks via a shared
+        // reference is the impl of `SharedGetMut::shared_get_mut`, see
+        // `Tracked::channel` impl.
+        unsafe { &*masks_ptr }.as_ref()
+    }
+
+    fn masks_mut(&mut self) -> Option<&mut TrackedMasks> {
+        self.masks.get_mut().as_mut()
+    }
+
     #[cfg(feature = "storage-event-control")]
     fn set_event_emission(&mut self, emit: bool) {
         self.event_emission = emit;
@@ -0,0 +1,107 @@
+use std::ops::Deref;
+
+use serde::{ser::SerializeSeq, Deserialize, Serialize, Serializer};
+use shrev::ReaderId;
+
+use crate::{
+    storage::{ComponentEvent, MaskedStorage, Storage, Tracked},
+    world::Component,
+};
+
+/// A `ComponentEvent` written by `forward_events`, with an optional copy of
+/// the component.
+///
+/// The serialized form is stable: a struct with the fields `event` and
+/// `component`, where `event` is the externally tagged `ComponentEvent`,
+/// e.g. `{"Inserted":3}` in JSON.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ForwardedEvent<T> {
+    /// The event.
+    pub event: ComponentEvent,
+    /// The component of the entity at the time the event was forwarded.
+    ///
+    /// This is `None` if payloads are not forwarded, for removals and if the
+    /// component has been removed since.
+    pub component: Option<T>,
+}
+
+/// Reads the events emitted by `storage` since the last read of `reader` and
+/// serializes them as a sequence of `ForwardedEvent`s, returning the number
+/// of events written.
+///
+/// If `payloads` is `true`, the current component of the entity is included
+/// for every event but removals. The serializer usually writes to a byte
+/// stream, like a socket, for debuggers or replay recorders running in
+/// another process; calling this once per frame writes one sequence per
+/// frame, which the other end deserializes as `Vec<ForwardedEvent<T>>`.
+///
+/// ## Examples
+///
+/// ```
+/// use serde::{Deserialize, Serialize};
+/// use specs::{
+///     prelude::*,
+///     storage::{forward_events, ForwardedEvent},
+/// };
+///
+/// #[derive(Debug, Deserialize, PartialEq, Serialize)]
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = FlaggedStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// let mut reader = world.write_storage::<Pos>().register_reader();
+/// let e = world.create_entity().with(Pos(1.0)).build();
+///
+/// let mut bytes = Vec::new();
+/// let pos = world.read_storage::<Pos>();
+/// let written = forward_events(
+///     &pos,
+///     &mut reader,
+///     true,
+///     &mut serde_json::Serializer::new(&mut bytes),
+/// )
+/// .unwrap();
+/// assert_eq!(written, 1);
+///
+/// // In the other process:
+/// let events: Vec<ForwardedEvent<Pos>> = serde_json::from_slice(&bytes).unwrap();
+/// assert_eq!(events[0].event, ComponentEvent::Inserted(e.id()));
+/// assert_eq!(events[0].component, Some(Pos(1.0)));
+/// ```
+pub fn forward_events<T, D, S>(
+    storage: &Storage<T, D>,
+    reader: &mut ReaderId<ComponentEvent>,
+    payloads: bool,
+    serializer: S,
+) -> Result<usize, S::Error>
+where
+    T: Component + Serialize,
+    T::Storage: Tracked,
+    D: Deref<Target = MaskedStorage<T>>,
+    S: Serializer,
+{
+    let events = storage.channel().read(reader);
+    let len = events.len();
+    let mut seq = serializer.serialize_seq(Some(len))?;
+    let entities = storage.fetched_entities();
+    for &event in events {
+        let component = match event {
+            ComponentEvent::Inserted(id)
+            | ComponentEvent::Modified(id)
+            | ComponentEvent::Loaded(id)
+                if payloads =>
+            {
+                storage.get(entities.entity(id))
+            }
+            _ => None,
+        };
+        seq.serialize_element(&ForwardedEvent { event, component })?;
+    }
+    seq.end()?;
+
+    Ok(len)
+}
@@ -1,6 +1,13 @@
+use std::ops::DerefMut;
+
+use shred::FetchMut;
+
 use crate::storage::{AccessMut, UnprotectedStorage};
 use crate::{
-    storage::{AccessMutReturn, InsertResult, ReadStorage, WriteStorage},
+    error::WrongGeneration,
+    storage::{
+        AccessMutReturn, InsertResult, MaskedStorage, ReadStorage, StorageEntry, WriteStorage,
+    },
     world::{Component, Entity},
 };
 
@@ -14,6 +21,9 @@ pub trait GenericReadStorage {
     /// Get immutable access to an `Entity`s component
     fn get(&self, entity: Entity) -> Option<&Self::Component>;
 
+    /// Returns `true` if the storage has a component for this entity.
+    fn contains(&self, entity: Entity) -> bool;
+
     /// Private function to seal the trait
     fn _private() -> Seal;
 }
@@ -28,6 +38,10 @@ where
         ReadStorage::get(self, entity)
     }
 
+    fn contains(&self, entity: Entity) -> bool {
+        ReadStorage::contains(self, entity)
+    }
+
     fn _private() -> Seal {
         Seal
     }
@@ -43,6 +57,10 @@ where
         ReadStorage::get(*self, entity)
     }
 
+    fn contains(&self, entity: Entity) -> bool {
+        ReadStorage::contains(*self, entity)
+    }
+
     fn _private() -> Seal {
         Seal
     }
@@ -58,6 +76,10 @@ where
         WriteStorage::get(self, entity)
     }
 
+    fn contains(&self, entity: Entity) -> bool {
+        WriteStorage::contains(self, entity)
+    }
+
     fn _private() -> Seal {
         Seal
     }
@@ -73,6 +95,29 @@ where
         WriteStorage::get(*self, entity)
     }
 
+    fn contains(&self, entity: Entity) -> bool {
+        WriteStorage::contains(*self, entity)
+    }
+
+    fn _private() -> Seal {
+        Seal
+    }
+}
+
+impl<'a: 'b, 'b, T> GenericReadStorage for &'b mut WriteStorage<'a, T>
+where
+    T: Component,
+{
+    type Component = T;
+
+    fn get(&self, entity: Entity) -> Option<&Self::Component> {
+        WriteStorage::get(*self, entity)
+    }
+
+    fn contains(&self, entity: Entity) -> bool {
+        WriteStorage::contains(*self, entity)
+    }
+
     fn _private() -> Seal {
         Seal
     }
@@ -80,11 +125,15 @@ where
 
 /// Provides generic write access to `WriteStorage`, both as a value and a
 /// mutable reference.
-pub trait GenericWriteStorage {
-    /// The component type of the storage
-    type Component: Component;
+///
+/// Read access, like `contains`, is inherited from `GenericReadStorage`.
+pub trait GenericWriteStorage: GenericReadStorage {
     /// The wrapper through with mutable access of a component is performed.
     type AccessMut<'a>: AccessMut<Target = Self::Component>
+    where
+        Self: 'a;
+    /// The entry returned by `entry`.
+    type Entry<'a>: GenericEntry<'a, Component = Self::Component>
     where
         Self: 'a;
 
@@ -92,7 +141,8 @@ pub trait GenericWriteStorage {
     fn get_mut(&mut self, entity: Entity) -> Option<AccessMutReturn<'_, Self::Component>>;
 
     /// Get mutable access to an `Entity`s component. If the component does not
-    /// exist, it is automatically created using `Default::default()`.
+    /// exist, it is automatically created using the constructor registered
+    /// with `WorldExt::register_with_ctor`, or `Default::default()`.
     ///
     /// Returns None if the entity is dead.
     fn get_mut_or_default(
@@ -105,8 +155,21 @@ pub trait GenericWriteStorage {
     /// Insert a component for an `Entity`
     fn insert(&mut self, entity: Entity, comp: Self::Component) -> InsertResult<Self::Component>;
 
-    /// Remove the component for an `Entity`
-    fn remove(&mut self, entity: Entity);
+    /// Insert a component for an `Entity` that was restored from a save
+    /// rather than freshly created. See `Storage::insert_loaded`.
+    fn insert_loaded(
+        &mut self,
+        entity: Entity,
+        comp: Self::Component,
+    ) -> InsertResult<Self::Component>;
+
+    /// Returns an entry to the component of an `Entity`, or an error if the
+    /// entity is dead. See `Storage::entry`.
+    fn entry(&mut self, entity: Entity) -> Result<Self::Entry<'_>, WrongGeneration>;
+
+    /// Remove the component for an `Entity`, returning it if the entity is
+    /// alive and had one.
+    fn remove(&mut self, entity: Entity) -> Option<Self::Component>;
 
     /// Private function to seal the trait
     fn _private() -> Seal;
@@ -118,7 +181,8 @@ where
 {
     type AccessMut<'b> = <<T as Component>::Storage as UnprotectedStorage<T>>::AccessMut<'b>
         where Self: 'b;
-    type Component = T;
+    type Entry<'b> = StorageEntry<'b, 'a, T, FetchMut<'a, MaskedStorage<T>>>
+        where Self: 'b;
 
     fn get_mut(&mut self, entity: Entity) -> Option<AccessMutReturn<'_, T>> {
         WriteStorage::get_mut(self, entity)
@@ -129,7 +193,8 @@ where
         Self::Component: Default,
     {
         if !self.contains(entity) {
-            self.insert(entity, Default::default())
+            let component = self.masked_storage().construct(entity);
+            self.insert(entity, component.unwrap_or_default())
                 .ok()
                 .and_then(move |_| self.get_mut(entity))
         } else {
@@ -141,8 +206,20 @@ where
         WriteStorage::insert(self, entity, comp)
     }
 
-    fn remove(&mut self, entity: Entity) {
-        WriteStorage::remove(self, entity);
+    fn insert_loaded(
+        &mut self,
+        entity: Entity,
+        comp: Self::Component,
+    ) -> InsertResult<Self::Component> {
+        WriteStorage::insert_loaded(self, entity, comp)
+    }
+
+    fn entry(&mut self, entity: Entity) -> Result<Self::Entry<'_>, WrongGeneration> {
+        WriteStorage::entry(self, entity)
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        WriteStorage::remove(self, entity)
     }
 
     fn _private() -> Seal {
@@ -156,7 +233,8 @@ where
 {
     type AccessMut<'c> = <<T as Component>::Storage as UnprotectedStorage<T>>::AccessMut<'c>
         where Self: 'c;
-    type Component = T;
+    type Entry<'c> = StorageEntry<'c, 'a, T, FetchMut<'a, MaskedStorage<T>>>
+        where Self: 'c;
 
     fn get_mut(&mut self, entity: Entity) -> Option<AccessMutReturn<'_, T>> {
         WriteStorage::get_mut(*self, entity)
@@ -167,7 +245,8 @@ where
         Self::Component: Default,
     {
         if !self.contains(entity) {
-            self.insert(entity, Default::default())
+            let component = self.masked_storage().construct(entity);
+            self.insert(entity, component.unwrap_or_default())
                 .ok()
                 .and_then(move |_| self.get_mut(entity))
         } else {
@@ -179,8 +258,70 @@ where
         WriteStorage::insert(*self, entity, comp)
     }
 
-    fn remove(&mut self, entity: Entity) {
-        WriteStorage::remove(*self, entity);
+    fn insert_loaded(
+        &mut self,
+        entity: Entity,
+        comp: Self::Component,
+    ) -> InsertResult<Self::Component> {
+        WriteStorage::insert_loaded(*self, entity, comp)
+    }
+
+    fn entry(&mut self, entity: Entity) -> Result<Self::Entry<'_>, WrongGeneration> {
+        WriteStorage::entry(*self, entity)
+    }
+
+    fn remove(&mut self, entity: Entity) -> Option<T> {
+        WriteStorage::remove(*self, entity)
+    }
+
+    fn _private() -> Seal {
+        Seal
+    }
+}
+
+/// Provides generic access to the `StorageEntry` returned by
+/// `GenericWriteStorage::entry`.
+pub trait GenericEntry<'a> {
+    /// The component type of the entry
+    type Component: Component;
+
+    /// Inserts a component and returns the old value in case this entry was
+    /// already occupied.
+    fn replace(self, component: Self::Component) -> Option<Self::Component>;
+
+    /// Inserts a component if the entity does not have it already.
+    fn or_insert(self, component: Self::Component) -> AccessMutReturn<'a, Self::Component>;
+
+    /// Inserts a component created by `default` if the entity does not have
+    /// it already.
+    fn or_insert_with<F>(self, default: F) -> AccessMutReturn<'a, Self::Component>
+    where
+        F: FnOnce() -> Self::Component;
+
+    /// Private function to seal the trait
+    fn _private() -> Seal;
+}
+
+impl<'a, 'b, T, D> GenericEntry<'a> for StorageEntry<'a, 'b, T, D>
+where
+    T: Component,
+    D: DerefMut<Target = MaskedStorage<T>>,
+{
+    type Component = T;
+
+    fn replace(self, component: T) -> Option<T> {
+        StorageEntry::replace(self, component)
+    }
+
+    fn or_insert(self, component: T) -> AccessMutReturn<'a, T> {
+        StorageEntry::or_insert(self, component)
+    }
+
+    fn or_insert_with<F>(self, default: F) -> AccessMutReturn<'a, T>
+    where
+        F: FnOnce() -> T,
+    {
+        StorageEntry::or_insert_with(self, default)
     }
 
     fn _private() -> Seal {
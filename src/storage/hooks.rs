@@ -0,0 +1,89 @@
+use std::fmt;
+
+use crate::world::Entity;
+
+type Hook<T> = Box<dyn FnMut(Entity, &T) + Send + Sync>;
+
+/// Closures called synchronously when components are inserted into or
+/// removed from a storage, see `Storage::observe`.
+///
+/// Unlike the `ComponentEvent`s of tracked storages, which are read later by
+/// systems, hooks run immediately and in the order of the changes, e.g. to
+/// keep an index of the components up to date.
+///
+/// `on_insert` is called after a component was inserted, including when it
+/// replaced another one. `on_remove` is called before a component is dropped,
+/// when it's removed, when its entity is deleted or when the storage is
+/// cleared. Components taken out with `Storage::drain` are handed to the
+/// caller without calling `on_remove`.
+///
+/// With the `parallel` feature, `World::maintain` deletes components and
+/// applies typed lazy updates on the rayon thread pool, so hooks may be
+/// called from any of its threads, concurrently with the hooks of other
+/// storages.
+pub struct StorageHooks<T> {
+    on_insert: Option<Hook<T>>,
+    on_remove: Option<Hook<T>>,
+}
+
+impl<T> StorageHooks<T> {
+    /// Creates hooks doing nothing.
+    pub fn new() -> Self {
+        StorageHooks {
+            on_insert: None,
+            on_remove: None,
+        }
+    }
+
+    /// Sets the closure called after a component was inserted.
+    pub fn on_insert<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Entity, &T) + Send + Sync + 'static,
+    {
+        self.on_insert = Some(Box::new(f));
+
+        self
+    }
+
+    /// Sets the closure called before a component is removed.
+    pub fn on_remove<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(Entity, &T) + Send + Sync + 'static,
+    {
+        self.on_remove = Some(Box::new(f));
+
+        self
+    }
+
+    pub(crate) fn inserted(&mut self, e: Entity, component: &T) {
+        if let Some(f) = &mut self.on_insert {
+            f(e, component);
+        }
+    }
+
+    pub(crate) fn removed(&mut self, e: Entity, component: &T) {
+        if let Some(f) = &mut self.on_remove {
+            f(e, component);
+        }
+    }
+
+    /// Returns `true` if there is an `on_remove` hook.
+    pub(crate) fn observes_removal(&self) -> bool {
+        self.on_remove.is_some()
+    }
+}
+
+impl<T> Default for StorageHooks<T> {
+    fn default() -> Self {
+        StorageHooks::new()
+    }
+}
+
+impl<T> fmt::Debug for StorageHooks<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StorageHooks")
+            .field("on_insert", &self.on_insert.is_some())
+            .field("on_remove", &self.on_remove.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    any::type_name,
+    ops::Deref,
+    panic::Location,
+    sync::{Arc, Mutex, Weak},
+};
+
+use hibitset::BitSetLike;
+
+use crate::{
+    error::LeaseConflict,
+    join::Join,
+    storage::{MaskedStorage, Storage},
+    world::{Component, Entity},
+};
+
+type Components<T> = Vec<(Entity, T)>;
+
+/// The leases of a `MaskedStorage`, registered until the last clone of a
+/// `Lease` is dropped.
+pub(crate) struct Leases<T>(Mutex<LeaseState<T>>);
+
+struct LeaseState<T> {
+    /// Where the leases were taken, with a handle telling if they are alive.
+    active: Vec<(&'static Location<'static>, Weak<()>)>,
+    /// The components shared by the active leases.
+    shared: Weak<Components<T>>,
+}
+
+// SAFETY: `shared` is only set and upgraded by `Storage::lease`, which
+// requires `T: Send + Sync`.
+unsafe impl<T> Send for LeaseState<T> {}
+
+impl<T> Default for Leases<T> {
+    fn default() -> Self {
+        Leases(Mutex::new(LeaseState {
+            active: Vec::new(),
+            shared: Weak::new(),
+        }))
+    }
+}
+
+impl<T> Leases<T> {
+    /// Returns an error listing the active leases, if any.
+    fn check(&self) -> Result<(), LeaseConflict> {
+        let mut state = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.active.retain(|(_, alive)| alive.strong_count() > 0);
+        if state.active.is_empty() {
+            return Ok(());
+        }
+
+        Err(LeaseConflict {
+            component: type_name::<T>(),
+            leases: state.active.iter().map(|&(location, _)| location).collect(),
+        })
+    }
+}
+
+impl<T: Component> MaskedStorage<T> {
+    /// Returns an error listing the active leases of this storage, if any.
+    pub(crate) fn check_leases(&self) -> Result<(), LeaseConflict> {
+        self.leases.check()
+    }
+}
+
+/// An owned snapshot of the components of a storage, returned by
+/// `Storage::lease`.
+///
+/// A lease is `'static`, so background jobs can hold it across frames. As
+/// long as it or one of its clones is alive, fetching a `WriteStorage` of
+/// the component panics with a `LeaseConflict` listing where the active
+/// leases were taken, and `WorldExt::try_write_storage` returns it as
+/// `Error::LeaseConflict`.
+///
+/// Leases taken while another one is alive share its components, since the
+/// storage can't have been written in between, so the components are only
+/// copied once for overlapping leases.
+pub struct Lease<T> {
+    components: Arc<Components<T>>,
+    location: &'static Location<'static>,
+    alive: Arc<()>,
+}
+
+impl<T> Lease<T> {
+    /// Returns the component `entity` had when the lease was taken.
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        let i = self
+            .components
+            .binary_search_by_key(&entity.id(), |(e, _)| e.id())
+            .ok()?;
+        let (e, component) = &self.components[i];
+
+        (*e == entity).then_some(component)
+    }
+
+    /// Iterates over the entities and components of the lease, in ascending
+    /// order of the entity ids.
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.components.iter().map(|(e, c)| (*e, c))
+    }
+
+    /// Returns the number of components in the lease.
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Returns `true` if the lease has no components.
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    /// Returns where the lease was taken.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+impl<T> Clone for Lease<T> {
+    fn clone(&self) -> Self {
+        Lease {
+            components: self.components.clone(),
+            location: self.location,
+            alive: self.alive.clone(),
+        }
+    }
+}
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component + Clone + Send + Sync,
+    D: Deref<Target = MaskedStorage<T>>,
+{
+    /// Takes an owned read lease of the components of this storage, a
+    /// snapshot background jobs can hold across frames.
+    ///
+    /// While the lease is alive, the storage must not be written: fetching
+    /// a `WriteStorage` of `T` panics with the locations of the active
+    /// leases, instead of silently diverging from the snapshot.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// let e = world.create_entity().with(Pos(1.0)).build();
+    ///
+    /// let lease = world.read_storage::<Pos>().lease();
+    /// let job = {
+    ///     let lease = lease.clone();
+    ///     std::thread::spawn(move || lease.get(e).cloned())
+    /// };
+    /// assert!(world.try_write_storage::<Pos>().is_err());
+    ///
+    /// assert_eq!(job.join().unwrap(), Some(Pos(1.0)));
+    /// drop(lease);
+    /// assert!(world.try_write_storage::<Pos>().is_ok());
+    /// ```
+    #[track_caller]
+    pub fn lease(&self) -> Lease<T> {
+        let mut state = self.data.leases.0.lock().unwrap_or_else(|e| e.into_inner());
+        // Components can still be removed by `World::maintain` while the
+        // storage is leased, so the shared ones are checked against the mask.
+        let shared = state.shared.upgrade().filter(|shared| {
+                self.mask()
+                    .iter()
+                    .eq(shared.iter().map(|(e, _)| e.id()))
+            });
+        let components = shared.unwrap_or_else(|| {
+            let components: Arc<Components<T>> = Arc::new(
+                (&self.entities, self)
+                    .join()
+                    .map(|(e, c)| (e, c.clone()))
+                    .collect(),
+            );
+            state.shared = Arc::downgrade(&components);
+            components
+        });
+
+        let location = Location::caller();
+        let alive = Arc::new(());
+        state.active.push((location, Arc::downgrade(&alive)));
+
+        Lease {
+            components,
+            location,
+            alive,
+        }
+    }
+}
@@ -1,63 +1,154 @@
 //! Component storage types, implementations for component joins, etc.
 
+#[cfg(feature = "tracking")]
 pub use self::deref_flagged::{DerefFlaggedStorage, FlaggedAccessMut};
+#[cfg(all(feature = "serde", feature = "tracking"))]
+pub use self::forward::{forward_events, ForwardedEvent};
+#[cfg(feature = "tracking")]
+pub use self::replication::{Replication, ReplicationKind, ReplicationQueue};
+#[cfg(feature = "zeroize")]
+pub use self::zeroize::{ZeroizeOnRemove, ZeroizeStorage, ZeroizeVacated};
 pub use self::{
     data::{ReadStorage, WriteStorage},
+    dynamic::{AsAny, DowncastJoin, DowncastJoinMut, DynAccessMut, DynStorage},
     entry::{Entries, OccupiedEntry, StorageEntry, VacantEntry},
+    flag_bool::{FlagBoolStorage, FlagValues},
     flagged::FlaggedStorage,
-    generic::{GenericReadStorage, GenericWriteStorage},
+    generic::{GenericEntry, GenericReadStorage, GenericWriteStorage},
+    hooks::StorageHooks,
+    lease::Lease,
+    non_send::{NonSendStorage, NonSendWriteStorage},
+    partition::Partition,
+    registry::{StorageAccessor, StorageRegistry},
     restrict::{
         PairedStorageRead, PairedStorageWriteExclusive, PairedStorageWriteShared,
         RestrictedStorage, SharedGetOnly,
     },
+    sharded::{ShardedComponent, ShardedRead, ShardedWrite, ShardedWriteAll},
+    soa::{Column, ColumnMut, Rows, SoAColumns, SoAComponent, SoARef, SoARowMut, SoAStorage},
     storages::{
-        BTreeStorage, DefaultVecStorage, DenseVecStorage, HashMapStorage, NullStorage, SliceAccess,
-        VecStorage,
+        AlignedVecStorage, AutoStorage, BTreeStorage, DefaultVecStorage, DenseVecStorage,
+        HashMapStorage, IdSliceAccess, NullStorage, SliceAccess, VecStorage,
     },
-    track::{ComponentEvent, Tracked},
+    track::{ComponentEvent, MaskClearing, Tracked, TrackedMasks},
 };
 
 use std::{
     self,
+    any::Any,
     marker::PhantomData,
-    ops::{Deref, DerefMut, Not},
+    ops::{Deref, DerefMut, Not, Range},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Arc,
 };
 
-use hibitset::{BitSet, BitSetLike, BitSetNot};
-use shred::{CastFrom, Fetch};
+use hibitset::{BitSet, BitSetAnd, BitSetLike, BitSetNot};
+#[cfg(feature = "parallel")]
+use rayon::iter::ParallelIterator;
+use shred::{CastFrom, Fetch, World};
 
 #[nougat::gat(Type)]
 use crate::join::LendJoin;
 #[cfg(feature = "parallel")]
 use crate::join::ParJoin;
 use crate::{
-    error::{Error, WrongGeneration},
-    join::{Join, RepeatableLendGet},
-    world::{Component, EntitiesRes, Entity, Index},
+    bitset::{self, CountedBitSet},
+    error::{Error, InsertError, InvalidComponent, SliceTooShort, WrongGeneration},
+    join::{InIndexRange, Join, JoinIter, RepeatableLendGet, Snapshot},
+    world::{Component, EntitiesRes, Entity, Generation, Index, WorldExt},
 };
 
-use self::drain::Drain;
+use self::{drain::Drain, lease::Leases};
+pub(crate) use self::sharded::{setup as setup_sharded, ShardMaintainers};
 use self::sync_unsafe_cell::SyncUnsafeCell;
 
 mod data;
+#[cfg(feature = "tracking")]
 mod deref_flagged;
 mod drain;
+mod dynamic;
 mod entry;
+mod flag_bool;
 mod flagged;
+#[cfg(all(feature = "serde", feature = "tracking"))]
+mod forward;
 mod generic;
+mod hooks;
+mod lease;
+mod non_send;
+mod partition;
+mod registry;
+#[cfg(feature = "tracking")]
+mod replication;
 mod restrict;
+mod sharded;
+mod soa;
 mod storages;
 mod sync_unsafe_cell;
 #[cfg(test)]
 mod tests;
 mod track;
+#[cfg(feature = "zeroize")]
+mod zeroize;
 
 type AccessMutReturn<'a, T> = <<T as Component>::Storage as UnprotectedStorage<T>>::AccessMut<'a>;
 
 /// An inverted storage type, only useful to iterate entities
 /// that do not have a particular component type.
+///
+/// Its mask contains every index not in the storage, so joining it alone
+/// iterates up to the largest possible index. Join it together with a
+/// constrained storage, like `Entities`, or use `AntiStorage::bounded`.
 pub struct AntiStorage<'a>(pub &'a BitSet);
 
+impl<'a> AntiStorage<'a> {
+    /// Returns `true` if the storage has no component at `id`, i.e. if the
+    /// anti storage matches it.
+    pub fn contains(&self, id: Index) -> bool {
+        !self.0.contains(id)
+    }
+
+    /// Creates an anti storage matching the indices of `bound` which are not
+    /// in `mask`, where `bound` is e.g. the mask of the alive entities or of
+    /// another storage.
+    ///
+    /// Unlike an `AntiStorage`, this can be joined alone.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{prelude::*, storage::AntiStorage};
+    ///
+    /// struct Pos;
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// struct Vel;
+    ///
+    /// impl Component for Vel {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// world.register::<Vel>();
+    /// world.create_entity().with(Pos).with(Vel).build();
+    /// let still = world.create_entity().with(Pos).build();
+    ///
+    /// let pos = world.read_storage::<Pos>();
+    /// let vel = world.read_storage::<Vel>();
+    /// // Entities with a position but without a velocity.
+    /// let without_vel = AntiStorage::bounded(vel.mask(), pos.mask());
+    /// assert!(without_vel.contains(still.id()));
+    /// assert_eq!(without_vel.join().count(), 1);
+    /// ```
+    pub fn bounded(mask: &'a BitSet, bound: &'a BitSet) -> BoundedAntiStorage<'a> {
+        BoundedAntiStorage { mask, bound }
+    }
+}
+
 // SAFETY: Items are just `()` and it is always safe to retrieve them regardless
 // of the mask and value returned by `open`.
 #[nougat::gat]
@@ -71,6 +162,11 @@ unsafe impl<'a> LendJoin for AntiStorage<'a> {
     }
 
     unsafe fn get<'next>(_: &'next mut (), _: Index) {}
+
+    #[inline]
+    fn is_unconstrained() -> bool {
+        true
+    }
 }
 
 // SAFETY: <AntiStorage as LendJoin>::get does nothing.
@@ -88,6 +184,11 @@ unsafe impl<'a> Join for AntiStorage<'a> {
     }
 
     unsafe fn get(_: &mut (), _: Index) {}
+
+    #[inline]
+    fn is_unconstrained() -> bool {
+        true
+    }
 }
 
 // SAFETY: Since `get` does not do anything it is safe to concurrently call.
@@ -104,12 +205,92 @@ unsafe impl<'a> ParJoin for AntiStorage<'a> {
     }
 
     unsafe fn get(_: &(), _: Index) {}
+
+    #[inline]
+    fn is_unconstrained() -> bool {
+        true
+    }
+}
+
+/// An `AntiStorage` restricted to the indices of another mask, see
+/// `AntiStorage::bounded`.
+pub struct BoundedAntiStorage<'a> {
+    mask: &'a BitSet,
+    bound: &'a BitSet,
+}
+
+impl<'a> BoundedAntiStorage<'a> {
+    /// Returns `true` if `id` is in the bound but the storage has no
+    /// component at it.
+    pub fn contains(&self, id: Index) -> bool {
+        self.bound.contains(id) && !self.mask.contains(id)
+    }
+}
+
+// SAFETY: Items are just `()` and it is always safe to retrieve them regardless
+// of the mask and value returned by `open`.
+#[nougat::gat]
+unsafe impl<'a> LendJoin for BoundedAntiStorage<'a> {
+    type Mask = BitSetAnd<&'a BitSet, BitSetNot<&'a BitSet>>;
+    type Type<'next> = ();
+    type Value = ();
+
+    unsafe fn open(self) -> (Self::Mask, ()) {
+        (BitSetAnd(self.bound, BitSetNot(self.mask)), ())
+    }
+
+    unsafe fn get(_: &mut (), _: Index) {}
+}
+
+// SAFETY: <BoundedAntiStorage as LendJoin>::get does nothing.
+unsafe impl RepeatableLendGet for BoundedAntiStorage<'_> {}
+
+// SAFETY: Items are just `()` and it is always safe to retrieve them regardless
+// of the mask and value returned by `open`.
+unsafe impl<'a> Join for BoundedAntiStorage<'a> {
+    type Mask = BitSetAnd<&'a BitSet, BitSetNot<&'a BitSet>>;
+    type Type = ();
+    type Value = ();
+
+    unsafe fn open(self) -> (Self::Mask, ()) {
+        (BitSetAnd(self.bound, BitSetNot(self.mask)), ())
+    }
+
+    unsafe fn get(_: &mut (), _: Index) {}
+}
+
+// SAFETY: Since `get` does not do anything it is safe to concurrently call.
+// Items are just `()` and it is always safe to retrieve them regardless of the
+// mask and value returned by `open`.
+#[cfg(feature = "parallel")]
+unsafe impl<'a> ParJoin for BoundedAntiStorage<'a> {
+    type Mask = BitSetAnd<&'a BitSet, BitSetNot<&'a BitSet>>;
+    type Type = ();
+    type Value = ();
+
+    unsafe fn open(self) -> (Self::Mask, ()) {
+        (BitSetAnd(self.bound, BitSetNot(self.mask)), ())
+    }
+
+    unsafe fn get(_: &(), _: Index) {}
 }
 
 /// A dynamic storage.
 pub trait AnyStorage {
     /// Drop components of given entities.
     fn drop(&mut self, entities: &[Entity]);
+
+    /// Runs the maintenance of the storage, see
+    /// `UnprotectedStorage::maintain`.
+    /// Does nothing by default.
+    fn maintain(&mut self) {}
+
+    /// Returns `true` if the components may only be dropped on the thread
+    /// owning the storage, see `UnprotectedStorage::is_thread_local`.
+    /// Returns `false` by default.
+    fn is_thread_local(&self) -> bool {
+        false
+    }
 }
 
 // SAFETY: Returned pointer has a vtable valid for `T` and retains the same
@@ -128,10 +309,23 @@ where
     T: Component,
 {
     fn drop(&mut self, entities: &[Entity]) {
-        for entity in entities {
-            MaskedStorage::drop(self, entity.id());
+        let observed = self.hooks.iter().any(StorageHooks::observes_removal);
+        for &entity in entities {
+            if observed {
+                self.remove_observed(entity);
+            } else {
+                MaskedStorage::drop(self, entity.id());
+            }
         }
     }
+
+    fn maintain(&mut self) {
+        self.inner.maintain();
+    }
+
+    fn is_thread_local(&self) -> bool {
+        self.inner.is_thread_local()
+    }
 }
 
 /// This is a marker trait which requires you to uphold the following guarantee:
@@ -165,10 +359,20 @@ pub type InsertResult<T> = Result<Option<T>, Error>;
 /// The `UnprotectedStorage` together with the `BitSet` that knows
 /// about which elements are stored, and which are not.
 pub struct MaskedStorage<T: Component> {
-    mask: BitSet,
+    mask: CountedBitSet,
     inner: T::Storage,
+    ctor: Option<Constructor<T>>,
+    validators: Vec<Validator<T>>,
+    hooks: Vec<StorageHooks<T>>,
+    leases: Leases<T>,
 }
 
+/// A constructor registered with `WorldExt::register_with_ctor`.
+type Constructor<T> = Arc<dyn Fn(Entity) -> T + Send + Sync>;
+
+/// A validator registered with `WorldExt::register_validator`.
+type Validator<T> = Arc<dyn Fn(&T) -> Result<(), String> + Send + Sync>;
+
 impl<T: Component> Default for MaskedStorage<T>
 where
     T::Storage: Default,
@@ -177,6 +381,10 @@ where
         Self {
             mask: Default::default(),
             inner: Default::default(),
+            ctor: None,
+            validators: Vec::new(),
+            hooks: Vec::new(),
+            leases: Leases::default(),
         }
     }
 }
@@ -186,12 +394,170 @@ impl<T: Component> MaskedStorage<T> {
     /// a new component type within the world.
     pub fn new(inner: T::Storage) -> MaskedStorage<T> {
         MaskedStorage {
-            mask: BitSet::new(),
+            mask: CountedBitSet::default(),
             inner,
+            ctor: None,
+            validators: Vec::new(),
+            hooks: Vec::new(),
+            leases: Leases::default(),
         }
     }
 
-    fn open_mut(&mut self) -> (&BitSet, &mut T::Storage) {
+    /// Sets the constructor creating the component of an entity in place of
+    /// `Default::default()`, see `WorldExt::register_with_ctor`.
+    pub fn set_constructor<F>(&mut self, ctor: F)
+    where
+        F: Fn(Entity) -> T + Send + Sync + 'static,
+    {
+        self.ctor = Some(Arc::new(ctor));
+    }
+
+    /// Creates a component for `e` with the registered constructor, if any.
+    pub fn construct(&self, e: Entity) -> Option<T> {
+        self.ctor.as_ref().map(|ctor| ctor(e))
+    }
+
+    /// Adds a validator checking components inserted in debug builds, see
+    /// `WorldExt::register_validator`.
+    pub fn add_validator<F>(&mut self, validator: F)
+    where
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validators.push(Arc::new(validator));
+    }
+
+    /// Copies the constructor and the validators of `other`, e.g. to a fork
+    /// of the world.
+    pub(crate) fn copy_config(&mut self, other: &Self) {
+        self.ctor = other.ctor.clone();
+        self.validators = other.validators.clone();
+    }
+
+    /// Runs the validators on the component `v` inserted for `e`, returning
+    /// the error of the first one rejecting it. Does nothing in release
+    /// builds.
+    pub fn validate(&self, e: Entity, v: &T) -> Result<(), InvalidComponent> {
+        if !cfg!(debug_assertions) {
+            return Ok(());
+        }
+
+        self.validators
+            .iter()
+            .try_for_each(|validator| validator(v))
+            .map_err(|reason| InvalidComponent {
+                entity: e,
+                type_name: std::any::type_name::<T>(),
+                reason,
+            })
+    }
+
+    /// Calls the `on_insert` hooks with the component of `e`, if it has one.
+    fn inserted(&mut self, e: Entity) {
+        if self.mask.contains(e.id()) {
+            for hooks in &mut self.hooks {
+                // SAFETY: We checked the mask.
+                hooks.inserted(e, unsafe { self.inner.get(e.id()) });
+            }
+        }
+    }
+
+    /// Removes the component of `e` like `remove`, calling the `on_remove`
+    /// hooks with it.
+    fn remove_observed(&mut self, e: Entity) -> Option<T> {
+        let component = self.remove(e.id())?;
+        for hooks in &mut self.hooks {
+            hooks.removed(e, &component);
+        }
+
+        Some(component)
+    }
+
+    /// Returns the mask of the indices having a component.
+    pub fn mask(&self) -> &BitSet {
+        &self.mask
+    }
+
+    /// Returns the wrapped storage.
+    pub fn unprotected_storage(&self) -> &T::Storage {
+        &self.inner
+    }
+
+    /// Returns the mask together with the wrapped storage.
+    ///
+    /// An index contained in the mask is sufficient to call
+    /// `UnprotectedStorage::get` with it, which makes this suitable for
+    /// implementing `Join` for custom storage wrappers.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{
+    ///     prelude::*,
+    ///     storage::{MaskedStorage, UnprotectedStorage},
+    ///     world::Index,
+    ///     BitSet,
+    /// };
+    ///
+    /// /// Joins over the components of a storage, like `&ReadStorage`.
+    /// struct Components<'a, T: Component>(&'a MaskedStorage<T>);
+    ///
+    /// // SAFETY: The mask and storage come from the same `MaskedStorage`.
+    /// unsafe impl<'a, T: Component> Join for Components<'a, T> {
+    ///     type Mask = &'a BitSet;
+    ///     type Type = &'a T;
+    ///     type Value = &'a T::Storage;
+    ///
+    ///     unsafe fn open(self) -> (Self::Mask, Self::Value) {
+    ///         self.0.open()
+    ///     }
+    ///
+    ///     unsafe fn get(value: &mut Self::Value, id: Index) -> &'a T {
+    ///         // SAFETY: The caller checked that `id` is in the mask.
+    ///         unsafe { value.get(id) }
+    ///     }
+    /// }
+    ///
+    /// struct Pos(u32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// world.create_entity().with(Pos(2)).build();
+    /// world.create_entity().with(Pos(3)).build();
+    ///
+    /// let pos = world.read_storage::<Pos>();
+    /// let sum: u32 = Components(pos.masked_storage()).join().map(|p| p.0).sum();
+    /// assert_eq!(sum, 5);
+    /// ```
+    pub fn open(&self) -> (&BitSet, &T::Storage) {
+        (&self.mask, &self.inner)
+    }
+
+    /// Returns the mask together with the wrapped storage, which only allows
+    /// calling `SharedGetMutStorage::shared_get_mut` through
+    /// `SharedGetMutOnly::get_mut`.
+    ///
+    /// This is suitable for implementing `Join` and `ParJoin` for custom
+    /// storage wrappers yielding mutable components.
+    pub fn open_shared_mut(&mut self) -> (&BitSet, SharedGetMutOnly<'_, T, T::Storage>)
+    where
+        T::Storage: SharedGetMutStorage<T>,
+    {
+        (&self.mask, SharedGetMutOnly::new(&mut self.inner))
+    }
+
+    /// Returns the mask together with mutable access to the wrapped storage.
+    ///
+    /// # Safety
+    ///
+    /// The mask can't be updated through the returned reference, so the
+    /// caller must not change which indices the storage holds a component
+    /// for, e.g. by inserting or removing components or by replacing the
+    /// storage.
+    pub unsafe fn open_mut(&mut self) -> (&BitSet, &mut T::Storage) {
         (&self.mask, &mut self.inner)
     }
 
@@ -203,7 +569,31 @@ impl<T: Component> MaskedStorage<T> {
         // SAFETY: `self.mask` is the correct mask as specified. We swap in a
         // temporary empty mask to ensure if this unwinds that the mask will be
         // cleared.
-        unsafe { self.inner.clean(&mask_temp) };
+        unsafe { self.inner.clean(&*mask_temp) };
+        mask_temp.clear();
+        self.mask = mask_temp;
+    }
+
+    /// Clears the contents of this storage like `clear`, but catches the
+    /// panics of the `Drop` implementations of the components, calling
+    /// `on_panic` with the index and the payload of each, and goes on
+    /// dropping the other components instead of unwinding.
+    pub fn clear_reporting<F>(&mut self, mut on_panic: F)
+    where
+        F: FnMut(Index, Box<dyn Any + Send>),
+    {
+        // NOTE: As in `clear`, the mask is taken so it is empty if `on_panic`
+        // unwinds, leaking the remaining components.
+        let mut mask_temp = core::mem::take(&mut self.mask);
+        for id in (&*mask_temp).iter() {
+            let inner = &mut self.inner;
+            // SAFETY: `id` is in the mask we took, so it is no longer in the
+            // storage's mask even if the drop impl unwinds.
+            let dropped = catch_unwind(AssertUnwindSafe(|| unsafe { inner.drop(id) }));
+            if let Err(payload) = dropped {
+                on_panic(id, payload);
+            }
+        }
         mask_temp.clear();
         self.mask = mask_temp;
     }
@@ -285,11 +675,72 @@ where
         }
     }
 
-    /// Computes the number of elements this `Storage` contains by counting the
-    /// bits in the bit set. This operation will never be performed in
-    /// constant time.
+    /// Reads the data associated with an `Entity` without checking the mask
+    /// and the generation of the entity in release builds.
+    ///
+    /// This is meant for inner loops where the entity has already been
+    /// validated, e.g. because it was yielded by a join over this storage,
+    /// and checking it again measurably costs. Debug builds still check it
+    /// and panic if it fails.
+    ///
+    /// # Safety
+    ///
+    /// The entity must be alive and have a component in this storage, i.e.
+    /// `contains(e)` must return `true`.
+    pub unsafe fn get_unchecked(&self, e: Entity) -> &T {
+        debug_assert!(
+            self.contains(e),
+            "`get_unchecked` called for {:?}, which has no `{}` or is dead",
+            e,
+            std::any::type_name::<T>()
+        );
+        // SAFETY: The caller guarantees that the id is in the mask.
+        unsafe { self.data.inner.get(e.id()) }
+    }
+
+    /// Tries to read the data associated with an `Entity`, like `get`, but
+    /// returns an error if the entity is dead instead of `None`.
+    ///
+    /// This allows detecting stale entity handles, `Ok(None)` means that the
+    /// entity is alive but has no component.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// let a = world.create_entity().with(Pos(1.0)).build();
+    /// let b = world.create_entity().build();
+    /// world.delete_entity(a).unwrap();
+    ///
+    /// let pos = world.read_storage::<Pos>();
+    /// assert!(pos.try_get(a).is_err());
+    /// assert!(pos.try_get(b).unwrap().is_none());
+    /// ```
+    pub fn try_get(&self, e: Entity) -> Result<Option<&T>, WrongGeneration> {
+        if self.entities.is_alive(e) {
+            Ok(self.get(e))
+        } else {
+            Err(WrongGeneration {
+                action: "get component for entity",
+                actual_gen: self.entities.entity(e.id()).gen(),
+                entity: e,
+            })
+        }
+    }
+
+    /// Returns the number of elements this `Storage` contains. The mask keeps
+    /// track of it, so this operation is very cheap.
     pub fn count(&self) -> usize {
-        self.mask().iter().count()
+        self.data.mask.len()
     }
 
     /// Checks whether this `Storage` is empty. This operation is very cheap.
@@ -308,6 +759,77 @@ where
     pub fn mask(&self) -> &BitSet {
         &self.data.mask
     }
+
+    /// Returns a copy of the bitset of this storage, e.g. to persist it with
+    /// `bitset_serde` or to keep it while the storage changes.
+    ///
+    /// This copies the layers of the bitset rather than adding every index.
+    pub fn mask_owned(&self) -> BitSet {
+        BitSet::clone(&self.data.mask)
+    }
+
+    /// Iterates over the entities and components whose entity index is in
+    /// `range`, skipping the rest of the mask layer by layer.
+    ///
+    /// This is the same as joining `(&entities, &storage)` with
+    /// `LendJoin::in_index_range`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// let entities: Vec<_> = (0..10)
+    ///     .map(|_| world.create_entity().with(Pos(0.0)).build())
+    ///     .collect();
+    ///
+    /// let pos = world.read_storage::<Pos>();
+    /// let region: Vec<_> = pos.range_iter(4..6).map(|(e, _)| e).collect();
+    /// assert_eq!(region, entities[4..6]);
+    /// ```
+    pub fn range_iter(&self, range: Range<Index>) -> JoinIter<InIndexRange<(&EntitiesRes, &Self)>> {
+        (&*self.entities, self).in_index_range(range).join()
+    }
+
+    /// Returns the `MaskedStorage` holding the mask and the wrapped storage.
+    ///
+    /// This allows implementing custom storage wrappers and `Join`s, see
+    /// `MaskedStorage::open`.
+    pub fn masked_storage(&self) -> &MaskedStorage<T> {
+        &self.data
+    }
+
+    /// Copies the components into a `Snapshot`, e.g. to compare them with
+    /// their later values using `WithPrevious`.
+    pub fn snapshot(&self) -> Snapshot<T>
+    where
+        T: Clone,
+        T::Storage: Default,
+    {
+        let mut components = MaskedStorage::<T>::default();
+        let mut generations = Vec::new();
+        for id in (&*self.data.mask).iter() {
+            // SAFETY: We checked the mask of this storage, and the snapshot
+            // doesn't contain `id` yet.
+            unsafe {
+                let component = self.data.inner.get(id).clone();
+                components.inner.insert(id, component);
+            }
+            components.mask.add(id);
+            generations.resize(id as usize + 1, Generation::one());
+            generations[id as usize] = self.entities.entity(id).gen();
+        }
+
+        Snapshot::new(components, generations)
+    }
 }
 
 impl<'e, T, D> Storage<'e, T, D>
@@ -340,6 +862,347 @@ where
     }
 }
 
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component,
+    D: DerefMut<Target = MaskedStorage<T>>,
+    T::Storage: SharedGetMutStorage<T>,
+{
+    /// Pairs every component with the element of `data` at the id of its
+    /// entity, e.g. to fill externally owned buffers that are laid out like
+    /// `VecStorage::as_slice`.
+    ///
+    /// Returns an error if `data` is too short to cover the id of every
+    /// entity having the component; on success, the returned iterator yields
+    /// the pairs in order of entity id.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// world.create_entity().build();
+    /// world.create_entity().with(Pos(1.0)).build();
+    ///
+    /// let mut staging = [0.0; 2];
+    /// let mut positions = world.write_storage::<Pos>();
+    /// for (pos, staged) in positions.join_with_slice(&mut staging).unwrap() {
+    ///     *staged = pos.0;
+    ///     pos.0 += 1.0;
+    /// }
+    ///
+    /// assert_eq!(staging, [0.0, 1.0]);
+    /// assert!(positions.join_with_slice(&mut staging[..1]).is_err());
+    /// ```
+    pub fn join_with_slice<'a, U>(
+        &'a mut self,
+        data: &'a mut [U],
+    ) -> Result<
+        impl Iterator<
+            Item = (
+                <T::Storage as UnprotectedStorage<T>>::AccessMut<'a>,
+                &'a mut U,
+            ),
+        >,
+        Error,
+    > {
+        let (mask, inner) = self.data.open();
+        if let Some(last) = bitset::last_index(mask) {
+            if last as usize >= data.len() {
+                return Err(Error::SliceTooShort(SliceTooShort {
+                    len: data.len(),
+                    required: last as usize + 1,
+                }));
+            }
+        }
+
+        let mut rest = data;
+        let mut offset = 0;
+
+        Ok(mask.iter().map(move |id| {
+            // NOTE: `as` cast is not lossy since we checked that `data` has an
+            // element at the highest id.
+            let index = id as usize;
+            // Ids are yielded in ascending order, so we can split off the
+            // elements up to this id.
+            let (head, tail) = std::mem::take(&mut rest).split_at_mut(index + 1 - offset);
+            rest = tail;
+            offset = index + 1;
+            // SAFETY: The id is in the mask, so the component is present.
+            // Each id is yielded only once and we hold exclusive access to the
+            // storage for `'a`, so the returned references can't alias.
+            let component = unsafe { inner.shared_get_mut(id) };
+            (component, &mut head[head.len() - 1])
+        }))
+    }
+
+    /// Exchanges the components of `a` and `b`.
+    ///
+    /// If only one of the entities has a component, it is moved to the
+    /// other one. Tracked storages emit `ComponentEvent::Modified` for both
+    /// entities if both have a component, or `ComponentEvent::Removed` and
+    /// `ComponentEvent::Inserted` for a moved component.
+    ///
+    /// Returns an error, without changing anything, if either entity is
+    /// dead.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Slot(u32);
+    ///
+    /// impl Component for Slot {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Slot>();
+    /// let a = world.create_entity().with(Slot(1)).build();
+    /// let b = world.create_entity().with(Slot(2)).build();
+    /// let c = world.create_entity().build();
+    ///
+    /// let mut slots = world.write_storage::<Slot>();
+    /// slots.swap(a, b).unwrap();
+    /// assert_eq!((slots.get(a), slots.get(b)), (Some(&Slot(2)), Some(&Slot(1))));
+    ///
+    /// slots.swap(a, c).unwrap();
+    /// assert_eq!((slots.get(a), slots.get(c)), (None, Some(&Slot(2))));
+    /// ```
+    pub fn swap(&mut self, a: Entity, b: Entity) -> Result<(), Error> {
+        for e in [a, b] {
+            if !self.entities.is_alive(e) {
+                return Err(Error::WrongGeneration(WrongGeneration {
+                    action: "swap components of entity",
+                    actual_gen: self.entities.entity(e.id()).gen(),
+                    entity: e,
+                }));
+            }
+        }
+        if a == b {
+            return Ok(());
+        }
+
+        match (self.data.mask.contains(a.id()), self.data.mask.contains(b.id())) {
+            (true, true) => {
+                // SAFETY: Both ids are in the mask and they are distinct, so
+                // the references don't alias. We hold exclusive access to the
+                // storage while they are alive.
+                unsafe {
+                    let inner = &self.data.inner;
+                    let mut x = inner.shared_get_mut(a.id());
+                    let mut y = inner.shared_get_mut(b.id());
+                    std::mem::swap(x.access_mut(), y.access_mut());
+                }
+                self.data.inserted(a);
+                self.data.inserted(b);
+            }
+            (true, false) => self.move_component(a, b),
+            (false, true) => self.move_component(b, a),
+            (false, false) => {}
+        }
+
+        Ok(())
+    }
+
+    /// Moves the component of `from` to `to`, which has none.
+    fn move_component(&mut self, from: Entity, to: Entity) {
+        let component = self
+            .data
+            .remove_observed(from)
+            .expect("`from` has a component");
+        // SAFETY: `to` has no component.
+        unsafe { self.not_present_insert(to.id(), component, UnprotectedStorage::insert) };
+    }
+}
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component<Storage = DenseVecStorage<T>>,
+    D: Deref<Target = MaskedStorage<T>>,
+{
+    /// Joins this storage with `other` by walking the dense component array
+    /// and checking the ids of their entities against the mask of `other`,
+    /// instead of intersecting the masks of both.
+    ///
+    /// This avoids the indirection through the sparse id table and the
+    /// bitset iteration, so it is faster than `(&storage, other).join()` if
+    /// this storage is the smallest of the join. Unlike a regular join, the
+    /// components are yielded in storage order rather than in order of entity
+    /// id. To iterate over only this storage, join it with `&entities`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = DenseVecStorage<Self>;
+    /// }
+    ///
+    /// struct Vel(f32);
+    ///
+    /// impl Component for Vel {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// world.register::<Vel>();
+    /// world.create_entity().with(Pos(0.0)).build();
+    /// let e = world.create_entity().with(Pos(1.0)).with(Vel(2.0)).build();
+    ///
+    /// let pos = world.read_storage::<Pos>();
+    /// let vel = world.read_storage::<Vel>();
+    /// let entities = world.entities();
+    /// let joined: Vec<_> = pos
+    ///     .dense_join((&entities, &vel))
+    ///     .map(|(pos, (entity, vel))| (entity, pos.0 + vel.0))
+    ///     .collect();
+    /// assert_eq!(joined, [(e, 3.0)]);
+    /// ```
+    pub fn dense_join<J: Join>(&self, other: J) -> impl Iterator<Item = (&T, J::Type)> {
+        // SAFETY: We only call `get` for ids checked to be in the mask.
+        let (mask, mut values) = unsafe { other.open() };
+        let inner = &self.data.inner;
+
+        inner
+            .entity_ids()
+            .iter()
+            .zip(inner.as_slice())
+            .filter(move |(&id, _)| mask.contains(id))
+            .map(move |(&id, component)| {
+                // SAFETY: The id is in the mask of `other`, and `entity_ids`
+                // doesn't contain duplicates so each id is only gotten once.
+                (component, unsafe { J::get(&mut values, id) })
+            })
+    }
+}
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component<Storage = DenseVecStorage<T>>,
+    D: DerefMut<Target = MaskedStorage<T>>,
+{
+    /// Sorts the components by entity id, see `DenseVecStorage::defragment`.
+    pub fn defragment(&mut self) {
+        self.data.inner.defragment();
+    }
+
+    /// Like `Storage::dense_join`, but yields the components of this storage
+    /// mutably.
+    pub fn dense_join_mut<J: Join>(&mut self, other: J) -> impl Iterator<Item = (&mut T, J::Type)> {
+        // SAFETY: We only call `get` for ids checked to be in the mask.
+        let (mask, mut values) = unsafe { other.open() };
+        let (ids, components) = self.data.inner.ids_and_mut_slice();
+
+        ids.iter()
+            .zip(components)
+            .filter(move |(&id, _)| mask.contains(id))
+            .map(move |(&id, component)| {
+                // SAFETY: The id is in the mask of `other`, and `entity_ids`
+                // doesn't contain duplicates so each id is only gotten once.
+                (component, unsafe { J::get(&mut values, id) })
+            })
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component,
+    D: Deref<Target = MaskedStorage<T>>,
+    T::Storage: Sync,
+{
+    /// Iterates over the entities and their components in parallel, like
+    /// `(&entities, &storage).par_join()`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Health(u32);
+    ///
+    /// impl Component for Health {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Health>();
+    /// world.create_entity().with(Health(3)).build();
+    /// world.create_entity().with(Health(4)).build();
+    ///
+    /// let health = world.read_storage::<Health>();
+    /// assert_eq!(health.par_iter().map(|(_, h)| h.0).sum::<u32>(), 7);
+    /// ```
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (Entity, &T)>
+    where
+        T: Sync,
+        D: Sync,
+    {
+        (&*self.entities, self).par_join()
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component,
+    D: DerefMut<Target = MaskedStorage<T>>,
+    T::Storage: Sync + SharedGetMutStorage<T> + DistinctStorage,
+{
+    /// Iterates over the entities and their components mutably in parallel,
+    /// like `(&entities, &mut storage).par_join()`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Health(u32);
+    ///
+    /// impl Component for Health {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Health>();
+    /// let e = world.create_entity().with(Health(3)).build();
+    ///
+    /// let mut health = world.write_storage::<Health>();
+    /// health.par_iter_mut().for_each(|(_, h)| h.0 += 1);
+    /// assert_eq!(health.get(e).unwrap().0, 4);
+    /// ```
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = (Entity, AccessMutReturn<'_, T>)>
+    where
+        T: Send + Sync,
+        for<'a> AccessMutReturn<'a, T>: Send,
+    {
+        let (mask, value) = self.data.open_shared_mut();
+        (&*self.entities, mask).par_join().map(move |(entity, _)| {
+            // SAFETY: The id is in the mask, and each id is yielded once, so
+            // there are no aliasing references to the component.
+            // `T::Storage: DistinctStorage`, so this can be called from
+            // multiple threads at once.
+            let component = unsafe { SharedGetMutOnly::get_mut(&value, entity.id()) };
+            (entity, component)
+        })
+    }
+}
+
 impl<'e, T, D> Storage<'e, T, D>
 where
     T: Component,
@@ -356,6 +1219,15 @@ where
         &mut self.data.inner
     }
 
+    /// Returns the `MaskedStorage` holding the mask and the wrapped storage
+    /// mutably.
+    ///
+    /// See `MaskedStorage::open_shared_mut` and `MaskedStorage::open_mut` for
+    /// implementing custom storage wrappers and `Join`s.
+    pub fn masked_storage_mut(&mut self) -> &mut MaskedStorage<T> {
+        &mut self.data
+    }
+
     /// Tries to mutate the data associated with an `Entity`.
     pub fn get_mut(&mut self, e: Entity) -> Option<AccessMutReturn<'_, T>> {
         if self.data.mask.contains(e.id()) && self.entities.is_alive(e) {
@@ -368,41 +1240,245 @@ where
         }
     }
 
+    /// Mutates the data associated with an `Entity` without checking the
+    /// mask and the generation of the entity in release builds, see
+    /// `get_unchecked`.
+    ///
+    /// # Safety
+    ///
+    /// The entity must be alive and have a component in this storage, i.e.
+    /// `contains(e)` must return `true`.
+    pub unsafe fn get_mut_unchecked(&mut self, e: Entity) -> AccessMutReturn<'_, T> {
+        debug_assert!(
+            self.contains(e),
+            "`get_mut_unchecked` called for {:?}, which has no `{}` or is dead",
+            e,
+            std::any::type_name::<T>()
+        );
+        // SAFETY: We have exclusive access and the caller guarantees that the
+        // id is in the mask.
+        unsafe { self.data.inner.get_mut(e.id()) }
+    }
+
+    /// Tries to mutate the data associated with an `Entity`, inserting a
+    /// component created by the constructor registered with
+    /// `WorldExt::register_with_ctor` if it has none.
+    ///
+    /// Returns `None` if the entity is dead, or if a validator registered with
+    /// `WorldExt::register_validator` rejects the constructed component in
+    /// debug builds.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if no constructor is registered for `T`.
+    pub fn get_mut_or_construct(&mut self, e: Entity) -> Option<AccessMutReturn<'_, T>> {
+        if !self.entities.is_alive(e) {
+            return None;
+        }
+        if !self.data.mask.contains(e.id()) {
+            let component = self.construct(e);
+            if let Err(err) = self.data.validate(e, &component) {
+                log::warn!("Skipped constructing a component: {}", err);
+                return None;
+            }
+            // SAFETY: The mask doesn't contain the id.
+            unsafe { self.not_present_insert(e.id(), component, UnprotectedStorage::insert) }
+        }
+
+        self.get_mut(e)
+    }
+
+    /// Creates a component for `e` with the registered constructor.
+    fn construct(&self, e: Entity) -> T {
+        self.data.construct(e).unwrap_or_else(|| {
+            panic!(
+                "No constructor registered for `{}`, see `WorldExt::register_with_ctor`",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
     /// Inserts new data for a given `Entity`.
     /// Returns the result of the operation as a `InsertResult<T>`
     ///
     /// If a component already existed for the given `Entity`, then it will
     /// be overwritten with the new component. If it did overwrite, then the
     /// result will contain `Some(T)` where `T` is the previous component.
-    pub fn insert(&mut self, e: Entity, mut v: T) -> InsertResult<T> {
+    ///
+    /// In debug builds, the component is checked by the validators registered
+    /// with `WorldExt::register_validator` first, returning
+    /// `Error::InvalidComponent` without inserting it if one rejects it.
+    pub fn insert(&mut self, e: Entity, v: T) -> InsertResult<T> {
+        self.insert_by(e, v, "insert component for entity", UnprotectedStorage::insert)
+    }
+
+    /// Inserts data for a given `Entity` that was restored from a save
+    /// rather than freshly created.
+    ///
+    /// This behaves exactly like `insert`, except that tracked storages emit
+    /// `ComponentEvent::Loaded` instead of `ComponentEvent::Inserted` when
+    /// the entity did not have the component yet. Overwriting an existing
+    /// component still emits `ComponentEvent::Modified`.
+    pub fn insert_loaded(&mut self, e: Entity, v: T) -> InsertResult<T> {
+        self.insert_by(
+            e,
+            v,
+            "insert loaded component for entity",
+            UnprotectedStorage::insert_loaded,
+        )
+    }
+
+    /// Inserts `v` for `e`, with `insert` if `e` has no component yet.
+    fn insert_by(
+        &mut self,
+        e: Entity,
+        mut v: T,
+        action: &'static str,
+        insert: unsafe fn(&mut T::Storage, Index, T),
+    ) -> InsertResult<T> {
         if self.entities.is_alive(e) {
+            self.data.validate(e, &v)?;
             let id = e.id();
             if self.data.mask.contains(id) {
                 // SAFETY: `id` is in the mask.
                 std::mem::swap(&mut v, unsafe { self.data.inner.get_mut(id) }.access_mut());
+                self.data.inserted(e);
                 Ok(Some(v))
             } else {
                 // SAFETY: The mask was previously empty, so this is safe to
                 // call.
-                unsafe { self.not_present_insert(id, v) }
+                unsafe { self.not_present_insert(id, v, insert) }
                 Ok(None)
             }
         } else {
             Err(Error::WrongGeneration(WrongGeneration {
-                action: "insert component for entity",
+                action,
                 actual_gen: self.entities.entity(e.id()).gen(),
                 entity: e,
             }))
         }
     }
 
-    /// Insert the provided value at `id` and adds `id` to the mask.
+    /// Inserts new data for a given `Entity` unless it already has a
+    /// component.
+    ///
+    /// Returns `Ok(true)` if `v` was inserted and `Ok(false)` if the existing
+    /// component was kept, in which case `v` is dropped and no
+    /// `ComponentEvent` is emitted. This is useful for idempotent setup code
+    /// that shouldn't touch components which are already present.
+    ///
+    /// ```
+    /// # use specs::prelude::*;
+    /// # struct Health(u32);
+    /// # impl Component for Health { type Storage = VecStorage<Self>; }
+    /// # let mut world = World::new(); world.register::<Health>();
+    /// let e = world.create_entity().with(Health(3)).build();
+    /// let mut healths = world.write_storage::<Health>();
+    ///
+    /// assert!(!healths.insert_if_absent(e, Health(10)).unwrap());
+    /// assert_eq!(healths.get(e).unwrap().0, 3);
+    /// ```
+    pub fn insert_if_absent(&mut self, e: Entity, v: T) -> Result<bool, Error> {
+        self.insert_with(e, move || v)
+    }
+
+    /// Inserts new data for a given `Entity`, failing if it already has a
+    /// component.
+    ///
+    /// Unlike `Storage::insert`, this never overwrites a component, for
+    /// invariants like every entity getting exactly one component of a type
+    /// when spawned. On failure, `v` is returned in the error and no
+    /// `ComponentEvent` is emitted. In debug builds, the component is checked
+    /// by the registered validators like with `Storage::insert`.
+    ///
+    /// ```
+    /// # use specs::prelude::*;
+    /// # #[derive(Debug, PartialEq)]
+    /// # struct Transform(u32);
+    /// # impl Component for Transform { type Storage = VecStorage<Self>; }
+    /// # let mut world = World::new(); world.register::<Transform>();
+    /// let e = world.create_entity().build();
+    /// let mut transforms = world.write_storage::<Transform>();
+    ///
+    /// assert!(transforms.insert_new(e, Transform(1)).is_ok());
+    /// let err = transforms.insert_new(e, Transform(2)).unwrap_err();
+    /// assert_eq!(err.into_component(), Transform(2));
+    /// assert_eq!(transforms.get(e), Some(&Transform(1)));
+    /// ```
+    pub fn insert_new(&mut self, e: Entity, v: T) -> Result<(), InsertError<T>> {
+        if !self.entities.is_alive(e) {
+            return Err(InsertError::WrongGeneration {
+                error: WrongGeneration {
+                    action: "insert component for entity",
+                    actual_gen: self.entities.entity(e.id()).gen(),
+                    entity: e,
+                },
+                component: v,
+            });
+        }
+
+        let id = e.id();
+        if self.data.mask.contains(id) {
+            Err(InsertError::AlreadyPresent {
+                entity: e,
+                component: v,
+            })
+        } else if let Err(error) = self.data.validate(e, &v) {
+            Err(InsertError::Invalid {
+                error,
+                component: v,
+            })
+        } else {
+            // SAFETY: The mask was previously empty, so this is safe to call.
+            unsafe { self.not_present_insert(id, v, UnprotectedStorage::insert) }
+            Ok(())
+        }
+    }
+
+    /// Inserts the component returned by `f` for a given `Entity` unless it
+    /// already has a component.
+    ///
+    /// Like `Storage::insert_if_absent`, but `f` is only called if the
+    /// component is missing. In debug builds, the component is checked by the
+    /// registered validators like with `Storage::insert`.
+    pub fn insert_with<F>(&mut self, e: Entity, f: F) -> Result<bool, Error>
+    where
+        F: FnOnce() -> T,
+    {
+        if !self.entities.is_alive(e) {
+            return Err(Error::WrongGeneration(WrongGeneration {
+                action: "insert component for entity",
+                actual_gen: self.entities.entity(e.id()).gen(),
+                entity: e,
+            }));
+        }
+
+        let id = e.id();
+        if self.data.mask.contains(id) {
+            Ok(false)
+        } else {
+            let v = f();
+            self.data.validate(e, &v)?;
+            // SAFETY: The mask was previously empty, so this is safe to call.
+            unsafe { self.not_present_insert(id, v, UnprotectedStorage::insert) }
+            Ok(true)
+        }
+    }
+
+    /// Insert the provided value at `id` using `insert` and adds `id` to the
+    /// mask.
     ///
     /// # Safety
     ///
-    /// May only be called if `id` is not present in the mask.
+    /// May only be called if `id` is not present in the mask. `insert` must
+    /// uphold the contract of `UnprotectedStorage::insert`.
     #[inline(always)]
-    unsafe fn not_present_insert(&mut self, id: Index, value: T) {
+    unsafe fn not_present_insert(
+        &mut self,
+        id: Index,
+        value: T,
+        insert: unsafe fn(&mut T::Storage, Index, T),
+    ) {
         // SAFETY: The mask was previously empty, so it is safe to
         // insert. We immediately add the value to the mask below and
         // unwinding from the `insert` call means that we don't need to
@@ -410,7 +1486,7 @@ where
         // remove the value via a drop guard.
         // NOTE: We rely on any panics in `Bitset::add` leaving the bitset in
         // the same state as before `add` was called!
-        unsafe { self.data.inner.insert(id, value) };
+        unsafe { insert(&mut self.data.inner, id, value) };
         if cfg!(panic = "abort") {
             self.data.mask.add(id);
         } else {
@@ -428,20 +1504,191 @@ where
             guard.0.mask.add(id);
             core::mem::forget(guard);
         }
+        let e = self.entities.entity(id);
+        self.data.inserted(e);
     }
 
     /// Removes the data associated with an `Entity`.
     pub fn remove(&mut self, e: Entity) -> Option<T> {
         if self.entities.is_alive(e) {
-            self.data.remove(e.id())
+            self.data.remove_observed(e)
         } else {
             None
         }
     }
 
+    /// Removes the data associated with an `Entity`, like `remove`, but
+    /// returns an error if the entity is dead instead of `None`.
+    ///
+    /// `Ok(None)` means that the entity is alive but had no component.
+    pub fn try_remove(&mut self, e: Entity) -> Result<Option<T>, WrongGeneration> {
+        if self.entities.is_alive(e) {
+            Ok(self.data.remove_observed(e))
+        } else {
+            Err(WrongGeneration {
+                action: "remove component for entity",
+                actual_gen: self.entities.entity(e.id()).gen(),
+                entity: e,
+            })
+        }
+    }
+
+    /// Removes the components of all indices in `mask`, returning the number
+    /// of removed components.
+    ///
+    /// This is much faster than calling `remove` for every entity, e.g. when
+    /// despawning a chunk of the world. Indices are not checked against the
+    /// generations of the entities. Like `remove`, this calls the `on_remove`
+    /// hook and tracked storages emit `ComponentEvent::Removed`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Tile(u32);
+    ///
+    /// impl Component for Tile {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Tile>();
+    /// let tiles: Vec<_> = (0..10)
+    ///     .map(|i| world.create_entity().with(Tile(i)).build())
+    ///     .collect();
+    ///
+    /// let chunk: BitSet = tiles[2..6].iter().map(|e| e.id()).collect();
+    /// let mut storage = world.write_storage::<Tile>();
+    /// assert_eq!(storage.remove_masked(&chunk), 4);
+    /// assert_eq!(storage.count(), 6);
+    /// assert!(!storage.contains(tiles[3]));
+    /// ```
+    pub fn remove_masked<B>(&mut self, mask: B) -> usize
+    where
+        B: BitSetLike,
+    {
+        let data = &mut *self.data;
+        // The indices are collected first since removing them changes the
+        // mask of the storage.
+        let ids: BitSet = BitSetAnd(mask, &*data.mask).iter().collect();
+        let mut removed = 0;
+        for id in (&ids).iter() {
+            for hooks in &mut data.hooks {
+                // SAFETY: `ids` is a subset of the mask.
+                hooks.removed(self.entities.entity(id), unsafe { data.inner.get(id) });
+            }
+            data.drop(id);
+            removed += 1;
+        }
+
+        removed
+    }
+
     /// Clears the contents of the storage.
     pub fn clear(&mut self) {
-        self.data.clear();
+        let data = &mut *self.data;
+        if data.hooks.iter().any(StorageHooks::observes_removal) {
+            for id in (&*data.mask).iter() {
+                for hooks in &mut data.hooks {
+                    // SAFETY: We checked the mask.
+                    hooks.removed(self.entities.entity(id), unsafe { data.inner.get(id) });
+                }
+            }
+        }
+        data.clear();
+    }
+
+    /// Clears the contents of the storage like `clear`, but reports the
+    /// panics of the `Drop` implementations of the components to `on_panic`,
+    /// with the entity and the payload of each, instead of unwinding.
+    ///
+    /// This allows destroying the remaining components when one of them,
+    /// e.g. owning an FFI handle, fails to be dropped.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Handle(bool);
+    ///
+    /// impl Component for Handle {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// impl Drop for Handle {
+    ///     fn drop(&mut self) {
+    ///         assert!(self.0, "invalid handle");
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Handle>();
+    /// world.create_entity().with(Handle(true)).build();
+    /// let invalid = world.create_entity().with(Handle(false)).build();
+    ///
+    /// let mut failed = Vec::new();
+    /// world
+    ///     .write_storage::<Handle>()
+    ///     .clear_reporting(|entity, _payload| failed.push(entity));
+    /// assert_eq!(failed, [invalid]);
+    /// assert_eq!(world.read_storage::<Handle>().count(), 0);
+    /// ```
+    pub fn clear_reporting<F>(&mut self, mut on_panic: F)
+    where
+        F: FnMut(Entity, Box<dyn Any + Send>),
+    {
+        let data = &mut *self.data;
+        if data.hooks.iter().any(StorageHooks::observes_removal) {
+            for id in (&*data.mask).iter() {
+                for hooks in &mut data.hooks {
+                    // SAFETY: We checked the mask.
+                    hooks.removed(self.entities.entity(id), unsafe { data.inner.get(id) });
+                }
+            }
+        }
+        let entities = &self.entities;
+        data.clear_reporting(|id, payload| on_panic(entities.entity(id), payload));
+    }
+
+    /// Adds hooks called when components of this storage are inserted or
+    /// removed, after the hooks added before.
+    ///
+    /// The hooks are stored in the storage, so they stay in place for later
+    /// fetches of it. See `StorageHooks` for when they are called.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// use specs::{prelude::*, storage::StorageHooks};
+    ///
+    /// struct Name(&'static str);
+    ///
+    /// impl Component for Name {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Name>();
+    ///
+    /// let log = Arc::new(Mutex::new(Vec::new()));
+    /// let (inserted, removed) = (log.clone(), log.clone());
+    /// world.write_storage::<Name>().observe(
+    ///     StorageHooks::new()
+    ///         .on_insert(move |_, name: &Name| inserted.lock().unwrap().push(format!("+{}", name.0)))
+    ///         .on_remove(move |_, name: &Name| removed.lock().unwrap().push(format!("-{}", name.0))),
+    /// );
+    ///
+    /// let e = world.create_entity().with(Name("a")).build();
+    /// world.write_storage::<Name>().insert(e, Name("b")).unwrap();
+    /// world.delete_entity(e).unwrap();
+    /// assert_eq!(*log.lock().unwrap(), ["+a", "+b", "-b"]);
+    /// ```
+    pub fn observe(&mut self, hooks: StorageHooks<T>) {
+        self.data.hooks.push(hooks);
     }
 
     /// Creates a draining storage wrapper which can be `.join`ed
@@ -569,7 +1816,8 @@ where
     type Value = &'a mut T::Storage;
 
     unsafe fn open(self) -> (Self::Mask, Self::Value) {
-        self.data.open_mut()
+        // SAFETY: `get` only accesses components through `get_mut`.
+        unsafe { self.data.open_mut() }
     }
 
     unsafe fn get<'next>(value: &'next mut Self::Value, id: Index) -> Self::Type<'next> {
@@ -591,6 +1839,8 @@ where
 mod shared_get_mut_only {
     use super::{Index, SharedGetMutStorage, UnprotectedStorage};
     use core::marker::PhantomData;
+    #[cfg(debug_assertions)]
+    use hibitset::AtomicBitSet;
 
     /// This type provides a way to ensure only `shared_get_mut` can be called
     /// for the lifetime `'a` and that no references previously obtained from
@@ -600,13 +1850,47 @@ mod shared_get_mut_only {
     ///
     /// This is useful for implementations of [`Join`](super::Join) and
     /// [`ParJoin`](super::ParJoin).
-    pub struct SharedGetMutOnly<'a, T, S>(&'a S, PhantomData<T>);
+    ///
+    /// With `debug_assertions` enabled, it keeps track of the ids passed to
+    /// `get_mut` and panics if one is fetched twice, since the component
+    /// borrowed first may still be alive. Release builds don't track anything.
+    pub struct SharedGetMutOnly<'a, T, S> {
+        storage: &'a S,
+        #[cfg(debug_assertions)]
+        fetched: AtomicBitSet,
+        phantom: PhantomData<T>,
+    }
 
     impl<'a, T, S> SharedGetMutOnly<'a, T, S> {
-        pub(crate) fn new(storage: &'a mut S) -> Self {
-            Self(storage, PhantomData)
+        /// Wraps an exclusive borrow of `storage`.
+        pub fn new(storage: &'a mut S) -> Self {
+            Self {
+                storage,
+                #[cfg(debug_assertions)]
+                fetched: AtomicBitSet::new(),
+                phantom: PhantomData,
+            }
+        }
+
+        /// Wraps a shared borrow of `storage`.
+        ///
+        /// # Safety
+        ///
+        /// For the lifetime `'a`, the components fetched through the returned
+        /// value must not be accessed in any other way, e.g. because the
+        /// caller only passes ids to `get_mut` which nothing else borrowing
+        /// `storage` accesses.
+        pub(crate) unsafe fn from_shared(storage: &'a S) -> Self {
+            Self {
+                storage,
+                #[cfg(debug_assertions)]
+                fetched: AtomicBitSet::new(),
+                phantom: PhantomData,
+            }
         }
 
+        /// Calls `SharedGetMutStorage::shared_get_mut` on the wrapped storage.
+        ///
         /// # Safety
         ///
         /// May only be called after a call to `insert` with `id` and no
@@ -616,29 +1900,36 @@ mod shared_get_mut_only {
         /// contained in the tracking mask is sufficient to call this method.
         ///
         /// There must be no extant aliasing references to this component (i.e.
-        /// obtained with the same `id`).
+        /// obtained with the same `id`). With `debug_assertions` enabled,
+        /// calling this twice with the same `id` panics.
         ///
         /// Unless `S: DistinctStorage`, calling this from multiple threads at
         /// once is unsound.
-        pub(crate) unsafe fn get_mut(
-            this: &Self,
-            id: Index,
-        ) -> <S as UnprotectedStorage<T>>::AccessMut<'a>
+        pub unsafe fn get_mut(this: &Self, id: Index) -> <S as UnprotectedStorage<T>>::AccessMut<'a>
         where
             S: SharedGetMutStorage<T>,
         {
+            #[cfg(debug_assertions)]
+            assert!(
+                !this.fetched.add_atomic(id),
+                "The component of `{}` with index {} has been fetched mutably twice \
+                 from the same join, the first borrow may still be alive",
+                core::any::type_name::<T>(),
+                id
+            );
             // SAFETY: `Self::new` takes an exclusive reference to this storage,
             // ensuring there are no extant references to its content at the
             // time `self` is created and ensuring that only `self` has access
             // to the storage for its lifetime and the lifetime of the produced
             // `AccessMutReturn`s (the reference we hold to the storage is not
-            // exposed outside of this module).
+            // exposed outside of this module). `Self::from_shared` passes
+            // this on to its caller for the components it fetches.
             //
             // This means we only have to worry about aliasing references being
             // produced by calling `SharedGetMutStorage::shared_get_mut`.
             // Ensuring these don't alias and the remaining safety requirements
             // are passed on to the caller.
-            unsafe { this.0.shared_get_mut(id) }
+            unsafe { this.storage.shared_get_mut(id) }
         }
     }
 }
@@ -659,9 +1950,7 @@ where
     type Value = SharedGetMutOnly<'a, T, T::Storage>;
 
     unsafe fn open(self) -> (Self::Mask, Self::Value) {
-        let (mask, value) = self.data.open_mut();
-        let value = SharedGetMutOnly::new(value);
-        (mask, value)
+        self.data.open_shared_mut()
     }
 
     unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
@@ -696,9 +1985,7 @@ where
     type Value = SharedGetMutOnly<'a, T, T::Storage>;
 
     unsafe fn open(self) -> (Self::Mask, Self::Value) {
-        let (mask, value) = self.data.open_mut();
-        let value = SharedGetMutOnly::new(value);
-        (mask, value)
+        self.data.open_shared_mut()
     }
 
     unsafe fn get(value: &Self::Value, id: Index) -> Self::Type {
@@ -738,6 +2025,129 @@ where
     }
 }
 
+/// Creates the storage of a component when it is set up, like shred's
+/// `SetupHandler` does for resources.
+///
+/// `StorageSetup<()>` is used when a component is set up without arguments,
+/// by `SystemData::setup` or `WorldExt::register_with` with `()`, and is
+/// implemented for all storages implementing `TryDefault`. Storages which
+/// need configuration, like a capacity or a handle to an arena, can implement
+/// it to fetch the configuration from a resource, or to return an error if
+/// it's missing. They can then implement `StorageSetup<A>` to be registered
+/// with `WorldExt::register_with`, passing the configuration as `A`.
+/// `WorldExt::register` requires a `Default` storage instead, so it can't
+/// fail.
+///
+/// Wrapping storages like `FlaggedStorage` are created with `Default`, so
+/// they still require the wrapped storage to implement `TryDefault`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     storage::{StorageSetup, UnprotectedStorage},
+///     world::Index,
+/// };
+///
+/// /// A handle to memory reserved up front.
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct Arena(u32);
+///
+/// struct ArenaStorage<T> {
+///     arena: Arena,
+///     components: VecStorage<T>,
+/// }
+/// # impl<T> UnprotectedStorage<T> for ArenaStorage<T> {
+/// #     type AccessMut<'a> = &'a mut T where T: 'a;
+/// #     unsafe fn clean<B: hibitset::BitSetLike>(&mut self, has: B) {
+/// #         unsafe { self.components.clean(has) }
+/// #     }
+/// #     unsafe fn get(&self, id: Index) -> &T {
+/// #         unsafe { self.components.get(id) }
+/// #     }
+/// #     unsafe fn get_mut(&mut self, id: Index) -> &mut T {
+/// #         unsafe { self.components.get_mut(id) }
+/// #     }
+/// #     unsafe fn insert(&mut self, id: Index, value: T) {
+/// #         unsafe { self.components.insert(id, value) }
+/// #     }
+/// #     unsafe fn remove(&mut self, id: Index) -> T {
+/// #         unsafe { self.components.remove(id) }
+/// #     }
+/// # }
+///
+/// impl<T> StorageSetup for ArenaStorage<T> {
+///     fn setup(world: &World, (): ()) -> Result<Self, String> {
+///         let arena = world
+///             .try_fetch::<Arena>()
+///             .ok_or("the `Arena` resource is missing")?;
+///         Self::setup(world, *arena)
+///     }
+/// }
+///
+/// impl<T> StorageSetup<Arena> for ArenaStorage<T> {
+///     fn setup(_: &World, arena: Arena) -> Result<Self, String> {
+///         Ok(ArenaStorage {
+///             arena,
+///             components: VecStorage::default(),
+///         })
+///     }
+/// }
+///
+/// struct Particle;
+///
+/// impl Component for Particle {
+///     type Storage = ArenaStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register_with::<Particle, _>(Arena(1));
+/// world.create_entity().with(Particle).build();
+///
+/// let particles = world.read_storage::<Particle>();
+/// assert_eq!(particles.unprotected_storage().arena, Arena(1));
+/// ```
+pub trait StorageSetup<A = ()>: Sized {
+    /// Creates the storage from `args`, returning an error describing the
+    /// missing configuration if that's not possible.
+    fn setup(world: &World, args: A) -> Result<Self, String>;
+}
+
+impl<T> StorageSetup for T
+where
+    T: TryDefault,
+{
+    fn setup(_: &World, (): ()) -> Result<Self, String> {
+        T::try_default()
+    }
+}
+
+/// Registers `T` like `WorldExt::register_with_storage`, creating the storage
+/// from `args` if it doesn't exist yet.
+///
+/// ## Panics
+///
+/// Panics if the storage doesn't exist and `StorageSetup::setup` fails.
+pub(crate) fn setup_storage<T, A>(world: &mut World, args: A)
+where
+    T: Component,
+    T::Storage: StorageSetup<A>,
+{
+    let storage = match world.has_value::<MaskedStorage<T>>() {
+        true => None,
+        false => Some(T::Storage::setup(world, args).unwrap_or_else(|e| {
+            panic!(
+                "Failed to set up the storage of `{}` ({})",
+                std::any::type_name::<T>(),
+                e
+            )
+        })),
+    };
+    // The storage is only created if it doesn't exist yet.
+    world.register_with_storage::<_, T>(|| storage.unwrap());
+}
+
 /// DerefMut without autoderefing.
 ///
 /// Allows forcing mutable access to be explicit. Useful to implement a flagged
@@ -760,7 +2170,7 @@ where
 }
 
 /// Used by the framework to quickly join components.
-pub trait UnprotectedStorage<T>: TryDefault {
+pub trait UnprotectedStorage<T>: StorageSetup {
     /// The wrapper through with mutable access of a component is performed.
     type AccessMut<'a>: AccessMut<Target = T>
     where
@@ -830,6 +2240,20 @@ pub trait UnprotectedStorage<T>: TryDefault {
     /// the safety requirements of other methods here.
     unsafe fn insert(&mut self, id: Index, value: T);
 
+    /// Inserts data for a given `Index` that was restored from a save rather
+    /// than freshly created (e.g. by `DeserializeComponents`).
+    /// Tracked storages use this to emit `ComponentEvent::Loaded` instead of
+    /// `ComponentEvent::Inserted`.
+    /// Defaults to simply calling `insert`.
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as `insert`.
+    unsafe fn insert_loaded(&mut self, id: Index, value: T) {
+        // SAFETY: Requirements passed to the caller.
+        unsafe { self.insert(id, value) };
+    }
+
     /// Removes the data associated with an `Index`.
     ///
     /// # Safety
@@ -855,6 +2279,21 @@ pub trait UnprotectedStorage<T>: TryDefault {
         // SAFETY: Requirements passed to the caller.
         unsafe { self.remove(id) };
     }
+
+    /// Called at the start of every `World::maintain`, e.g. to reset
+    /// per-frame state. Wrapper storages should forward this to the storage
+    /// they wrap.
+    /// Does nothing by default.
+    fn maintain(&mut self) {}
+
+    /// Returns `true` if the components may only be accessed and dropped on
+    /// the thread owning the storage, like in a `NonSendStorage`, so
+    /// `World::maintain` must not drop them on its thread pool. Wrapper
+    /// storages should forward this to the storage they wrap.
+    /// Returns `false` by default.
+    fn is_thread_local(&self) -> bool {
+        false
+    }
 }
 
 /// Used by the framework to mutably access components in contexts where
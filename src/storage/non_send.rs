@@ -0,0 +1,245 @@
+use std::{
+    mem::{self, ManuallyDrop},
+    ops::{Deref, DerefMut},
+    thread::{self, ThreadId},
+};
+
+use hibitset::BitSetLike;
+use shred::{ResourceId, SystemData, World};
+
+use crate::{
+    storage::{DenseVecStorage, SharedGetMutStorage, UnprotectedStorage, WriteStorage},
+    world::{Component, Index},
+};
+
+/// Storage for components which are not `Send` or `Sync`, like components
+/// wrapping an `Rc` or a platform handle.
+///
+/// The storage belongs to the thread it was created on, usually the main
+/// thread registering the component. Accessing the components from any
+/// other thread panics, so systems using them have to fetch the storage as
+/// `NonSendWriteStorage` and be added to the dispatcher with
+/// `DispatcherBuilder::add_thread_local`.
+///
+/// If the storage is cleared or dropped on another thread, e.g. because the
+/// `World` is, its components are leaked instead of being dropped there.
+///
+/// ## Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// use specs::{prelude::*, storage::NonSendStorage};
+///
+/// struct Texture(Rc<u32>);
+///
+/// impl Component for Texture {
+///     type Storage = NonSendStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Texture>();
+/// let e = world.create_entity().build();
+/// // `Builder::with` requires `Send + Sync`, insert into the storage instead.
+/// world
+///     .write_storage::<Texture>()
+///     .insert(e, Texture(Rc::new(7)))
+///     .unwrap();
+/// assert_eq!(*world.read_storage::<Texture>().get(e).unwrap().0, 7);
+/// ```
+pub struct NonSendStorage<T> {
+    owner: ThreadId,
+    inner: ManuallyDrop<DenseVecStorage<T>>,
+}
+
+// SAFETY: The components are only accessed and dropped on the `owner` thread,
+// see `check_thread`, `clean` and the `Drop` impl.
+unsafe impl<T> Send for NonSendStorage<T> {}
+
+// SAFETY: See the `Send` impl above.
+unsafe impl<T> Sync for NonSendStorage<T> {}
+
+impl<T> NonSendStorage<T> {
+    /// Returns `true` if this is called on the thread owning the storage.
+    pub fn is_owner_thread(&self) -> bool {
+        thread::current().id() == self.owner
+    }
+
+    fn check_thread(&self) {
+        assert!(
+            self.is_owner_thread(),
+            "Components of `{}` can only be accessed on the thread which created \
+             their storage",
+            std::any::type_name::<T>()
+        );
+    }
+}
+
+impl<T> Default for NonSendStorage<T> {
+    fn default() -> Self {
+        NonSendStorage {
+            owner: thread::current().id(),
+            inner: Default::default(),
+        }
+    }
+}
+
+impl<T> Drop for NonSendStorage<T> {
+    fn drop(&mut self) {
+        if self.is_owner_thread() {
+            // SAFETY: `inner` is not used after this.
+            unsafe { ManuallyDrop::drop(&mut self.inner) };
+        }
+    }
+}
+
+impl<T> UnprotectedStorage<T> for NonSendStorage<T> {
+    type AccessMut<'a> = &'a mut T where T: 'a;
+
+    unsafe fn clean<B>(&mut self, has: B)
+    where
+        B: BitSetLike,
+    {
+        if self.is_owner_thread() {
+            // SAFETY: Requirements passed to caller.
+            unsafe { self.inner.clean(has) };
+        } else {
+            // The components can't be dropped here, leak them.
+            mem::forget(mem::take(&mut *self.inner));
+        }
+    }
+
+    unsafe fn get(&self, id: Index) -> &T {
+        self.check_thread();
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.inner.get(id) }
+    }
+
+    unsafe fn get_mut(&mut self, id: Index) -> &mut T {
+        self.check_thread();
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.inner.get_mut(id) }
+    }
+
+    unsafe fn insert(&mut self, id: Index, value: T) {
+        self.check_thread();
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.inner.insert(id, value) };
+    }
+
+    unsafe fn remove(&mut self, id: Index) -> T {
+        self.check_thread();
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.inner.remove(id) }
+    }
+
+    fn is_thread_local(&self) -> bool {
+        true
+    }
+}
+
+impl<T> SharedGetMutStorage<T> for NonSendStorage<T> {
+    unsafe fn shared_get_mut(&self, id: Index) -> &mut T {
+        self.check_thread();
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.inner.shared_get_mut(id) }
+    }
+}
+
+/// A `WriteStorage` of components stored in a `NonSendStorage`.
+///
+/// Fetching it panics on any thread but the one owning the storage, so a
+/// system using it fails right away if it has not been added with
+/// `DispatcherBuilder::add_thread_local`.
+///
+/// ## Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// use specs::{prelude::*, storage::{NonSendStorage, NonSendWriteStorage}};
+///
+/// struct Texture(Rc<u32>);
+///
+/// impl Component for Texture {
+///     type Storage = NonSendStorage<Self>;
+/// }
+///
+/// struct Reload;
+///
+/// impl<'a> System<'a> for Reload {
+///     type SystemData = NonSendWriteStorage<'a, Texture>;
+///
+///     fn run(&mut self, mut textures: Self::SystemData) {
+///         for texture in (&mut *textures).join() {
+///             texture.0 = Rc::new(*texture.0 + 1);
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with_thread_local(Reload)
+///     .build();
+/// dispatcher.setup(&mut world);
+///
+/// let e = world.create_entity().build();
+/// world
+///     .write_storage::<Texture>()
+///     .insert(e, Texture(Rc::new(1)))
+///     .unwrap();
+/// dispatcher.dispatch(&world);
+/// assert_eq!(*world.read_storage::<Texture>().get(e).unwrap().0, 2);
+/// ```
+pub struct NonSendWriteStorage<'a, T: Component> {
+    storage: WriteStorage<'a, T>,
+}
+
+impl<'a, T> Deref for NonSendWriteStorage<'a, T>
+where
+    T: Component,
+{
+    type Target = WriteStorage<'a, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.storage
+    }
+}
+
+impl<'a, T> DerefMut for NonSendWriteStorage<'a, T>
+where
+    T: Component,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.storage
+    }
+}
+
+impl<'a, T> SystemData<'a> for NonSendWriteStorage<'a, T>
+where
+    T: Component<Storage = NonSendStorage<T>>,
+{
+    fn setup(world: &mut World) {
+        WriteStorage::<T>::setup(world);
+    }
+
+    fn fetch(world: &'a World) -> Self {
+        let storage = WriteStorage::<T>::fetch(world);
+        assert!(
+            storage.unprotected_storage().is_owner_thread(),
+            "`NonSendWriteStorage<{}>` has been fetched on another thread than \
+             the one owning the storage, add the system with `add_thread_local`",
+            std::any::type_name::<T>()
+        );
+
+        NonSendWriteStorage { storage }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        WriteStorage::<T>::reads()
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        WriteStorage::<T>::writes()
+    }
+}
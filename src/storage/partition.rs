@@ -0,0 +1,244 @@
+use std::{marker::PhantomData, ops::DerefMut};
+
+use hibitset::{BitSet, BitSetLike};
+
+use crate::{
+    join::Join,
+    storage::{
+        AccessMutReturn, DistinctStorage, MaskedStorage, SharedGetMutOnly, SharedGetMutStorage,
+        Storage, UnprotectedStorage,
+    },
+    world::{Component, EntitiesRes, Entity, Index},
+};
+#[cfg(feature = "parallel")]
+use crate::join::ParJoin;
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component,
+    D: DerefMut<Target = MaskedStorage<T>>,
+    T::Storage: SharedGetMutStorage<T>,
+{
+    /// Splits the components into the ones `predicate` returns `true` for
+    /// and the others, returning two disjoint views which can be joined
+    /// mutably at the same time.
+    ///
+    /// This allows processing interactions between two groups of the same
+    /// component type, e.g. of two teams, in one pass. The views can't insert
+    /// or remove components.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Unit {
+    ///     team: u8,
+    ///     hits: u32,
+    /// }
+    ///
+    /// impl Component for Unit {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Unit>();
+    /// for team in [0, 1, 1] {
+    ///     world.create_entity().with(Unit { team, hits: 0 }).build();
+    /// }
+    ///
+    /// let mut units = world.write_storage::<Unit>();
+    /// let (mut red, mut blue) = units.partition_mut(|unit| unit.team == 0);
+    /// for a in (&mut red).join() {
+    ///     for b in (&mut blue).join() {
+    ///         a.hits += 1;
+    ///         b.hits += 1;
+    ///     }
+    /// }
+    /// drop((red, blue));
+    ///
+    /// let hits: Vec<_> = units.join().map(|unit| unit.hits).collect();
+    /// assert_eq!(hits, [2, 1, 1]);
+    /// ```
+    pub fn partition_mut<F>(&mut self, mut predicate: F) -> (Partition<'_, T>, Partition<'_, T>)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        let entities: &EntitiesRes = &self.entities;
+        let data = &mut *self.data;
+        let storage = &data.inner;
+        let (mut matching, mut rest) = (BitSet::new(), BitSet::new());
+        for id in (&*data.mask).iter() {
+            // SAFETY: `id` is in the mask.
+            if predicate(unsafe { storage.get(id) }) {
+                matching.add(id);
+            } else {
+                rest.add(id);
+            }
+        }
+
+        (
+            Partition::new(matching, storage, entities),
+            Partition::new(rest, storage, entities),
+        )
+    }
+}
+
+/// One of the two disjoint views of a storage returned by
+/// `Storage::partition_mut`.
+///
+/// Joining `&mut Partition` yields the components mutably, joining
+/// `&Partition` immutably.
+pub struct Partition<'a, T: Component> {
+    mask: BitSet,
+    storage: &'a T::Storage,
+    entities: &'a EntitiesRes,
+    // The other partition may fetch components mutably through the same
+    // storage, which is only thread safe for a `DistinctStorage`.
+    phantom: PhantomData<*const T>,
+}
+
+// SAFETY: The partitions only access disjoint components, and calling
+// `shared_get_mut` for distinct ids from multiple threads at once is safe
+// since `T::Storage: DistinctStorage`. `T::Storage: Sync` makes sharing the
+// storage reference safe.
+unsafe impl<'a, T> Send for Partition<'a, T>
+where
+    T: Component + Send + Sync,
+    T::Storage: Sync + DistinctStorage,
+{
+}
+
+// SAFETY: See the `Send` impl, `&Partition` only allows getting shared
+// references to the components.
+unsafe impl<'a, T> Sync for Partition<'a, T>
+where
+    T: Component + Send + Sync,
+    T::Storage: Sync + DistinctStorage,
+{
+}
+
+impl<'a, T> Partition<'a, T>
+where
+    T: Component,
+    T::Storage: SharedGetMutStorage<T>,
+{
+    fn new(mask: BitSet, storage: &'a T::Storage, entities: &'a EntitiesRes) -> Self {
+        Partition {
+            mask,
+            storage,
+            entities,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the mask of the indices in this partition.
+    pub fn mask(&self) -> &BitSet {
+        &self.mask
+    }
+
+    /// Returns `true` if `e` is alive and its component is in this
+    /// partition.
+    pub fn contains(&self, e: Entity) -> bool {
+        self.mask.contains(e.id()) && self.entities.is_alive(e)
+    }
+
+    /// Returns the component of `e` if it is in this partition.
+    pub fn get(&self, e: Entity) -> Option<&T> {
+        if self.contains(e) {
+            // SAFETY: The mask is a subset of the mask of the storage.
+            Some(unsafe { self.storage.get(e.id()) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the component of `e` mutably if it is in this partition.
+    pub fn get_mut(&mut self, e: Entity) -> Option<AccessMutReturn<'_, T>> {
+        if self.contains(e) {
+            // SAFETY: The mask is a subset of the mask of the storage, and
+            // disjoint from the mask of the other partition. Borrowing `self`
+            // mutably ensures there are no other references to the component.
+            Some(unsafe { self.storage.shared_get_mut(e.id()) })
+        } else {
+            None
+        }
+    }
+}
+
+// SAFETY: The mask is a subset of the mask of the storage. Iterating the mask
+// does not repeat indices.
+unsafe impl<'b, 'a, T> Join for &'b Partition<'a, T>
+where
+    T: Component,
+    T::Storage: SharedGetMutStorage<T>,
+{
+    type Mask = &'b BitSet;
+    type Type = &'b T;
+    type Value = &'b T::Storage;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (&self.mask, self.storage)
+    }
+
+    unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
+        // SAFETY: Since we require that the mask was checked, an element for
+        // `id` must have been inserted without being removed.
+        unsafe { value.get(id) }
+    }
+}
+
+// SAFETY: The mask is a subset of the mask of the storage and disjoint from
+// the mask of the other partition, so the other partition never accesses the
+// components fetched through the `SharedGetMutOnly`. Borrowing the partition
+// mutably ensures it doesn't access them either. Iterating the mask does not
+// repeat indices.
+unsafe impl<'b, 'a, T> Join for &'b mut Partition<'a, T>
+where
+    T: Component,
+    T::Storage: SharedGetMutStorage<T>,
+{
+    type Mask = &'b BitSet;
+    type Type = AccessMutReturn<'b, T>;
+    type Value = SharedGetMutOnly<'b, T, T::Storage>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: See the safety comment of this impl.
+        (&self.mask, unsafe { SharedGetMutOnly::from_shared(self.storage) })
+    }
+
+    unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
+        // SAFETY: Since we require that the mask was checked, an element for
+        // `id` must have been inserted without being removed. We also require
+        // that there are no subsequent calls with the same `id`, and having an
+        // exclusive reference to `Self::Value` means this isn't called from
+        // multiple threads at once.
+        unsafe { SharedGetMutOnly::get_mut(value, id) }
+    }
+}
+
+// SAFETY: As for `Join`, and calling `SharedGetMutOnly::get_mut` from
+// multiple threads at once is safe since `T::Storage: DistinctStorage`.
+#[cfg(feature = "parallel")]
+unsafe impl<'b, 'a, T> ParJoin for &'b mut Partition<'a, T>
+where
+    T: Component,
+    T::Storage: Sync + SharedGetMutStorage<T> + DistinctStorage,
+{
+    type Mask = &'b BitSet;
+    type Type = AccessMutReturn<'b, T>;
+    type Value = SharedGetMutOnly<'b, T, T::Storage>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: See the safety comment of the `Join` impl.
+        (&self.mask, unsafe { SharedGetMutOnly::from_shared(self.storage) })
+    }
+
+    unsafe fn get(value: &Self::Value, id: Index) -> Self::Type {
+        // SAFETY: Since we require that the mask was checked, an element for
+        // `id` must have been inserted without being removed. We also require
+        // that the returned value is no longer alive before subsequent calls
+        // with the same `id`.
+        unsafe { SharedGetMutOnly::get_mut(value, id) }
+    }
+}
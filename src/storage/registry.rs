@@ -0,0 +1,232 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    mem::size_of,
+};
+
+use ahash::AHashMap as HashMap;
+use hibitset::BitSet;
+use shred::{ResourceId, World};
+
+use crate::world::{events, Component, Entity, WorldEvent, WorldExt};
+
+use super::{AccessMut, AnyStorage, MaskedStorage};
+
+/// Type-erased access to every storage registered in a `World`, keyed by the
+/// `TypeId` of its component.
+///
+/// Components are added when their storage is registered, either with
+/// `WorldExt::register` or by the setup of `ReadStorage` and `WriteStorage`.
+/// This allows plugins like network replication, save games or inspectors to
+/// operate over all components without knowing their types at compile time.
+/// Serializing works by downcasting the values passed to
+/// `StorageAccessor::read`, with serialize functions the plugin maps to
+/// `StorageAccessor::type_id`.
+///
+/// ## Examples
+///
+/// ```
+/// use std::any::TypeId;
+///
+/// use specs::{prelude::*, storage::StorageRegistry};
+///
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// let e = world.create_entity().with(Pos(1.0)).build();
+///
+/// let registry = world.fetch::<StorageRegistry>();
+/// let pos = registry.get(TypeId::of::<Pos>()).unwrap();
+/// assert!(pos.contains(&world, e));
+///
+/// pos.write(&world, e, &mut |value| {
+///     value.downcast_mut::<Pos>().unwrap().0 = 2.0;
+/// });
+///
+/// let removed = pos.remove(&world, e).unwrap();
+/// assert_eq!(removed.downcast_ref::<Pos>().unwrap().0, 2.0);
+/// assert!(!pos.contains(&world, e));
+/// ```
+#[derive(Default)]
+pub struct StorageRegistry {
+    accessors: Vec<StorageAccessor>,
+    indices: HashMap<TypeId, usize>,
+}
+
+impl StorageRegistry {
+    /// Returns the accessor of the storage of the component with the given
+    /// type id.
+    pub fn get(&self, ty: TypeId) -> Option<&StorageAccessor> {
+        self.indices.get(&ty).map(|&index| &self.accessors[index])
+    }
+
+    /// Returns the accessor of the storage with the given resource id.
+    pub(crate) fn get_by_storage(&self, id: &ResourceId) -> Option<&StorageAccessor> {
+        self.accessors
+            .iter()
+            .find(|accessor| ResourceId::from_type_id(accessor.storage_type_id) == *id)
+    }
+
+    /// Iterates over the accessors of all storages, in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &StorageAccessor> {
+        self.accessors.iter()
+    }
+
+    /// Returns the number of registered storages.
+    pub fn len(&self) -> usize {
+        self.accessors.len()
+    }
+
+    /// Returns `true` if no storage has been registered.
+    pub fn is_empty(&self) -> bool {
+        self.accessors.is_empty()
+    }
+
+    /// Adds the accessor of `T` to the registry of `world`, publishing a
+    /// `WorldEvent::ComponentRegistered` if it is new.
+    pub(crate) fn register<T: Component>(world: &mut World) {
+        let new = world
+            .entry::<StorageRegistry>()
+            .or_insert_with(Default::default)
+            .insert::<T>();
+        if new {
+            events::publish(
+                world,
+                [WorldEvent::ComponentRegistered {
+                    type_id: TypeId::of::<T>(),
+                    name: type_name::<T>(),
+                }],
+            );
+        }
+    }
+
+    /// Adds the accessor of `T`, returning `false` if it already was.
+    fn insert<T: Component>(&mut self) -> bool {
+        if self.indices.contains_key(&TypeId::of::<T>()) {
+            return false;
+        }
+        self.indices.insert(TypeId::of::<T>(), self.accessors.len());
+        self.accessors.push(StorageAccessor::new::<T>());
+
+        true
+    }
+}
+
+/// Type-erased operations on the storage of one component type, see
+/// `StorageRegistry`.
+///
+/// Each operation fetches the storage from the world, so it panics if the
+/// storage is already borrowed in a conflicting way.
+#[derive(Clone, Copy)]
+pub struct StorageAccessor {
+    type_id: TypeId,
+    type_name: &'static str,
+    storage_type_name: &'static str,
+    size: usize,
+    storage_type_id: TypeId,
+    contains: fn(&World, Entity) -> bool,
+    mask: fn(&World) -> BitSet,
+    read: ReadComponent,
+    write: WriteComponent,
+    remove: fn(&World, Entity) -> Option<Box<dyn Any>>,
+    drop: fn(&World, &[Entity]),
+}
+
+type ReadComponent = fn(&World, Entity, &mut dyn FnMut(&dyn Any)) -> bool;
+type WriteComponent = fn(&World, Entity, &mut dyn FnMut(&mut dyn Any)) -> bool;
+
+impl StorageAccessor {
+    fn new<T: Component>() -> Self {
+        StorageAccessor {
+            type_id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+            storage_type_name: type_name::<T::Storage>(),
+            size: size_of::<T>(),
+            storage_type_id: TypeId::of::<MaskedStorage<T>>(),
+            contains: |world, entity| world.read_storage::<T>().contains(entity),
+            mask: |world| world.read_storage::<T>().mask().clone(),
+            read: |world, entity, f| match world.read_storage::<T>().get(entity) {
+                Some(component) => {
+                    f(component);
+                    true
+                }
+                None => false,
+            },
+            write: |world, entity, f| match world.write_storage::<T>().get_mut(entity) {
+                Some(mut component) => {
+                    f(component.access_mut());
+                    true
+                }
+                None => false,
+            },
+            remove: |world, entity| {
+                world
+                    .write_storage::<T>()
+                    .remove(entity)
+                    .map(|component| Box::new(component) as Box<dyn Any>)
+            },
+            drop: |world, entities| {
+                AnyStorage::drop(&mut *world.fetch_mut::<MaskedStorage<T>>(), entities);
+            },
+        }
+    }
+
+    /// Returns the type id of the component.
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Returns the type name of the component.
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Returns the type name of the storage of the component.
+    pub fn storage_type_name(&self) -> &'static str {
+        self.storage_type_name
+    }
+
+    /// Returns the size of the component in bytes.
+    pub fn component_size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns `true` if `entity` has the component.
+    pub fn contains(&self, world: &World, entity: Entity) -> bool {
+        (self.contains)(world, entity)
+    }
+
+    /// Returns a copy of the mask of the entities having the component.
+    pub fn mask(&self, world: &World) -> BitSet {
+        (self.mask)(world)
+    }
+
+    /// Calls `f` with the component of `entity`, returning whether the entity
+    /// has one.
+    pub fn read(&self, world: &World, entity: Entity, f: &mut dyn FnMut(&dyn Any)) -> bool {
+        (self.read)(world, entity, f)
+    }
+
+    /// Calls `f` with the component of `entity` mutably, returning whether the
+    /// entity has one.
+    ///
+    /// The component counts as modified for flagged storages.
+    pub fn write(&self, world: &World, entity: Entity, f: &mut dyn FnMut(&mut dyn Any)) -> bool {
+        (self.write)(world, entity, f)
+    }
+
+    /// Removes the component of `entity`, returning it.
+    pub fn remove(&self, world: &World, entity: Entity) -> Option<Box<dyn Any>> {
+        (self.remove)(world, entity)
+    }
+
+    /// Drops the components of `entities`, like `World::maintain` does for
+    /// deleted entities.
+    pub(crate) fn drop_components(&self, world: &World, entities: &[Entity]) {
+        (self.drop)(world, entities)
+    }
+}
@@ -0,0 +1,257 @@
+use std::{marker::PhantomData, ops::Deref};
+
+use ahash::AHashMap as HashMap;
+use hibitset::BitSetLike;
+use shrev::ReaderId;
+
+use crate::{
+    storage::{ComponentEvent, MaskedStorage, Storage, Tracked, WriteStorage},
+    world::{Component, Entity, Index},
+};
+
+/// What has to be replicated for a dirty component, in increasing priority.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ReplicationKind {
+    /// The component has been modified.
+    Modified,
+    /// The component has been inserted or loaded.
+    Inserted,
+    /// The component has been removed.
+    Removed,
+}
+
+/// A dirty component drained from a `ReplicationQueue`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Replication<T> {
+    /// The entity of the component.
+    pub entity: Entity,
+    /// What has to be replicated.
+    pub kind: ReplicationKind,
+    /// A copy of the component, `None` if it has been removed.
+    pub component: Option<T>,
+}
+
+/// Collects the components of a tracked storage that have to be sent to
+/// clients, for bandwidth-limited sync loops of network servers.
+///
+/// The queue reads the `ComponentEvent`s of the storage and keeps a single
+/// entry per entity, coalescing its events: the entry keeps the highest
+/// `ReplicationKind` seen, except that an insertion after a removal
+/// replaces it. `drain_up_to` then takes the entries with the highest kind,
+/// the oldest ones first.
+///
+/// Events only carry the index of their entity, so the queue remembers
+/// which entity owned each component as of the last update. A component
+/// whose index has been reused by a new entity since then yields separate
+/// entries for the removal from the old entity and the insertion into the
+/// new one, while components inserted and removed between two updates are
+/// never replicated.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     storage::{ReplicationKind, ReplicationQueue},
+/// };
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = FlaggedStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// let a = world.create_entity().build();
+/// let b = world.create_entity().build();
+///
+/// let mut queue = ReplicationQueue::new(&mut world.write_storage::<Pos>());
+/// let mut pos = world.write_storage::<Pos>();
+/// pos.insert(a, Pos(1.0)).unwrap();
+/// pos.insert(b, Pos(2.0)).unwrap();
+/// queue.update(&pos);
+/// // Only one update fits into this packet.
+/// let sent = queue.drain_up_to(&pos, 1);
+/// assert_eq!(sent[0].entity, a);
+/// assert_eq!(sent[0].kind, ReplicationKind::Inserted);
+/// assert_eq!(sent[0].component, Some(Pos(1.0)));
+///
+/// pos.get_mut(a).unwrap().0 = 3.0;
+/// // The insertion of `b` is sent before the modification of `a`.
+/// let sent = queue.drain_up_to(&pos, 1);
+/// assert_eq!(sent[0].entity, b);
+/// assert_eq!(queue.len(), 1);
+/// ```
+pub struct ReplicationQueue<T> {
+    reader: ReaderId<ComponentEvent>,
+    dirty: HashMap<Entity, Dirty>,
+    /// The entities owning the components, as of the last update.
+    owners: HashMap<Index, Entity>,
+    next_seq: u64,
+    /// The queue only stores entities, so it's `Send` and `Sync` for any `T`.
+    pd: PhantomData<fn() -> T>,
+}
+
+/// The entry of a dirty component.
+#[derive(Clone, Copy)]
+struct Dirty {
+    kind: ReplicationKind,
+    /// When the component became dirty, to send older entries first.
+    seq: u64,
+}
+
+impl<T> ReplicationQueue<T>
+where
+    T: Component,
+    T::Storage: Tracked,
+{
+    /// Creates a queue, registering a reader for the events of `storage`.
+    ///
+    /// Components the storage already contains are not dirty.
+    pub fn new(storage: &mut WriteStorage<T>) -> Self {
+        let entities = storage.fetched_entities();
+        let owners = storage
+            .mask()
+            .iter()
+            .map(|id| (id, entities.entity(id)))
+            .collect();
+
+        ReplicationQueue {
+            reader: storage.register_reader(),
+            dirty: HashMap::new(),
+            owners,
+            next_seq: 0,
+            pd: PhantomData,
+        }
+    }
+
+    /// Reads the events emitted by `storage` since the last update.
+    ///
+    /// Call this regularly, even if nothing is drained, so events don't pile
+    /// up in the channel of the storage.
+    pub fn update<D>(&mut self, storage: &Storage<T, D>)
+    where
+        D: Deref<Target = MaskedStorage<T>>,
+    {
+        // Whether each touched index has been inserted into, in the order
+        // of their first events.
+        let mut touched = Vec::new();
+        let mut inserted = HashMap::new();
+        for event in storage.channel().read(&mut self.reader) {
+            let (id, insertion) = match *event {
+                ComponentEvent::Inserted(id) | ComponentEvent::Loaded(id) => (id, true),
+                ComponentEvent::Modified(id) | ComponentEvent::Removed(id) => (id, false),
+            };
+            let seen = inserted.entry(id).or_insert_with(|| {
+                touched.push(id);
+                false
+            });
+            *seen |= insertion;
+        }
+
+        let entities = storage.fetched_entities();
+        for id in touched {
+            let before = self.owners.get(&id).copied();
+            let after = storage.mask().contains(id).then(|| entities.entity(id));
+            match (before, after) {
+                (Some(before), Some(after)) if before == after => {
+                    let kind = if inserted[&id] {
+                        ReplicationKind::Inserted
+                    } else {
+                        ReplicationKind::Modified
+                    };
+                    self.mark(after, kind);
+                }
+                _ => {
+                    if let Some(before) = before {
+                        self.mark(before, ReplicationKind::Removed);
+                        self.owners.remove(&id);
+                    }
+                    if let Some(after) = after {
+                        self.mark(after, ReplicationKind::Inserted);
+                        self.owners.insert(id, after);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Adds a dirty entry for `entity`, or coalesces it with the existing one.
+    fn mark(&mut self, entity: Entity, kind: ReplicationKind) {
+        let seq = self.next_seq;
+        let dirty = self.dirty.entry(entity).or_insert(Dirty { kind, seq });
+        if dirty.seq == seq {
+            self.next_seq += 1;
+        } else if kind > dirty.kind
+            || (dirty.kind == ReplicationKind::Removed && kind == ReplicationKind::Inserted)
+        {
+            dirty.kind = kind;
+        }
+    }
+
+    /// Updates the queue and removes up to `n` dirty components from it, the
+    /// highest `ReplicationKind`s first, returning them with a copy of their
+    /// current value.
+    pub fn drain_up_to<D>(&mut self, storage: &Storage<T, D>, n: usize) -> Vec<Replication<T>>
+    where
+        T: Clone,
+        D: Deref<Target = MaskedStorage<T>>,
+    {
+        let mut drained = Vec::new();
+        self.drain_up_to_with(storage, n, |entity, kind, component| {
+            drained.push(Replication {
+                entity,
+                kind,
+                component: component.cloned(),
+            })
+        });
+
+        drained
+    }
+
+    /// Like `drain_up_to`, but calls `f` with each dirty component, which is
+    /// `None` if it has been removed, instead of copying them.
+    pub fn drain_up_to_with<D, F>(&mut self, storage: &Storage<T, D>, n: usize, mut f: F)
+    where
+        D: Deref<Target = MaskedStorage<T>>,
+        F: FnMut(Entity, ReplicationKind, Option<&T>),
+    {
+        self.update(storage);
+
+        let mut dirty: Vec<_> = self.dirty.iter().map(|(&e, &dirty)| (e, dirty)).collect();
+        let order = |a: &(Entity, Dirty), b: &(Entity, Dirty)| {
+            b.1.kind.cmp(&a.1.kind).then(a.1.seq.cmp(&b.1.seq))
+        };
+        if n < dirty.len() {
+            dirty.select_nth_unstable_by(n, order);
+            dirty.truncate(n);
+        }
+        dirty.sort_unstable_by(order);
+
+        for (entity, Dirty { kind, .. }) in dirty {
+            self.dirty.remove(&entity);
+            let component = match kind {
+                ReplicationKind::Removed => None,
+                _ => storage.get(entity),
+            };
+            // The entity may have been deleted since.
+            let kind = match component {
+                Some(_) => kind,
+                None => ReplicationKind::Removed,
+            };
+            f(entity, kind, component);
+        }
+    }
+
+    /// Returns the number of dirty components, as of the last update.
+    pub fn len(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Returns `true` if no component is dirty, as of the last update.
+    pub fn is_empty(&self) -> bool {
+        self.dirty.is_empty()
+    }
+}
@@ -88,7 +88,9 @@ where
     /// restricted access to the inner components without allowing
     /// invalidating the bitset for iteration in `Join`.
     pub fn restrict_mut<'rf>(&'rf mut self) -> RestrictedStorage<'rf, T, &mut T::Storage> {
-        let (mask, data) = self.data.open_mut();
+        // SAFETY: `RestrictedStorage` doesn't allow inserting or removing
+        // components.
+        let (mask, data) = unsafe { self.data.open_mut() };
         RestrictedStorage {
             bitset: mask,
             data,
@@ -142,7 +144,7 @@ where
 // contained in the wrapped `Storage`. Iterating the mask does not repeat
 // indices.
 #[nougat::gat]
-unsafe impl<'rf, C, S> LendJoin for &'rf mut RestrictedStorage<'rf, C, S>
+unsafe impl<'rf, 'st: 'rf, C, S> LendJoin for &'rf mut RestrictedStorage<'st, C, S>
 where
     C: Component,
     S: BorrowMut<C::Storage>,
@@ -175,7 +177,7 @@ where
 
 // SAFETY: LendJoin::get impl for this type can safely be called multiple times
 // with the same ID.
-unsafe impl<'rf, C, S> RepeatableLendGet for &'rf mut RestrictedStorage<'rf, C, S>
+unsafe impl<'rf, 'st: 'rf, C, S> RepeatableLendGet for &'rf mut RestrictedStorage<'st, C, S>
 where
     C: Component,
     S: BorrowMut<C::Storage>,
@@ -323,7 +325,7 @@ pub use shared_get_only::SharedGetOnly;
 // SAFETY: `open` returns references to corresponding mask and storage values
 // contained in the wrapped `Storage`. Iterating the mask does not repeat
 // indices.
-unsafe impl<'rf, C, S> Join for &'rf mut RestrictedStorage<'rf, C, S>
+unsafe impl<'rf, 'st: 'rf, C, S> Join for &'rf mut RestrictedStorage<'st, C, S>
 where
     C: Component,
     S: BorrowMut<C::Storage>,
@@ -396,7 +398,7 @@ where
 //
 // Iterating the mask does not repeat indices.
 #[cfg(feature = "parallel")]
-unsafe impl<'rf, C, S> ParJoin for &'rf mut RestrictedStorage<'rf, C, S>
+unsafe impl<'rf, 'st: 'rf, C, S> ParJoin for &'rf mut RestrictedStorage<'st, C, S>
 where
     C: Component,
     S: BorrowMut<C::Storage>,
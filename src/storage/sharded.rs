@@ -0,0 +1,573 @@
+use std::{
+    any::{type_name, TypeId},
+    ops::Deref,
+};
+
+use hibitset::BitSet;
+use shred::{Fetch, FetchMut, ResourceId, SystemData, World};
+
+#[nougat::gat(Type)]
+use crate::join::LendJoin;
+#[cfg(feature = "parallel")]
+use crate::join::ParJoin;
+use crate::{
+    error::{Error, WrongShard},
+    join::{Join, RepeatableLendGet},
+    storage::{
+        AccessMutReturn, InsertResult, MaskedStorage, SharedGetMutOnly, SharedGetMutStorage,
+        Storage, StorageSetup, UnprotectedStorage, WriteStorage,
+    },
+    world::{Component, EntitiesRes, Entity, Index},
+};
+
+/// A component whose storage is split into shards, which can be written by
+/// different systems at the same time.
+///
+/// Entities are assigned to shards by their index, see
+/// `ShardedComponent::shard_of`. Each shard is a separate resource, so a
+/// system fetching `ShardedWrite<T, 0>` and one fetching `ShardedWrite<T, 1>`
+/// can run in parallel, while `ShardedRead<T>` and `ShardedWriteAll<T>`
+/// access all shards.
+///
+/// The shards are independent of the regular storage of the component, i.e.
+/// sharded components are not accessible through `ReadStorage` and
+/// `WriteStorage`. They are set up by `WorldExt::register_sharded` or by the
+/// `setup` of the sharded `SystemData`, and maintained by
+/// `World::maintain` like regular storages.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     storage::{ShardedComponent, ShardedRead, ShardedWrite, ShardedWriteAll},
+/// };
+///
+/// struct Heat(u32);
+///
+/// impl Component for Heat {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// impl ShardedComponent for Heat {
+///     const SHARDS: usize = 2;
+/// }
+///
+/// /// Cools the entities of one shard.
+/// struct Cool<const SHARD: usize>;
+///
+/// impl<'a, const SHARD: usize> System<'a> for Cool<SHARD> {
+///     type SystemData = ShardedWrite<'a, Heat, SHARD>;
+///
+///     fn run(&mut self, mut heat: Self::SystemData) {
+///         for heat in (&mut heat).join() {
+///             heat.0 -= 1;
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register_sharded::<Heat>();
+/// for _ in 0..4 {
+///     let e = world.create_entity().build();
+///     world
+///         .system_data::<ShardedWriteAll<Heat>>()
+///         .insert(e, Heat(10))
+///         .unwrap();
+/// }
+///
+/// // Both systems run in parallel.
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with(Cool::<0>, "cool_0", &[])
+///     .with(Cool::<1>, "cool_1", &[])
+///     .build();
+/// dispatcher.dispatch(&world);
+///
+/// let heat = world.system_data::<ShardedRead<Heat>>();
+/// assert!(heat.join().all(|heat| heat.0 == 9));
+/// ```
+pub trait ShardedComponent: Component {
+    /// The number of shards, must not be zero.
+    const SHARDS: usize;
+
+    /// Returns the shard of an entity.
+    ///
+    /// Defaults to the entity's index modulo `SHARDS`.
+    fn shard_of(entity: Entity) -> usize {
+        entity.id() as usize % Self::SHARDS
+    }
+}
+
+/// Returns the resource id of a shard; dynamic id `0` is used by the regular
+/// storage.
+fn shard_id<T: Component>(shard: usize) -> ResourceId {
+    ResourceId::new_with_dynamic_id::<MaskedStorage<T>>(shard as u64 + 1)
+}
+
+fn fetch_shard<T: Component>(world: &World, shard: usize) -> Fetch<'_, MaskedStorage<T>> {
+    world
+        .try_fetch_by_id(shard_id::<T>(shard))
+        .unwrap_or_else(|| panic!("Shard {} of `{}` is not set up", shard, type_name::<T>()))
+}
+
+fn fetch_shard_mut<T: Component>(world: &World, shard: usize) -> FetchMut<'_, MaskedStorage<T>> {
+    world
+        .try_fetch_mut_by_id(shard_id::<T>(shard))
+        .unwrap_or_else(|| panic!("Shard {} of `{}` is not set up", shard, type_name::<T>()))
+}
+
+/// Inserts the shards of `T` and registers them for maintenance.
+pub(crate) fn setup<T: ShardedComponent>(world: &mut World) {
+    assert!(T::SHARDS > 0, "`{}` must have shards", type_name::<T>());
+    for shard in 0..T::SHARDS {
+        let id = shard_id::<T>(shard);
+        if !world.has_value_raw(id.clone()) {
+            let storage = T::Storage::setup(world, ()).unwrap_or_else(|e| {
+                panic!(
+                    "Failed to set up the storage of `{}` ({})",
+                    type_name::<T>(),
+                    e
+                )
+            });
+            world.insert_by_id(id, MaskedStorage::<T>::new(storage));
+        }
+    }
+
+    let mut maintainers = world
+        .entry::<ShardMaintainers>()
+        .or_insert_with(Default::default);
+    if !maintainers.0.iter().any(|m| m.ty == TypeId::of::<T>()) {
+        maintainers.0.push(ShardMaintainer {
+            ty: TypeId::of::<T>(),
+            maintain: maintain_shards::<T>,
+            drop: drop_from_shards::<T>,
+        });
+    }
+}
+
+/// The sharded components set up in a `World`, maintained by
+/// `World::maintain`.
+#[derive(Default)]
+pub(crate) struct ShardMaintainers(Vec<ShardMaintainer>);
+
+struct ShardMaintainer {
+    ty: TypeId,
+    maintain: fn(&World),
+    drop: fn(&World, &[Entity]),
+}
+
+impl ShardMaintainers {
+    /// Runs `UnprotectedStorage::maintain` for all shards.
+    pub(crate) fn maintain(&self, world: &World) {
+        for maintainer in &self.0 {
+            (maintainer.maintain)(world);
+        }
+    }
+
+    /// Drops the components of `entities` from all shards.
+    pub(crate) fn drop(&self, world: &World, entities: &[Entity]) {
+        for maintainer in &self.0 {
+            (maintainer.drop)(world, entities);
+        }
+    }
+}
+
+fn maintain_shards<T: ShardedComponent>(world: &World) {
+    for shard in 0..T::SHARDS {
+        fetch_shard_mut::<T>(world, shard).inner.maintain();
+    }
+}
+
+fn drop_from_shards<T: ShardedComponent>(world: &World, entities: &[Entity]) {
+    let mut shards: Vec<_> = (0..T::SHARDS)
+        .map(|shard| fetch_shard_mut::<T>(world, shard))
+        .collect();
+    for entity in entities {
+        shards[T::shard_of(*entity)].drop(entity.id());
+    }
+}
+
+fn shard_reads<T: ShardedComponent>() -> Vec<ResourceId> {
+    Some(ResourceId::new::<EntitiesRes>())
+        .into_iter()
+        .chain((0..T::SHARDS).map(shard_id::<T>))
+        .collect()
+}
+
+fn wrong_shard<T: ShardedComponent>(entity: Entity, shard: usize) -> Error {
+    Error::WrongShard(WrongShard {
+        entity,
+        shard,
+        expected: T::shard_of(entity),
+    })
+}
+
+/// Read access to all shards of a `ShardedComponent`.
+///
+/// `&ShardedRead` can be joined like `&ReadStorage`.
+pub struct ShardedRead<'a, T: Component> {
+    shards: Vec<Storage<'a, T, Fetch<'a, MaskedStorage<T>>>>,
+}
+
+impl<'a, T: ShardedComponent> ShardedRead<'a, T> {
+    /// Returns the component of an `Entity`.
+    pub fn get(&self, e: Entity) -> Option<&T> {
+        self.shards[T::shard_of(e)].get(e)
+    }
+
+    /// Returns `true` if the entity has a component.
+    pub fn contains(&self, e: Entity) -> bool {
+        self.shards[T::shard_of(e)].contains(e)
+    }
+
+    /// Returns the number of components in all shards.
+    pub fn count(&self) -> usize {
+        self.shards.iter().map(Storage::count).sum()
+    }
+
+    /// Returns the storage of a single shard.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `shard` is out of bounds.
+    pub fn shard(&self, shard: usize) -> &Storage<'a, T, Fetch<'a, MaskedStorage<T>>> {
+        &self.shards[shard]
+    }
+}
+
+impl<'a, T: ShardedComponent> SystemData<'a> for ShardedRead<'a, T> {
+    fn setup(world: &mut World) {
+        setup::<T>(world);
+    }
+
+    fn fetch(world: &'a World) -> Self {
+        ShardedRead {
+            shards: (0..T::SHARDS)
+                .map(|shard| Storage::new(world.fetch(), fetch_shard::<T>(world, shard)))
+                .collect(),
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        shard_reads::<T>()
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        vec![]
+    }
+}
+
+/// Returns the mask of all shards together with their masks and storages.
+fn open_shards<'a, T, D>(shards: &'a [Storage<'_, T, D>]) -> (BitSet, ShardValues<'a, T>)
+where
+    T: Component,
+    D: Deref<Target = MaskedStorage<T>>,
+{
+    let mut mask = BitSet::new();
+    for shard in shards {
+        mask |= shard.mask();
+    }
+    let values = shards
+        .iter()
+        .map(|shard| shard.masked_storage().open())
+        .collect();
+
+    (mask, values)
+}
+
+type ShardValues<'a, T> = Vec<(&'a BitSet, &'a <T as Component>::Storage)>;
+
+/// Returns the component at `id` from the shard containing it.
+///
+/// # Safety
+///
+/// The mask of one of the shards must contain `id`.
+unsafe fn get_from_shards<'a, T: ShardedComponent>(
+    values: &ShardValues<'a, T>,
+    id: Index,
+) -> &'a T {
+    // The shard is usually the one given by `shard_of`, but this only relies
+    // on the masks.
+    let guess = id as usize % T::SHARDS;
+    let (_, storage) = Some(&values[guess])
+        .filter(|(mask, _)| mask.contains(id))
+        .or_else(|| values.iter().find(|(mask, _)| mask.contains(id)))
+        .expect("Index is not contained in any shard");
+    // SAFETY: We checked the mask of the shard.
+    unsafe { storage.get(id) }
+}
+
+// SAFETY: The mask is the union of the masks of all shards and `get` only
+// reads from the shard whose mask contains the index. Iterating the mask does
+// not repeat indices.
+#[nougat::gat]
+unsafe impl<'a, 'e, T> LendJoin for &'a ShardedRead<'e, T>
+where
+    T: ShardedComponent,
+{
+    type Mask = BitSet;
+    type Type<'next> = &'a T;
+    type Value = ShardValues<'a, T>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        open_shards(&self.shards)
+    }
+
+    unsafe fn get<'next>(value: &'next mut Self::Value, id: Index) -> &'a T {
+        // SAFETY: Since we require that the mask was checked, one of the
+        // shards contains `id`.
+        unsafe { get_from_shards::<T>(value, id) }
+    }
+}
+
+// SAFETY: LendJoin::get impl for this type is safe to call multiple times with
+// the same ID.
+unsafe impl<'a, 'e, T> RepeatableLendGet for &'a ShardedRead<'e, T> where T: ShardedComponent {}
+
+// SAFETY: The mask is the union of the masks of all shards and `get` only
+// reads from the shard whose mask contains the index. Iterating the mask does
+// not repeat indices.
+unsafe impl<'a, 'e, T> Join for &'a ShardedRead<'e, T>
+where
+    T: ShardedComponent,
+{
+    type Mask = BitSet;
+    type Type = &'a T;
+    type Value = ShardValues<'a, T>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        open_shards(&self.shards)
+    }
+
+    unsafe fn get(value: &mut Self::Value, id: Index) -> &'a T {
+        // SAFETY: Since we require that the mask was checked, one of the
+        // shards contains `id`.
+        unsafe { get_from_shards::<T>(value, id) }
+    }
+}
+
+// SAFETY: It is safe to call `<T::Storage as UnprotectedStorage>::get` from
+// multiple threads at once since `T::Storage: Sync`.
+//
+// The mask is the union of the masks of all shards and `get` only reads from
+// the shard whose mask contains the index. Iterating the mask does not repeat
+// indices.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, 'e, T> ParJoin for &'a ShardedRead<'e, T>
+where
+    T: ShardedComponent,
+    T::Storage: Sync,
+{
+    type Mask = BitSet;
+    type Type = &'a T;
+    type Value = ShardValues<'a, T>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        open_shards(&self.shards)
+    }
+
+    unsafe fn get(value: &Self::Value, id: Index) -> &'a T {
+        // SAFETY: Since we require that the mask was checked, one of the
+        // shards contains `id`.
+        unsafe { get_from_shards::<T>(value, id) }
+    }
+}
+
+/// Write access to the shard `SHARD` of a `ShardedComponent`.
+///
+/// Systems writing different shards of the same component can run in
+/// parallel. This derefs to the `Storage` of the shard, and `&mut
+/// ShardedWrite` can be joined like `&mut WriteStorage`.
+pub struct ShardedWrite<'a, T: Component, const SHARD: usize> {
+    storage: WriteStorage<'a, T>,
+}
+
+impl<'a, T: ShardedComponent, const SHARD: usize> ShardedWrite<'a, T, SHARD> {
+    /// Returns the component of an `Entity` mutably.
+    pub fn get_mut(&mut self, e: Entity) -> Option<AccessMutReturn<'_, T>> {
+        self.storage.get_mut(e)
+    }
+
+    /// Inserts a component for an `Entity`, see `Storage::insert`.
+    ///
+    /// Fails if the entity belongs to another shard.
+    pub fn insert(&mut self, e: Entity, v: T) -> InsertResult<T> {
+        if T::shard_of(e) != SHARD {
+            return Err(wrong_shard::<T>(e, SHARD));
+        }
+
+        self.storage.insert(e, v)
+    }
+
+    /// Removes the component of an `Entity`.
+    pub fn remove(&mut self, e: Entity) -> Option<T> {
+        self.storage.remove(e)
+    }
+}
+
+impl<'a, T: Component, const SHARD: usize> Deref for ShardedWrite<'a, T, SHARD> {
+    type Target = WriteStorage<'a, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.storage
+    }
+}
+
+impl<'a, T: ShardedComponent, const SHARD: usize> SystemData<'a> for ShardedWrite<'a, T, SHARD> {
+    fn setup(world: &mut World) {
+        setup::<T>(world);
+    }
+
+    fn fetch(world: &'a World) -> Self {
+        assert!(
+            SHARD < T::SHARDS,
+            "`{}` only has {} shards",
+            type_name::<T>(),
+            T::SHARDS
+        );
+
+        ShardedWrite {
+            storage: Storage::new(world.fetch(), fetch_shard_mut::<T>(world, SHARD)),
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        vec![ResourceId::new::<EntitiesRes>()]
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        vec![shard_id::<T>(SHARD)]
+    }
+}
+
+// SAFETY: Forwarded to the `LendJoin` impl of `&mut WriteStorage`.
+#[nougat::gat]
+unsafe impl<'a, 'e, T, const SHARD: usize> LendJoin for &'a mut ShardedWrite<'e, T, SHARD>
+where
+    T: ShardedComponent,
+{
+    type Mask = &'a BitSet;
+    type Type<'next> = AccessMutReturn<'next, T>;
+    type Value = &'a mut T::Storage;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: Requirements passed to caller.
+        unsafe { LendJoin::open(&mut self.storage) }
+    }
+
+    unsafe fn get<'next>(value: &'next mut Self::Value, id: Index) -> Self::Type<'next> {
+        // SAFETY: Requirements passed to caller.
+        unsafe { <&'a mut WriteStorage<'e, T> as LendJoin>::get(value, id) }
+    }
+}
+
+// SAFETY: LendJoin::get impl for this type is safe to call multiple times with
+// the same ID.
+unsafe impl<'a, 'e, T, const SHARD: usize> RepeatableLendGet for &'a mut ShardedWrite<'e, T, SHARD> where
+    T: ShardedComponent
+{
+}
+
+// SAFETY: Forwarded to the `Join` impl of `&mut WriteStorage`.
+unsafe impl<'a, 'e, T, const SHARD: usize> Join for &'a mut ShardedWrite<'e, T, SHARD>
+where
+    T: ShardedComponent,
+    T::Storage: SharedGetMutStorage<T>,
+{
+    type Mask = &'a BitSet;
+    type Type = AccessMutReturn<'a, T>;
+    type Value = SharedGetMutOnly<'a, T, T::Storage>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: Requirements passed to caller.
+        unsafe { Join::open(&mut self.storage) }
+    }
+
+    unsafe fn get(value: &mut Self::Value, id: Index) -> Self::Type {
+        // SAFETY: Requirements passed to caller.
+        unsafe { <&'a mut WriteStorage<'e, T> as Join>::get(value, id) }
+    }
+}
+
+// SAFETY: Forwarded to the `ParJoin` impl of `&mut WriteStorage`.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, 'e, T, const SHARD: usize> ParJoin for &'a mut ShardedWrite<'e, T, SHARD>
+where
+    T: ShardedComponent,
+    T::Storage: Sync + SharedGetMutStorage<T> + crate::storage::DistinctStorage,
+{
+    type Mask = &'a BitSet;
+    type Type = AccessMutReturn<'a, T>;
+    type Value = SharedGetMutOnly<'a, T, T::Storage>;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        // SAFETY: Requirements passed to caller.
+        unsafe { ParJoin::open(&mut self.storage) }
+    }
+
+    unsafe fn get(value: &Self::Value, id: Index) -> Self::Type {
+        // SAFETY: Requirements passed to caller.
+        unsafe { <&'a mut WriteStorage<'e, T> as ParJoin>::get(value, id) }
+    }
+}
+
+/// Write access to all shards of a `ShardedComponent`, e.g. for inserting
+/// components of entities of any shard.
+pub struct ShardedWriteAll<'a, T: Component> {
+    shards: Vec<WriteStorage<'a, T>>,
+}
+
+impl<'a, T: ShardedComponent> ShardedWriteAll<'a, T> {
+    /// Returns the component of an `Entity`.
+    pub fn get(&self, e: Entity) -> Option<&T> {
+        self.shards[T::shard_of(e)].get(e)
+    }
+
+    /// Returns the component of an `Entity` mutably.
+    pub fn get_mut(&mut self, e: Entity) -> Option<AccessMutReturn<'_, T>> {
+        self.shards[T::shard_of(e)].get_mut(e)
+    }
+
+    /// Returns `true` if the entity has a component.
+    pub fn contains(&self, e: Entity) -> bool {
+        self.shards[T::shard_of(e)].contains(e)
+    }
+
+    /// Inserts a component for an `Entity` into its shard, see
+    /// `Storage::insert`.
+    pub fn insert(&mut self, e: Entity, v: T) -> InsertResult<T> {
+        self.shards[T::shard_of(e)].insert(e, v)
+    }
+
+    /// Removes the component of an `Entity`.
+    pub fn remove(&mut self, e: Entity) -> Option<T> {
+        self.shards[T::shard_of(e)].remove(e)
+    }
+
+    /// Returns the number of components in all shards.
+    pub fn count(&self) -> usize {
+        self.shards.iter().map(Storage::count).sum()
+    }
+}
+
+impl<'a, T: ShardedComponent> SystemData<'a> for ShardedWriteAll<'a, T> {
+    fn setup(world: &mut World) {
+        setup::<T>(world);
+    }
+
+    fn fetch(world: &'a World) -> Self {
+        ShardedWriteAll {
+            shards: (0..T::SHARDS)
+                .map(|shard| Storage::new(world.fetch(), fetch_shard_mut::<T>(world, shard)))
+                .collect(),
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        vec![ResourceId::new::<EntitiesRes>()]
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        (0..T::SHARDS).map(shard_id::<T>).collect()
+    }
+}
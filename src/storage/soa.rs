@@ -0,0 +1,460 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use hibitset::{BitSet, BitSetLike};
+
+#[cfg(feature = "parallel")]
+use crate::join::ParJoin;
+use crate::{
+    join::Join,
+    storage::{MaskedStorage, Storage, UnprotectedStorage},
+    world::{Component, Entity, Index},
+};
+
+/// A component whose fields are stored in separate columns, one `Vec` per
+/// field, by a `SoAStorage`.
+///
+/// This is usually implemented with `#[derive(SoAComponent)]`, which also
+/// implements `Component` with `SoAStorage<Self>` as storage and generates
+/// the columns type, named after the component with a `Columns` suffix. It
+/// has an accessor for the slice of each column, like `x()` and `x_mut()` for
+/// a field `x`. The derive also generates a proxy with a reference to each
+/// field, named after the component with a `Ref` suffix, which is returned
+/// by `Storage::row` and joined over by `Storage::rows`.
+///
+/// The component still acts as one component for registration and mutable
+/// joins, while `Storage::column` and `Storage::column_mut` join over single
+/// columns, for cache-friendly kernels touching only some fields.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     storage::{SoAColumns, SoAComponent, SoAStorage},
+/// };
+///
+/// #[derive(Clone, Debug, PartialEq, specs_derive::SoAComponent)]
+/// struct Pos {
+///     x: f32,
+///     y: f32,
+/// }
+///
+/// struct Vel(f32);
+///
+/// impl Component for Vel {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// world.register::<Vel>();
+/// let e = world
+///     .create_entity()
+///     .with(Pos { x: 1.0, y: 2.0 })
+///     .with(Vel(3.0))
+///     .build();
+///
+/// let mut pos = world.write_storage::<Pos>();
+/// let vel = world.read_storage::<Vel>();
+/// // Only the `x` column is touched.
+/// for (x, vel) in (pos.column_mut(|c| c.x_mut()), &vel).join() {
+///     *x += vel.0;
+/// }
+/// let row = pos.row(e).unwrap();
+/// assert_eq!((*row.x, *row.y), (4.0, 2.0));
+/// assert_eq!(pos.columns().y(), [2.0]);
+/// ```
+pub trait SoAComponent: Sized + 'static {
+    /// The columns storing the fields of the component.
+    type Columns: SoAColumns<Self>;
+}
+
+/// The columns of a `SoAComponent`, see there.
+///
+/// Rows are addressed by their position, all columns have the same length.
+pub trait SoAColumns<T>: Default {
+    /// The proxy borrowing the fields of a row.
+    type Ref<'a>
+    where
+        Self: 'a;
+
+    /// Borrows the fields of the row at `index`.
+    fn get(&self, index: usize) -> Self::Ref<'_>;
+
+    /// Appends `row`, splitting it into its fields.
+    fn push(&mut self, row: T);
+
+    /// Removes the row at `index`, replacing it with the last row.
+    fn swap_remove(&mut self, index: usize) -> T;
+
+    /// Returns a copy of the row at `index`, used for mutable access.
+    fn row(&self, index: usize) -> T;
+
+    /// Replaces the row at `index`.
+    fn set_row(&mut self, index: usize, row: T);
+
+    /// Removes all rows.
+    fn clear(&mut self);
+}
+
+/// The proxy borrowing the fields of a `SoAComponent`, see
+/// `SoAColumns::Ref`.
+pub type SoARef<'a, T> = <<T as SoAComponent>::Columns as SoAColumns<T>>::Ref<'a>;
+
+/// Storage of a `SoAComponent`, keeping each field in a separate column.
+///
+/// The columns are densely packed, like the components of a
+/// `DenseVecStorage`. Since there is no component value to borrow,
+/// `Storage::get` and joins over `&Storage` panic, use `Storage::row` and
+/// `Storage::rows` instead, which return proxies borrowing the fields.
+/// Mutable access returns a `SoARowMut` writing the row back into the columns
+/// when dropped. Joining `&mut` storages requires `LendJoin` for the same
+/// reason.
+pub struct SoAStorage<T: SoAComponent> {
+    columns: T::Columns,
+    entity_id: Vec<Index>,
+    data_id: Vec<Index>,
+}
+
+impl<T: SoAComponent> Default for SoAStorage<T> {
+    fn default() -> Self {
+        SoAStorage {
+            columns: Default::default(),
+            entity_id: Vec::new(),
+            data_id: Vec::new(),
+        }
+    }
+}
+
+impl<T: SoAComponent> SoAStorage<T> {
+    /// Returns the columns.
+    pub fn columns(&self) -> &T::Columns {
+        &self.columns
+    }
+
+    /// Returns the columns mutably.
+    pub fn columns_mut(&mut self) -> &mut T::Columns {
+        &mut self.columns
+    }
+
+    /// Returns the ids of the entities of the rows, in the same order.
+    pub fn entity_ids(&self) -> &[Index] {
+        &self.entity_id
+    }
+}
+
+impl<T: SoAComponent> UnprotectedStorage<T> for SoAStorage<T> {
+    type AccessMut<'a> = SoARowMut<'a, T>;
+
+    unsafe fn clean<B>(&mut self, _has: B)
+    where
+        B: BitSetLike,
+    {
+        self.columns.clear();
+        self.entity_id.clear();
+    }
+
+    unsafe fn get(&self, _id: Index) -> &T {
+        panic!(
+            "`SoAStorage` can't borrow a `{}`, use `Storage::row` or `Storage::rows`",
+            std::any::type_name::<T>()
+        )
+    }
+
+    unsafe fn get_mut(&mut self, id: Index) -> SoARowMut<'_, T> {
+        let did = self.data_id[id as usize] as usize;
+        let row = self.columns.row(did);
+
+        SoARowMut {
+            columns: &mut self.columns,
+            index: did,
+            row: Some(row),
+        }
+    }
+
+    unsafe fn insert(&mut self, id: Index, value: T) {
+        let id = id as usize;
+        if self.data_id.len() <= id {
+            self.data_id.resize(id + 1, 0);
+        }
+        // NOTE: `as` casts are not lossy since the number of rows can't exceed
+        // the number of ids.
+        self.data_id[id] = self.entity_id.len() as Index;
+        self.entity_id.push(id as Index);
+        self.columns.push(value);
+    }
+
+    unsafe fn remove(&mut self, id: Index) -> T {
+        let did = self.data_id[id as usize] as usize;
+        let last = *self.entity_id.last().unwrap();
+        self.data_id[last as usize] = did as Index;
+        self.entity_id.swap_remove(did);
+
+        self.columns.swap_remove(did)
+    }
+}
+
+/// Mutable access to a component of a `SoAStorage`, writing it back into the
+/// columns when dropped.
+pub struct SoARowMut<'a, T: SoAComponent> {
+    columns: &'a mut T::Columns,
+    index: usize,
+    row: Option<T>,
+}
+
+impl<'a, T: SoAComponent> Deref for SoARowMut<'a, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.row.as_ref().unwrap()
+    }
+}
+
+impl<'a, T: SoAComponent> DerefMut for SoARowMut<'a, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.row.as_mut().unwrap()
+    }
+}
+
+impl<'a, T: SoAComponent> Drop for SoARowMut<'a, T> {
+    fn drop(&mut self) {
+        if let Some(row) = self.row.take() {
+            self.columns.set_row(self.index, row);
+        }
+    }
+}
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component<Storage = SoAStorage<T>> + SoAComponent,
+    D: Deref<Target = MaskedStorage<T>>,
+{
+    /// Returns the columns of the components.
+    pub fn columns(&self) -> &T::Columns {
+        self.unprotected_storage().columns()
+    }
+
+    /// Borrows the fields of the component of `e`, if it has one.
+    pub fn row(&self, e: Entity) -> Option<SoARef<'_, T>> {
+        if self.contains(e) {
+            let storage = self.unprotected_storage();
+            Some(storage.columns.get(storage.data_id[e.id() as usize] as usize))
+        } else {
+            None
+        }
+    }
+
+    /// Returns a join over the components, yielding a proxy borrowing the
+    /// fields of each component.
+    pub fn rows(&self) -> Rows<'_, T> {
+        let (mask, storage) = self.masked_storage().open();
+
+        Rows {
+            mask,
+            data_id: &storage.data_id,
+            columns: &storage.columns,
+        }
+    }
+
+    /// Returns a join over the column selected by `select`, yielding a
+    /// reference to the field of each component.
+    pub fn column<'a, F>(
+        &'a self,
+        select: impl FnOnce(&'a T::Columns) -> &'a [F],
+    ) -> Column<'a, F> {
+        let (mask, storage) = self.masked_storage().open();
+
+        Column {
+            mask,
+            data_id: &storage.data_id,
+            values: select(&storage.columns),
+        }
+    }
+}
+
+impl<'e, T, D> Storage<'e, T, D>
+where
+    T: Component<Storage = SoAStorage<T>> + SoAComponent,
+    D: DerefMut<Target = MaskedStorage<T>>,
+{
+    /// Returns a join over the column selected by `select`, yielding a
+    /// mutable reference to the field of each component.
+    pub fn column_mut<'a, F>(
+        &'a mut self,
+        select: impl FnOnce(&'a mut T::Columns) -> &'a mut [F],
+    ) -> ColumnMut<'a, F> {
+        // SAFETY: The rows of the storage are neither added nor removed.
+        let (mask, storage) = unsafe { self.masked_storage_mut().open_mut() };
+        let storage: &'a mut SoAStorage<T> = storage;
+        let values = select(&mut storage.columns);
+
+        ColumnMut {
+            mask,
+            data_id: &storage.data_id,
+            values: values.as_mut_ptr(),
+            len: values.len(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+/// A join over the rows of a `SoAStorage`, see `Storage::rows`.
+pub struct Rows<'a, T: SoAComponent> {
+    mask: &'a BitSet,
+    data_id: &'a [Index],
+    columns: &'a T::Columns,
+}
+
+// SAFETY: `get` only reads the columns. Iterating the mask does not repeat
+// indices.
+unsafe impl<'a, T: SoAComponent> Join for Rows<'a, T> {
+    type Mask = &'a BitSet;
+    type Type = SoARef<'a, T>;
+    type Value = (&'a [Index], &'a T::Columns);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, (self.data_id, self.columns))
+    }
+
+    unsafe fn get(&mut (data_id, columns): &mut Self::Value, id: Index) -> Self::Type {
+        columns.get(data_id[id as usize] as usize)
+    }
+}
+
+// SAFETY: `get` only reads the columns, so it is safe to call concurrently.
+// Iterating the mask does not repeat indices.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, T> ParJoin for Rows<'a, T>
+where
+    T: SoAComponent,
+    T::Columns: Sync,
+{
+    type Mask = &'a BitSet;
+    type Type = SoARef<'a, T>;
+    type Value = (&'a [Index], &'a T::Columns);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, (self.data_id, self.columns))
+    }
+
+    unsafe fn get(&(data_id, columns): &Self::Value, id: Index) -> Self::Type {
+        columns.get(data_id[id as usize] as usize)
+    }
+}
+
+/// A join over one column of a `SoAStorage`, see `Storage::column`.
+pub struct Column<'a, F> {
+    mask: &'a BitSet,
+    data_id: &'a [Index],
+    values: &'a [F],
+}
+
+// SAFETY: `get` only reads the column. Iterating the mask does not repeat
+// indices.
+unsafe impl<'a, F> Join for Column<'a, F> {
+    type Mask = &'a BitSet;
+    type Type = &'a F;
+    type Value = (&'a [Index], &'a [F]);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, (self.data_id, self.values))
+    }
+
+    unsafe fn get(&mut (data_id, values): &mut Self::Value, id: Index) -> &'a F {
+        &values[data_id[id as usize] as usize]
+    }
+}
+
+// SAFETY: `get` only reads the column, so it is safe to call concurrently.
+// Iterating the mask does not repeat indices.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, F> ParJoin for Column<'a, F>
+where
+    F: Sync,
+{
+    type Mask = &'a BitSet;
+    type Type = &'a F;
+    type Value = (&'a [Index], &'a [F]);
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, (self.data_id, self.values))
+    }
+
+    unsafe fn get(&(data_id, values): &Self::Value, id: Index) -> &'a F {
+        &values[data_id[id as usize] as usize]
+    }
+}
+
+/// A mutable join over one column of a `SoAStorage`, see
+/// `Storage::column_mut`.
+pub struct ColumnMut<'a, F> {
+    mask: &'a BitSet,
+    data_id: &'a [Index],
+    values: *mut F,
+    len: usize,
+    phantom: PhantomData<&'a mut [F]>,
+}
+
+// SAFETY: `ColumnMut` is a mutable slice with the ids of its rows.
+unsafe impl<F: Send> Send for ColumnMut<'_, F> {}
+
+// SAFETY: Shared access only hands out mutable references to distinct rows,
+// which may then be used on other threads.
+unsafe impl<F: Send + Sync> Sync for ColumnMut<'_, F> {}
+
+impl<'a, F> ColumnMut<'a, F> {
+    /// Returns the value of the row of `id`.
+    ///
+    /// # Safety
+    ///
+    /// `id` must be in the mask, and no other reference to its value may be
+    /// alive.
+    unsafe fn get_mut(&self, id: Index) -> &'a mut F {
+        let did = self.data_id[id as usize] as usize;
+        assert!(did < self.len);
+        // SAFETY: `did` is in bounds and the caller ensures there are no
+        // other references to the value.
+        unsafe { &mut *self.values.add(did) }
+    }
+}
+
+// SAFETY: Each id of the mask has its own row and iterating the mask does not
+// repeat indices, so `get` never returns aliasing references.
+unsafe impl<'a, F> Join for ColumnMut<'a, F> {
+    type Mask = &'a BitSet;
+    type Type = &'a mut F;
+    type Value = Self;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, self)
+    }
+
+    unsafe fn get(value: &mut Self, id: Index) -> &'a mut F {
+        // SAFETY: The caller checked the mask and doesn't repeat `id`.
+        unsafe { value.get_mut(id) }
+    }
+}
+
+// SAFETY: Rows are distinct per id, so `get` is safe to call concurrently with
+// different ids. Iterating the mask does not repeat indices.
+#[cfg(feature = "parallel")]
+unsafe impl<'a, F> ParJoin for ColumnMut<'a, F>
+where
+    F: Send + Sync,
+{
+    type Mask = &'a BitSet;
+    type Type = &'a mut F;
+    type Value = Self;
+
+    unsafe fn open(self) -> (Self::Mask, Self::Value) {
+        (self.mask, self)
+    }
+
+    unsafe fn get(value: &Self, id: Index) -> &'a mut F {
+        // SAFETY: The caller checked the mask and doesn't repeat `id`.
+        unsafe { value.get_mut(id) }
+    }
+}
@@ -1,13 +1,19 @@
 //! Different types of storages you can use for your components.
 
 use core::{marker::PhantomData, mem::MaybeUninit, ptr, ptr::NonNull};
-use std::collections::BTreeMap;
+use std::{
+    alloc::{self, Layout},
+    collections::BTreeMap,
+};
 
 use ahash::AHashMap as HashMap;
 use hibitset::BitSetLike;
+use shred::World;
 
 use crate::{
-    storage::{DistinctStorage, SharedGetMutStorage, SyncUnsafeCell, UnprotectedStorage},
+    storage::{
+        DistinctStorage, SharedGetMutStorage, StorageSetup, SyncUnsafeCell, UnprotectedStorage,
+    },
     world::Index,
 };
 
@@ -26,6 +32,18 @@ pub trait SliceAccess<T> {
     fn as_mut_slice(&mut self) -> &mut [Self::Element];
 }
 
+/// Storages whose `SliceAccess` slices are indexed by entity id.
+///
+/// This allows `BatchJoin` to hand out the components of consecutive ids as
+/// slices.
+///
+/// # Safety
+///
+/// For each id the storage has a component for, the element at the index of
+/// the id must be that component, and `Element` must have the same layout as
+/// `T`.
+pub unsafe trait IdSliceAccess<T>: SliceAccess<T> {}
+
 /// BTreeMap-based storage.
 pub struct BTreeStorage<T>(BTreeMap<Index, SyncUnsafeCell<T>>);
 
@@ -182,6 +200,57 @@ impl<T> SliceAccess<T> for DenseVecStorage<T> {
     }
 }
 
+impl<T> DenseVecStorage<T> {
+    /// Returns the ids of the entities of the components in `as_slice`, in
+    /// the same order.
+    #[inline]
+    pub fn entity_ids(&self) -> &[Index] {
+        &self.entity_id
+    }
+
+    /// Sorts the components by the ids of their entities, restoring the
+    /// cache locality of joins which also touch storages indexed by id, like
+    /// `VecStorage`, after many insertions and removals.
+    ///
+    /// This only moves the components within the storage, so tracked
+    /// storages emit no events for it.
+    pub fn defragment(&mut self) {
+        if self.entity_id.windows(2).all(|ids| ids[0] < ids[1]) {
+            return;
+        }
+
+        let mut components: Vec<_> = std::mem::take(&mut self.entity_id)
+            .into_iter()
+            .zip(std::mem::take(&mut self.data))
+            .collect();
+        // Entity ids are unique, so an unstable sort keeps the order stable.
+        components.sort_unstable_by_key(|&(id, _)| id);
+        (self.entity_id, self.data) = components.into_iter().unzip();
+
+        for (did, &id) in self.entity_id.iter().enumerate() {
+            // NOTE: `as` casts are not lossy, see `insert`.
+            // SAFETY: `data_id` has an element for every inserted id.
+            unsafe { self.data_id.get_unchecked_mut(id as usize) }.write(did as Index);
+        }
+    }
+
+    /// Returns the unused capacity of the dense data, which still holds the
+    /// bytes of removed components.
+    #[cfg(feature = "zeroize")]
+    pub(crate) fn spare_capacity_mut(&mut self) -> &mut [MaybeUninit<SyncUnsafeCell<T>>] {
+        self.data.spare_capacity_mut()
+    }
+
+    /// Returns `entity_ids` together with the mutable components.
+    #[inline]
+    pub(crate) fn ids_and_mut_slice(&mut self) -> (&[Index], &mut [T]) {
+        (
+            &self.entity_id,
+            SyncUnsafeCell::as_slice_mut(self.data.as_mut_slice()),
+        )
+    }
+}
+
 impl<T> UnprotectedStorage<T> for DenseVecStorage<T> {
     type AccessMut<'a> = &'a mut T where T: 'a;
 
@@ -298,6 +367,227 @@ impl<T> SharedGetMutStorage<T> for DenseVecStorage<T> {
 // threads at once.
 unsafe impl<T> DistinctStorage for DenseVecStorage<T> {}
 
+/// Adaptive storage, which starts out as a `HashMapStorage` and promotes
+/// itself to a `DenseVecStorage` once it holds many components.
+///
+/// This is useful for components whose density is hard to predict: rare
+/// components don't pay for the sparse index table of `DenseVecStorage`, and
+/// common ones don't pay for hashing on every access. Promotion moves all
+/// components over, the mask of the storage is unaffected.
+///
+/// By default the storage is promoted once it holds more than
+/// `AutoStorage::DEFAULT_PROMOTE_AT` components and never demoted. Use
+/// `WorldExt::register_with` with the promotion threshold, or
+/// `WorldExt::register_with_storage` with `AutoStorage::new` and
+/// `AutoStorage::with_demotion` to configure this.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, storage::AutoStorage};
+///
+/// struct Burning;
+///
+/// impl Component for Burning {
+///     type Storage = AutoStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register_with_storage::<_, Burning>(|| AutoStorage::new(2).with_demotion(2));
+///
+/// let entities: Vec<_> = (0..3)
+///     .map(|_| world.create_entity().with(Burning).build())
+///     .collect();
+/// assert!(world.read_storage::<Burning>().unprotected_storage().is_dense());
+///
+/// world.write_storage::<Burning>().remove(entities[0]);
+/// world.write_storage::<Burning>().remove(entities[1]);
+/// assert!(!world.read_storage::<Burning>().unprotected_storage().is_dense());
+/// ```
+pub struct AutoStorage<T> {
+    inner: AutoInner<T>,
+    promote_at: usize,
+    demote_at: Option<usize>,
+}
+
+enum AutoInner<T> {
+    Sparse(HashMapStorage<T>),
+    Dense(DenseVecStorage<T>),
+}
+
+impl<T> AutoStorage<T> {
+    /// The number of components above which a default `AutoStorage` is
+    /// promoted.
+    pub const DEFAULT_PROMOTE_AT: usize = 64;
+
+    /// Creates an empty storage which is promoted once it holds more than
+    /// `promote_at` components.
+    pub fn new(promote_at: usize) -> Self {
+        AutoStorage {
+            inner: AutoInner::Sparse(HashMapStorage::default()),
+            promote_at,
+            demote_at: None,
+        }
+    }
+
+    /// Demotes the storage back to a `HashMapStorage` once it holds fewer
+    /// than `demote_at` components.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `demote_at` exceeds the promotion threshold, which could
+    /// make the storage switch back and forth on every insertion and removal.
+    pub fn with_demotion(mut self, demote_at: usize) -> Self {
+        assert!(
+            demote_at <= self.promote_at,
+            "Demotion threshold {} must not exceed the promotion threshold {}",
+            demote_at,
+            self.promote_at
+        );
+        self.demote_at = Some(demote_at);
+
+        self
+    }
+
+    /// Returns `true` if the storage currently is dense, i.e. backed by a
+    /// `DenseVecStorage`.
+    pub fn is_dense(&self) -> bool {
+        matches!(self.inner, AutoInner::Dense(_))
+    }
+
+    /// Returns the number of components in the storage.
+    pub fn len(&self) -> usize {
+        match &self.inner {
+            AutoInner::Sparse(map) => map.0.len(),
+            AutoInner::Dense(dense) => dense.data.len(),
+        }
+    }
+
+    /// Returns `true` if the storage holds no components.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn should_demote(&self) -> bool {
+        self.demote_at
+            .is_some_and(|demote_at| self.len() < demote_at)
+    }
+
+    fn promote(&mut self) {
+        if let AutoInner::Sparse(map) = &mut self.inner {
+            let map = core::mem::take(&mut map.0);
+            let mut dense = DenseVecStorage::default();
+            dense.data.reserve(map.len());
+            dense.entity_id.reserve(map.len());
+            for (id, component) in map {
+                // SAFETY: Each index is only contained once in the map.
+                unsafe { dense.insert(id, component.0.into_inner()) };
+            }
+            self.inner = AutoInner::Dense(dense);
+        }
+    }
+
+    fn demote(&mut self) {
+        if let AutoInner::Dense(dense) = &mut self.inner {
+            let dense = core::mem::take(dense);
+            let map = dense.entity_id.into_iter().zip(dense.data).collect();
+            self.inner = AutoInner::Sparse(HashMapStorage(map));
+        }
+    }
+}
+
+impl<T> Default for AutoStorage<T> {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_PROMOTE_AT)
+    }
+}
+
+/// Creates the storage with `AutoStorage::new`, for
+/// `WorldExt::register_with`.
+impl<T> StorageSetup<usize> for AutoStorage<T> {
+    fn setup(_: &World, promote_at: usize) -> Result<Self, String> {
+        Ok(Self::new(promote_at))
+    }
+}
+
+impl<T> UnprotectedStorage<T> for AutoStorage<T> {
+    type AccessMut<'a> = &'a mut T where T: 'a;
+
+    unsafe fn clean<B>(&mut self, has: B)
+    where
+        B: BitSetLike,
+    {
+        match &mut self.inner {
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Sparse(map) => unsafe { map.clean(has) },
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Dense(dense) => unsafe { dense.clean(has) },
+        }
+        if self.should_demote() {
+            self.demote();
+        }
+    }
+
+    unsafe fn get(&self, id: Index) -> &T {
+        match &self.inner {
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Sparse(map) => unsafe { map.get(id) },
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Dense(dense) => unsafe { dense.get(id) },
+        }
+    }
+
+    unsafe fn get_mut(&mut self, id: Index) -> &mut T {
+        match &mut self.inner {
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Sparse(map) => unsafe { map.get_mut(id) },
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Dense(dense) => unsafe { dense.get_mut(id) },
+        }
+    }
+
+    unsafe fn insert(&mut self, id: Index, v: T) {
+        match &mut self.inner {
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Sparse(map) => unsafe { map.insert(id, v) },
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Dense(dense) => unsafe { dense.insert(id, v) },
+        }
+        if self.len() > self.promote_at {
+            self.promote();
+        }
+    }
+
+    unsafe fn remove(&mut self, id: Index) -> T {
+        let v = match &mut self.inner {
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Sparse(map) => unsafe { map.remove(id) },
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Dense(dense) => unsafe { dense.remove(id) },
+        };
+        if self.should_demote() {
+            self.demote();
+        }
+
+        v
+    }
+}
+
+impl<T> SharedGetMutStorage<T> for AutoStorage<T> {
+    unsafe fn shared_get_mut(&self, id: Index) -> &mut T {
+        match &self.inner {
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Sparse(map) => unsafe { map.shared_get_mut(id) },
+            // SAFETY: Requirements passed to caller.
+            AutoInner::Dense(dense) => unsafe { dense.shared_get_mut(id) },
+        }
+    }
+}
+
+// SAFETY: `shared_get_mut` is forwarded to `HashMapStorage` or
+// `DenseVecStorage`, which both implement `DistinctStorage`.
+unsafe impl<T> DistinctStorage for AutoStorage<T> {}
+
 /// A null storage type, used for cases where the component
 /// doesn't contain any data and instead works as a simple flag.
 pub struct NullStorage<T>(PhantomData<T>);
@@ -416,6 +706,10 @@ impl<T> SliceAccess<T> for VecStorage<T> {
     }
 }
 
+// SAFETY: Components are stored at the index of their id, and
+// `MaybeUninit<T>` has the same layout as `T`.
+unsafe impl<T> IdSliceAccess<T> for VecStorage<T> {}
+
 impl<T> UnprotectedStorage<T> for VecStorage<T> {
     type AccessMut<'a> = &'a mut T where T: 'a;
 
@@ -643,3 +937,289 @@ where
 // accesses when provided distinct indices and is safe to call from multiple
 // threads at once.
 unsafe impl<T> DistinctStorage for DefaultVecStorage<T> {}
+
+// SAFETY: Components are stored at the index of their id.
+unsafe impl<T> IdSliceAccess<T> for DefaultVecStorage<T> {}
+
+/// Vector storage, like `DefaultVecStorage`, but the start of the slices
+/// returned by `as_slice()` and `as_mut_slice()` is aligned to at least `A`
+/// bytes.
+///
+/// This allows running SIMD kernels over the component slices without
+/// copying them into aligned buffers first. Only the start of the slice is
+/// aligned; to align every single component, use `#[repr(align(N))]` on the
+/// component type instead. `A` must be a power of two; alignments lower than
+/// the one of `T` have no effect.
+///
+/// Requires the component to implement `Default`.
+///
+/// `as_slice()` and `as_mut_slice()` indices correspond to entity IDs.
+/// These can be compared to other `DefaultVecStorage`s, to other
+/// `VecStorage`s, and to `Entity::id()`s for live entities.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, storage::AlignedVecStorage};
+///
+/// #[derive(Clone, Copy, Default)]
+/// struct Mass(f32);
+///
+/// impl Component for Mass {
+///     type Storage = AlignedVecStorage<Self, 32>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Mass>();
+/// world.create_entity().with(Mass(1.0)).build();
+///
+/// let masses = world.read_storage::<Mass>();
+/// assert_eq!(masses.as_slice().as_ptr() as usize % 32, 0);
+/// ```
+pub struct AlignedVecStorage<T, const A: usize>(AlignedVec<T, A>);
+
+impl<T, const A: usize> Default for AlignedVecStorage<T, A> {
+    fn default() -> Self {
+        Self(AlignedVec::new())
+    }
+}
+
+impl<T, const A: usize> SliceAccess<T> for AlignedVecStorage<T, A> {
+    type Element = T;
+
+    /// Returns a slice of all the components in this storage.
+    #[inline]
+    fn as_slice(&self) -> &[Self::Element] {
+        self.0.as_slice()
+    }
+
+    /// Returns a mutable slice of all the components in this storage.
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [Self::Element] {
+        self.0.as_mut_slice()
+    }
+}
+
+// SAFETY: Components are stored at the index of their id.
+unsafe impl<T, const A: usize> IdSliceAccess<T> for AlignedVecStorage<T, A> {}
+
+impl<T, const A: usize> UnprotectedStorage<T> for AlignedVecStorage<T, A>
+where
+    T: Default,
+{
+    type AccessMut<'a> = &'a mut T where T: 'a;
+
+    unsafe fn clean<B>(&mut self, _has: B)
+    where
+        B: BitSetLike,
+    {
+        self.0.clear();
+    }
+
+    unsafe fn get(&self, id: Index) -> &T {
+        // NOTE: `as` cast is not lossy since insert would have encountered an
+        // allocation failure if this would overflow `usize.`
+        // SAFETY: Caller required to call `insert` with this `id` (with no
+        // following call to `clean`), so it is in bounds. See `VecStorage`
+        // impl for aliasing.
+        unsafe { &*self.0.ptr(id as usize) }
+    }
+
+    unsafe fn get_mut(&mut self, id: Index) -> &mut T {
+        // NOTE: `as` cast is not lossy since insert would have encountered an
+        // allocation failure if this would overflow `usize.`
+        // SAFETY: Caller required to call `insert` with this `id` (with no
+        // following call to `clean`), so it is in bounds. We have exclusive
+        // access through `&mut self`.
+        unsafe { &mut *self.0.ptr(id as usize) }
+    }
+
+    unsafe fn insert(&mut self, id: Index, v: T) {
+        let id = if Index::BITS > usize::BITS {
+            // Saturate the cast to usize::MAX so if this overflows usize the
+            // allocation below will fail.
+            core::cmp::min(id, usize::MAX as Index) as usize
+        } else {
+            id as usize
+        };
+
+        if self.0.len() <= id {
+            // fill all the empty slots with default values
+            self.0.resize_with(id, Default::default);
+            // store the desired value
+            self.0.push(v);
+        } else {
+            // store the desired value directly
+            self.0.as_mut_slice()[id] = v;
+        }
+    }
+
+    unsafe fn remove(&mut self, id: Index) -> T {
+        // Take value leaving a default instance behind
+        // SAFETY: Caller required to have called `insert` with this `id`.
+        core::mem::take(unsafe { self.get_mut(id) })
+    }
+}
+
+impl<T, const A: usize> SharedGetMutStorage<T> for AlignedVecStorage<T, A>
+where
+    T: Default,
+{
+    unsafe fn shared_get_mut(&self, id: Index) -> &mut T {
+        // NOTE: `as` cast is not lossy since insert would have encountered an
+        // allocation failure if this would overflow `usize.`
+        // SAFETY: Caller required to call `insert` with this `id`, so it is in
+        // bounds. The elements are not owned through a reference, so handing
+        // out exclusive references from `&self` is fine as long as the caller
+        // manages aliasing, see `VecStorage` impl.
+        unsafe { &mut *self.0.ptr(id as usize) }
+    }
+}
+
+// SAFETY: `shared_get_mut` doesn't perform any overlapping mutable
+// accesses when provided distinct indices and is safe to call from multiple
+// threads at once.
+unsafe impl<T, const A: usize> DistinctStorage for AlignedVecStorage<T, A> {}
+
+/// Growable buffer whose allocation is aligned to at least `A` bytes.
+struct AlignedVec<T, const A: usize> {
+    ptr: NonNull<T>,
+    len: usize,
+    cap: usize,
+    phantom: PhantomData<T>,
+}
+
+// SAFETY: `AlignedVec` owns its elements like a `Vec`.
+unsafe impl<T: Send, const A: usize> Send for AlignedVec<T, A> {}
+
+// SAFETY: `AlignedVec` owns its elements like a `Vec`.
+unsafe impl<T: Sync, const A: usize> Sync for AlignedVec<T, A> {}
+
+impl<T, const A: usize> AlignedVec<T, A> {
+    const ALIGN: usize = {
+        assert!(A.is_power_of_two(), "Alignment must be a power of two");
+        if A > core::mem::align_of::<T>() {
+            A
+        } else {
+            core::mem::align_of::<T>()
+        }
+    };
+
+    fn new() -> Self {
+        // NOTE: A dangling pointer with the requested alignment keeps empty
+        // slices aligned as well. It's derived from `NonNull::dangling`, at
+        // address 1, instead of casting `ALIGN` to keep it without provenance.
+        let dangling = NonNull::<u8>::dangling()
+            .as_ptr()
+            .wrapping_add(Self::ALIGN - 1)
+            .cast::<T>();
+        Self {
+            // SAFETY: `ALIGN` is a power of two, so `dangling` is at a non-zero
+            // address.
+            ptr: unsafe { NonNull::new_unchecked(dangling) },
+            len: 0,
+            cap: if core::mem::size_of::<T>() == 0 {
+                usize::MAX
+            } else {
+                0
+            },
+            phantom: PhantomData,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn layout(cap: usize) -> Layout {
+        core::mem::size_of::<T>()
+            .checked_mul(cap)
+            .and_then(|size| Layout::from_size_align(size, Self::ALIGN).ok())
+            .expect("capacity overflow")
+    }
+
+    /// Returns a pointer to the element at `index`.
+    ///
+    /// # Safety
+    ///
+    /// `index` must be in bounds.
+    unsafe fn ptr(&self, index: usize) -> *mut T {
+        // SAFETY: Caller required to pass an index within the allocation.
+        unsafe { self.ptr.as_ptr().add(index) }
+    }
+
+    fn as_slice(&self) -> &[T] {
+        // SAFETY: The first `len` elements are initialized.
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        // SAFETY: The first `len` elements are initialized and we have
+        // exclusive access.
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required <= self.cap {
+            return;
+        }
+
+        let cap = core::cmp::max(core::cmp::max(self.cap * 2, required), 4);
+        let layout = Self::layout(cap);
+        let ptr = if self.cap == 0 {
+            // SAFETY: `layout` has a non-zero size since `T` isn't zero sized
+            // (otherwise `cap` would be `usize::MAX`) and `cap` isn't zero.
+            unsafe { alloc::alloc(layout) }
+        } else {
+            // SAFETY: `ptr` was allocated with the layout for `self.cap`, and
+            // the new size is larger and valid for `ALIGN`.
+            unsafe {
+                alloc::realloc(
+                    self.ptr.as_ptr().cast(),
+                    Self::layout(self.cap),
+                    layout.size(),
+                )
+            }
+        };
+
+        self.ptr = NonNull::new(ptr.cast()).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        self.cap = cap;
+    }
+
+    fn push(&mut self, value: T) {
+        self.reserve(1);
+        // SAFETY: `reserve` made sure there is room for one more element.
+        unsafe { self.ptr(self.len).write(value) };
+        self.len += 1;
+    }
+
+    fn resize_with<F>(&mut self, len: usize, mut f: F)
+    where
+        F: FnMut() -> T,
+    {
+        self.reserve(len.saturating_sub(self.len));
+        while self.len < len {
+            self.push(f());
+        }
+    }
+
+    fn clear(&mut self) {
+        let elems: *mut [T] = self.as_mut_slice();
+        // NOTE: Set the length first so a panicking `Drop` impl leaks the
+        // remaining elements instead of dropping them twice.
+        self.len = 0;
+        // SAFETY: The elements were initialized and are no longer accessible.
+        unsafe { ptr::drop_in_place(elems) };
+    }
+}
+
+impl<T, const A: usize> Drop for AlignedVec<T, A> {
+    fn drop(&mut self) {
+        self.clear();
+        if self.cap != 0 && core::mem::size_of::<T>() != 0 {
+            // SAFETY: `ptr` was allocated with the layout for `self.cap`.
+            unsafe { alloc::dealloc(self.ptr.as_ptr().cast(), Self::layout(self.cap)) };
+        }
+    }
+}
@@ -19,6 +19,7 @@ where
 
 mod map_test {
     use super::*;
+    use crate::world::Builder;
 
     #[derive(Debug)]
     struct Comp<T>(T);
@@ -86,6 +87,116 @@ mod map_test {
         }
     }
 
+    #[test]
+    fn try_remove() {
+        let mut w = World::new();
+        w.register::<Comp<u32>>();
+        let a = w.create_entity().with(Comp(1u32)).build();
+        let b = w.create_entity().build();
+
+        let mut c = w.write_storage::<Comp<u32>>();
+        assert_eq!(c.try_remove(a).unwrap().map(|c| c.0), Some(1));
+        assert!(c.try_remove(a).unwrap().is_none());
+        assert!(c.try_remove(b).unwrap().is_none());
+        drop(c);
+
+        w.delete_entity(a).unwrap();
+        let mut c = w.write_storage::<Comp<u32>>();
+        let err = c.try_remove(a).unwrap_err();
+        assert_eq!(err.entity, a);
+        assert_eq!(err.action, "remove component for entity");
+        assert!(c.try_get(a).is_err());
+    }
+
+    #[test]
+    fn constructor() {
+        let mut w = World::new();
+        w.register_with_ctor(|e: Entity| Comp(e.id()));
+        let a = w.create_entity().build();
+        let b = w.create_entity().with(Comp(7u32)).build();
+        let c = w.create_entity().build();
+
+        let mut s = w.write_storage::<Comp<u32>>();
+        assert_eq!(s.get_mut_or_construct(a).map(|comp| comp.0), Some(a.id()));
+        assert_eq!(s.get_mut_or_construct(b).map(|comp| comp.0), Some(7));
+        assert_eq!(s.entry(c).unwrap().or_construct().0, c.id());
+        drop(s);
+
+        w.delete_entity(a).unwrap();
+        let mut s = w.write_storage::<Comp<u32>>();
+        assert!(s.get_mut_or_construct(a).is_none());
+    }
+
+    #[test]
+    fn observe() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::storage::StorageHooks;
+
+        let mut w = World::new();
+        w.register::<Comp<u32>>();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let (inserted, removed) = (log.clone(), log.clone());
+        w.write_storage::<Comp<u32>>().observe(
+            StorageHooks::new()
+                .on_insert(move |e: Entity, c: &Comp<u32>| {
+                    inserted.lock().unwrap().push((true, e.id(), c.0))
+                })
+                .on_remove(move |e: Entity, c: &Comp<u32>| {
+                    removed.lock().unwrap().push((false, e.id(), c.0))
+                }),
+        );
+
+        let a = w.create_entity().with(Comp(1u32)).build();
+        let b = w.create_entity().with(Comp(2u32)).build();
+        let c = w.create_entity().with(Comp(3u32)).build();
+        {
+            let mut s = w.write_storage::<Comp<u32>>();
+            s.insert(a, Comp(4)).unwrap();
+            s.remove(b);
+            s.remove(b);
+        }
+        w.delete_entity(a).unwrap();
+        w.write_storage::<Comp<u32>>().clear();
+
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                (true, a.id(), 1),
+                (true, b.id(), 2),
+                (true, c.id(), 3),
+                (true, a.id(), 4),
+                (false, b.id(), 2),
+                (false, a.id(), 4),
+                (false, c.id(), 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn observe_keeps_previous_hooks() {
+        use std::sync::{Arc, Mutex};
+
+        use crate::storage::StorageHooks;
+
+        let mut w = World::new();
+        w.register::<Comp<u32>>();
+        let log = Arc::new(Mutex::new(Vec::new()));
+        for hook in 0..2 {
+            let (inserted, removed) = (log.clone(), log.clone());
+            w.write_storage::<Comp<u32>>().observe(
+                StorageHooks::new()
+                    .on_insert(move |_, c: &Comp<u32>| inserted.lock().unwrap().push((hook, c.0)))
+                    .on_remove(move |_, c: &Comp<u32>| removed.lock().unwrap().push((hook, c.0))),
+            );
+        }
+
+        let a = w.create_entity().with(Comp(1u32)).build();
+        w.delete_entity(a).unwrap();
+
+        assert_eq!(*log.lock().unwrap(), [(0, 1), (1, 1), (0, 1), (1, 1)]);
+    }
+
     #[test]
     fn test_gen() {
         let mut w = World::new();
@@ -140,7 +251,7 @@ mod test {
         type Storage = NullStorage<Self>;
     }
 
-    #[derive(PartialEq, Eq, Debug, Default)]
+    #[derive(Clone, PartialEq, Eq, Debug, Default)]
     struct Cvec(u32);
     impl From<u32> for Cvec {
         fn from(v: u32) -> Cvec {
@@ -156,7 +267,8 @@ mod test {
         type Storage = VecStorage<Self>;
     }
 
-    #[derive(PartialEq, Eq, Debug)]
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     struct FlaggedCvec(u32);
     impl From<u32> for FlaggedCvec {
         fn from(v: u32) -> FlaggedCvec {
@@ -204,6 +316,22 @@ mod test {
         type Storage = BTreeStorage<Self>;
     }
 
+    #[derive(PartialEq, Eq, Debug)]
+    struct CAuto(u32);
+    impl From<u32> for CAuto {
+        fn from(v: u32) -> CAuto {
+            CAuto(v)
+        }
+    }
+    impl AsMut<u32> for CAuto {
+        fn as_mut(&mut self) -> &mut u32 {
+            &mut self.0
+        }
+    }
+    impl Component for CAuto {
+        type Storage = AutoStorage<Self>;
+    }
+
     #[derive(Debug, Default, PartialEq)]
     struct Cnull;
 
@@ -217,6 +345,13 @@ mod test {
         type Storage = NullStorage<Self>;
     }
 
+    #[derive(Debug, Default, PartialEq)]
+    struct CFlag;
+
+    impl Component for CFlag {
+        type Storage = FlagBoolStorage<Self>;
+    }
+
     struct CEntries(u32);
 
     impl From<u32> for CEntries {
@@ -245,6 +380,22 @@ mod test {
         type Storage = DefaultVecStorage<Self>;
     }
 
+    #[derive(PartialEq, Eq, Debug, Default)]
+    struct CalignedVec(u32);
+    impl From<u32> for CalignedVec {
+        fn from(v: u32) -> CalignedVec {
+            CalignedVec(v)
+        }
+    }
+    impl AsMut<u32> for CalignedVec {
+        fn as_mut(&mut self) -> &mut u32 {
+            &mut self.0
+        }
+    }
+    impl Component for CalignedVec {
+        type Storage = AlignedVecStorage<Self, 64>;
+    }
+
     fn test_add<T: Component + From<u32> + Debug + Eq>()
     where
         T::Storage: Default,
@@ -537,6 +688,32 @@ mod test {
         }
     }
 
+    #[test]
+    fn snapshot_index_reuse() {
+        use crate::join::WithPrevious;
+
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let old = w.create_entity().with(Cvec(1)).build();
+        let kept = w.create_entity().with(Cvec(2)).build();
+
+        let previous = w.read_storage::<Cvec>().snapshot();
+        w.delete_entity(old).unwrap();
+        let new = w.create_entity().with(Cvec(3)).build();
+        assert_eq!(new.id(), old.id());
+
+        assert_eq!(previous.get(old), Some(&Cvec(1)));
+        assert_eq!(previous.get(new), None);
+
+        let s = w.read_storage::<Cvec>();
+        let joined: Vec<_> = WithPrevious::new(&s, &previous).join().collect();
+        assert_eq!(joined, [(&Cvec(3), None), (&Cvec(2), Some(&Cvec(2)))]);
+        assert_eq!(
+            WithPrevious::new(&s, &previous).get(kept),
+            Some((&Cvec(2), Some(&Cvec(2))))
+        );
+    }
+
     #[test]
     fn default_vec_test_add() {
         test_add::<CdefaultVec>();
@@ -609,6 +786,55 @@ mod test {
         );
     }
 
+    #[test]
+    fn aligned_vec_test_add() {
+        test_add::<CalignedVec>();
+    }
+    #[test]
+    fn aligned_vec_test_sub() {
+        test_sub::<CalignedVec>();
+    }
+    #[test]
+    fn aligned_vec_test_get_mut() {
+        test_get_mut::<CalignedVec>();
+    }
+    #[test]
+    fn aligned_vec_test_get_mut_or_default() {
+        test_get_mut_or_default::<CalignedVec>();
+    }
+    #[test]
+    fn aligned_vec_test_clear() {
+        test_clear::<CalignedVec>();
+    }
+    #[test]
+    fn aligned_vec_test_anti() {
+        test_anti::<CalignedVec>();
+    }
+    #[test]
+    fn aligned_vec_test_slice_access() {
+        test_slice_access::<CalignedVec>();
+    }
+
+    #[test]
+    fn aligned_vec_test_alignment() {
+        let mut w = World::new();
+        let mut s: Storage<CalignedVec, _> = create(&mut w);
+        assert_eq!(s.as_slice().as_ptr() as usize % 64, 0);
+
+        // growing the buffer keeps the alignment
+        for i in 0..ITERATIONS {
+            s.insert(Entity::new(i, Generation::new(1)), i.into())
+                .unwrap();
+            assert_eq!(s.as_slice().as_ptr() as usize % 64, 0);
+        }
+
+        s.insert(Entity::new(ITERATIONS + 2, Generation::new(1)), 7.into())
+            .unwrap();
+        assert_eq!(s.as_slice().len(), ITERATIONS as usize + 3);
+        assert_eq!(s.as_slice()[ITERATIONS as usize], CalignedVec(0));
+        assert_eq!(s.as_mut_slice().as_ptr() as usize % 64, 0);
+    }
+
     #[test]
     fn hash_test_add() {
         test_add::<Cmap>();
@@ -634,6 +860,135 @@ mod test {
         test_clear::<Cmap>();
     }
 
+    #[test]
+    fn auto_test_add() {
+        test_add::<CAuto>();
+    }
+    #[test]
+    fn auto_test_sub() {
+        test_sub::<CAuto>();
+    }
+    #[test]
+    fn auto_test_get_mut() {
+        test_get_mut::<CAuto>();
+    }
+    #[test]
+    fn auto_test_add_gen() {
+        test_add_gen::<CAuto>();
+    }
+    #[test]
+    fn auto_test_sub_gen() {
+        test_sub_gen::<CAuto>();
+    }
+    #[test]
+    fn auto_test_clear() {
+        test_clear::<CAuto>();
+    }
+
+    #[test]
+    fn auto_storage_promotion() {
+        let mut w = World::new();
+        w.register_with_storage::<_, CAuto>(|| AutoStorage::new(10).with_demotion(5));
+        let mut s: Storage<CAuto, _> = w.write_storage();
+        let e = |i| Entity::new(i, Generation::new(1));
+
+        for i in 0..10 {
+            s.insert(e(i), CAuto(i)).unwrap();
+        }
+        assert!(!s.unprotected_storage().is_dense());
+        s.insert(e(10), CAuto(10)).unwrap();
+        assert!(s.unprotected_storage().is_dense());
+        for i in 0..=10 {
+            assert_eq!(s.get(e(i)), Some(&CAuto(i)));
+        }
+
+        for i in 0..6 {
+            assert_eq!(s.remove(e(i)), Some(CAuto(i)));
+        }
+        assert!(s.unprotected_storage().is_dense());
+        assert_eq!(s.remove(e(6)), Some(CAuto(6)));
+        assert!(!s.unprotected_storage().is_dense());
+        assert_eq!(s.unprotected_storage().len(), 4);
+        for i in 7..=10 {
+            assert_eq!(s.get(e(i)), Some(&CAuto(i)));
+        }
+        assert_eq!(s.mask().iter().count(), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "must not exceed")]
+    fn auto_storage_invalid_demotion() {
+        let _ = AutoStorage::<CAuto>::new(10).with_demotion(11);
+    }
+
+    #[derive(PartialEq, Eq, Debug)]
+    struct CSharded(u32);
+    impl Component for CSharded {
+        type Storage = VecStorage<Self>;
+    }
+    impl ShardedComponent for CSharded {
+        const SHARDS: usize = 2;
+    }
+
+    #[test]
+    fn sharded_storage() {
+        use shred::{RunNow, System, SystemData};
+
+        let mut w = World::new();
+        w.register_sharded::<CSharded>();
+        let entities: Vec<_> = (0..4).map(|_| w.create_entity().build()).collect();
+        {
+            let mut all = w.system_data::<ShardedWriteAll<CSharded>>();
+            for (i, e) in entities.iter().enumerate() {
+                all.insert(*e, CSharded(i as u32)).unwrap();
+            }
+            assert_eq!(all.count(), 4);
+        }
+
+        // Systems writing different shards don't conflict.
+        assert_ne!(
+            ShardedWrite::<CSharded, 0>::writes(),
+            ShardedWrite::<CSharded, 1>::writes()
+        );
+
+        struct Double<const SHARD: usize>;
+        impl<'a, const SHARD: usize> System<'a> for Double<SHARD> {
+            type SystemData = ShardedWrite<'a, CSharded, SHARD>;
+
+            fn run(&mut self, mut data: Self::SystemData) {
+                for c in (&mut data).join() {
+                    c.0 *= 2;
+                }
+            }
+        }
+        Double::<1>.run_now(&w);
+
+        {
+            let read = w.system_data::<ShardedRead<CSharded>>();
+            let mut values: Vec<_> = read.join().map(|c| c.0).collect();
+            values.sort_unstable();
+            assert_eq!(values, vec![0, 2, 2, 6]);
+            assert_eq!(read.shard(0).count(), 2);
+        }
+
+        {
+            let mut shard = w.system_data::<ShardedWrite<CSharded, 0>>();
+            match shard.insert(entities[1], CSharded(5)) {
+                Err(Error::WrongShard(e)) => {
+                    assert_eq!(e.shard, 0);
+                    assert_eq!(e.expected, 1);
+                }
+                _ => panic!("inserted into the wrong shard"),
+            }
+        }
+
+        w.delete_entity(entities[2]).unwrap();
+        w.maintain();
+        let read = w.system_data::<ShardedRead<CSharded>>();
+        assert!(!read.contains(entities[2]));
+        assert_eq!(read.count(), 3);
+    }
+
     #[test]
     fn btree_test_add() {
         test_add::<CBtree>();
@@ -665,112 +1020,709 @@ mod test {
     }
 
     #[test]
-    fn test_null_insert_twice() {
+    fn get_unchecked() {
         let mut w = World::new();
+        w.register::<Cvec>();
+        let e = w.create_entity().with(Cvec(1)).build();
 
-        w.register::<Cnull>();
+        let mut s = w.write_storage::<Cvec>();
+        for (e, _) in (&w.entities(), &s.mask().clone()).join() {
+            // SAFETY: The entity is alive and in the mask.
+            unsafe { s.get_mut_unchecked(e) }.0 += 1;
+        }
+        // SAFETY: The entity is alive and in the mask.
+        assert_eq!(unsafe { s.get_unchecked(e) }, &Cvec(2));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "`get_unchecked` called for")]
+    fn get_unchecked_missing() {
+        let mut w = World::new();
+        w.register::<Cvec>();
         let e = w.create_entity().build();
 
-        let mut null = w.write_storage::<Cnull>();
+        // SAFETY: Debug builds check the entity, so this panics instead.
+        unsafe { w.read_storage::<Cvec>().get_unchecked(e) };
+    }
 
-        assert_eq!(null.get(e), None);
-        match null.insert(e, Cnull) {
-            Ok(None) => {}
-            r => panic!("Expected Ok(None) got {:?}", r),
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn swap() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let a = w.create_entity().with(FlaggedCvec(1)).build();
+        let b = w.create_entity().with(FlaggedCvec(2)).build();
+        let c = w.create_entity().build();
+        let dead = w.create_entity().with(FlaggedCvec(3)).build();
+        w.delete_entity(dead).unwrap();
+
+        let mut s = w.write_storage::<FlaggedCvec>();
+        let mut reader = s.register_reader();
+        s.swap(a, b).unwrap();
+        assert_eq!(s.get(a), Some(&FlaggedCvec(2)));
+        assert_eq!(s.get(b), Some(&FlaggedCvec(1)));
+        s.swap(c, a).unwrap();
+        assert_eq!(s.get(a), None);
+        assert_eq!(s.get(c), Some(&FlaggedCvec(2)));
+        s.swap(a, a).unwrap();
+        assert!(s.swap(b, dead).is_err());
+        assert_eq!(s.get(b), Some(&FlaggedCvec(1)));
+
+        let events: Vec<_> = s.channel().read(&mut reader).copied().collect();
+        assert_eq!(
+            events,
+            [
+                ComponentEvent::Modified(a.id()),
+                ComponentEvent::Modified(b.id()),
+                ComponentEvent::Removed(a.id()),
+                ComponentEvent::Inserted(c.id()),
+            ]
+        );
+    }
+
+    #[test]
+    fn clear_reporting() {
+        struct Handle(u32);
+
+        impl Component for Handle {
+            type Storage = DenseVecStorage<Self>;
         }
-        match null.insert(e, Cnull) {
-            Ok(Some(Cnull)) => {}
-            r => panic!("Expected Ok(Some(Cnull)) got {:?}", r),
+
+        impl Drop for Handle {
+            fn drop(&mut self) {
+                assert!(self.0 % 2 == 0, "odd handle");
+            }
         }
+
+        let mut w = World::new();
+        w.register::<Handle>();
+        let e: Vec<_> = (0..4)
+            .map(|i| w.create_entity().with(Handle(i)).build())
+            .collect();
+
+        let mut failed = Vec::new();
+        w.write_storage::<Handle>().clear_reporting(|entity, payload| {
+            assert_eq!(payload.downcast_ref::<&str>(), Some(&"odd handle"));
+            failed.push(entity);
+        });
+        assert_eq!(failed, [e[1], e[3]]);
+        assert_eq!(w.read_storage::<Handle>().count(), 0);
+        w.write_storage::<Handle>().insert(e[0], Handle(2)).unwrap();
     }
 
     #[test]
-    fn restricted_storage() {
-        use crate::join::Join;
-        use std::collections::HashSet;
+    fn range_iter() {
+        use crate::join::LendJoin;
 
         let mut w = World::new();
         w.register::<Cvec>();
-        let mut s1: Storage<Cvec, _> = w.write_storage();
-        let mut components = HashSet::new();
+        let e: Vec<_> = (0..70_000)
+            .map(|i| {
+                let builder = w.create_entity();
+                if i % 7 == 0 {
+                    builder.with(Cvec(i)).build()
+                } else {
+                    builder.build()
+                }
+            })
+            .collect();
 
-        for i in 0..50 {
-            let c = i + 10;
-            if let Err(err) = s1.insert(Entity::new(i, Generation::new(1)), c.into()) {
-                panic!("Failed to insert component into entity! {:?}", err);
-            }
-            components.insert(c);
+        let s = w.read_storage::<Cvec>();
+        for range in [0..0, 5..5, 0..1, 1..7, 60..4100, 4095..4097, 0..70_000, 69_990..80_000] {
+            let expected: Vec<_> = (range.clone())
+                .filter(|&i| i < 70_000 && i % 7 == 0)
+                .map(|i| (e[i as usize], i))
+                .collect();
+            let found: Vec<_> = s.range_iter(range.clone()).map(|(e, c)| (e, c.0)).collect();
+            assert_eq!(found, expected, "range {:?}", range);
+            assert_eq!(s.range_iter(range).count_matched(), expected.len());
         }
+        drop(s);
 
-        for mut comps in (&mut s1.restrict_mut()).join() {
-            let c1 = { comps.get().0 };
-
-            let c2 = { comps.get_mut().0 };
-
-            assert_eq!(
-                c1, c2,
-                "Mutable and immutable gets returned different components."
-            );
-            assert!(
-                components.remove(&c1),
-                "Same component was iterated twice in join."
-            );
+        let mut s = w.write_storage::<Cvec>();
+        for c in (&mut s).in_index_range(100..200).join() {
+            c.0 = 0;
         }
-        assert!(
-            components.is_empty(),
-            "Some components weren't iterated in join."
-        );
+        assert_eq!(s.get(e[98]), Some(&Cvec(98)));
+        assert_eq!(s.get(e[105]), Some(&Cvec(0)));
+        assert_eq!(s.get(e[203]), Some(&Cvec(203)));
     }
 
     #[test]
-    #[cfg(feature = "parallel")]
-    fn par_restricted_storage() {
-        use crate::join::ParJoin;
-        use rayon::iter::ParallelIterator;
-        use std::{collections::HashSet, sync::Mutex};
+    fn join_sorted_by_key() {
+        use crate::join::SortBuffer;
 
         let mut w = World::new();
         w.register::<Cvec>();
-        let mut s1: Storage<Cvec, _> = w.write_storage();
-        let mut components = HashSet::new();
+        w.register::<Cmap>();
+        let e: Vec<_> = (0..10)
+            .map(|i| {
+                let builder = w.create_entity().with(Cvec(i));
+                if i != 4 {
+                    builder.with(Cmap(i % 3)).build()
+                } else {
+                    builder.build()
+                }
+            })
+            .collect();
+
+        let mut buffer = SortBuffer::with_capacity(16);
+        let capacity = buffer.capacity();
+        let keys = w.read_storage::<Cmap>();
+        let mut s = w.write_storage::<Cvec>();
+        let sorted = (&mut s).join_sorted_by_key(&keys, &mut buffer, |c| c.0);
+        assert_eq!(sorted.len(), 9);
+        for c in sorted {
+            c.0 += 10;
+        }
+        assert_eq!(s.get(e[4]), Some(&Cvec(4)));
+        assert_eq!(s.get(e[5]), Some(&Cvec(15)));
+
+        let order: Vec<_> = (&w.entities(), &s)
+            .join_sorted_by_key(&keys, &mut buffer, |c| 2 - c.0)
+            .map(|(e, c)| (e.id(), c.0))
+            .collect();
+        assert_eq!(
+            order,
+            [(2, 12), (5, 15), (8, 18), (1, 11), (7, 17), (0, 10), (3, 13), (6, 16), (9, 19)]
+        );
+        assert_eq!(buffer.capacity(), capacity);
+    }
 
-        for i in 0..50 {
-            let c = i + 10;
-            if let Err(err) = s1.insert(Entity::new(i, Generation::new(1)), c.into()) {
-                panic!("Failed to insert component into entity! {:?}", err);
+    #[test]
+    fn partition_mut() {
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let e: Vec<_> = (0..6).map(|i| w.create_entity().with(Cvec(i)).build()).collect();
+
+        let mut s = w.write_storage::<Cvec>();
+        let (mut even, mut odd) = s.partition_mut(|c| c.0 % 2 == 0);
+        assert_eq!((&even).join().map(|c| c.0).collect::<Vec<_>>(), [0, 2, 4]);
+        assert!(even.contains(e[2]) && !even.contains(e[3]));
+        assert_eq!(odd.get(e[3]), Some(&Cvec(3)));
+        assert_eq!(odd.get(e[2]), None);
+
+        for a in (&mut even).join() {
+            for b in (&mut odd).join() {
+                a.0 += 10;
+                b.0 += 100;
             }
-            components.insert(c);
         }
+        even.get_mut(e[0]).unwrap().0 = 0;
+        assert!(odd.get_mut(e[0]).is_none());
+        drop((even, odd));
 
-        let components2 = Mutex::new(Vec::new());
-        let components2_mut = Mutex::new(Vec::new());
-
-        (&mut s1.restrict_mut()).par_join().for_each(|mut comps| {
-            let (mut components2, mut components2_mut) =
-                (components2.lock().unwrap(), components2_mut.lock().unwrap());
-            components2.push(comps.get().0);
-            components2_mut.push(comps.get_mut().0);
-        });
-        let components2 = components2.into_inner().unwrap();
-        assert_eq!(
-            components2,
-            components2_mut.into_inner().unwrap(),
-            "Mutable and immutable gets returned different components."
-        );
         assert_eq!(
-            components,
-            components2.into_iter().collect(),
-            "Components iterated weren't as should've been."
+            (&s).join().map(|c| c.0).collect::<Vec<_>>(),
+            [0, 301, 32, 303, 34, 305]
         );
     }
 
     #[test]
-    fn storage_entry() {
-        let mut w = World::new();
-        w.register::<Cvec>();
+    fn dyn_storage() {
+        trait Shape: AsAny + Send + Sync {
+            fn area(&self) -> u32;
+        }
 
-        let e1 = w.create_entity().build();
-        let e2 = w.create_entity().with(Cvec(10)).build();
+        impl Component for Box<dyn Shape> {
+            type Storage = DynStorage<dyn Shape>;
+        }
+
+        struct Square(u32);
+
+        impl Shape for Square {
+            fn area(&self) -> u32 {
+                self.0 * self.0
+            }
+        }
+
+        struct Rect(u32, u32);
+
+        impl Shape for Rect {
+            fn area(&self) -> u32 {
+                self.0 * self.1
+            }
+        }
+
+        let mut w = World::new();
+        w.register::<Box<dyn Shape>>();
+        let e: Vec<_> = (0..4)
+            .map(|i| {
+                let shape: Box<dyn Shape> = if i % 2 == 0 {
+                    Box::new(Square(i))
+                } else {
+                    Box::new(Rect(i, 2))
+                };
+                w.create_entity().with(shape).build()
+            })
+            .collect();
+
+        let mut s = w.write_storage::<Box<dyn Shape>>();
+        let areas: Vec<_> = s.join().map(|shape| shape.area()).collect();
+        assert_eq!(areas, [0, 2, 4, 6]);
+        assert_eq!(s.downcast::<Rect>(e[1]).map(|r| r.0), Some(1));
+        assert!(s.downcast::<Square>(e[1]).is_none());
+
+        for square in s.downcast_join_mut::<Square>().join() {
+            square.0 += 1;
+        }
+        let squares: Vec<_> = s.downcast_join::<Square>().join().map(|s| s.0).collect();
+        assert_eq!(squares, [1, 3]);
+
+        // Replacing components through mutable access updates the masks.
+        *s.get_mut(e[2]).unwrap() = Box::new(Rect(1, 1));
+        s.insert(e[3], Box::new(Square(5))).unwrap();
+        s.remove(e[0]);
+        let squares: Vec<_> = s.downcast_join::<Square>().join().map(|s| s.0).collect();
+        assert_eq!(squares, [5]);
+        let rects: Vec<_> = s.downcast_join::<Rect>().join().map(|r| r.0).collect();
+        assert_eq!(rects, [1, 1]);
+        assert_eq!(s.downcast_join::<u32>().join().count(), 0);
+    }
+
+    #[test]
+    fn mask_owned() {
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let e: Vec<_> = (0..5000)
+            .map(|i| w.create_entity().with(Cvec(i)).build())
+            .collect();
+        for &e in e.iter().filter(|e| e.id() % 3 != 0) {
+            w.delete_entity(e).unwrap();
+        }
+        w.maintain();
+
+        let mask = w.read_storage::<Cvec>().mask_owned();
+        w.write_storage::<Cvec>().remove(e[0]);
+        assert!(mask.contains(0));
+        assert_eq!(crate::BitSetLen::len(&mask), 1667);
+
+        #[cfg(feature = "serde")]
+        {
+            #[derive(serde::Serialize, serde::Deserialize)]
+            struct Saved(#[serde(with = "crate::bitset_serde")] BitSet);
+
+            let json = serde_json::to_string(&Saved(mask.clone())).unwrap();
+            let Saved(loaded) = serde_json::from_str(&json).unwrap();
+            assert_eq!(
+                (&loaded).iter().collect::<Vec<_>>(),
+                (&mask).iter().collect::<Vec<_>>()
+            );
+
+            for json in ["[[4294967295,1]]", "[[262144,9]]"] {
+                let err = serde_json::from_str::<Saved>(json).err().unwrap();
+                assert!(err.to_string().contains("exceeds the capacity"));
+            }
+            assert!(serde_json::from_str::<Saved>("[[4294967295,0]]").is_ok());
+        }
+    }
+
+    #[test]
+    fn lease() {
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let e: Vec<_> = (0..3)
+            .map(|i| w.create_entity().with(Cvec(i)).build())
+            .collect();
+        w.delete_entity(e[1]).unwrap();
+
+        let lease = w.read_storage::<Cvec>().lease();
+        let clone = lease.clone();
+        assert_eq!(lease.len(), 2);
+        assert_eq!(lease.get(e[2]), Some(&Cvec(2)));
+        assert_eq!(lease.get(e[1]), None);
+        assert_eq!(
+            lease.iter().collect::<Vec<_>>(),
+            [(e[0], &Cvec(0)), (e[2], &Cvec(2))]
+        );
+
+        match w.try_write_storage::<Cvec>().err().unwrap() {
+            Error::LeaseConflict(conflict) => assert_eq!(conflict.leases, [lease.location()]),
+            e => panic!("unexpected error {:?}", e),
+        }
+        // Overlapping leases share their components, until some are removed.
+        let other = w.read_storage::<Cvec>().lease();
+        assert!(std::ptr::eq(other.get(e[0]).unwrap(), lease.get(e[0]).unwrap()));
+        w.delete_entity(e[2]).unwrap();
+        let after_delete = w.read_storage::<Cvec>().lease();
+        assert_eq!(after_delete.len(), 1);
+        assert!(!std::ptr::eq(after_delete.get(e[0]).unwrap(), lease.get(e[0]).unwrap()));
+        drop((other, after_delete));
+
+        drop(lease);
+        assert!(w.try_write_storage::<Cvec>().is_err());
+        drop(clone);
+        w.write_storage::<Cvec>().insert(e[0], Cvec(5)).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "while it is leased, by the leases taken at")]
+    fn lease_write_conflict() {
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let _lease = w.read_storage::<Cvec>().lease();
+
+        w.write_storage::<Cvec>();
+    }
+
+    #[test]
+    fn generic_storage_entry_and_remove() {
+        fn bump<S>(storage: &mut S, entity: Entity) -> Option<u32>
+        where
+            S: GenericWriteStorage<Component = Cvec>,
+        {
+            let existed = storage.contains(entity);
+            let c = storage.entry(entity).ok()?.or_insert(Cvec(0));
+            assert_eq!(existed, c.0 > 0);
+            c.0 += 1;
+            Some(c.0)
+        }
+
+        fn has<S: GenericReadStorage<Component = Cvec>>(storage: S, entity: Entity) -> bool {
+            storage.contains(entity)
+        }
+
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let e = w.create_entity().build();
+        let dead = w.create_entity().build();
+        w.delete_entity(dead).unwrap();
+
+        let mut s = w.write_storage::<Cvec>();
+        assert!(!has(&s, e));
+        assert_eq!(bump(&mut s, e), Some(1));
+        assert_eq!(bump(&mut s, e), Some(2));
+        assert_eq!(bump(&mut s, dead), None);
+        assert!(has(&s, e));
+        assert_eq!(GenericWriteStorage::remove(&mut s, e), Some(Cvec(2)));
+        assert_eq!(GenericWriteStorage::remove(&mut s, e), None);
+        assert!(!has(&s, e));
+    }
+
+    #[test]
+    fn test_null_insert_twice() {
+        let mut w = World::new();
+
+        w.register::<Cnull>();
+        let e = w.create_entity().build();
+
+        let mut null = w.write_storage::<Cnull>();
+
+        assert_eq!(null.get(e), None);
+        match null.insert(e, Cnull) {
+            Ok(None) => {}
+            r => panic!("Expected Ok(None) got {:?}", r),
+        }
+        match null.insert(e, Cnull) {
+            Ok(Some(Cnull)) => {}
+            r => panic!("Expected Ok(Some(Cnull)) got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn flag_bool_storage() {
+        use crate::join::Join;
+
+        let mut w = World::new();
+
+        w.register::<CFlag>();
+        let entities: Vec<_> = w.create_iter().take(4).collect();
+        let dead = w.create_entity().build();
+        w.delete_entity(dead).unwrap();
+
+        let mut flags = w.write_storage::<CFlag>();
+
+        assert_eq!(flags.value(entities[0]), Some(false));
+        assert_eq!(flags.value(dead), None);
+        assert!(flags.set(dead, true).is_err());
+
+        assert!(!flags.set(entities[0], true).unwrap());
+        assert!(flags.toggle(entities[1]).unwrap());
+        assert!(!flags.toggle(entities[1]).unwrap());
+        assert_eq!(flags.value(entities[0]), Some(true));
+
+        let mut mask = BitSet::new();
+        mask.add(entities[2].id());
+        mask.add(entities[3].id());
+        mask.add(dead.id());
+        flags.set_all(&mask, true);
+        assert_eq!(flags.count(), 3);
+        assert_eq!(flags.value(dead), None);
+
+        let values: Vec<_> = (&w.entities(), flags.values())
+            .join()
+            .map(|(_, v)| v)
+            .collect();
+        assert_eq!(values, vec![true, false, true, true]);
+    }
+
+    #[test]
+    fn join_with_slice() {
+        let mut w = World::new();
+        let mut s: Storage<Cvec, _> = create(&mut w);
+
+        let ids = [1, 64, 4097, 300_000];
+        for &id in &ids {
+            s.insert(Entity::new(id, Generation::new(1)), id.into())
+                .unwrap();
+        }
+
+        let mut data = vec![0; 300_000];
+        match s.join_with_slice(&mut data) {
+            Err(Error::SliceTooShort(e)) => {
+                assert_eq!(e.len, 300_000);
+                assert_eq!(e.required, 300_001);
+            }
+            _ => panic!("Expected the slice to be too short"),
+        }
+
+        data.push(0);
+        for (c, d) in s.join_with_slice(&mut data).unwrap() {
+            *d = c.0;
+            c.0 += 1;
+        }
+
+        for &id in &ids {
+            assert_eq!(data[id as usize], id);
+            assert_eq!(
+                s.get(Entity::new(id, Generation::new(1))),
+                Some(&Cvec(id + 1))
+            );
+        }
+        assert_eq!(data.iter().filter(|&&d| d != 0).count(), ids.len());
+
+        let mut empty: [u32; 0] = [];
+        s.clear();
+        assert_eq!(s.join_with_slice(&mut empty).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn dense_join() {
+        #[derive(Debug, PartialEq)]
+        struct Cdense(u32);
+        impl Component for Cdense {
+            type Storage = DenseVecStorage<Self>;
+        }
+
+        let mut w = World::new();
+        w.register::<Cdense>();
+        w.register::<Cvec>();
+
+        let entities: Vec<_> = (0..10).map(|_| w.create_entity().build()).collect();
+        let mut dense = w.write_storage::<Cdense>();
+        let mut vec = w.write_storage::<Cvec>();
+        // Inserted out of id order so storage order differs from id order.
+        for &e in entities.iter().rev() {
+            if e.id() % 2 == 0 {
+                dense.insert(e, Cdense(e.id())).unwrap();
+            }
+            if e.id() % 3 == 0 {
+                vec.insert(e, Cvec(e.id() * 10)).unwrap();
+            }
+        }
+
+        let joined: Vec<_> = dense.dense_join(&vec).map(|(d, v)| (d.0, v.0)).collect();
+        assert_eq!(joined, [(6, 60), (0, 0)]);
+
+        for (d, v) in dense.dense_join_mut(&mut vec) {
+            d.0 += 1;
+            v.0 += 1;
+        }
+        assert_eq!(dense.get(entities[6]), Some(&Cdense(7)));
+        assert_eq!(vec.get(entities[6]), Some(&Cvec(61)));
+        assert_eq!(dense.get(entities[4]), Some(&Cdense(4)));
+        assert_eq!(vec.get(entities[3]), Some(&Cvec(30)));
+
+        let ents = w.entities();
+        let mut all: Vec<_> = dense.dense_join(&ents).map(|(_, e)| e).collect();
+        all.sort();
+        assert_eq!(all, entities.iter().copied().step_by(2).collect::<Vec<_>>());
+        assert_eq!(
+            dense
+                .dense_join(!&vec)
+                .map(|(d, _)| d.0)
+                .collect::<Vec<_>>(),
+            [8, 4, 2]
+        );
+    }
+
+    #[test]
+    fn dense_defragment() {
+        #[derive(Debug, PartialEq)]
+        struct Cdense(u32);
+        impl Component for Cdense {
+            type Storage = DenseVecStorage<Self>;
+        }
+
+        let mut w = World::new();
+        let mut s: Storage<Cdense, _> = create(&mut w);
+        for id in [7, 3, 9, 1, 5, 0] {
+            s.insert(Entity::new(id, Generation::new(1)), Cdense(id))
+                .unwrap();
+        }
+        s.remove(Entity::new(3, Generation::new(1)));
+
+        s.defragment();
+        assert_eq!(s.unprotected_storage().entity_ids(), [0, 1, 5, 7, 9]);
+        assert_eq!(
+            s.as_slice(),
+            [Cdense(0), Cdense(1), Cdense(5), Cdense(7), Cdense(9)]
+        );
+        for id in [0, 1, 5, 7, 9] {
+            assert_eq!(
+                s.get(Entity::new(id, Generation::new(1))),
+                Some(&Cdense(id))
+            );
+        }
+
+        s.remove(Entity::new(0, Generation::new(1)));
+        s.insert(Entity::new(3, Generation::new(1)), Cdense(3))
+            .unwrap();
+        s.defragment();
+        assert_eq!(s.unprotected_storage().entity_ids(), [1, 3, 5, 7, 9]);
+        assert_eq!(s.get(Entity::new(3, Generation::new(1))), Some(&Cdense(3)));
+    }
+
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn storage_registry() {
+        use std::any::TypeId;
+
+        let mut w = World::new();
+        w.register::<Cvec>();
+        w.register::<Cvec>();
+        // Registered by the setup of the storage.
+        w.setup_for::<WriteStorage<FlaggedCvec>>();
+
+        let e1 = w.create_entity().with(Cvec(1)).build();
+        let e2 = w.create_entity().with(FlaggedCvec(2)).build();
+        let mut reader = w.write_storage::<FlaggedCvec>().register_reader();
+
+        let registry = w.fetch::<StorageRegistry>();
+        let names: Vec<_> = registry.iter().map(|a| a.type_name()).collect();
+        assert_eq!(
+            names,
+            [
+                std::any::type_name::<Cvec>(),
+                std::any::type_name::<FlaggedCvec>()
+            ]
+        );
+
+        let cvec = registry.get(TypeId::of::<Cvec>()).unwrap();
+        let mut read = None;
+        assert!(cvec.read(&w, e1, &mut |c| {
+            read = c.downcast_ref::<Cvec>().map(|c| c.0)
+        }));
+        assert_eq!(read, Some(1));
+        assert!(!cvec.read(&w, e2, &mut |_| panic!()));
+        assert_eq!(cvec.mask(&w).iter().collect::<Vec<_>>(), [e1.id()]);
+
+        let flagged = registry.get(TypeId::of::<FlaggedCvec>()).unwrap();
+        assert!(flagged.write(&w, e2, &mut |c| {
+            c.downcast_mut::<FlaggedCvec>().unwrap().0 += 1
+        }));
+        let events: Vec<_> = w
+            .read_storage::<FlaggedCvec>()
+            .channel()
+            .read(&mut reader)
+            .cloned()
+            .collect();
+        assert_eq!(events, [ComponentEvent::Modified(e2.id())]);
+
+        let removed = flagged.remove(&w, e2).unwrap();
+        assert_eq!(removed.downcast_ref::<FlaggedCvec>(), Some(&FlaggedCvec(3)));
+        assert!(!flagged.contains(&w, e2));
+        assert!(flagged.remove(&w, e2).is_none());
+    }
+
+    #[test]
+    fn restricted_storage() {
+        use crate::join::Join;
+        use std::collections::HashSet;
+
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let mut s1: Storage<Cvec, _> = w.write_storage();
+        let mut components = HashSet::new();
+
+        for i in 0..50 {
+            let c = i + 10;
+            if let Err(err) = s1.insert(Entity::new(i, Generation::new(1)), c.into()) {
+                panic!("Failed to insert component into entity! {:?}", err);
+            }
+            components.insert(c);
+        }
+
+        for mut comps in (&mut s1.restrict_mut()).join() {
+            let c1 = { comps.get().0 };
+
+            let c2 = { comps.get_mut().0 };
+
+            assert_eq!(
+                c1, c2,
+                "Mutable and immutable gets returned different components."
+            );
+            assert!(
+                components.remove(&c1),
+                "Same component was iterated twice in join."
+            );
+        }
+        assert!(
+            components.is_empty(),
+            "Some components weren't iterated in join."
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn par_restricted_storage() {
+        use crate::join::ParJoin;
+        use rayon::iter::ParallelIterator;
+        use std::{collections::HashSet, sync::Mutex};
+
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let mut s1: Storage<Cvec, _> = w.write_storage();
+        let mut components = HashSet::new();
+
+        for i in 0..50 {
+            let c = i + 10;
+            if let Err(err) = s1.insert(Entity::new(i, Generation::new(1)), c.into()) {
+                panic!("Failed to insert component into entity! {:?}", err);
+            }
+            components.insert(c);
+        }
+
+        let components2 = Mutex::new(Vec::new());
+        let components2_mut = Mutex::new(Vec::new());
+
+        (&mut s1.restrict_mut()).par_join().for_each(|mut comps| {
+            let (mut components2, mut components2_mut) =
+                (components2.lock().unwrap(), components2_mut.lock().unwrap());
+            components2.push(comps.get().0);
+            components2_mut.push(comps.get_mut().0);
+        });
+        let components2 = components2.into_inner().unwrap();
+        assert_eq!(
+            components2,
+            components2_mut.into_inner().unwrap(),
+            "Mutable and immutable gets returned different components."
+        );
+        assert_eq!(
+            components,
+            components2.into_iter().collect(),
+            "Components iterated weren't as should've been."
+        );
+    }
+
+    #[test]
+    fn storage_entry() {
+        let mut w = World::new();
+        w.register::<Cvec>();
+
+        let e1 = w.create_entity().build();
+        let e2 = w.create_entity().with(Cvec(10)).build();
 
         let e3 = w.create_entity().build();
         let e4 = w.create_entity().with(Cvec(10)).build();
@@ -883,6 +1835,293 @@ mod test {
         assert_eq!((s1.mask()).join().count(), 50);
     }
 
+    #[test]
+    fn custom_join_wrapper() {
+        use crate::join::Join;
+
+        struct Doubled<'a>(&'a mut MaskedStorage<Cvec>);
+
+        // SAFETY: The mask and storage come from the same `MaskedStorage`,
+        // which is borrowed exclusively. Iterating the mask does not repeat
+        // indices.
+        unsafe impl<'a> Join for Doubled<'a> {
+            type Mask = &'a BitSet;
+            type Type = u32;
+            type Value = SharedGetMutOnly<'a, Cvec, VecStorage<Cvec>>;
+
+            unsafe fn open(self) -> (Self::Mask, Self::Value) {
+                self.0.open_shared_mut()
+            }
+
+            unsafe fn get(value: &mut Self::Value, id: Index) -> u32 {
+                // SAFETY: The mask was checked and `id` is not repeated.
+                let c = unsafe { SharedGetMutOnly::get_mut(value, id) };
+                c.0 *= 2;
+                c.0
+            }
+        }
+
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let mut s1: Storage<Cvec, _> = w.write_storage();
+
+        for i in 0..10 {
+            s1.insert(Entity::new(i, Generation::new(1)), i.into())
+                .unwrap();
+        }
+
+        let doubled: Vec<u32> = Doubled(s1.masked_storage_mut()).join().collect();
+        assert_eq!(doubled, (0..10).map(|i| i * 2).collect::<Vec<_>>());
+        assert_eq!(s1.masked_storage().mask().iter().count(), 10);
+        assert_eq!(s1.get(Entity::new(3, Generation::new(1))), Some(&Cvec(6)));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "has been fetched mutably twice")]
+    fn shared_get_mut_only_aliasing() {
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let mut s1: Storage<Cvec, _> = w.write_storage();
+        s1.insert(Entity::new(0, Generation::new(1)), Cvec(1))
+            .unwrap();
+
+        let (_, value) = s1.masked_storage_mut().open_shared_mut();
+        // SAFETY: The id is in the mask, fetching it twice is caught before
+        // the second borrow is created.
+        let first = unsafe { SharedGetMutOnly::get_mut(&value, 0) };
+        let second = unsafe { SharedGetMutOnly::get_mut(&value, 0) };
+        first.0 += second.0;
+    }
+
+    #[test]
+    fn storage_count() {
+        use crate::bitset::BitSetLen;
+
+        let mut w = World::new();
+        w.register::<Cvec>();
+        let entities: Vec<_> = (0..100).map(|_| w.create_entity().build()).collect();
+
+        {
+            let mut s: Storage<Cvec, _> = w.write_storage();
+            for (i, &e) in entities.iter().enumerate() {
+                s.insert(e, Cvec(i as u32)).unwrap();
+            }
+            s.insert(entities[0], Cvec(7)).unwrap();
+            s.remove(entities[1]);
+            s.remove(entities[1]);
+            assert_eq!(s.count(), 99);
+            assert_eq!(s.mask().len(), 99);
+
+            let drained = s.drain().join().take(9).count();
+            assert_eq!(drained, 9);
+            assert_eq!(s.count(), 90);
+        }
+
+        w.delete_entities(&entities[50..]).unwrap();
+        assert_eq!(w.read_storage::<Cvec>().count(), 40);
+        assert_eq!(w.entities().count(), 50);
+
+        w.write_storage::<Cvec>().clear();
+        assert_eq!(w.read_storage::<Cvec>().count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn replication_queue() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let e: Vec<_> = (0..6).map(|_| w.create_entity().build()).collect();
+        {
+            let mut s = w.write_storage::<FlaggedCvec>();
+            s.insert(e[0], FlaggedCvec(0)).unwrap();
+            s.insert(e[1], FlaggedCvec(1)).unwrap();
+            s.insert(e[5], FlaggedCvec(5)).unwrap();
+        }
+
+        let mut queue = ReplicationQueue::new(&mut w.write_storage::<FlaggedCvec>());
+        {
+            let mut s = w.write_storage::<FlaggedCvec>();
+            // Modified, then removed.
+            s.get_mut(e[0]).unwrap().0 = 10;
+            s.remove(e[0]);
+            // Modified only.
+            s.get_mut(e[1]).unwrap().0 = 11;
+            // Inserted, then modified.
+            s.insert(e[2], FlaggedCvec(2)).unwrap();
+            s.get_mut(e[2]).unwrap().0 = 12;
+            // Inserted after an earlier insertion was removed.
+            s.insert(e[3], FlaggedCvec(3)).unwrap();
+            s.remove(e[3]);
+            s.insert(e[3], FlaggedCvec(13)).unwrap();
+            // Inserted, then deleted.
+            s.insert(e[4], FlaggedCvec(4)).unwrap();
+        }
+        w.delete_entity(e[4]).unwrap();
+        // Deleted, and its index reused by a new entity.
+        w.delete_entity(e[5]).unwrap();
+        let reused = w.create_entity().with(FlaggedCvec(15)).build();
+        assert_eq!(reused.id(), e[5].id());
+
+        let s = w.read_storage::<FlaggedCvec>();
+        queue.update(&s);
+        assert_eq!(queue.len(), 6);
+
+        let drained: Vec<_> = queue
+            .drain_up_to(&s, 4)
+            .into_iter()
+            .map(|r| (r.entity, r.kind, r.component.map(|c| c.0)))
+            .collect();
+        assert_eq!(
+            drained,
+            [
+                (e[0], ReplicationKind::Removed, None),
+                (e[5], ReplicationKind::Removed, None),
+                (e[2], ReplicationKind::Inserted, Some(12)),
+                (e[3], ReplicationKind::Inserted, Some(13)),
+            ]
+        );
+
+        let drained: Vec<_> = queue
+            .drain_up_to(&s, 4)
+            .into_iter()
+            .map(|r| (r.entity, r.kind, r.component.map(|c| c.0)))
+            .collect();
+        assert_eq!(
+            drained,
+            [
+                (reused, ReplicationKind::Inserted, Some(15)),
+                (e[1], ReplicationKind::Modified, Some(11)),
+            ]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    #[cfg(all(feature = "serde", feature = "tracking"))]
+    fn forward_events() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let mut reader = w.write_storage::<FlaggedCvec>().register_reader();
+        let e0 = w.create_entity().with(FlaggedCvec(0)).build();
+        let e1 = w.create_entity().with(FlaggedCvec(1)).build();
+        {
+            let mut s = w.write_storage::<FlaggedCvec>();
+            s.get_mut(e0).unwrap().0 = 10;
+            s.remove(e1);
+        }
+
+        let mut bytes = Vec::new();
+        let s = w.read_storage::<FlaggedCvec>();
+        let mut ser = serde_json::Serializer::new(&mut bytes);
+        assert_eq!(
+            super::forward_events(&s, &mut reader, true, &mut ser).unwrap(),
+            4
+        );
+        assert_eq!(
+            super::forward_events(&s, &mut reader, false, &mut ser).unwrap(),
+            0
+        );
+
+        let mut stream = serde_json::Deserializer::from_slice(&bytes)
+            .into_iter::<Vec<ForwardedEvent<FlaggedCvec>>>();
+        let events = stream.next().unwrap().unwrap();
+        assert_eq!(
+            events,
+            [
+                ForwardedEvent {
+                    event: ComponentEvent::Inserted(e0.id()),
+                    component: Some(FlaggedCvec(10)),
+                },
+                ForwardedEvent {
+                    event: ComponentEvent::Inserted(e1.id()),
+                    component: None,
+                },
+                ForwardedEvent {
+                    event: ComponentEvent::Modified(e0.id()),
+                    component: Some(FlaggedCvec(10)),
+                },
+                ForwardedEvent {
+                    event: ComponentEvent::Removed(e1.id()),
+                    component: None,
+                },
+            ]
+        );
+        assert!(stream.next().unwrap().unwrap().is_empty());
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn non_send_storage() {
+        use shred::SystemData;
+        use std::{rc::Rc, thread};
+
+        struct Handle(Rc<u32>);
+        impl Component for Handle {
+            type Storage = NonSendStorage<Self>;
+        }
+
+        let mut w = World::new();
+        w.register::<Handle>();
+        let e = w.create_entity().build();
+        w.write_storage::<Handle>()
+            .insert(e, Handle(Rc::new(3)))
+            .unwrap();
+
+        let w = &w;
+        thread::scope(|scope| {
+            let read = scope.spawn(move || w.read_storage::<Handle>().get(e).is_some());
+            assert!(read.join().is_err());
+            let fetch = scope.spawn(move || {
+                NonSendWriteStorage::<Handle>::fetch(w);
+            });
+            assert!(fetch.join().is_err());
+            let count = scope.spawn(move || w.read_storage::<Handle>().count());
+            assert_eq!(count.join().unwrap(), 1);
+        });
+
+        let mut handles = NonSendWriteStorage::<Handle>::fetch(w);
+        handles.get_mut(e).unwrap().0 = Rc::new(4);
+        assert_eq!(*handles.get(e).unwrap().0, 4);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn non_send_storage_maintain() {
+        use std::rc::Rc;
+
+        struct Handle(Rc<u32>);
+        impl Component for Handle {
+            type Storage = NonSendStorage<Self>;
+        }
+
+        let mut w = World::new();
+        w.register::<Handle>();
+        w.register::<Cvec>();
+        let rc = Rc::new(3);
+        let entities: Vec<_> = (0..400)
+            .map(|i| w.create_entity().with(Cvec(i)).build())
+            .collect();
+        for &e in &entities {
+            w.write_storage::<Handle>()
+                .insert(e, Handle(rc.clone()))
+                .unwrap();
+        }
+
+        // Enough deletions to drop the other storages in parallel.
+        w.delete_entities(&entities[..300]).unwrap();
+        w.maintain();
+        // The handles have been dropped on this thread rather than leaked.
+        assert_eq!(Rc::strong_count(&rc), 101);
+        assert_eq!(w.read_storage::<Cvec>().count(), 100);
+
+        w.delete_entity(entities[300]).unwrap();
+        w.maintain();
+        assert_eq!(Rc::strong_count(&rc), 100);
+        assert_eq!(w.read_storage::<Cvec>().count(), 99);
+    }
+
     #[test]
     #[cfg(feature = "parallel")]
     fn par_storage_mask() {
@@ -903,6 +2142,7 @@ mod test {
     }
 
     #[test]
+    #[cfg(feature = "tracking")]
     fn flagged() {
         use crate::join::Join;
 
@@ -934,6 +2174,7 @@ mod test {
                     ComponentEvent::Modified(id) => modified.add(*id),
                     ComponentEvent::Inserted(id) => inserted.add(*id),
                     ComponentEvent::Removed(id) => removed.add(*id),
+                    ComponentEvent::Loaded(_) => panic!("unexpected load event"),
                 };
             }
         }
@@ -959,6 +2200,7 @@ mod test {
                     ComponentEvent::Modified(id) => modified.add(*id),
                     ComponentEvent::Inserted(id) => inserted.add(*id),
                     ComponentEvent::Removed(id) => removed.add(*id),
+                    ComponentEvent::Loaded(_) => panic!("unexpected load event"),
                 };
             }
         }
@@ -984,6 +2226,7 @@ mod test {
                     ComponentEvent::Modified(id) => modified.add(*id),
                     ComponentEvent::Inserted(id) => inserted.add(*id),
                     ComponentEvent::Removed(id) => removed.add(*id),
+                    ComponentEvent::Loaded(_) => panic!("unexpected load event"),
                 };
             }
         }
@@ -995,6 +2238,225 @@ mod test {
         }
     }
 
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn flagged_read_only() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let mut reader_id = w.write_storage::<FlaggedCvec>().register_reader();
+        for i in 0..3 {
+            w.create_entity().with(FlaggedCvec(i)).build();
+        }
+
+        let s = w.read_storage::<FlaggedCvec>();
+        assert_eq!(s.channel().read(&mut reader_id).len(), 3);
+        // SAFETY: The components are stored at the index of their entity.
+        let slice: Vec<_> = s.unprotected_storage().inner().as_slice()[..3]
+            .iter()
+            .map(|c| unsafe { c.assume_init_ref() }.0)
+            .collect();
+        assert_eq!(slice, [0, 1, 2]);
+    }
+
+    #[test]
+    fn flagged_masks() {
+        use crate::join::Join;
+
+        let mut w = World::new();
+        w.register_with_storage::<_, FlaggedCvec>(|| {
+            FlaggedStorage::default().with_masks(MaskClearing::Maintain)
+        });
+
+        let entities: Vec<_> = (0..4)
+            .map(|i| w.create_entity().with(FlaggedCvec(i)).build())
+            .collect();
+        {
+            let s = w.read_storage::<FlaggedCvec>();
+            assert_eq!(s.inserted_mask().iter().count(), 4);
+            assert!(s.modified_mask().is_empty());
+        }
+
+        w.maintain();
+        {
+            let mut s = w.write_storage::<FlaggedCvec>();
+            assert!(s.inserted_mask().is_empty());
+
+            s.get_mut(entities[1]).unwrap().0 += 1;
+            for (comp, _) in (&mut s, &BitSet::from_iter([entities[2].id()])).join() {
+                comp.0 += 1;
+            }
+            s.remove(entities[3]);
+            s.flag(ComponentEvent::Modified(entities[0].id()));
+
+            let modified: Vec<_> = (&w.entities(), s.modified_mask())
+                .join()
+                .map(|(e, _)| e)
+                .collect();
+            assert_eq!(modified, entities[..3]);
+            assert_eq!(
+                (&w.entities(), s.removed_mask()).join().next(),
+                Some((entities[3], entities[3].id()))
+            );
+
+            s.clear_masks();
+            assert!(s.modified_mask().is_empty());
+            assert!(s.removed_mask().is_empty());
+        }
+
+        // Deletions during maintain are recorded after the masks are cleared.
+        w.entities().delete(entities[0]).unwrap();
+        w.maintain();
+        assert_eq!(
+            w.read_storage::<FlaggedCvec>()
+                .removed_mask()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![entities[0].id()]
+        );
+
+        // Manual clearing keeps the masks over maintains.
+        // SAFETY: Changing the mask clearing doesn't touch the components.
+        unsafe { w.write_storage::<FlaggedCvec>().unprotected_storage_mut() }
+            .set_masks(Some(MaskClearing::Manual));
+        w.maintain();
+        assert!(!w.read_storage::<FlaggedCvec>().removed_mask().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn insert_if_absent() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let a = w.create_entity().build();
+        let b = w.create_entity().build();
+
+        let mut s: Storage<FlaggedCvec, _> = w.write_storage();
+        let mut reader_id = s.register_reader();
+        s.insert(a, 1.into()).unwrap();
+        s.channel().read(&mut reader_id).for_each(drop);
+
+        assert!(!s.insert_if_absent(a, 2.into()).unwrap());
+        assert!(!s.insert_with(a, || panic!("component is present")).unwrap());
+        assert_eq!(s.channel().read(&mut reader_id).count(), 0);
+        assert_eq!(s.get(a), Some(&1.into()));
+
+        assert!(s.insert_with(b, || 3.into()).unwrap());
+        assert_eq!(s.get(b), Some(&3.into()));
+        let events: Vec<_> = s.channel().read(&mut reader_id).cloned().collect();
+        assert_eq!(events, vec![ComponentEvent::Inserted(b.id())]);
+
+        let dead = Entity::new(a.id(), Generation::new(2));
+        assert!(matches!(
+            s.insert_if_absent(dead, 4.into()),
+            Err(Error::WrongGeneration(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "tracking")]
+    fn insert_new() {
+        use crate::{error::InsertError, world::LazyUpdate};
+
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let a = w.create_entity().build();
+        let b = w.create_entity().build();
+
+        let mut reader_id = {
+            let mut s: Storage<FlaggedCvec, _> = w.write_storage();
+            let mut reader_id = s.register_reader();
+            s.insert_new(a, 1.into()).unwrap();
+            let events: Vec<_> = s.channel().read(&mut reader_id).cloned().collect();
+            assert_eq!(events, vec![ComponentEvent::Inserted(a.id())]);
+
+            assert_eq!(
+                s.insert_new(a, 2.into()),
+                Err(InsertError::AlreadyPresent {
+                    entity: a,
+                    component: 2.into()
+                })
+            );
+            assert_eq!(s.channel().read(&mut reader_id).count(), 0);
+            assert_eq!(s.get(a), Some(&1.into()));
+
+            let dead = Entity::new(b.id(), Generation::new(2));
+            let err = s.insert_new(dead, 3.into()).unwrap_err();
+            assert!(matches!(err, InsertError::WrongGeneration { .. }));
+            assert_eq!(err.into_component(), 3.into());
+            reader_id
+        };
+
+        {
+            let lazy = w.fetch::<LazyUpdate>();
+            lazy.insert_new(a, FlaggedCvec(4));
+            lazy.insert_new(b, FlaggedCvec(5));
+        }
+        w.maintain();
+
+        let s: Storage<FlaggedCvec, _> = w.write_storage();
+        assert_eq!(s.get(a), Some(&1.into()));
+        assert_eq!(s.get(b), Some(&5.into()));
+        let events: Vec<_> = s.channel().read(&mut reader_id).cloned().collect();
+        assert_eq!(events, vec![ComponentEvent::Inserted(b.id())]);
+    }
+
+    #[test]
+    #[cfg(feature = "tracking")]
+    #[should_panic(expected = "Storage doesn't maintain tracked masks")]
+    fn flagged_masks_disabled() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let _ = w.read_storage::<FlaggedCvec>().inserted_mask();
+    }
+
+    #[test]
+    #[cfg(not(feature = "tracking"))]
+    fn flagged_masks_without_channel() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let e = w.create_entity().with(FlaggedCvec(1)).build();
+
+        let mut s = w.write_storage::<FlaggedCvec>();
+        s.get_mut(e).unwrap().0 += 1;
+        assert!(s.inserted_mask().contains(e.id()));
+        assert!(s.modified_mask().contains(e.id()));
+    }
+
+    #[test]
+    fn remove_masked() {
+        let mut w = World::new();
+        w.register::<FlaggedCvec>();
+        let e: Vec<_> = (0..6)
+            .map(|i| w.create_entity().with(FlaggedCvec(i)).build())
+            .collect();
+
+        let mut s = w.write_storage::<FlaggedCvec>();
+        s.remove(e[2]);
+        #[cfg(feature = "tracking")]
+        let mut reader_id = s.register_reader();
+
+        let mut mask: BitSet = [1, 2, 3, 5].iter().map(|&i| e[i].id()).collect();
+        mask.add(100);
+        assert_eq!(s.remove_masked(&mask), 3);
+        assert_eq!(s.count(), 2);
+        assert_eq!(s.get(e[0]), Some(&FlaggedCvec(0)));
+        assert_eq!(s.get(e[4]), Some(&FlaggedCvec(4)));
+        assert_eq!(s.get(e[1]), None);
+
+        #[cfg(feature = "tracking")]
+        {
+            let removed: Vec<_> = s.channel().read(&mut reader_id).cloned().collect();
+            assert_eq!(
+                removed,
+                [
+                    ComponentEvent::Removed(e[1].id()),
+                    ComponentEvent::Removed(e[3].id()),
+                    ComponentEvent::Removed(e[5].id()),
+                ]
+            );
+        }
+    }
+
     #[test]
     fn entries() {
         use crate::{join::LendJoin, storage::WriteStorage, world::Entities};
@@ -1032,3 +2494,162 @@ mod test {
         assert_eq!(sum, 135);
     }
 }
+
+#[cfg(all(feature = "mutation-audit", feature = "tracking"))]
+mod mutation_audit_test {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::world::Builder;
+
+    #[derive(Hash)]
+    struct Audited(u32);
+
+    impl Component for Audited {
+        type Storage = DerefFlaggedStorage<Self>;
+    }
+
+    static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Logger;
+
+    impl log::Log for Logger {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            let message = record.args().to_string();
+            if message.contains("Audited") {
+                WARNINGS.lock().unwrap().push(message);
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn unchanged_components_are_reported() {
+        let _ = log::set_logger(&Logger);
+        log::set_max_level(log::LevelFilter::Warn);
+
+        let mut w = World::new();
+        w.register_with_storage::<_, Audited>(|| {
+            DerefFlaggedStorage::default().with_mutation_audit()
+        });
+        let a = w.create_entity().with(Audited(1)).build();
+        let b = w.create_entity().with(Audited(2)).build();
+        let c = w.create_entity().with(Audited(3)).build();
+
+        {
+            let mut s = w.write_storage::<Audited>();
+            // Changed.
+            s.get_mut(a).unwrap().0 += 1;
+            // Changed back, so unchanged.
+            s.get_mut(b).unwrap().0 += 1;
+            s.get_mut(b).unwrap().0 -= 1;
+            // Only read.
+            assert_eq!(s.get_mut(c).unwrap().0, 3);
+        }
+        w.maintain();
+
+        let warnings = WARNINGS.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains(&format!("index {}", b.id())));
+    }
+}
+
+#[cfg(feature = "zeroize")]
+mod zeroize_test {
+    use std::{
+        ptr,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::world::Builder;
+
+    const CANARY: [u8; 16] = [0xA5; 16];
+
+    struct Secret([u8; 16]);
+
+    impl Component for Secret {
+        type Storage = ZeroizeStorage<VecStorage<Self>>;
+    }
+
+    impl ZeroizeOnRemove for Secret {
+        fn zeroize(&mut self) {
+            for byte in &mut self.0 {
+                // SAFETY: `byte` is a valid reference.
+                unsafe { ptr::write_volatile(byte, 0) };
+            }
+        }
+    }
+
+    static DENSE_WIPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct DenseSecret(u32);
+
+    impl Component for DenseSecret {
+        type Storage = ZeroizeStorage<DenseVecStorage<Self>>;
+    }
+
+    impl ZeroizeOnRemove for DenseSecret {
+        fn zeroize(&mut self) {
+            DENSE_WIPED.fetch_add(1, Ordering::Relaxed);
+            self.0 = 0;
+        }
+    }
+
+    fn slot_bytes(w: &World, id: Index) -> [u8; 16] {
+        let storage = w.read_storage::<Secret>();
+        let slot = &storage.unprotected_storage().as_slice()[id as usize];
+        // SAFETY: The slot is either a live component or has been zeroed.
+        unsafe { ptr::read(slot.as_ptr().cast()) }
+    }
+
+    #[test]
+    fn vec_storage_slots_are_zeroed() {
+        let mut w = World::new();
+        w.register::<Secret>();
+        let entities: Vec<_> = (0..4)
+            .map(|_| w.create_entity().with(Secret(CANARY)).build())
+            .collect();
+
+        let removed = w.write_storage::<Secret>().remove(entities[0]).unwrap();
+        assert_eq!(removed.0, CANARY);
+        assert_eq!(slot_bytes(&w, entities[0].id()), [0; 16]);
+
+        w.delete_entity(entities[1]).unwrap();
+        assert_eq!(slot_bytes(&w, entities[1].id()), [0; 16]);
+        assert_eq!(slot_bytes(&w, entities[2].id()), CANARY);
+
+        w.write_storage::<Secret>().clear();
+        for e in &entities {
+            assert_eq!(slot_bytes(&w, e.id()), [0; 16]);
+        }
+    }
+
+    #[test]
+    fn dense_storage_zeroizes_dropped_components() {
+        let mut w = World::new();
+        w.register::<DenseSecret>();
+        let entities: Vec<_> = (1..=4)
+            .map(|i| w.create_entity().with(DenseSecret(i)).build())
+            .collect();
+
+        w.delete_entity(entities[0]).unwrap();
+        assert_eq!(DENSE_WIPED.load(Ordering::Relaxed), 1);
+        let removed = w.write_storage::<DenseSecret>().remove(entities[1]).unwrap();
+        assert_eq!(removed.0, 2);
+        assert_eq!(DENSE_WIPED.load(Ordering::Relaxed), 1);
+
+        {
+            let storage = w.read_storage::<DenseSecret>();
+            assert_eq!(storage.get(entities[2]).unwrap().0, 3);
+            assert_eq!(storage.get(entities[3]).unwrap().0, 4);
+        }
+        w.write_storage::<DenseSecret>().clear();
+        assert_eq!(DENSE_WIPED.load(Ordering::Relaxed), 3);
+    }
+}
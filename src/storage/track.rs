@@ -1,5 +1,7 @@
 use std::ops::{Deref, DerefMut};
 
+use hibitset::BitSet;
+#[cfg(feature = "tracking")]
 use shrev::{EventChannel, ReaderId};
 
 use crate::{
@@ -10,10 +12,15 @@ use crate::{
 
 /// `UnprotectedStorage`s that track modifications, insertions, and
 /// removals of components.
+///
+/// Without the `tracking` feature, there is no event channel and the
+/// modifications are only recorded in the `TrackedMasks`.
 pub trait Tracked {
     /// Event channel tracking modified/inserted/removed components.
+    #[cfg(feature = "tracking")]
     fn channel(&self) -> &EventChannel<ComponentEvent>;
     /// Mutable event channel tracking modified/inserted/removed components.
+    #[cfg(feature = "tracking")]
     fn channel_mut(&mut self) -> &mut EventChannel<ComponentEvent>;
 
     /// Controls the events signal emission.
@@ -25,11 +32,100 @@ pub trait Tracked {
     /// Returns the actual state of the event emission.
     #[cfg(feature = "storage-event-control")]
     fn event_emission(&self) -> bool;
+
+    /// Returns the masks of the components inserted, modified, removed and
+    /// loaded since they were last cleared, or `None` if this storage doesn't
+    /// maintain them.
+    ///
+    /// Returns `None` by default.
+    fn masks(&self) -> Option<&TrackedMasks> {
+        None
+    }
+
+    /// Returns the masks returned by `Tracked::masks` mutably.
+    ///
+    /// Returns `None` by default.
+    fn masks_mut(&mut self) -> Option<&mut TrackedMasks> {
+        None
+    }
+}
+
+/// When the masks of a `Tracked` storage get cleared.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MaskClearing {
+    /// Clear the masks at the start of every `World::maintain`.
+    ///
+    /// Systems then see all changes made since the last maintain, including
+    /// the ones applied by it (like lazy updates). Note that changes made by
+    /// systems running after a system in the same dispatch are only seen by
+    /// that system if they are applied before the next maintain.
+    Maintain,
+    /// Only clear the masks when calling `Storage::clear_masks`.
+    Manual,
+}
+
+/// Masks of the components inserted, modified, removed and loaded since the
+/// masks were last cleared, as maintained by tracked storages like
+/// `FlaggedStorage`.
+///
+/// Every `ComponentEvent` emitted by the storage adds its index to the
+/// corresponding mask, so an index may be part of several masks, e.g. when a
+/// component has been inserted and modified afterwards.
+#[derive(Debug, Default)]
+pub struct TrackedMasks {
+    inserted: BitSet,
+    modified: BitSet,
+    removed: BitSet,
+    loaded: BitSet,
+}
+
+impl TrackedMasks {
+    /// Returns the mask of inserted components.
+    pub fn inserted(&self) -> &BitSet {
+        &self.inserted
+    }
+
+    /// Returns the mask of modified components.
+    pub fn modified(&self) -> &BitSet {
+        &self.modified
+    }
+
+    /// Returns the mask of removed components.
+    pub fn removed(&self) -> &BitSet {
+        &self.removed
+    }
+
+    /// Returns the mask of loaded components, see `ComponentEvent::Loaded`.
+    pub fn loaded(&self) -> &BitSet {
+        &self.loaded
+    }
+
+    /// Clears all masks.
+    pub fn clear(&mut self) {
+        self.inserted.clear();
+        self.modified.clear();
+        self.removed.clear();
+        self.loaded.clear();
+    }
+
+    /// Adds the index of `event` to the corresponding mask.
+    pub fn record(&mut self, event: ComponentEvent) {
+        match event {
+            ComponentEvent::Inserted(id) => self.inserted.add(id),
+            ComponentEvent::Modified(id) => self.modified.add(id),
+            ComponentEvent::Removed(id) => self.removed.add(id),
+            ComponentEvent::Loaded(id) => self.loaded.add(id),
+        };
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Component storage events received from a `FlaggedStorage` or any storage
 /// that implements `Tracked`.
+///
+/// More kinds of events may be added, so matches need a wildcard arm.
+#[non_exhaustive]
 pub enum ComponentEvent {
     /// An insertion event, note that a modification event will be triggered if
     /// the entity already had a component and had a new one inserted.
@@ -40,6 +136,10 @@ pub enum ComponentEvent {
     Modified(Index),
     /// A removal event.
     Removed(Index),
+    /// A load event, emitted instead of `Inserted` when a component is
+    /// restored from a save (e.g. by `DeserializeComponents`) rather than
+    /// freshly created.
+    Loaded(Index),
 }
 
 impl<'e, T, D> Storage<'e, T, D>
@@ -49,6 +149,7 @@ where
     D: Deref<Target = MaskedStorage<T>>,
 {
     /// Returns the event channel tracking modified components.
+    #[cfg(feature = "tracking")]
     pub fn channel(&self) -> &EventChannel<ComponentEvent> {
         unsafe { self.open() }.1.channel()
     }
@@ -58,6 +159,48 @@ where
     pub fn event_emission(&self) -> bool {
         unsafe { self.open() }.1.event_emission()
     }
+
+    /// Returns the masks of the components inserted, modified, removed and
+    /// loaded since they were last cleared, or `None` if the storage doesn't
+    /// maintain them (see `FlaggedStorage::with_masks`).
+    pub fn masks(&self) -> Option<&TrackedMasks> {
+        unsafe { self.open() }.1.masks()
+    }
+
+    /// Returns the mask of the components inserted since the masks were last
+    /// cleared.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the storage doesn't maintain masks.
+    pub fn inserted_mask(&self) -> &BitSet {
+        self.expect_masks().inserted()
+    }
+
+    /// Returns the mask of the components modified since the masks were last
+    /// cleared.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the storage doesn't maintain masks.
+    pub fn modified_mask(&self) -> &BitSet {
+        self.expect_masks().modified()
+    }
+
+    /// Returns the mask of the components removed since the masks were last
+    /// cleared.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the storage doesn't maintain masks.
+    pub fn removed_mask(&self) -> &BitSet {
+        self.expect_masks().removed()
+    }
+
+    fn expect_masks(&self) -> &TrackedMasks {
+        self.masks()
+            .expect("Storage doesn't maintain tracked masks, see `FlaggedStorage::with_masks`")
+    }
 }
 
 impl<'e, T, D> Storage<'e, T, D>
@@ -68,6 +211,7 @@ where
 {
     /// Returns the event channel for insertions/removals/modifications of this
     /// storage's components.
+    #[cfg(feature = "tracking")]
     pub fn channel_mut(&mut self) -> &mut EventChannel<ComponentEvent> {
         self.data.inner.channel_mut()
     }
@@ -75,13 +219,25 @@ where
     /// Starts tracking component events. Note that this reader id should be
     /// used every frame, otherwise events will pile up and memory use by
     /// the event channel will grow waiting for this reader.
+    #[cfg(feature = "tracking")]
     pub fn register_reader(&mut self) -> ReaderId<ComponentEvent> {
         self.channel_mut().register_reader()
     }
 
     /// Flags an index with a `ComponentEvent`.
     pub fn flag(&mut self, event: ComponentEvent) {
+        #[cfg(feature = "tracking")]
         self.channel_mut().single_write(event);
+        if let Some(masks) = self.data.inner.masks_mut() {
+            masks.record(event);
+        }
+    }
+
+    /// Clears the masks returned by `Storage::masks`.
+    pub fn clear_masks(&mut self) {
+        if let Some(masks) = self.data.inner.masks_mut() {
+            masks.clear();
+        }
     }
 
     /// Controls the events signal emission.
@@ -0,0 +1,225 @@
+use std::{
+    mem::{self, MaybeUninit},
+    ptr,
+    sync::atomic::{self, Ordering},
+};
+
+use hibitset::BitSetLike;
+
+use crate::{
+    storage::{
+        AccessMut, DenseVecStorage, DistinctStorage, IdSliceAccess, SharedGetMutStorage,
+        SliceAccess, TryDefault, UnprotectedStorage, VecStorage,
+    },
+    world::Index,
+};
+
+/// Components holding sensitive data, like authentication tokens, which have
+/// to be wiped before they are dropped by a `ZeroizeStorage`.
+pub trait ZeroizeOnRemove {
+    /// Overwrites the sensitive data of the component, including heap buffers
+    /// it owns, e.g. the bytes of a `String`.
+    ///
+    /// Use volatile writes (e.g. `std::ptr::write_volatile`), otherwise the
+    /// compiler may remove the writes since the component is dropped right
+    /// after.
+    fn zeroize(&mut self);
+}
+
+/// Storages whose memory of removed components can be zeroed by a
+/// `ZeroizeStorage`.
+pub trait ZeroizeVacated<T>: UnprotectedStorage<T> {
+    /// Zeroes the memory the component of `id` was left in by `remove`.
+    ///
+    /// # Safety
+    ///
+    /// May only be called right after `remove` with `id`.
+    unsafe fn zeroize_vacated(&mut self, id: Index);
+
+    /// Zeroes the memory of all components.
+    ///
+    /// # Safety
+    ///
+    /// May only be called right after `clean`.
+    unsafe fn zeroize_all_vacated(&mut self);
+}
+
+/// Wrapper storage zeroing the memory of components when they are removed,
+/// dropped or cleaned, for components holding sensitive data.
+///
+/// Components are wiped with `ZeroizeOnRemove::zeroize` before the storage
+/// drops them, and the slots the wrapped storage leaves behind are zeroed
+/// afterwards. Components returned by `Storage::remove` are moved out intact,
+/// wiping them is up to the caller.
+///
+/// Growing the storage (or `DenseVecStorage::defragment`) reallocates it
+/// without zeroing the old memory, reserve enough capacity up front if that
+/// matters.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     storage::{ZeroizeOnRemove, ZeroizeStorage},
+/// };
+///
+/// struct Token(String);
+///
+/// impl Component for Token {
+///     type Storage = ZeroizeStorage<DenseVecStorage<Self>>;
+/// }
+///
+/// impl ZeroizeOnRemove for Token {
+///     fn zeroize(&mut self) {
+///         // SAFETY: Only ASCII zeroes are written, keeping the `String` valid
+///         // UTF-8.
+///         for byte in unsafe { self.0.as_bytes_mut() } {
+///             unsafe { std::ptr::write_volatile(byte, 0) };
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Token>();
+/// let e = world.create_entity().with(Token("hunter2".into())).build();
+/// world.delete_entity(e).unwrap();
+/// assert!(world.read_storage::<Token>().is_empty());
+/// ```
+pub struct ZeroizeStorage<S>(S);
+
+impl<S: TryDefault> Default for ZeroizeStorage<S> {
+    fn default() -> Self {
+        ZeroizeStorage(S::unwrap_default())
+    }
+}
+
+impl<T, S> UnprotectedStorage<T> for ZeroizeStorage<S>
+where
+    T: ZeroizeOnRemove,
+    S: ZeroizeVacated<T> + TryDefault,
+{
+    type AccessMut<'a> = S::AccessMut<'a> where Self: 'a;
+
+    unsafe fn clean<B>(&mut self, has: B)
+    where
+        B: BitSetLike,
+    {
+        for id in (&has).iter() {
+            // SAFETY: Requirements passed to caller.
+            unsafe { self.0.get_mut(id) }.access_mut().zeroize();
+        }
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.clean(has) };
+        // SAFETY: Called right after `clean`.
+        unsafe { self.0.zeroize_all_vacated() };
+    }
+
+    unsafe fn get(&self, id: Index) -> &T {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.get(id) }
+    }
+
+    unsafe fn get_mut(&mut self, id: Index) -> Self::AccessMut<'_> {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.get_mut(id) }
+    }
+
+    unsafe fn insert(&mut self, id: Index, value: T) {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.insert(id, value) };
+    }
+
+    unsafe fn remove(&mut self, id: Index) -> T {
+        // SAFETY: Requirements passed to caller.
+        let value = unsafe { self.0.remove(id) };
+        // SAFETY: Called right after `remove` with `id`.
+        unsafe { self.0.zeroize_vacated(id) };
+        value
+    }
+
+    unsafe fn drop(&mut self, id: Index) {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.get_mut(id) }.access_mut().zeroize();
+        // SAFETY: Requirements passed to caller.
+        drop(unsafe { self.remove(id) });
+    }
+
+    fn maintain(&mut self) {
+        self.0.maintain();
+    }
+
+    fn is_thread_local(&self) -> bool {
+        self.0.is_thread_local()
+    }
+}
+
+impl<T, S> SharedGetMutStorage<T> for ZeroizeStorage<S>
+where
+    T: ZeroizeOnRemove,
+    S: ZeroizeVacated<T> + SharedGetMutStorage<T> + TryDefault,
+{
+    unsafe fn shared_get_mut(&self, id: Index) -> S::AccessMut<'_> {
+        // SAFETY: Requirements passed to caller.
+        unsafe { self.0.shared_get_mut(id) }
+    }
+}
+
+// SAFETY: `shared_get_mut` is forwarded to the wrapped storage.
+unsafe impl<S: DistinctStorage> DistinctStorage for ZeroizeStorage<S> {}
+
+impl<T, S: SliceAccess<T>> SliceAccess<T> for ZeroizeStorage<S> {
+    type Element = S::Element;
+
+    #[inline]
+    fn as_slice(&self) -> &[Self::Element] {
+        self.0.as_slice()
+    }
+
+    #[inline]
+    fn as_mut_slice(&mut self) -> &mut [Self::Element] {
+        self.0.as_mut_slice()
+    }
+}
+
+// SAFETY: The slices of the wrapped storage are returned unchanged.
+unsafe impl<T, S: IdSliceAccess<T>> IdSliceAccess<T> for ZeroizeStorage<S> {}
+
+impl<T> ZeroizeVacated<T> for VecStorage<T> {
+    unsafe fn zeroize_vacated(&mut self, id: Index) {
+        // NOTE: `as` cast is not lossy, see `VecStorage::insert`.
+        let slot = &mut self.as_mut_slice()[id as usize];
+        zero_memory(std::slice::from_mut(slot));
+    }
+
+    unsafe fn zeroize_all_vacated(&mut self) {
+        zero_memory(self.as_mut_slice());
+    }
+}
+
+impl<T> ZeroizeVacated<T> for DenseVecStorage<T> {
+    unsafe fn zeroize_vacated(&mut self, _id: Index) {
+        // `remove` swaps the component to the end of the dense data and moves
+        // it out from there, leaving it in the first spare slot.
+        let spare = self.spare_capacity_mut();
+        let len = spare.len().min(1);
+        zero_memory(&mut spare[..len]);
+    }
+
+    unsafe fn zeroize_all_vacated(&mut self) {
+        zero_memory(self.spare_capacity_mut());
+    }
+}
+
+/// Zeroes the bytes of `slots` with volatile writes, so they aren't removed as
+/// dead stores.
+fn zero_memory<T>(slots: &mut [MaybeUninit<T>]) {
+    let bytes = slots.as_mut_ptr().cast::<u8>();
+    for i in 0..mem::size_of_val(slots) {
+        // SAFETY: The byte is within `slots`, and any byte is a valid
+        // `MaybeUninit<T>`.
+        unsafe { ptr::write_volatile(bytes.add(i), 0) };
+    }
+    // Keep the writes from being reordered after later uses of the memory.
+    atomic::compiler_fence(Ordering::SeqCst);
+}
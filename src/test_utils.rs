@@ -0,0 +1,233 @@
+//! Helpers for testing code using specs, enabled by the `test-support`
+//! feature.
+//!
+//! `EventCollector` records the `ComponentEvent`s of a tracked storage and
+//! offers assertions on them, `TickWorld` runs a dispatcher and maintains the
+//! `World` in a loop, like a game loop would. `EventCollector` requires
+//! the `tracking` feature, which provides the event channels.
+//!
+//! ## Examples
+//!
+#![cfg_attr(feature = "tracking", doc = "```")]
+#![cfg_attr(not(feature = "tracking"), doc = "```ignore")]
+//! use specs::{
+//!     prelude::*,
+//!     test_utils::{EventCollector, TickWorld},
+//! };
+//!
+//! struct Health(u32);
+//!
+//! impl Component for Health {
+//!     type Storage = FlaggedStorage<Self>;
+//! }
+//!
+//! struct Spawn;
+//!
+//! impl<'a> System<'a> for Spawn {
+//!     type SystemData = (Entities<'a>, WriteStorage<'a, Health>);
+//!
+//!     fn run(&mut self, (entities, mut health): Self::SystemData) {
+//!         entities.build_entity().with(Health(3), &mut health).build();
+//!     }
+//! }
+//!
+//! let dispatcher = DispatcherBuilder::new().with(Spawn, "spawn", &[]).build();
+//! let mut tick = TickWorld::new(World::new(), dispatcher);
+//! let mut events = EventCollector::<Health>::attach(tick.world_mut());
+//!
+//! events.collect(tick.world()).assert_no_events();
+//! tick.run(2);
+//! assert_eq!(tick.ticks(), 2);
+//!
+//! let first = tick.world().entities().entity(0);
+//! events.collect(tick.world()).assert_inserted(first);
+//! assert_eq!(events.events().len(), 2);
+//! ```
+
+#[cfg(feature = "tracking")]
+use std::{fmt, marker::PhantomData};
+
+#[cfg(feature = "tracking")]
+use shrev::ReaderId;
+
+#[cfg(feature = "tracking")]
+use crate::{
+    storage::{ComponentEvent, Tracked},
+    world::{Component, Entity},
+};
+use crate::{world::WorldExt, Dispatcher, World};
+
+/// Records the `ComponentEvent`s of the storage of `T`.
+///
+/// Events are read with `EventCollector::collect` and kept until
+/// `EventCollector::clear` is called, so assertions see all events since the
+/// last clear.
+#[cfg(feature = "tracking")]
+pub struct EventCollector<T> {
+    reader: ReaderId<ComponentEvent>,
+    events: Vec<ComponentEvent>,
+    phantom: PhantomData<T>,
+}
+
+#[cfg(feature = "tracking")]
+impl<T> EventCollector<T>
+where
+    T: Component,
+    T::Storage: Tracked,
+{
+    /// Registers a reader on the storage of `T`, which has to be registered
+    /// already. Only events emitted afterwards are recorded.
+    pub fn attach(world: &mut World) -> Self {
+        EventCollector {
+            reader: world.write_storage::<T>().register_reader(),
+            events: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Reads the events emitted since the last call, returning `self` for
+    /// chaining assertions.
+    pub fn collect(&mut self, world: &World) -> &mut Self {
+        let storage = world.read_storage::<T>();
+        self.events
+            .extend(storage.channel().read(&mut self.reader).cloned());
+
+        self
+    }
+
+    /// Returns the recorded events.
+    pub fn events(&self) -> &[ComponentEvent] {
+        &self.events
+    }
+
+    /// Removes the recorded events.
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Asserts that a component was inserted for `e`.
+    #[track_caller]
+    pub fn assert_inserted(&self, e: Entity) {
+        self.assert_event(ComponentEvent::Inserted(e.id()), "inserted", e);
+    }
+
+    /// Asserts that the component of `e` was modified.
+    #[track_caller]
+    pub fn assert_modified(&self, e: Entity) {
+        self.assert_event(ComponentEvent::Modified(e.id()), "modified", e);
+    }
+
+    /// Asserts that the component of `e` was removed.
+    #[track_caller]
+    pub fn assert_removed(&self, e: Entity) {
+        self.assert_event(ComponentEvent::Removed(e.id()), "removed", e);
+    }
+
+    /// Asserts that no events were recorded.
+    #[track_caller]
+    pub fn assert_no_events(&self) {
+        assert!(
+            self.events.is_empty(),
+            "expected no events for `{}`, got {}",
+            std::any::type_name::<T>(),
+            Events(&self.events)
+        );
+    }
+
+    #[track_caller]
+    fn assert_event(&self, event: ComponentEvent, what: &str, e: Entity) {
+        assert!(
+            self.events.contains(&event),
+            "expected the `{}` of {:?} to be {}, got {}",
+            std::any::type_name::<T>(),
+            e,
+            what,
+            Events(&self.events)
+        );
+    }
+}
+
+/// Formats events for assertion messages.
+#[cfg(feature = "tracking")]
+struct Events<'a>(&'a [ComponentEvent]);
+
+#[cfg(feature = "tracking")]
+impl fmt::Display for Events<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("no events");
+        }
+
+        f.debug_list().entries(self.0).finish()
+    }
+}
+
+/// A `World` together with a `Dispatcher`, running both like a game loop.
+pub struct TickWorld<'a, 'b> {
+    world: World,
+    dispatcher: Dispatcher<'a, 'b>,
+    ticks: u64,
+}
+
+impl<'a, 'b> TickWorld<'a, 'b> {
+    /// Creates a harness for `world`, setting up `dispatcher` on it.
+    pub fn new(mut world: World, mut dispatcher: Dispatcher<'a, 'b>) -> Self {
+        dispatcher.setup(&mut world);
+
+        TickWorld {
+            world,
+            dispatcher,
+            ticks: 0,
+        }
+    }
+
+    /// Dispatches the systems once and maintains the `World`.
+    pub fn tick(&mut self) {
+        self.dispatcher.dispatch(&self.world);
+        self.world.maintain();
+        self.ticks += 1;
+    }
+
+    /// Calls `TickWorld::tick` `n` times.
+    pub fn run(&mut self, n: usize) {
+        for _ in 0..n {
+            self.tick();
+        }
+    }
+
+    /// Ticks until `done` returns `true`, at most `max` times. Returns
+    /// `false` if `done` never returned `true`.
+    pub fn run_until<F>(&mut self, max: usize, mut done: F) -> bool
+    where
+        F: FnMut(&World) -> bool,
+    {
+        for _ in 0..max {
+            if done(&self.world) {
+                return true;
+            }
+            self.tick();
+        }
+
+        done(&self.world)
+    }
+
+    /// Returns the number of ticks so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Returns the `World`.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Returns the `World` mutably, e.g. to create entities between ticks.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    /// Returns the `World`, dropping the dispatcher.
+    pub fn into_world(self) -> World {
+        self.world
+    }
+}
@@ -0,0 +1,199 @@
+use std::any::{type_name, Any};
+
+use ahash::AHashMap as HashMap;
+use shred::{Fetch, Resource, ResourceId, World};
+
+use crate::{
+    storage::{MaskedStorage, Storage},
+    world::{Component, EntitiesRes},
+};
+
+/// A named set of storages and resources, fetched at once for code running
+/// outside of systems.
+///
+/// Code running outside of systems, like UI code, often fetches the same
+/// storages many times per frame. A `WorldCache` only stores which storages
+/// and resources to fetch, so it doesn't borrow the `World` and can be kept
+/// across frames and `World::maintain`. [`WorldCache::fetch`] fetches them
+/// all once and returns a [`CacheGuard`] handing out cheap views of them,
+/// which releases all read guards at once when released or dropped.
+///
+/// While a `CacheGuard` is held, fetching a cached storage or resource
+/// mutably panics, which catches writes during the scope in all builds.
+/// Release it before dispatching systems writing to them.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::WorldCache};
+///
+/// struct Health(u32);
+///
+/// impl Component for Health {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Health>();
+/// let e = world.create_entity().with(Health(3)).build();
+///
+/// let cache = WorldCache::new("health_bars").with_storage::<Health>();
+/// let guard = cache.fetch(&world);
+/// for _ in 0..3 {
+///     let health = guard.read_storage::<Health>();
+///     assert_eq!(health.get(e).unwrap().0, 3);
+///     assert_eq!((guard.entities(), &health).join().count(), 1);
+/// }
+///
+/// guard.release();
+/// world.write_storage::<Health>().get_mut(e).unwrap().0 = 2;
+/// world.maintain();
+///
+/// let guard = cache.fetch(&world);
+/// assert_eq!(guard.read_storage::<Health>().get(e).unwrap().0, 2);
+/// ```
+pub struct WorldCache {
+    scope: &'static str,
+    fetchers: Vec<(ResourceId, Fetcher)>,
+}
+
+impl WorldCache {
+    /// Creates an empty cache, named `scope` in panic messages.
+    pub fn new(scope: &'static str) -> Self {
+        WorldCache {
+            scope,
+            fetchers: Vec::new(),
+        }
+    }
+
+    /// Returns the name of the scope.
+    pub fn scope(&self) -> &'static str {
+        self.scope
+    }
+
+    /// Adds the storage of `T` to the cache.
+    ///
+    /// Same as [`WorldCache::add_storage`], but returns `self` to enable
+    /// method chaining.
+    pub fn with_storage<T: Component>(mut self) -> Self {
+        self.add_storage::<T>();
+
+        self
+    }
+
+    /// Adds the storage of `T` to the cache.
+    pub fn add_storage<T: Component>(&mut self) {
+        self.add::<MaskedStorage<T>>();
+    }
+
+    /// Adds the resource `R` to the cache.
+    ///
+    /// Same as [`WorldCache::add_resource`], but returns `self` to enable
+    /// method chaining.
+    pub fn with_resource<R: Resource>(mut self) -> Self {
+        self.add_resource::<R>();
+
+        self
+    }
+
+    /// Adds the resource `R` to the cache.
+    pub fn add_resource<R: Resource>(&mut self) {
+        self.add::<R>();
+    }
+
+    fn add<R: Resource>(&mut self) {
+        let id = ResourceId::new::<R>();
+        if self.fetchers.iter().any(|(other, _)| *other == id) {
+            return;
+        }
+
+        self.fetchers.push((id, fetch_guard::<R>));
+    }
+
+    /// Fetches all storages and resources of the cache from `world`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if one of them doesn't exist or is borrowed mutably.
+    pub fn fetch<'a>(&self, world: &'a World) -> CacheGuard<'a> {
+        CacheGuard {
+            scope: self.scope,
+            entities: world.fetch(),
+            guards: self
+                .fetchers
+                .iter()
+                .map(|(id, fetch)| (id.clone(), fetch(world)))
+                .collect(),
+        }
+    }
+}
+
+/// Read guards of the storages and resources of a [`WorldCache`], fetched
+/// by [`WorldCache::fetch`].
+pub struct CacheGuard<'a> {
+    scope: &'static str,
+    entities: Fetch<'a, EntitiesRes>,
+    guards: HashMap<ResourceId, Box<dyn CachedGuard + 'a>>,
+}
+
+impl<'a> CacheGuard<'a> {
+    /// Returns the name of the scope.
+    pub fn scope(&self) -> &'static str {
+        self.scope
+    }
+
+    /// Returns the entities.
+    pub fn entities(&self) -> &EntitiesRes {
+        &self.entities
+    }
+
+    /// Returns the cached storage of `T`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the storage wasn't added to the cache.
+    pub fn read_storage<T: Component>(&self) -> Storage<'a, T, &MaskedStorage<T>> {
+        Storage::new(self.entities.clone(), self.get::<MaskedStorage<T>>())
+    }
+
+    /// Returns the cached resource `R`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the resource wasn't added to the cache.
+    pub fn read_resource<R: Resource>(&self) -> &R {
+        self.get::<R>()
+    }
+
+    fn get<R: Resource>(&self) -> &R {
+        match self.guards.get(&ResourceId::new::<R>()) {
+            Some(guard) => guard.resource().downcast_ref().unwrap(),
+            None => panic!(
+                "`{}` is not in the `WorldCache` of `{}`",
+                type_name::<R>(),
+                self.scope
+            ),
+        }
+    }
+
+    /// Releases all guards, allowing to write to the storages and resources
+    /// again. Same as dropping the `CacheGuard`.
+    pub fn release(self) {}
+}
+
+type Fetcher = for<'a> fn(&'a World) -> Box<dyn CachedGuard + 'a>;
+
+fn fetch_guard<R: Resource>(world: &World) -> Box<dyn CachedGuard + '_> {
+    Box::new(world.fetch::<R>())
+}
+
+/// A read guard of a resource, with the resource type erased.
+trait CachedGuard {
+    fn resource(&self) -> &dyn Any;
+}
+
+impl<'a, R: Resource> CachedGuard for Fetch<'a, R> {
+    fn resource(&self) -> &dyn Any {
+        &**self
+    }
+}
@@ -0,0 +1,218 @@
+use std::any::TypeId;
+
+use ahash::AHashSet as HashSet;
+use shred::{Resource, World};
+
+use super::{
+    history::{capture_components, Captured},
+    Component, Entity, WorldExt,
+};
+use crate::storage::MaskedStorage;
+
+/// Selects the components copied by `WorldExt::copy_components_from`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::ComponentFilter};
+///
+/// #[derive(Clone)]
+/// struct Pos(f32, f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// #[derive(Clone)]
+/// struct Name(String);
+///
+/// impl Component for Name {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let all_but_name = ComponentFilter::all().without::<Name>();
+/// assert!(all_but_name.contains::<Pos>());
+/// assert!(!all_but_name.contains::<Name>());
+///
+/// let only_pos = ComponentFilter::none().with::<Pos>();
+/// assert!(only_pos.contains::<Pos>());
+/// assert!(!only_pos.contains::<Name>());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ComponentFilter {
+    /// `None` includes all components.
+    included: Option<HashSet<TypeId>>,
+    excluded: HashSet<TypeId>,
+}
+
+impl ComponentFilter {
+    /// Creates a filter including all components.
+    pub fn all() -> Self {
+        Default::default()
+    }
+
+    /// Creates a filter including no components, to be extended with
+    /// `ComponentFilter::with`.
+    pub fn none() -> Self {
+        ComponentFilter {
+            included: Some(HashSet::new()),
+            excluded: HashSet::new(),
+        }
+    }
+
+    /// Includes the component `T`.
+    pub fn with<T: Component>(mut self) -> Self {
+        let ty = TypeId::of::<T>();
+        self.excluded.remove(&ty);
+        if let Some(included) = &mut self.included {
+            included.insert(ty);
+        }
+
+        self
+    }
+
+    /// Excludes the component `T`.
+    pub fn without<T: Component>(mut self) -> Self {
+        let ty = TypeId::of::<T>();
+        self.excluded.insert(ty);
+        if let Some(included) = &mut self.included {
+            included.remove(&ty);
+        }
+
+        self
+    }
+
+    /// Returns whether the component `T` is included.
+    pub fn contains<T: Component>(&self) -> bool {
+        self.contains_id(TypeId::of::<T>())
+    }
+
+    fn contains_id(&self, ty: TypeId) -> bool {
+        !self.excluded.contains(&ty)
+            && self
+                .included
+                .as_ref()
+                .map_or(true, |included| included.contains(&ty))
+    }
+}
+
+/// The components registered with `WorldExt::register_cloneable` and the
+/// resources registered with `WorldExt::register_cloneable_resource`.
+#[derive(Default)]
+pub(crate) struct CloneRegistry {
+    components: Vec<(TypeId, CloneComponent, CaptureComponents, ForkStorage)>,
+    resources: Vec<(TypeId, ForkResource)>,
+}
+
+/// Clones the component of the first entity to the second one, returning
+/// whether the first entity had one.
+type CloneComponent = fn(&World, Entity, Entity) -> bool;
+
+/// Clones all components of a type, see `History`.
+type CaptureComponents = fn(&World) -> Box<dyn Captured>;
+
+/// Registers the component as cloneable in a fork, copying the configuration
+/// of its storage, see `WorldExt::fork`.
+type ForkStorage = fn(&World, &mut World);
+
+/// Copies the resource to a fork, see `WorldExt::fork`.
+type ForkResource = fn(&World, &mut World);
+
+impl CloneRegistry {
+    pub(crate) fn register<T>(&mut self)
+    where
+        T: Component + Clone,
+        T::Storage: Default,
+    {
+        if !self.components.iter().any(|(ty, ..)| *ty == TypeId::of::<T>()) {
+            self.components.push((
+                TypeId::of::<T>(),
+                clone_component::<T>,
+                capture_components::<T>,
+                fork_storage::<T>,
+            ));
+        }
+    }
+
+    pub(crate) fn register_resource<R>(&mut self)
+    where
+        R: Resource + Clone,
+    {
+        if !self.resources.iter().any(|(ty, _)| *ty == TypeId::of::<R>()) {
+            self.resources.push((TypeId::of::<R>(), fork_resource::<R>));
+        }
+    }
+
+    /// Copies the components of `src` included by `filter` to `dst`,
+    /// returning the number of copied components.
+    pub(crate) fn copy(
+        &self,
+        world: &World,
+        src: Entity,
+        dst: Entity,
+        filter: &ComponentFilter,
+    ) -> usize {
+        self.components
+            .iter()
+            .filter(|(ty, ..)| filter.contains_id(*ty))
+            .filter(|(_, clone, ..)| clone(world, src, dst))
+            .count()
+    }
+
+    /// Clones the components of the types included by `filter`.
+    pub(crate) fn capture(&self, world: &World, filter: &ComponentFilter) -> Vec<Box<dyn Captured>> {
+        self.components
+            .iter()
+            .filter(|(ty, ..)| filter.contains_id(*ty))
+            .map(|(_, _, capture, _)| capture(world))
+            .collect()
+    }
+
+    /// Copies the components and resources of `world` to `fork`, registering
+    /// them as cloneable there too.
+    pub(crate) fn fork(&self, world: &World, fork: &mut World) {
+        // Resources are copied first, since storages may be set up from them.
+        for (_, fork_resource) in &self.resources {
+            fork_resource(world, fork);
+        }
+        for (_, _, capture, fork_storage) in &self.components {
+            fork_storage(world, fork);
+            capture(world).restore(fork);
+        }
+    }
+}
+
+fn fork_storage<T>(world: &World, fork: &mut World)
+where
+    T: Component + Clone,
+    T::Storage: Default,
+{
+    fork.register_cloneable::<T>();
+    fork.fetch_mut::<MaskedStorage<T>>()
+        .copy_config(&world.fetch::<MaskedStorage<T>>());
+}
+
+fn fork_resource<R>(world: &World, fork: &mut World)
+where
+    R: Resource + Clone,
+{
+    if let Some(resource) = world.try_fetch::<R>() {
+        fork.insert(R::clone(&resource));
+    }
+    fork.register_cloneable_resource::<R>();
+}
+
+fn clone_component<T>(world: &World, src: Entity, dst: Entity) -> bool
+where
+    T: Component + Clone,
+{
+    let mut storage = world.write_storage::<T>();
+    match storage.get(src).cloned() {
+        Some(component) => {
+            // `dst` has been checked to be alive.
+            let _ = storage.insert(dst, component);
+            true
+        }
+        None => false,
+    }
+}
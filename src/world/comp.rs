@@ -1,5 +1,8 @@
 use std::any::Any;
 
+use shred::World;
+
+use super::{Entity, WorldExt};
 use crate::storage::UnprotectedStorage;
 
 /// Abstract component type.
@@ -69,3 +72,116 @@ pub trait Component: Any + Sized {
     #[cfg(not(feature = "parallel"))]
     type Storage: UnprotectedStorage<Self> + Any;
 }
+
+/// A type split into a `Hot` component, with the frequently accessed fields,
+/// and a `Cold` component, with the bulky or rarely read ones, so joins over
+/// the hot fields touch less memory.
+///
+/// Usually derived with `#[derive(SplitComponent)]`, which also generates a
+/// facade borrowing both parts. The parts are inserted with
+/// `Builder::with_split` and have to be registered like other components.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::SplitComponent};
+///
+/// struct Body {
+///     pos: f32,
+///     mesh: Vec<u8>,
+/// }
+///
+/// struct BodyHot(f32);
+///
+/// impl Component for BodyHot {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct BodyCold(Vec<u8>);
+///
+/// impl Component for BodyCold {
+///     type Storage = DenseVecStorage<Self>;
+/// }
+///
+/// impl SplitComponent for Body {
+///     type Cold = BodyCold;
+///     type Hot = BodyHot;
+///
+///     fn split(self) -> (BodyHot, BodyCold) {
+///         (BodyHot(self.pos), BodyCold(self.mesh))
+///     }
+///
+///     fn unsplit(hot: BodyHot, cold: BodyCold) -> Self {
+///         Body {
+///             pos: hot.0,
+///             mesh: cold.0,
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<BodyHot>();
+/// world.register::<BodyCold>();
+/// let e = world
+///     .create_entity()
+///     .with_split(Body {
+///         pos: 1.0,
+///         mesh: vec![0; 1024],
+///     })
+///     .build();
+/// assert_eq!(world.read_storage::<BodyHot>().get(e).unwrap().0, 1.0);
+/// ```
+pub trait SplitComponent: Sized {
+    /// The component with the frequently accessed fields.
+    type Hot: Component;
+    /// The component with the rarely accessed fields.
+    type Cold: Component;
+
+    /// Splits the value into its parts.
+    fn split(self) -> (Self::Hot, Self::Cold);
+
+    /// Joins the parts back into the value.
+    fn unsplit(hot: Self::Hot, cold: Self::Cold) -> Self;
+}
+
+/// A tuple of component types which can be removed from an entity in one
+/// call, see `WorldExt::remove_components` and
+/// `LazyUpdate::remove_components`.
+///
+/// Implemented for tuples of up to 16 components.
+pub trait ComponentGroup: 'static {
+    /// The tuple of removed components, `Option`s for each component type.
+    type Removed;
+
+    /// Removes all components of this group from `entity`, fetching each
+    /// storage in turn.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if one of the components has not been registered or its
+    /// storage is already borrowed.
+    fn remove(world: &World, entity: Entity) -> Self::Removed;
+}
+
+macro_rules! component_group {
+    ($($comp:ident),*) => {
+        impl<$($comp,)*> ComponentGroup for ($($comp,)*)
+        where
+            $($comp: Component,)*
+        {
+            type Removed = ($(Option<$comp>,)*);
+
+            fn remove(world: &World, entity: Entity) -> Self::Removed {
+                ($(world.write_storage::<$comp>().remove(entity),)*)
+            }
+        }
+
+        component_group!(@pop $($comp),*);
+    };
+    (@pop $head:ident) => {};
+    (@pop $head:ident $(, $tail:ident)+) => {
+        component_group!($($tail),+);
+    };
+}
+
+component_group!(A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P);
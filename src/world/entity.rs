@@ -1,10 +1,17 @@
+#[cfg(not(feature = "parallel"))]
+use std::cell::RefCell;
 use std::{
+    collections::VecDeque,
     fmt,
     num::NonZeroI32,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
-use hibitset::{AtomicBitSet, BitSet, BitSetOr};
+#[cfg(feature = "parallel")]
+use hibitset::AtomicBitSet;
+#[cfg(not(feature = "parallel"))]
+use hibitset::BitSetLike;
+use hibitset::{BitSet, BitSetOr};
 use shred::Read;
 
 #[nougat::gat(Type)]
@@ -12,7 +19,8 @@ use crate::join::LendJoin;
 #[cfg(feature = "parallel")]
 use crate::join::ParJoin;
 use crate::{
-    error::WrongGeneration,
+    bitset::{BitSetLen, CountedBitSet},
+    error::{InvalidEntityBits, WrongGeneration},
     join::{Join, RepeatableLendGet},
     storage::WriteStorage,
     world::Component,
@@ -53,16 +61,91 @@ pub type Index = u32;
 /// ```
 pub type Entities<'a> = Read<'a, EntitiesRes>;
 
+/// The set of ids raised or killed through `&self`, which has to be atomic
+/// if entities may be created from several threads at once.
+#[cfg(feature = "parallel")]
+type SharedBitSet = AtomicBitSet;
+#[cfg(not(feature = "parallel"))]
+type SharedBitSet = LocalBitSet;
+
+/// A `BitSet` which can be added to through a shared reference, used instead
+/// of an `AtomicBitSet` by single-threaded builds.
+#[cfg(not(feature = "parallel"))]
+#[derive(Default, Debug)]
+pub struct LocalBitSet(RefCell<BitSet>);
+
+#[cfg(not(feature = "parallel"))]
+impl LocalBitSet {
+    /// Adds `id`, returning `true` if it was already in the set.
+    fn add_atomic(&self, id: Index) -> bool {
+        self.0.borrow_mut().add(id)
+    }
+
+    fn remove(&mut self, id: Index) -> bool {
+        self.0.get_mut().remove(id)
+    }
+
+    fn clear(&mut self) {
+        self.0.get_mut().clear();
+    }
+}
+
+#[cfg(not(feature = "parallel"))]
+impl BitSetLike for LocalBitSet {
+    fn layer3(&self) -> usize {
+        self.0.borrow().layer3()
+    }
+
+    fn layer2(&self, i: usize) -> usize {
+        self.0.borrow().layer2(i)
+    }
+
+    fn layer1(&self, i: usize) -> usize {
+        self.0.borrow().layer1(i)
+    }
+
+    fn layer0(&self, i: usize) -> usize {
+        self.0.borrow().layer0(i)
+    }
+
+    fn contains(&self, i: Index) -> bool {
+        self.0.borrow().contains(i)
+    }
+}
+
 /// Internally used structure for `Entity` allocation.
 #[derive(Default, Debug)]
 pub(crate) struct Allocator {
     generations: Vec<ZeroableGeneration>,
 
-    alive: BitSet,
-    raised: AtomicBitSet,
-    killed: AtomicBitSet,
+    alive: CountedBitSet,
+    raised: SharedBitSet,
+    killed: SharedBitSet,
     cache: EntityCache,
     max_id: AtomicUsize,
+    streams: Vec<Option<EntityStreamState>>,
+
+    /// Entities created or deleted non-atomically since the last `merge`.
+    created: Vec<Entity>,
+    deleted: Vec<Entity>,
+    /// Entities created or deleted before the last `merge`.
+    created_last: Vec<Entity>,
+    deleted_last: Vec<Entity>,
+
+    /// Number of merges for which freed ids are kept from being reused.
+    reuse_delay: usize,
+    /// Ids freed since the last `merge`, if `reuse_delay` is not zero.
+    freed: Vec<Index>,
+    /// Ids freed before the last merges, oldest first.
+    quarantine: VecDeque<Vec<Index>>,
+}
+
+/// Ids reserved for an `EntityStream`.
+#[derive(Default, Debug)]
+struct EntityStreamState {
+    reserved: Vec<Index>,
+    next: AtomicUsize,
+    min: usize,
 }
 
 impl Allocator {
@@ -87,11 +170,13 @@ impl Allocator {
 
             if self.raised.remove(entity.id()) {
                 self.generations[id].raise();
+                self.created.push(entity);
             }
             self.generations[id].die();
+            self.deleted.push(entity);
         }
 
-        self.cache.extend(delete.iter().map(|e| e.0));
+        self.release(delete.iter().map(|e| e.0));
 
         Ok(())
     }
@@ -153,6 +238,24 @@ impl Allocator {
             atomic_increment(&self.max_id).expect("No entity left to allocate") as Index
         });
 
+        self.raise_atomic(id)
+    }
+
+    /// Allocate a new entity from the ids reserved for a stream, falling back
+    /// to `allocate_atomic` once they are used up.
+    pub fn allocate_from(&self, stream: usize) -> Entity {
+        let state = self.streams[stream]
+            .as_ref()
+            .expect("Entity stream has been removed");
+        let next = state.next.fetch_add(1, Ordering::Relaxed);
+
+        match state.reserved.get(next) {
+            Some(&id) => self.raise_atomic(id),
+            None => self.allocate_atomic(),
+        }
+    }
+
+    fn raise_atomic(&self, id: Index) -> Entity {
         self.raised.add_atomic(id);
         let gen = self
             .generation(id)
@@ -163,6 +266,18 @@ impl Allocator {
 
     /// Allocate a new entity
     pub fn allocate(&mut self) -> Entity {
+        let id = self.reserve_id();
+
+        self.alive.add(id);
+
+        let gen = self.generations[id as usize].raise();
+        self.created.push(Entity(id, gen));
+
+        Entity(id, gen)
+    }
+
+    /// Takes a free id out of the cache or increments the maximum id.
+    fn reserve_id(&mut self) -> Index {
         let id = self.cache.pop().unwrap_or_else(|| {
             let id = *self.max_id.get_mut();
             *self.max_id.get_mut() = id.checked_add(1).expect("No entity left to allocate");
@@ -171,11 +286,103 @@ impl Allocator {
 
         self.update_generation_length(id as usize);
 
-        self.alive.add(id as Index);
+        id
+    }
 
-        let gen = self.generations[id as usize].raise();
+    /// Adds a stream with at least `min` reserved ids, returning its index.
+    pub fn add_stream(&mut self, min: usize) -> usize {
+        let mut state = EntityStreamState {
+            min,
+            ..Default::default()
+        };
+        self.refill_stream(&mut state);
+
+        match self.streams.iter().position(Option::is_none) {
+            Some(index) => {
+                self.streams[index] = Some(state);
+                index
+            }
+            None => {
+                self.streams.push(Some(state));
+                self.streams.len() - 1
+            }
+        }
+    }
+
+    /// Removes a stream, releasing the ids it didn't use.
+    pub fn remove_stream(&mut self, stream: usize) {
+        let mut state = self.streams[stream]
+            .take()
+            .expect("Entity stream has been removed");
+        let used = (*state.next.get_mut()).min(state.reserved.len());
+
+        self.cache.extend(state.reserved.drain(used..));
+    }
+
+    /// Drops the used ids of a stream and reserves as many ids as have been
+    /// requested since the last refill, but at least the stream's minimum.
+    fn refill_stream(&mut self, state: &mut EntityStreamState) {
+        let requested = std::mem::take(state.next.get_mut());
+        let used = requested.min(state.reserved.len());
+        state.reserved.drain(..used);
+
+        let target = requested.max(state.min);
+        if state.reserved.len() > target {
+            self.cache.extend(state.reserved.drain(target..));
+        }
+        while state.reserved.len() < target {
+            let id = self.reserve_id();
+            state.reserved.push(id);
+        }
+    }
+
+    /// Copies the allocator, including the entities created or deleted
+    /// atomically since the last `merge`, see `WorldExt::fork`.
+    pub(crate) fn fork(&self) -> Allocator {
+        use hibitset::BitSetLike;
 
-        Entity(id as Index, gen)
+        let copy_shared = |set: &SharedBitSet| {
+            let copy = SharedBitSet::default();
+            for id in set.iter() {
+                copy.add_atomic(id);
+            }
+            copy
+        };
+        let mut alive = CountedBitSet::default();
+        for id in (&*self.alive).iter() {
+            alive.add(id);
+        }
+        let copy_atomic = |value: &AtomicUsize| AtomicUsize::new(value.load(Ordering::Relaxed));
+
+        Allocator {
+            generations: self.generations.clone(),
+            alive,
+            raised: copy_shared(&self.raised),
+            killed: copy_shared(&self.killed),
+            cache: EntityCache {
+                cache: self.cache.cache.clone(),
+                len: copy_atomic(&self.cache.len),
+            },
+            max_id: copy_atomic(&self.max_id),
+            streams: self
+                .streams
+                .iter()
+                .map(|state| {
+                    state.as_ref().map(|state| EntityStreamState {
+                        reserved: state.reserved.clone(),
+                        next: copy_atomic(&state.next),
+                        min: state.min,
+                    })
+                })
+                .collect(),
+            created: self.created.clone(),
+            deleted: self.deleted.clone(),
+            created_last: self.created_last.clone(),
+            deleted_last: self.deleted_last.clone(),
+            reuse_delay: self.reuse_delay,
+            freed: self.freed.clone(),
+            quarantine: self.quarantine.clone(),
+        }
     }
 
     /// Maintains the allocated entities, mainly dealing with atomically
@@ -189,8 +396,9 @@ impl Allocator {
         self.update_generation_length(max_id + 1);
 
         for i in (&self.raised).iter() {
-            self.generations[i as usize].raise();
+            let gen = self.generations[i as usize].raise();
             self.alive.add(i);
+            self.created.push(Entity(i, gen));
         }
         self.raised.clear();
 
@@ -201,11 +409,58 @@ impl Allocator {
         }
         self.killed.clear();
 
-        self.cache.extend(deleted.iter().map(|e| e.0));
+        self.release(deleted.iter().map(|e| e.0));
+        if self.reuse_delay > 0 || !self.quarantine.is_empty() {
+            self.quarantine.push_back(std::mem::take(&mut self.freed));
+            while self.quarantine.len() > self.reuse_delay {
+                let ids = self.quarantine.pop_front().unwrap();
+                // The ids that have been freed first are reused first.
+                self.cache.extend_front(ids);
+            }
+        }
+
+        let mut streams = std::mem::take(&mut self.streams);
+        for state in streams.iter_mut().flatten() {
+            self.refill_stream(state);
+        }
+        self.streams = streams;
+
+        self.created_last = std::mem::take(&mut self.created);
+        self.deleted_last = std::mem::take(&mut self.deleted);
+        self.deleted_last.extend_from_slice(&deleted);
 
         deleted
     }
 
+    /// Makes the ids of killed entities available for reuse, or quarantines
+    /// them if there is a `reuse_delay`.
+    ///
+    /// Ids whose generation can't be raised anymore are retired for good, so
+    /// generations never wrap around.
+    fn release(&mut self, ids: impl IntoIterator<Item = Index>) {
+        let generations = &self.generations;
+        let ids = ids
+            .into_iter()
+            .filter(|&id| generations[id as usize].can_raise());
+        if self.reuse_delay == 0 {
+            self.cache.extend(ids);
+        } else {
+            self.freed.extend(ids);
+        }
+    }
+
+    /// Sets the number of merges for which freed ids are kept from being
+    /// reused.
+    pub fn set_reuse_delay(&mut self, merges: usize) {
+        self.reuse_delay = merges;
+    }
+
+    /// Returns the number of merges for which freed ids are kept from being
+    /// reused.
+    pub fn reuse_delay(&self) -> usize {
+        self.reuse_delay
+    }
+
     fn update_generation_length(&mut self, i: usize) {
         if self.generations.len() <= i {
             self.generations.resize(i + 1, ZeroableGeneration(None));
@@ -228,6 +483,61 @@ impl<'a> Iterator for CreateIterAtomic<'a> {
     }
 }
 
+/// A handle to a range of entity ids reserved for deterministic creation.
+///
+/// Entities created atomically with `EntitiesRes::create` get their ids in
+/// the order in which systems running in parallel happen to call it, so the
+/// ids may differ between runs. Entities created with
+/// `EntitiesRes::create_from` instead take their ids from the ones reserved
+/// for the stream during the last `World::maintain`, which only depends on
+/// the order of operations with exclusive access to the `World`.
+///
+/// Give every system that creates entities its own stream and create the
+/// entities of one stream from a single thread, in a deterministic order.
+/// If more entities are created from a stream than it has reserved ids, the
+/// remaining ones fall back to `EntitiesRes::create`, and the stream reserves
+/// more ids on the next maintain.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::prelude::*;
+/// use specs::world::EntityStream;
+///
+/// struct Spawner {
+///     stream: Option<EntityStream>,
+/// }
+///
+/// impl<'a> System<'a> for Spawner {
+///     type SystemData = Entities<'a>;
+///
+///     fn run(&mut self, entities: Self::SystemData) {
+///         let stream = self.stream.as_ref().unwrap();
+///         for _ in 0..3 {
+///             entities.create_from(stream);
+///         }
+///     }
+///
+///     fn setup(&mut self, world: &mut World) {
+///         Self::SystemData::setup(world);
+///         self.stream = Some(world.entities_mut().create_stream(16));
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with(Spawner { stream: None }, "spawner", &[])
+///     .build();
+/// dispatcher.setup(&mut world);
+///
+/// dispatcher.dispatch(&world);
+/// world.maintain();
+///
+/// assert_eq!(world.entities().join().count(), 3);
+/// ```
+#[derive(Debug)]
+pub struct EntityStream(usize);
+
 /// `Entity` type, as seen by the user.
 #[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
 pub struct Entity(Index, Generation);
@@ -250,6 +560,91 @@ impl Entity {
     pub fn gen(self) -> Generation {
         self.1
     }
+
+    /// Packs the `Entity` into a `u64`, e.g. for compact network encoding.
+    ///
+    /// The index is stored in the lower and the generation in the upper 32
+    /// bits. Use `Entity::from_bits` or `EntitiesRes::entity_from_bits` to
+    /// unpack it.
+    #[inline]
+    pub fn to_bits(self) -> u64 {
+        (self.1.id() as u32 as u64) << 32 | self.0 as u64
+    }
+
+    /// Unpacks an `Entity` packed with `Entity::to_bits`.
+    ///
+    /// Returns `None` if the generation is zero, which `to_bits` never
+    /// produces. The `Entity` is not checked to be alive, see
+    /// `EntitiesRes::entity_from_bits`.
+    #[inline]
+    pub fn from_bits(bits: u64) -> Option<Entity> {
+        let gen = NonZeroI32::new((bits >> 32) as u32 as i32)?;
+
+        Some(Entity(bits as Index, Generation(gen)))
+    }
+}
+
+impl From<Entity> for u64 {
+    fn from(entity: Entity) -> Self {
+        entity.to_bits()
+    }
+}
+
+impl TryFrom<u64> for Entity {
+    type Error = InvalidEntityBits;
+
+    fn try_from(bits: u64) -> Result<Self, Self::Error> {
+        Entity::from_bits(bits).ok_or(InvalidEntityBits { bits })
+    }
+}
+
+/// A reference to an `Entity` which may have been deleted.
+///
+/// Unlike a raw `Entity` field, which is assumed to always be alive, a
+/// `WeakEntity` makes it explicit that the entity may be gone, and has to be
+/// upgraded before use. Saving a `WeakEntity` pointing to an entity without a
+/// marker, e.g. a deleted one, stores no reference instead of failing, and
+/// loading it back yields a dangling `WeakEntity`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::WeakEntity};
+///
+/// let mut world = World::new();
+/// let e = world.create_entity().build();
+/// let weak = WeakEntity::from(e);
+/// assert_eq!(weak.upgrade(&world.entities()), Some(e));
+///
+/// world.delete_entity(e).unwrap();
+/// assert_eq!(weak.upgrade(&world.entities()), None);
+/// ```
+#[derive(Clone, Copy, Debug, Hash, Eq, Ord, PartialEq, PartialOrd)]
+pub struct WeakEntity(Option<Entity>);
+
+impl WeakEntity {
+    /// Creates a `WeakEntity` referencing no entity, which never upgrades.
+    pub fn dangling() -> Self {
+        WeakEntity(None)
+    }
+
+    /// Returns the referenced `Entity` if it is still alive.
+    #[inline]
+    pub fn upgrade(self, entities: &EntitiesRes) -> Option<Entity> {
+        self.0.filter(|&e| entities.is_alive(e))
+    }
+
+    /// Returns the referenced `Entity`, without checking if it is alive.
+    #[inline]
+    pub fn entity(self) -> Option<Entity> {
+        self.0
+    }
+}
+
+impl From<Entity> for WeakEntity {
+    fn from(entity: Entity) -> Self {
+        WeakEntity(Some(entity))
+    }
 }
 
 /// The entities of this ECS. This is a resource, stored in the `World`.
@@ -282,6 +677,30 @@ impl EntitiesRes {
         self.alloc.allocate_atomic()
     }
 
+    /// Creates a new entity atomically from the ids reserved for `stream`.
+    /// This will be persistent as soon as you call `World::maintain`.
+    ///
+    /// Unlike `EntitiesRes::create`, the created ids don't depend on how
+    /// creations from other systems interleave with this one, see
+    /// `EntityStream`.
+    pub fn create_from(&self, stream: &EntityStream) -> Entity {
+        self.alloc.allocate_from(stream.0)
+    }
+
+    /// Adds a new `EntityStream`, immediately reserving `min` ids for it.
+    ///
+    /// On every `World::maintain`, the stream reserves as many ids as have
+    /// been created from it since the last maintain, but at least `min`.
+    pub fn create_stream(&mut self, min: usize) -> EntityStream {
+        EntityStream(self.alloc.add_stream(min))
+    }
+
+    /// Removes an `EntityStream`, releasing the ids it has reserved but not
+    /// used.
+    pub fn remove_stream(&mut self, stream: EntityStream) {
+        self.alloc.remove_stream(stream.0);
+    }
+
     /// Returns an iterator which creates
     /// new entities atomically.
     /// They will be persistent as soon
@@ -321,12 +740,118 @@ impl EntitiesRes {
     pub fn is_alive(&self, e: Entity) -> bool {
         self.alloc.is_alive(e)
     }
+
+    /// Returns the number of alive entities, including the ones created
+    /// atomically since the last `World::maintain`, like joining over
+    /// `Entities` would yield.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// let a = world.create_entity().build();
+    /// world.create_entity().build();
+    /// world.entities().create();
+    /// assert_eq!(world.entities().count(), 3);
+    ///
+    /// world.delete_entity(a).unwrap();
+    /// assert_eq!(world.entities().count(), 2);
+    /// ```
+    pub fn count(&self) -> usize {
+        self.alloc.alive.len() + self.alloc.raised.len()
+    }
+
+    /// Unpacks an `Entity` packed with `Entity::to_bits`, returning `None`
+    /// if the bits are invalid or the entity is not alive.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// let e = world.create_entity().build();
+    /// let bits = e.to_bits();
+    ///
+    /// assert_eq!(world.entities().entity_from_bits(bits), Some(e));
+    /// world.delete_entity(e).unwrap();
+    /// assert_eq!(world.entities().entity_from_bits(bits), None);
+    /// ```
+    pub fn entity_from_bits(&self, bits: u64) -> Option<Entity> {
+        Entity::from_bits(bits).filter(|&e| self.is_alive(e))
+    }
+
+    /// Returns the entities created before the last `World::maintain`, both
+    /// atomically and with exclusive access to the `World`.
+    ///
+    /// The list is replaced on every `World::maintain`, so systems running
+    /// after it see the entities created during the previous frame. Entities
+    /// that have been deleted again are included as well, see
+    /// `EntitiesRes::deleted_this_maintain`.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// let a = world.create_entity().build();
+    /// let b = world.entities().create();
+    /// world.maintain();
+    ///
+    /// assert_eq!(world.entities().created_this_maintain(), &[a, b]);
+    ///
+    /// world.maintain();
+    /// assert!(world.entities().created_this_maintain().is_empty());
+    /// ```
+    pub fn created_this_maintain(&self) -> &[Entity] {
+        &self.alloc.created_last
+    }
+
+    /// Returns the entities deleted before the last `World::maintain`, both
+    /// atomically and with exclusive access to the `World`.
+    ///
+    /// The entities keep the generation they had while alive, so they can be
+    /// used to look up data stored elsewhere, but `EntitiesRes::is_alive`
+    /// returns `false` for them. The list is replaced on every
+    /// `World::maintain`.
+    pub fn deleted_this_maintain(&self) -> &[Entity] {
+        &self.alloc.deleted_last
+    }
+
+    /// Sets the number of `World::maintain` calls for which the indices of
+    /// deleted entities are kept from being reused, `0` by default.
+    ///
+    /// By default, the index of a deleted entity is reused right away, so a
+    /// stale `Entity` only differs from the new one in its generation. With a
+    /// delay, indices are quarantined and reused in the order in which they
+    /// were freed, making bugs with stale entities easier to notice.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// world.entities_mut().set_reuse_delay(1);
+    ///
+    /// let a = world.create_entity().build();
+    /// world.delete_entity(a).unwrap();
+    /// world.maintain();
+    /// assert_ne!(world.create_entity().build().id(), a.id());
+    ///
+    /// world.maintain();
+    /// assert_eq!(world.create_entity().build().id(), a.id());
+    /// ```
+    pub fn set_reuse_delay(&mut self, maintains: usize) {
+        self.alloc.set_reuse_delay(maintains);
+    }
+
+    /// Returns the number of `World::maintain` calls for which the indices of
+    /// deleted entities are kept from being reused.
+    pub fn reuse_delay(&self) -> usize {
+        self.alloc.reuse_delay()
+    }
 }
 
 // SAFETY: It is safe to retrieve elements with any `id` regardless of the mask.
 #[nougat::gat]
 unsafe impl<'a> LendJoin for &'a EntitiesRes {
-    type Mask = BitSetOr<&'a BitSet, &'a AtomicBitSet>;
+    type Mask = BitSetOr<&'a BitSet, &'a SharedBitSet>;
     type Type<'next> = Entity;
     type Value = Self;
 
@@ -350,7 +875,7 @@ unsafe impl<'a> RepeatableLendGet for &'a EntitiesRes {}
 
 // SAFETY: It is safe to retrieve elements with any `id` regardless of the mask.
 unsafe impl<'a> Join for &'a EntitiesRes {
-    type Mask = BitSetOr<&'a BitSet, &'a AtomicBitSet>;
+    type Mask = BitSetOr<&'a BitSet, &'a SharedBitSet>;
     type Type = Entity;
     type Value = Self;
 
@@ -374,7 +899,7 @@ unsafe impl<'a> Join for &'a EntitiesRes {
 // It is safe to retrieve elements with any `id` regardless of the mask.
 #[cfg(feature = "parallel")]
 unsafe impl<'a> ParJoin for &'a EntitiesRes {
-    type Mask = BitSetOr<&'a BitSet, &'a AtomicBitSet>;
+    type Mask = BitSetOr<&'a BitSet, &'a SharedBitSet>;
     type Type = Entity;
     type Value = Self;
 
@@ -462,6 +987,17 @@ impl Generation {
         self.id() > 0
     }
 
+    /// Returns the `Generation` of the next entity placed at the same index,
+    /// or `None` if the generations are exhausted.
+    ///
+    /// Indices are not reused once their generations are exhausted, so
+    /// generations never wrap around.
+    pub fn next(self) -> Option<Generation> {
+        let gen = self.id().checked_abs()?.checked_add(1)?;
+
+        NonZeroI32::new(gen).map(Generation)
+    }
+
     /// Revives and increments a dead `Generation`.
     ///
     /// # Panics
@@ -469,11 +1005,12 @@ impl Generation {
     /// Panics if it is alive.
     fn raised(self) -> Generation {
         assert!(!self.is_alive());
+        // Ids are retired before their generation could overflow, see
+        // `ZeroableGeneration::can_raise`.
+        let gen = 1i32.checked_sub(self.id()).expect("generation overflow");
         // SAFETY: Since `self` is not alive, `self.id()` will be negative so
-        // subtracting it from `1` will give us a value `>= 2`. If this
-        // overflows it will at most wrap to `i32::MIN + 1` (so it will never be
-        // zero).
-        unsafe { Generation(NonZeroI32::new_unchecked(1 - self.id())) }
+        // subtracting it from `1` will give us a value `>= 2`.
+        unsafe { Generation(NonZeroI32::new_unchecked(gen)) }
     }
 }
 
@@ -505,6 +1042,13 @@ impl ZeroableGeneration {
         self.0 = NonZeroI32::new(-self.id()).map(Generation);
     }
 
+    /// Returns `false` if this `Generation` is the last one, so its id can't
+    /// be reused.
+    #[inline]
+    fn can_raise(self) -> bool {
+        self.id() != -i32::MAX
+    }
+
     /// Revives and increments a dead `Generation`.
     ///
     /// # Panics
@@ -551,6 +1095,13 @@ impl EntityCache {
     fn maintain(&mut self) {
         self.cache.truncate(*(self.len.get_mut()));
     }
+
+    /// Adds ids which are popped after all the ids already in the cache.
+    fn extend_front(&mut self, ids: Vec<Index>) {
+        self.maintain();
+        self.cache.splice(0..0, ids.into_iter().rev());
+        *self.len.get_mut() = self.cache.len();
+    }
 }
 
 impl Extend<Index> for EntityCache {
@@ -601,6 +1152,29 @@ mod tests {
         assert_eq!(size_of::<Option<Entity>>(), size_of::<Entity>());
     }
 
+    #[test]
+    fn generation_next() {
+        assert_eq!(Generation::new(1).next(), Some(Generation::new(2)));
+        assert_eq!(Generation::new(-3).next(), Some(Generation::new(4)));
+        assert_eq!(Generation::new(i32::MAX).next(), None);
+        assert_eq!(Generation::new(-i32::MAX).next(), None);
+    }
+
+    #[test]
+    fn entity_bits_round_trip() {
+        for entity in [
+            Entity::new(0, Generation::new(1)),
+            Entity::new(Index::MAX, Generation::new(i32::MAX)),
+            Entity::new(7, Generation::new(-2)),
+        ] {
+            assert_eq!(Entity::from_bits(entity.to_bits()), Some(entity));
+            assert_eq!(Entity::try_from(u64::from(entity)), Ok(entity));
+        }
+
+        assert_eq!(Entity::from_bits(5), None);
+        assert_eq!(Entity::try_from(5), Err(InvalidEntityBits { bits: 5 }));
+    }
+
     #[test]
     fn kill_atomic_create_merge() {
         let mut allocator = Allocator::default();
@@ -633,4 +1207,84 @@ mod tests {
         assert_eq!(allocator.killed.contains(entity.id()), false);
         assert_eq!(allocator.merge(), vec![]);
     }
+
+    #[test]
+    fn reuse_delay_quarantines_ids() {
+        let mut allocator = Allocator::default();
+        allocator.set_reuse_delay(2);
+
+        let entities: Vec<_> = (0..3).map(|_| allocator.allocate()).collect();
+        allocator.kill(&entities[..2]).unwrap();
+        allocator.kill_atomic(entities[2]).unwrap();
+        allocator.merge();
+        assert_eq!(allocator.allocate().id(), 3);
+        allocator.merge();
+        assert_eq!(allocator.allocate().id(), 4);
+        allocator.merge();
+
+        // Reused in the order in which they were freed.
+        let ids: Vec<_> = (0..4).map(|_| allocator.allocate().id()).collect();
+        assert_eq!(ids, vec![0, 1, 2, 5]);
+    }
+
+    #[test]
+    fn exhausted_ids_are_retired() {
+        let mut allocator = Allocator::default();
+
+        let entity = allocator.allocate();
+        allocator.generations[0] = ZeroableGeneration(Some(Generation::new(i32::MAX)));
+        let entity = Entity(entity.id(), Generation::new(i32::MAX));
+        allocator.kill(&[entity]).unwrap();
+
+        assert_eq!(allocator.allocate().id(), 1);
+        assert!(!allocator.is_alive(entity));
+    }
+
+    #[test]
+    fn stream_ids_independent_of_interleaving() {
+        let run = |a_first: bool| {
+            let mut allocator = Allocator::default();
+            let a = allocator.add_stream(2);
+            let b = allocator.add_stream(2);
+
+            let mut frames = vec![];
+            for _ in 0..3 {
+                let (mut ids_a, mut ids_b) = (vec![], vec![]);
+                for _ in 0..3 {
+                    if a_first {
+                        ids_a.push(allocator.allocate_from(a));
+                        ids_b.push(allocator.allocate_from(b));
+                    } else {
+                        ids_b.push(allocator.allocate_from(b));
+                        ids_a.push(allocator.allocate_from(a));
+                    }
+                }
+                allocator.merge();
+                frames.push((ids_a, ids_b));
+            }
+
+            frames
+        };
+
+        let frames = run(true);
+        // The third creation of each stream falls back to atomic allocation
+        // in the first frame, after that the reservations grow.
+        assert_eq!(frames[1..], run(false)[1..]);
+        assert_eq!(frames[0].0[..2], run(false)[0].0[..2]);
+        assert_eq!(frames[0].1[..2], run(false)[0].1[..2]);
+    }
+
+    #[test]
+    fn remove_stream_releases_ids() {
+        let mut allocator = Allocator::default();
+        let stream = allocator.add_stream(4);
+
+        let entity = allocator.allocate_from(stream);
+        allocator.merge();
+        assert!(allocator.is_alive(entity));
+
+        allocator.remove_stream(stream);
+        let ids: Vec<_> = (0..4).map(|_| allocator.allocate().id()).collect();
+        assert!(ids.iter().all(|&id| id != entity.id() && id < 5));
+    }
 }
@@ -0,0 +1,86 @@
+use std::any::TypeId;
+
+use shred::World;
+#[cfg(feature = "tracking")]
+use shrev::EventChannel;
+
+use super::Entity;
+
+/// A structural change of a `World`, published to `WorldEvents`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum WorldEvent {
+    /// An entity has been created. Published by `WorldExt::maintain` for
+    /// the entities created since the previous `maintain`, atomically or not.
+    EntityCreated(Entity),
+    /// An entity has been deleted. Published by `WorldExt::maintain` for the
+    /// entities deleted since the previous `maintain`, atomically or not.
+    EntityDeleted(Entity),
+    /// The storage of a component has been added, either with
+    /// `WorldExt::register` or by the setup of `ReadStorage` and
+    /// `WriteStorage`.
+    ComponentRegistered {
+        /// The type id of the component.
+        type_id: TypeId,
+        /// The type name of the component.
+        name: &'static str,
+    },
+}
+
+/// The channel the `World` publishes its `WorldEvent`s to, so tools and
+/// systems can observe structural changes in one place.
+///
+/// It is added by `WorldExt::new`. Events are only written while the channel
+/// has readers. Requires the `tracking` feature.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     world::{WorldEvent, WorldEvents},
+/// };
+///
+/// let mut world = World::new();
+/// let mut reader = world.write_resource::<WorldEvents>().register_reader();
+///
+/// let e = world.create_entity().build();
+/// world.maintain();
+/// world.delete_entity(e).unwrap();
+/// world.maintain();
+///
+/// let events: Vec<_> = world
+///     .read_resource::<WorldEvents>()
+///     .read(&mut reader)
+///     .cloned()
+///     .collect();
+/// assert_eq!(
+///     events,
+///     [WorldEvent::EntityCreated(e), WorldEvent::EntityDeleted(e)]
+/// );
+/// ```
+#[cfg(feature = "tracking")]
+pub type WorldEvents = EventChannel<WorldEvent>;
+
+/// Writes `events` to the `WorldEvents` of `world`, if it has a reader.
+#[cfg(feature = "tracking")]
+pub(crate) fn publish<I>(world: &World, events: I)
+where
+    I: IntoIterator<Item = WorldEvent>,
+    I::IntoIter: ExactSizeIterator,
+{
+    if let Some(mut channel) = world.try_fetch_mut::<WorldEvents>() {
+        if channel.would_write() {
+            channel.iter_write(events);
+        }
+    }
+}
+
+/// Drops `events`, there is no `WorldEvents` channel without the `tracking`
+/// feature.
+#[cfg(not(feature = "tracking"))]
+pub(crate) fn publish<I>(_world: &World, _events: I)
+where
+    I: IntoIterator<Item = WorldEvent>,
+    I::IntoIter: ExactSizeIterator,
+{
+}
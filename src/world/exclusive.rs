@@ -0,0 +1,212 @@
+use std::{
+    mem,
+    sync::{Arc, Mutex},
+};
+
+use shred::{Dispatcher, DispatcherBuilder, RunNow, System, World};
+
+/// A system that requires exclusive access to the `World`.
+///
+/// Exclusive systems can do everything that would otherwise require
+/// out-of-band code between two dispatches, like deleting entities,
+/// calling `World::maintain` or adding and removing resources.
+///
+/// They are added to a dispatcher with
+/// [`ExclusiveDispatcherBuilder::with_exclusive`] and run by
+/// [`ExclusiveDispatcher::dispatch_exclusive`].
+#[cfg(feature = "parallel")]
+pub trait ExclusiveSystem: Send + 'static {
+    /// Runs the system with exclusive access to the `World`.
+    fn run(&mut self, world: &mut World);
+
+    /// Sets up the `World`, called by `Dispatcher::setup`.
+    ///
+    /// Does nothing by default.
+    fn setup(&mut self, world: &mut World) {
+        let _ = world;
+    }
+}
+
+/// A system that requires exclusive access to the `World`.
+///
+/// Exclusive systems can do everything that would otherwise require
+/// out-of-band code between two dispatches, like deleting entities,
+/// calling `World::maintain` or adding and removing resources.
+///
+/// They are added to a dispatcher with
+/// [`ExclusiveDispatcherBuilder::with_exclusive`] and run by
+/// [`ExclusiveDispatcher::dispatch_exclusive`].
+#[cfg(not(feature = "parallel"))]
+pub trait ExclusiveSystem: 'static {
+    /// Runs the system with exclusive access to the `World`.
+    fn run(&mut self, world: &mut World);
+
+    /// Sets up the `World`, called by `Dispatcher::setup`.
+    ///
+    /// Does nothing by default.
+    fn setup(&mut self, world: &mut World) {
+        let _ = world;
+    }
+}
+
+/// Extension trait for shred's `DispatcherBuilder` to add
+/// [`ExclusiveSystem`]s.
+///
+/// Since a dispatch only has shared access to the `World`, exclusive systems
+/// can't run inside of it. Instead, they are scheduled like thread-local
+/// systems, after all parallel stages, where every dispatch queues them.
+/// [`ExclusiveDispatcher::dispatch_exclusive`] then runs the queued systems
+/// right after the dispatch, in the order they were added.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::prelude::*;
+///
+/// struct Health(i32);
+///
+/// impl Component for Health {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Reaper;
+///
+/// impl ExclusiveSystem for Reaper {
+///     fn run(&mut self, world: &mut World) {
+///         let dead: Vec<_> = (&world.entities(), &world.read_storage::<Health>())
+///             .join()
+///             .filter(|(_, health)| health.0 <= 0)
+///             .map(|(entity, _)| entity)
+///             .collect();
+///         world.delete_entities(&dead).unwrap();
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Health>();
+/// world.create_entity().with(Health(0)).build();
+/// world.create_entity().with(Health(3)).build();
+///
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with_exclusive(Reaper, "reaper", &[])
+///     .build();
+/// dispatcher.dispatch_exclusive(&mut world);
+///
+/// assert_eq!(world.read_storage::<Health>().count(), 1);
+/// ```
+pub trait ExclusiveDispatcherBuilder {
+    /// Adds a new exclusive system with a given name and a list of
+    /// dependencies.
+    ///
+    /// Same as [`ExclusiveDispatcherBuilder::add_exclusive`], but returns
+    /// `self` to enable method chaining.
+    fn with_exclusive<S: ExclusiveSystem>(self, system: S, name: &str, dep: &[&str]) -> Self;
+
+    /// Adds a new exclusive system with a given name and a list of
+    /// dependencies.
+    ///
+    /// The name and dependencies are checked like the ones of
+    /// `DispatcherBuilder::add`. Exclusive systems run after all other
+    /// systems, so their dependencies are always met, but systems depending
+    /// on an exclusive system don't wait for it.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if a dependency has not been added, or if the name is already
+    /// taken.
+    fn add_exclusive<S: ExclusiveSystem>(&mut self, system: S, name: &str, dep: &[&str]);
+}
+
+impl<'a, 'b> ExclusiveDispatcherBuilder for DispatcherBuilder<'a, 'b> {
+    fn with_exclusive<S: ExclusiveSystem>(mut self, system: S, name: &str, dep: &[&str]) -> Self {
+        self.add_exclusive(system, name, dep);
+
+        self
+    }
+
+    fn add_exclusive<S: ExclusiveSystem>(&mut self, system: S, name: &str, dep: &[&str]) {
+        self.add(ExclusiveMarker, name, dep);
+        self.add_thread_local(ExclusiveRunner {
+            name: name.into(),
+            system: Arc::new(Mutex::new(system)),
+        });
+    }
+}
+
+/// Extension trait for shred's `Dispatcher` to run its
+/// [`ExclusiveSystem`]s.
+pub trait ExclusiveDispatcher {
+    /// Dispatches all systems like `Dispatcher::dispatch`, then runs the
+    /// exclusive systems with mutable access to the `World`, in the order
+    /// they were added.
+    ///
+    /// `Dispatcher::dispatch` only queues the exclusive systems, a queued
+    /// system runs once at the next call to this method.
+    fn dispatch_exclusive(&mut self, world: &mut World);
+}
+
+impl<'a, 'b> ExclusiveDispatcher for Dispatcher<'a, 'b> {
+    fn dispatch_exclusive(&mut self, world: &mut World) {
+        self.dispatch(world);
+
+        let queued = match world.get_mut::<ExclusiveQueue>() {
+            Some(queue) => mem::take(&mut queue.0),
+            None => return,
+        };
+        for (name, system) in queued {
+            system
+                .lock()
+                .unwrap_or_else(|_| {
+                    panic!("Exclusive system `{}` panicked during a previous run", name)
+                })
+                .run(world);
+        }
+    }
+}
+
+/// Empty system registering the name and dependencies of an exclusive system
+/// with the `DispatcherBuilder`, which checks them.
+struct ExclusiveMarker;
+
+impl<'a> System<'a> for ExclusiveMarker {
+    type SystemData = ();
+
+    fn run(&mut self, _: Self::SystemData) {}
+}
+
+/// The exclusive systems queued by the dispatches since the last
+/// `ExclusiveDispatcher::dispatch_exclusive`.
+#[derive(Default)]
+pub(crate) struct ExclusiveQueue(Vec<(Arc<str>, SharedSystem)>);
+
+type SharedSystem = Arc<Mutex<dyn ExclusiveSystem>>;
+
+/// Thread-local system queueing an `ExclusiveSystem` on the
+/// `ExclusiveQueue`.
+struct ExclusiveRunner<S> {
+    name: Arc<str>,
+    system: Arc<Mutex<S>>,
+}
+
+impl<'a, S: ExclusiveSystem> RunNow<'a> for ExclusiveRunner<S> {
+    fn run_now(&mut self, world: &'a World) {
+        let system: SharedSystem = self.system.clone();
+        let mut queue = world.fetch_mut::<ExclusiveQueue>();
+        if !queue.0.iter().any(|(_, queued)| Arc::ptr_eq(queued, &system)) {
+            queue.0.push((self.name.clone(), system));
+        }
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        world.entry::<ExclusiveQueue>().or_insert_with(Default::default);
+        self.system
+            .lock()
+            .unwrap_or_else(|_| {
+                panic!(
+                    "Exclusive system `{}` panicked during a previous run",
+                    self.name
+                )
+            })
+            .setup(world);
+    }
+}
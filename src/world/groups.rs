@@ -0,0 +1,200 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+use ahash::AHashMap as HashMap;
+use shred::{DispatcherBuilder, RunningTime, System, World};
+
+/// The groups of systems added with [`GroupDispatcherBuilder::with_group`],
+/// by name, allowing to enable and disable them at runtime.
+///
+/// The systems of a disabled group are skipped by the dispatcher, without
+/// rebuilding it. Systems depending on them still wait for them as usual.
+/// Groups are enabled when they are created.
+///
+/// This is inserted by `Dispatcher::setup`.
+#[derive(Debug, Default)]
+pub struct SystemGroups {
+    groups: HashMap<String, Arc<AtomicBool>>,
+}
+
+impl SystemGroups {
+    /// Enables the group `name`, returning `false` if there's no such group.
+    pub fn enable_group(&mut self, name: &str) -> bool {
+        self.set_enabled(name, true)
+    }
+
+    /// Disables the group `name`, returning `false` if there's no such
+    /// group.
+    pub fn disable_group(&mut self, name: &str) -> bool {
+        self.set_enabled(name, false)
+    }
+
+    /// Returns `true` if the group `name` is enabled, `None` if there's no
+    /// such group.
+    pub fn is_group_enabled(&self, name: &str) -> Option<bool> {
+        self.groups
+            .get(name)
+            .map(|enabled| enabled.load(Ordering::Relaxed))
+    }
+
+    /// Returns the names of the groups.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.groups.keys().map(String::as_str)
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.groups.get(name) {
+            Some(flag) => {
+                flag.store(enabled, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Extension trait for shred's `DispatcherBuilder` to add systems in named
+/// groups, which can be enabled and disabled at runtime with
+/// [`SystemGroups`].
+///
+/// This allows toggling subsystems depending on the platform or the
+/// settings, like audio on a server, without building different dispatchers.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::SystemGroups};
+///
+/// #[derive(Default)]
+/// struct Played(u32);
+///
+/// struct PlaySounds;
+///
+/// impl<'a> System<'a> for PlaySounds {
+///     type SystemData = Write<'a, Played>;
+///
+///     fn run(&mut self, mut played: Self::SystemData) {
+///         played.0 += 1;
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with_group("audio", |group| {
+///         group.with(PlaySounds, "play_sounds", &[]);
+///     })
+///     .build();
+/// dispatcher.setup(&mut world);
+///
+/// dispatcher.dispatch(&world);
+/// world.write_resource::<SystemGroups>().disable_group("audio");
+/// dispatcher.dispatch(&world);
+/// assert_eq!(world.read_resource::<Played>().0, 1);
+/// ```
+pub trait GroupDispatcherBuilder<'a, 'b> {
+    /// Adds the systems added to the group by `f`.
+    ///
+    /// Same as [`GroupDispatcherBuilder::add_group`], but returns `self` to
+    /// enable method chaining.
+    fn with_group<F>(self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(&mut SystemGroup<'_, 'a, 'b>);
+
+    /// Adds the systems added to the group by `f`.
+    ///
+    /// Adding systems to a group with the same name again extends it.
+    fn add_group<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(&mut SystemGroup<'_, 'a, 'b>);
+}
+
+impl<'a, 'b> GroupDispatcherBuilder<'a, 'b> for DispatcherBuilder<'a, 'b> {
+    fn with_group<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnOnce(&mut SystemGroup<'_, 'a, 'b>),
+    {
+        self.add_group(name, f);
+
+        self
+    }
+
+    fn add_group<F>(&mut self, name: &str, f: F)
+    where
+        F: FnOnce(&mut SystemGroup<'_, 'a, 'b>),
+    {
+        f(&mut SystemGroup {
+            builder: self,
+            name: name.to_owned(),
+            enabled: Arc::new(AtomicBool::new(true)),
+        });
+    }
+}
+
+/// A group of systems being added to a `DispatcherBuilder`, see
+/// [`GroupDispatcherBuilder::with_group`].
+pub struct SystemGroup<'g, 'a, 'b> {
+    builder: &'g mut DispatcherBuilder<'a, 'b>,
+    name: String,
+    enabled: Arc<AtomicBool>,
+}
+
+impl<'g, 'a, 'b> SystemGroup<'g, 'a, 'b> {
+    /// Returns the name of the group.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Adds a new system to the group, like `DispatcherBuilder::add`.
+    pub fn with<S>(&mut self, system: S, name: &str, dep: &[&str]) -> &mut Self
+    where
+        S: for<'c> System<'c> + Send + 'a,
+    {
+        let system = Grouped {
+            group: self.name.clone(),
+            enabled: self.enabled.clone(),
+            system,
+        };
+        self.builder.add(system, name, dep);
+
+        self
+    }
+}
+
+/// A system which is skipped while its group is disabled.
+struct Grouped<S> {
+    group: String,
+    /// The flag of the group, shared with the `SystemGroups` during setup.
+    enabled: Arc<AtomicBool>,
+    system: S,
+}
+
+impl<'c, S> System<'c> for Grouped<S>
+where
+    S: for<'d> System<'d>,
+{
+    type SystemData = <S as System<'c>>::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        if self.enabled.load(Ordering::Relaxed) {
+            self.system.run(data);
+        }
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        self.system.setup(world);
+        // Groups added more than once share the flag of the first one.
+        self.enabled = world
+            .entry::<SystemGroups>()
+            .or_insert_with(Default::default)
+            .groups
+            .entry(self.group.clone())
+            .or_insert_with(|| self.enabled.clone())
+            .clone();
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::VecDeque;
+#[cfg(feature = "tracking")]
+use std::mem;
+
+#[cfg(feature = "tracking")]
+use ahash::AHashMap as HashMap;
+#[cfg(feature = "tracking")]
+use hibitset::BitSet;
+use shred::World;
+#[cfg(feature = "tracking")]
+use shrev::ReaderId;
+
+use super::{clone::CloneRegistry, Component, ComponentFilter, Entity, WorldExt};
+#[cfg(feature = "tracking")]
+use super::Index;
+use crate::join::Join;
+#[cfg(feature = "tracking")]
+use crate::storage::{ComponentEvent, Tracked};
+
+/// A ring buffer of snapshots of the components of a `World`, to step back
+/// a few frames when debugging desyncs or gameplay bugs.
+///
+/// Only components registered with `WorldExt::register_cloneable` and
+/// included by the filter of the history are captured, so e.g. large render
+/// data can be left out with `ComponentFilter::without`.
+///
+/// Snapshots are full copies of the components, except for the ones of
+/// tracked storages opted in with `History::track`, which are captured as
+/// diffs read from their `ComponentEvent`s, so only the components changed
+/// since the last capture are cloned.
+///
+/// Snapshots only contain components, entities are not restored: entities
+/// deleted since a snapshot stay deleted, and entities created since keep
+/// living without the captured components. Resources are not captured
+/// either.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::History};
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Pos(i32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register_cloneable::<Pos>();
+/// let e = world.create_entity().with(Pos(0)).build();
+///
+/// let mut history = History::new(16);
+/// for frame in 1..=5 {
+///     world.write_storage::<Pos>().get_mut(e).unwrap().0 = frame;
+///     history.capture(&world);
+/// }
+///
+/// assert!(history.step_back(&mut world, 2));
+/// assert_eq!(world.read_storage::<Pos>().get(e), Some(&Pos(3)));
+/// assert_eq!(history.len(), 3);
+/// ```
+pub struct History {
+    capacity: usize,
+    filter: ComponentFilter,
+    snapshots: VecDeque<Vec<Box<dyn Captured>>>,
+    #[cfg(feature = "tracking")]
+    tracked: Vec<Box<dyn Diffed>>,
+}
+
+impl History {
+    /// Creates a history keeping the last `capacity` snapshots of all
+    /// cloneable components.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        History::with_filter(capacity, ComponentFilter::all())
+    }
+
+    /// Creates a history keeping the last `capacity` snapshots of the
+    /// cloneable components included by `filter`.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_filter(capacity: usize, filter: ComponentFilter) -> Self {
+        assert!(capacity > 0, "a history needs room for one snapshot");
+
+        History {
+            capacity,
+            filter,
+            snapshots: VecDeque::with_capacity(capacity),
+            #[cfg(feature = "tracking")]
+            tracked: Vec::new(),
+        }
+    }
+
+    /// Captures the components `T` as diffs, cloning only the components
+    /// changed since the last capture, as reported by the events of their
+    /// tracked storage. `T` is captured even if the filter of the history
+    /// excludes it.
+    ///
+    /// The current components are copied once, and the history is cleared.
+    #[cfg(feature = "tracking")]
+    pub fn track<T>(&mut self, world: &World)
+    where
+        T: Component + Clone,
+        T::Storage: Tracked,
+    {
+        self.filter = mem::take(&mut self.filter).without::<T>();
+        self.tracked.push(Box::new(DiffedComponents::<T>::new(world)));
+        self.clear();
+    }
+
+    /// Returns the maximum number of snapshots kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of snapshots.
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Returns `true` if no snapshot has been captured.
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    /// Discards all snapshots.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        #[cfg(feature = "tracking")]
+        for tracked in &mut self.tracked {
+            tracked.clear();
+        }
+    }
+
+    /// Captures a snapshot of the components, usually once per frame after
+    /// `World::maintain`, dropping the oldest one if the history is full.
+    pub fn capture(&mut self, world: &World) {
+        let snapshot = world
+            .try_fetch::<CloneRegistry>()
+            .map_or_else(Vec::new, |registry| registry.capture(world, &self.filter));
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+            #[cfg(feature = "tracking")]
+            for tracked in &mut self.tracked {
+                tracked.pop_front();
+            }
+        }
+        self.snapshots.push_back(snapshot);
+        #[cfg(feature = "tracking")]
+        for tracked in &mut self.tracked {
+            tracked.capture(world);
+        }
+    }
+
+    /// Restores the components to the snapshot captured `n` snapshots before
+    /// the last one, discarding the newer snapshots, so capturing again
+    /// continues the history from there.
+    ///
+    /// `step_back(world, 0)` resets the components to the last snapshot.
+    /// Returns `false` and does nothing if there are not more than `n`
+    /// snapshots.
+    pub fn step_back(&mut self, world: &mut World, n: usize) -> bool {
+        if n >= self.snapshots.len() {
+            return false;
+        }
+
+        self.snapshots.truncate(self.snapshots.len() - n);
+        for captured in self.snapshots.back().into_iter().flatten() {
+            captured.restore(world);
+        }
+        #[cfg(feature = "tracking")]
+        for tracked in &mut self.tracked {
+            tracked.step_back(world, n);
+        }
+
+        true
+    }
+}
+
+/// The components of one type captured by a `History`.
+pub(crate) trait Captured {
+    /// Replaces the components in the storage with the captured ones.
+    fn restore(&self, world: &World);
+}
+
+struct CapturedComponents<T>(Vec<(Entity, T)>);
+
+impl<T> Captured for CapturedComponents<T>
+where
+    T: Component + Clone,
+{
+    fn restore(&self, world: &World) {
+        let mut storage = world.write_storage::<T>();
+        storage.clear();
+        for (entity, component) in &self.0 {
+            // Entities deleted since the snapshot are skipped.
+            let _ = storage.insert(*entity, component.clone());
+        }
+    }
+}
+
+pub(crate) fn capture_components<T>(world: &World) -> Box<dyn Captured>
+where
+    T: Component + Clone,
+{
+    let entities = world.entities();
+    let storage = world.read_storage::<T>();
+    let components = (&entities, &storage)
+        .join()
+        .map(|(entity, component)| (entity, component.clone()))
+        .collect();
+
+    Box::new(CapturedComponents(components))
+}
+
+/// The components of one tracked storage captured as diffs by a `History`.
+#[cfg(feature = "tracking")]
+trait Diffed {
+    /// Captures the components changed since the last capture.
+    fn capture(&mut self, world: &World);
+
+    /// Forgets the oldest snapshot.
+    fn pop_front(&mut self);
+
+    /// Forgets all snapshots.
+    fn clear(&mut self);
+
+    /// Restores the components of the snapshot captured `n` snapshots before
+    /// the last one, forgetting the newer snapshots.
+    fn step_back(&mut self, world: &World, n: usize);
+}
+
+#[cfg(feature = "tracking")]
+struct DiffedComponents<T> {
+    reader: ReaderId<ComponentEvent>,
+    /// The components as of the last capture.
+    current: HashMap<Index, (Entity, T)>,
+    /// For each snapshot, the components it changed with their values in the
+    /// previous snapshot. The changes of the oldest snapshot are not used.
+    undo: VecDeque<Vec<Undo<T>>>,
+}
+
+/// The index of a changed component with its previous value, if any.
+#[cfg(feature = "tracking")]
+type Undo<T> = (Index, Option<(Entity, T)>);
+
+#[cfg(feature = "tracking")]
+impl<T> DiffedComponents<T>
+where
+    T: Component + Clone,
+    T::Storage: Tracked,
+{
+    fn new(world: &World) -> Self {
+        let mut storage = world.write_storage::<T>();
+        let reader = storage.register_reader();
+        let current = (&world.entities(), &storage)
+            .join()
+            .map(|(entity, component)| (entity.id(), (entity, component.clone())))
+            .collect();
+
+        DiffedComponents {
+            reader,
+            current,
+            undo: VecDeque::new(),
+        }
+    }
+
+    /// Returns the indices of the components changed since the last call.
+    fn changed(&mut self, world: &World) -> BitSet {
+        world
+            .read_storage::<T>()
+            .channel()
+            .read(&mut self.reader)
+            .map(|event| match *event {
+                ComponentEvent::Inserted(id)
+                | ComponentEvent::Modified(id)
+                | ComponentEvent::Removed(id)
+                | ComponentEvent::Loaded(id) => id,
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "tracking")]
+impl<T> Diffed for DiffedComponents<T>
+where
+    T: Component + Clone,
+    T::Storage: Tracked,
+{
+    fn capture(&mut self, world: &World) {
+        let changed = self.changed(world);
+        let entities = world.entities();
+        let storage = world.read_storage::<T>();
+        let undo = (&changed)
+            .join()
+            .map(|id| {
+                let entity = entities.entity(id);
+                let previous = match storage.get(entity) {
+                    Some(component) => self.current.insert(id, (entity, component.clone())),
+                    None => self.current.remove(&id),
+                };
+                (id, previous)
+            })
+            .collect();
+        self.undo.push_back(undo);
+    }
+
+    fn pop_front(&mut self) {
+        self.undo.pop_front();
+    }
+
+    fn clear(&mut self) {
+        self.undo.clear();
+    }
+
+    fn step_back(&mut self, world: &World, n: usize) {
+        // Components changed since the last capture are reset as well.
+        let mut reset = self.changed(world);
+        for undo in self.undo.drain(self.undo.len() - n..).rev() {
+            for (id, previous) in undo {
+                reset.add(id);
+                match previous {
+                    Some(previous) => self.current.insert(id, previous),
+                    None => self.current.remove(&id),
+                };
+            }
+        }
+
+        let mut storage = world.write_storage::<T>();
+        for id in (&reset).join() {
+            match self.current.get(&id) {
+                // Entities deleted since the snapshot are skipped.
+                Some((entity, component)) => {
+                    let _ = storage.insert(*entity, component.clone());
+                }
+                None => {
+                    storage.remove(world.entities().entity(id));
+                }
+            }
+        }
+        // Skip the events of the restoration.
+        storage.channel().read(&mut self.reader).for_each(drop);
+    }
+}
@@ -0,0 +1,167 @@
+use std::any::{type_name, Any, TypeId};
+
+use shred::World;
+
+use super::{Component, Entity, WorldExt};
+use crate::storage::AccessMut;
+
+/// Reflection of the fields of a component, for debug UIs showing and
+/// editing components without code for every type.
+///
+/// This can be derived with `#[derive(Inspect)]` (requires the
+/// `specs-derive` feature), which visits every named or tuple field of a
+/// struct in order. Fields marked with `#[inspect(skip)]` are left out. The
+/// derive requires `Inspect`, `InspectVisitor` and `InspectVisitorMut` to be
+/// in scope.
+///
+/// Components registered with `WorldExt::register_inspectable` can be walked
+/// with `WorldExt::inspect_entity` and `WorldExt::inspect_entity_mut`.
+///
+/// ## Examples
+///
+/// ```
+/// use std::any::Any;
+///
+/// use specs::{
+///     prelude::*,
+///     world::{Inspect, InspectVisitor, InspectVisitorMut},
+/// };
+///
+/// struct Health {
+///     current: u32,
+///     max: u32,
+/// }
+///
+/// impl Component for Health {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// // What `#[derive(Inspect)]` generates.
+/// impl Inspect for Health {
+///     fn inspect(&self, visitor: &mut dyn InspectVisitor) {
+///         visitor.field("current", &self.current);
+///         visitor.field("max", &self.max);
+///     }
+///
+///     fn inspect_mut(&mut self, visitor: &mut dyn InspectVisitorMut) {
+///         visitor.field("current", &mut self.current);
+///         visitor.field("max", &mut self.max);
+///     }
+/// }
+///
+/// /// Prints `u32` fields and ignores the others.
+/// struct Printer(Vec<String>);
+///
+/// impl InspectVisitor for Printer {
+///     fn component(&mut self, name: &'static str) {
+///         self.0.push(name.rsplit("::").next().unwrap().to_owned());
+///     }
+///
+///     fn field(&mut self, name: &'static str, value: &dyn Any) {
+///         if let Some(value) = value.downcast_ref::<u32>() {
+///             self.0.push(format!("{}: {}", name, value));
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register_inspectable::<Health>();
+/// let e = world
+///     .create_entity()
+///     .with(Health { current: 3, max: 5 })
+///     .build();
+///
+/// let mut printer = Printer(vec![]);
+/// world.inspect_entity(e, &mut printer);
+/// assert_eq!(printer.0, ["Health", "current: 3", "max: 5"]);
+/// ```
+pub trait Inspect {
+    /// Visits the fields of `self`.
+    fn inspect(&self, visitor: &mut dyn InspectVisitor);
+
+    /// Visits the fields of `self` mutably.
+    fn inspect_mut(&mut self, visitor: &mut dyn InspectVisitorMut);
+}
+
+/// Receives the fields visited by `Inspect::inspect`.
+pub trait InspectVisitor {
+    /// Called by `WorldExt::inspect_entity` before the fields of a component
+    /// are visited, with the type name of the component.
+    fn component(&mut self, name: &'static str) {
+        let _ = name;
+    }
+
+    /// Visits a field. Downcast `value` to the types that can be displayed.
+    fn field(&mut self, name: &'static str, value: &dyn Any);
+}
+
+/// Receives the fields visited by `Inspect::inspect_mut`.
+pub trait InspectVisitorMut {
+    /// Called by `WorldExt::inspect_entity_mut` before the fields of a
+    /// component are visited, with the type name of the component.
+    fn component(&mut self, name: &'static str) {
+        let _ = name;
+    }
+
+    /// Visits a field. Downcast `value` to the types that can be edited.
+    fn field(&mut self, name: &'static str, value: &mut dyn Any);
+}
+
+/// The components registered with `WorldExt::register_inspectable`.
+#[derive(Default)]
+pub(crate) struct InspectRegistry(Vec<(TypeId, InspectComponent, InspectComponentMut)>);
+
+type InspectComponent = fn(&World, Entity, &mut dyn InspectVisitor);
+type InspectComponentMut = fn(&World, Entity, &mut dyn InspectVisitorMut);
+
+impl InspectRegistry {
+    pub(crate) fn register<T>(&mut self)
+    where
+        T: Component + Inspect,
+    {
+        if !self.0.iter().any(|(ty, ..)| *ty == TypeId::of::<T>()) {
+            self.0.push((
+                TypeId::of::<T>(),
+                inspect_component::<T>,
+                inspect_component_mut::<T>,
+            ));
+        }
+    }
+
+    pub(crate) fn inspect(&self, world: &World, entity: Entity, visitor: &mut dyn InspectVisitor) {
+        for (_, inspect, _) in &self.0 {
+            inspect(world, entity, visitor);
+        }
+    }
+
+    pub(crate) fn inspect_mut(
+        &self,
+        world: &World,
+        entity: Entity,
+        visitor: &mut dyn InspectVisitorMut,
+    ) {
+        for (_, _, inspect) in &self.0 {
+            inspect(world, entity, visitor);
+        }
+    }
+}
+
+fn inspect_component<T>(world: &World, entity: Entity, visitor: &mut dyn InspectVisitor)
+where
+    T: Component + Inspect,
+{
+    if let Some(component) = world.read_storage::<T>().get(entity) {
+        visitor.component(type_name::<T>());
+        component.inspect(visitor);
+    }
+}
+
+fn inspect_component_mut<T>(world: &World, entity: Entity, visitor: &mut dyn InspectVisitorMut)
+where
+    T: Component + Inspect,
+{
+    if let Some(mut component) = world.write_storage::<T>().get_mut(entity) {
+        visitor.component(type_name::<T>());
+        component.access_mut().inspect_mut(visitor);
+    }
+}
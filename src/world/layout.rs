@@ -0,0 +1,208 @@
+use std::fmt::{Display, Formatter, Result as FmtResult};
+
+use ahash::AHashMap as HashMap;
+use shred::World;
+
+use super::{Index, WorldExt};
+use crate::{join::Join, storage::StorageRegistry};
+
+/// The maximum number of entities whose component sets are compared to find
+/// group candidates.
+const SAMPLE_SIZE: usize = 4096;
+
+/// The share of entities from which `VecStorage` is suggested.
+const VEC_OCCUPANCY: f64 = 0.5;
+
+/// The share of entities from which `DenseVecStorage` is suggested, rather
+/// than `HashMapStorage`.
+const DENSE_OCCUPANCY: f64 = 0.01;
+
+/// The share of sampled entities a component set needs to be reported as a
+/// group candidate.
+const GROUP_SHARE: f64 = 0.05;
+
+/// A report on how the components of a `World` are spread over its
+/// entities, returned by `WorldExt::analyze_layout`.
+///
+/// Its `Display` implementation lists the suggestions in a human readable
+/// way, while tools can serialize it with the `serde` feature.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutReport {
+    /// The number of alive entities.
+    pub entities: usize,
+    /// The number of entities whose component sets were compared.
+    pub sampled: usize,
+    /// The registered components, in registration order.
+    pub components: Vec<ComponentLayout>,
+    /// The sets of components found together on many of the sampled
+    /// entities, most common first.
+    pub groups: Vec<GroupCandidate>,
+}
+
+/// The share of entities having a component, and the storage suggested for
+/// it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComponentLayout {
+    /// The type name of the component.
+    pub component: String,
+    /// The type name of the storage the component uses.
+    pub storage: String,
+    /// The number of alive entities having the component.
+    pub count: usize,
+    /// The share of alive entities having the component, between 0 and 1.
+    pub occupancy: f64,
+    /// The storage suggested for the component.
+    pub suggestion: StorageSuggestion,
+}
+
+/// A storage suggested by `WorldExt::analyze_layout`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StorageSuggestion {
+    /// `NullStorage`, for zero sized components.
+    Null,
+    /// `VecStorage`, for components most entities have.
+    Vec,
+    /// `DenseVecStorage`, for components a fair share of entities have.
+    DenseVec,
+    /// `HashMapStorage`, for components few entities have.
+    HashMap,
+}
+
+impl Display for StorageSuggestion {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(match *self {
+            StorageSuggestion::Null => "NullStorage",
+            StorageSuggestion::Vec => "VecStorage",
+            StorageSuggestion::DenseVec => "DenseVecStorage",
+            StorageSuggestion::HashMap => "HashMapStorage",
+        })
+    }
+}
+
+/// A set of components found together on many entities, which could be
+/// joined or stored together.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupCandidate {
+    /// The type names of the components.
+    pub components: Vec<String>,
+    /// The number of sampled entities having exactly these components.
+    pub count: usize,
+    /// The share of sampled entities having exactly these components.
+    pub share: f64,
+}
+
+impl Display for LayoutReport {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for component in &self.components {
+            writeln!(
+                f,
+                "{} is on {:.1}% of entities -> {} (uses {})",
+                component.component,
+                component.occupancy * 100.0,
+                component.suggestion,
+                component.storage,
+            )?;
+        }
+        for group in &self.groups {
+            writeln!(
+                f,
+                "({}) are together on {:.1}% of sampled entities",
+                group.components.join(", "),
+                group.share * 100.0,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Analyzes the layout of `world`, see `WorldExt::analyze_layout`.
+pub(crate) fn analyze(world: &World) -> LayoutReport {
+    let entities = world.entities();
+    let ids: Vec<Index> = entities.join().map(|e| e.id()).collect();
+    let registry = match world.try_fetch::<StorageRegistry>() {
+        Some(registry) => registry,
+        None => {
+            return LayoutReport {
+                entities: ids.len(),
+                sampled: 0,
+                components: Vec::new(),
+                groups: Vec::new(),
+            }
+        }
+    };
+
+    let masks: Vec<_> = registry.iter().map(|acc| acc.mask(world)).collect();
+    let components = registry
+        .iter()
+        .zip(&masks)
+        .map(|(accessor, mask)| {
+            let count = (&entities, mask).join().count();
+            let occupancy = share(count, ids.len());
+            let suggestion = if accessor.component_size() == 0 {
+                StorageSuggestion::Null
+            } else if occupancy >= VEC_OCCUPANCY {
+                StorageSuggestion::Vec
+            } else if occupancy >= DENSE_OCCUPANCY {
+                StorageSuggestion::DenseVec
+            } else {
+                StorageSuggestion::HashMap
+            };
+
+            ComponentLayout {
+                component: accessor.type_name().to_owned(),
+                storage: accessor.storage_type_name().to_owned(),
+                count,
+                occupancy,
+                suggestion,
+            }
+        })
+        .collect();
+
+    let stride = ((ids.len() + SAMPLE_SIZE - 1) / SAMPLE_SIZE).max(1);
+    let mut sets: HashMap<Vec<usize>, usize> = HashMap::new();
+    let mut sampled = 0;
+    for &id in ids.iter().step_by(stride) {
+        let set: Vec<usize> = (0..masks.len())
+            .filter(|&i| masks[i].contains(id))
+            .collect();
+        *sets.entry(set).or_default() += 1;
+        sampled += 1;
+    }
+
+    let names: Vec<_> = registry.iter().map(|acc| acc.type_name()).collect();
+    let mut groups: Vec<_> = sets
+        .into_iter()
+        .filter(|(set, count)| set.len() >= 2 && share(*count, sampled) >= GROUP_SHARE)
+        .map(|(set, count)| GroupCandidate {
+            components: set.iter().map(|&i| names[i].to_owned()).collect(),
+            count,
+            share: share(count, sampled),
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.components.cmp(&b.components))
+    });
+
+    LayoutReport {
+        entities: ids.len(),
+        sampled,
+        components,
+        groups,
+    }
+}
+
+/// Returns `count` as a share of `total`, 0 if `total` is.
+fn share(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
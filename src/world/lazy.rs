@@ -1,7 +1,21 @@
+use ahash::AHashMap as HashMap;
 use crossbeam_queue::SegQueue;
+#[cfg(feature = "parallel")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
-use crate::{prelude::*, world::EntitiesRes};
-use std::sync::Arc;
+#[cfg(feature = "parallel")]
+use crate::storage::{AnyStorage, MaskedStorage};
+use crate::{
+    error::{Error, InsertError},
+    prelude::*,
+    world::{ComponentGroup, EntitiesRes},
+};
+use std::{
+    any::{Any, TypeId},
+    cmp::Reverse,
+    iter,
+    sync::Arc,
+};
 
 struct Queue<T>(SegQueue<T>);
 
@@ -74,6 +88,100 @@ macro_rules! parallel_feature {
     };
 }
 
+#[cfg(feature = "parallel")]
+type AnyOp = dyn Any + Send + Sync;
+
+#[cfg(not(feature = "parallel"))]
+type AnyOp = dyn Any;
+
+/// A queued lazy update.
+enum Update {
+    /// A closure with world access, executed on its own.
+    Closure(Box<dyn LazyUpdateInternal>),
+    /// An update of a single component, applied in a batch with the other
+    /// updates of its component type.
+    Component(ComponentUpdate),
+}
+
+/// A boxed `ComponentOp` with the functions applying it.
+struct ComponentUpdate {
+    component: TypeId,
+    op: Box<AnyOp>,
+    apply: fn(&World, Vec<Box<AnyOp>>) -> Vec<SkippedLazyUpdate>,
+    #[cfg(feature = "parallel")]
+    is_thread_local: fn(&World) -> bool,
+}
+
+enum ComponentOp<C> {
+    Insert(Entity, C),
+    InsertNew(Entity, C),
+    Remove(Entity),
+}
+
+impl Update {
+    parallel_feature! {
+        fn component<C>(op: ComponentOp<C>) -> Self
+        where
+            C: Component,
+        {
+            Update::Component(ComponentUpdate {
+                component: TypeId::of::<C>(),
+                op: Box::new(op),
+                apply: apply_ops::<C>,
+                #[cfg(feature = "parallel")]
+                is_thread_local: |world| {
+                    world
+                        .try_fetch::<MaskedStorage<C>>()
+                        .is_some_and(|storage| storage.is_thread_local())
+                },
+            })
+        }
+    }
+}
+
+/// The updates of one component type, in the order they were requested.
+struct Batch {
+    ops: Vec<Box<AnyOp>>,
+    apply: fn(&World, Vec<Box<AnyOp>>) -> Vec<SkippedLazyUpdate>,
+    #[cfg(feature = "parallel")]
+    is_thread_local: fn(&World) -> bool,
+}
+
+impl Batch {
+    fn apply(self, world: &World) -> Vec<SkippedLazyUpdate> {
+        (self.apply)(world, self.ops)
+    }
+}
+
+/// Applies the `ComponentOp`s of `C`, returning the ones skipped because
+/// their entity was dead.
+fn apply_ops<C: Component>(world: &World, ops: Vec<Box<AnyOp>>) -> Vec<SkippedLazyUpdate> {
+    let mut storage: WriteStorage<C> = SystemData::fetch(world);
+    let mut skipped = Vec::new();
+    for op in ops {
+        let op = op
+            .downcast::<ComponentOp<C>>()
+            .expect("Batches only contain operations of their component");
+        match *op {
+            ComponentOp::Insert(e, c) => insert_or_skip(&mut storage, e, c, &mut skipped),
+            ComponentOp::InsertNew(e, c) => match storage.insert_new(e, c) {
+                Err(InsertError::WrongGeneration { .. }) => {
+                    skipped.push(skip::<C>(e, "insert"));
+                }
+                Err(err) => log::warn!("Lazy insert of new component failed: {}", err),
+                Ok(()) => {}
+            },
+            ComponentOp::Remove(e) => {
+                if storage.try_remove(e).is_err() {
+                    skipped.push(skip::<C>(e, "removal"));
+                }
+            }
+        }
+    }
+
+    skipped
+}
+
 /// Like `EntityBuilder`, but inserts the component
 /// lazily, meaning on `maintain`.
 /// If you need those components to exist immediately,
@@ -96,16 +204,8 @@ impl<'a> Builder for LazyBuilder<'a> {
         where
             C: Component,
         {
-            let entity = self.entity;
-            self.lazy.exec(move |world| {
-                let mut storage: WriteStorage<C> = SystemData::fetch(world);
-                if storage.insert(entity, component).is_err() {
-                    log::warn!(
-                        "Lazy insert of component failed because {:?} was dead.",
-                        entity
-                    );
-                }
-            });
+            let op = ComponentOp::Insert(self.entity, component);
+            self.lazy.push(0, Update::component(op));
 
             self
         }
@@ -145,16 +245,29 @@ where
 /// They work lazily in the sense that they are
 /// dispatched when calling `world.maintain()`.
 ///
-/// Lazy updates are dispatched in the order that they
-/// are requested. Multiple updates sent from one system
-/// may be overridden by updates sent from other systems.
+/// Lazy updates are dispatched by priority, higher priorities
+/// first (see `LazyUpdate::execute_with_priority`). Updates of
+/// the same priority are dispatched in the order that they are
+/// requested, across all systems. Multiple updates sent from one
+/// system may be overridden by updates sent from other systems.
+///
+/// Because systems may run in parallel, the order of updates
+/// requested by different systems is only fixed if the systems
+/// are ordered by dependencies. To reliably run some updates
+/// after others, e.g. deleting entities after inserting their
+/// components, give them a lower priority instead.
+///
+/// Insertions and removals of components, requested with `insert`,
+/// `insert_new`, `remove` or a `LazyBuilder`, are applied in one batch per
+/// component type between the closures, in parallel with the `parallel`
+/// feature. The order of the updates of each component type is kept.
 ///
 /// Please note that the provided methods take `&self`
 /// so there's no need to get `LazyUpdate` mutably.
 /// This resource is added to the world by default.
 #[derive(Default)]
 pub struct LazyUpdate {
-    queue: Arc<Queue<Box<dyn LazyUpdateInternal>>>,
+    queue: Arc<Queue<(i32, Update)>>,
 }
 
 impl LazyUpdate {
@@ -187,12 +300,18 @@ impl LazyUpdate {
         where
             C: Component,
         {
-            self.exec(move |world| {
-                let mut storage: WriteStorage<C> = SystemData::fetch(world);
-                if storage.insert(e, c).is_err() {
-                    log::warn!("Lazy insert of component failed because {:?} was dead.", e);
-                }
-            });
+            self.push(0, Update::component(ComponentOp::Insert(e, c)));
+        }
+
+        /// Lazily inserts a component for an entity, unless it already has
+        /// one by the time the insertion is executed.
+        ///
+        /// See `Storage::insert_new`; failures are logged.
+        pub fn insert_new<C>(&self, e: Entity, c: C)
+        where
+            C: Component,
+        {
+            self.push(0, Update::component(ComponentOp::InsertNew(e, c)));
         }
 
         /// Lazily inserts components for entities.
@@ -228,11 +347,11 @@ impl LazyUpdate {
         {
             self.exec(move |world| {
                 let mut storage: WriteStorage<C> = SystemData::fetch(world);
+                let mut skipped = Vec::new();
                 for (e, c) in iter {
-                    if storage.insert(e, c).is_err() {
-                        log::warn!("Lazy insert of component failed because {:?} was dead.", e);
-                    }
+                    insert_or_skip(&mut storage, e, c, &mut skipped);
                 }
+                report(world, skipped);
             });
         }
 
@@ -265,10 +384,7 @@ impl LazyUpdate {
         where
             C: Component,
         {
-            self.exec(move |world| {
-                let mut storage: WriteStorage<C> = SystemData::fetch(world);
-                storage.remove(e);
-            });
+            self.push(0, Update::component(ComponentOp::Remove::<C>(e)));
         }
 
         /// Lazily executes a closure with world access.
@@ -304,9 +420,7 @@ impl LazyUpdate {
         where
             F: FnOnce(&mut World) + 'static,
         {
-            self.queue
-                .0
-                .push(Box::new(f));
+            self.execute_with_priority(0, f);
         }
 
         /// Lazily executes a closure with mutable world access.
@@ -338,8 +452,85 @@ impl LazyUpdate {
         where
             F: FnOnce(&mut World) + 'static,
         {
-            self.queue.0.push(Box::new(f));
+            self.execute_with_priority(0, f);
         }
+
+        /// Lazily executes a closure with mutable world access, with the
+        /// given priority.
+        ///
+        /// Updates with a higher priority are executed first. All other
+        /// methods of `LazyUpdate` use priority `0`, and updates of the same
+        /// priority are executed in the order they have been requested.
+        ///
+        /// ## Examples
+        ///
+        /// ```
+        /// # use specs::prelude::*;
+        /// #
+        /// struct Pos;
+        ///
+        /// impl Component for Pos {
+        ///     type Storage = VecStorage<Self>;
+        /// }
+        ///
+        /// let mut world = World::new();
+        /// world.register::<Pos>();
+        /// let e = world.create_entity().build();
+        ///
+        /// {
+        ///     let lazy = world.read_resource::<LazyUpdate>();
+        ///     // Runs after the insertion, despite being requested first.
+        ///     lazy.execute_with_priority(-1, move |world| {
+        ///         world.delete_entity(e).unwrap();
+        ///     });
+        ///     lazy.insert(e, Pos);
+        /// }
+        ///
+        /// world.maintain();
+        /// assert!(!world.is_alive(e));
+        /// ```
+        pub fn execute_with_priority<F>(&self, priority: i32, f: F)
+        where
+            F: FnOnce(&mut World) + 'static,
+        {
+            self.push(priority, Update::Closure(Box::new(f)));
+        }
+    }
+
+    /// Lazily removes a group of components from an entity.
+    ///
+    /// See `WorldExt::remove_components`; the removed components are
+    /// dropped.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// # use specs::prelude::*;
+    /// #
+    /// struct Pos;
+    /// # impl Component for Pos { type Storage = VecStorage<Self>; }
+    /// struct Vel;
+    /// # impl Component for Vel { type Storage = VecStorage<Self>; }
+    ///
+    /// struct Freeze;
+    ///
+    /// impl<'a> System<'a> for Freeze {
+    ///     type SystemData = (Entities<'a>, Read<'a, LazyUpdate>);
+    ///
+    ///     fn run(&mut self, (ent, lazy): Self::SystemData) {
+    ///         for entity in ent.join() {
+    ///             lazy.remove_components::<(Pos, Vel)>(entity);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn remove_components<G>(&self, e: Entity)
+    where
+        G: ComponentGroup,
+    {
+        self.exec(move |world| {
+            G::remove(world, e);
+        });
     }
 
     /// Creates a new `LazyBuilder` which inserts components
@@ -374,13 +565,177 @@ impl LazyUpdate {
         }
     }
 
+    fn push(&self, priority: i32, update: Update) {
+        self.queue.0.push((priority, update));
+    }
+
     pub(super) fn maintain(&self, world: &mut World) {
-        while let Some(l) = self.queue.0.pop() {
-            l.update(world);
+        if let Some(mut report) = world.try_fetch_mut::<LazyReport>() {
+            report.skipped.clear();
+        }
+        // Updates requested while maintaining are executed after the current
+        // batch, sorted by priority again.
+        let mut updates = Vec::new();
+        let mut components = Vec::new();
+        loop {
+            updates.extend(iter::from_fn(|| self.queue.0.pop()));
+            if updates.is_empty() {
+                break;
+            }
+
+            // The sort is stable, so updates of the same priority keep their
+            // order.
+            updates.sort_by_key(|&(priority, _)| Reverse(priority));
+            for (_, update) in updates.drain(..) {
+                match update {
+                    Update::Component(update) => components.push(update),
+                    Update::Closure(update) => {
+                        apply_components(world, components.drain(..));
+                        update.update(world);
+                    }
+                }
+            }
+            apply_components(world, components.drain(..));
         }
     }
 }
 
+/// Applies the component updates requested between two closures, in one
+/// `Batch` per component type. With the `parallel` feature, the batches are
+/// applied on the thread pool, except for the ones of thread-local storages.
+fn apply_components(world: &World, updates: impl Iterator<Item = ComponentUpdate>) {
+    let mut batches: Vec<Batch> = Vec::new();
+    let mut indices = HashMap::new();
+    for update in updates {
+        let i = *indices.entry(update.component).or_insert_with(|| {
+            batches.push(Batch {
+                ops: Vec::new(),
+                apply: update.apply,
+                #[cfg(feature = "parallel")]
+                is_thread_local: update.is_thread_local,
+            });
+            batches.len() - 1
+        });
+        batches[i].ops.push(update.op);
+    }
+
+    #[cfg(feature = "parallel")]
+    let skipped = {
+        let (local, shared): (Vec<_>, Vec<_>) = batches
+            .into_iter()
+            .partition(|batch| (batch.is_thread_local)(world));
+        let mut skipped: Vec<_> = shared
+            .into_par_iter()
+            .flat_map_iter(|batch| batch.apply(world))
+            .collect();
+        skipped.extend(local.into_iter().flat_map(|batch| batch.apply(world)));
+        skipped
+    };
+    #[cfg(not(feature = "parallel"))]
+    let skipped: Vec<_> = batches
+        .into_iter()
+        .flat_map(|batch| batch.apply(world))
+        .collect();
+
+    report(world, skipped);
+}
+
+/// A lazy update of a component skipped by `World::maintain`, because the
+/// entity was dead by the time the update was applied.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SkippedLazyUpdate {
+    /// The dead entity.
+    pub entity: Entity,
+    /// The skipped operation, like `"insert"`.
+    pub action: &'static str,
+    /// The type name of the component.
+    pub component: &'static str,
+}
+
+/// The lazy updates skipped by the last `World::maintain`.
+///
+/// Lazy updates are checked against the generation of their entity when
+/// they are applied, so an update of an entity deleted in the same frame
+/// never touches a component of an entity reusing its index. Such updates
+/// are skipped, logged and listed here.
+///
+/// This resource is added by `WorldExt::new`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::LazyReport};
+///
+/// struct Pos;
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// let e = world.create_entity().build();
+///
+/// world.entities().delete(e).unwrap();
+/// world.read_resource::<LazyUpdate>().insert(e, Pos);
+/// world.maintain();
+///
+/// let report = world.read_resource::<LazyReport>();
+/// assert_eq!(report.skipped()[0].entity, e);
+/// assert_eq!(report.skipped()[0].action, "insert");
+/// ```
+#[derive(Debug, Default)]
+pub struct LazyReport {
+    skipped: Vec<SkippedLazyUpdate>,
+}
+
+impl LazyReport {
+    /// Returns the skipped updates, in the order they were applied.
+    pub fn skipped(&self) -> &[SkippedLazyUpdate] {
+        &self.skipped
+    }
+}
+
+/// Inserts a component lazily, logging it if a validator rejects it and
+/// skipping it if `entity` is dead.
+fn insert_or_skip<C: Component>(
+    storage: &mut WriteStorage<C>,
+    entity: Entity,
+    component: C,
+    skipped: &mut Vec<SkippedLazyUpdate>,
+) {
+    match storage.insert(entity, component) {
+        Err(Error::InvalidComponent(err)) => log::warn!("Lazy insert of component failed: {}", err),
+        Err(_) => skipped.push(skip::<C>(entity, "insert")),
+        Ok(_) => {}
+    }
+}
+
+/// Logs a lazy update of `C` skipped because `entity` was dead.
+fn skip<C>(entity: Entity, action: &'static str) -> SkippedLazyUpdate {
+    log::warn!(
+        "Lazy {} of component failed because {:?} was dead.",
+        action,
+        entity
+    );
+
+    SkippedLazyUpdate {
+        entity,
+        action,
+        component: std::any::type_name::<C>(),
+    }
+}
+
+/// Adds skipped updates to the `LazyReport`.
+fn report(world: &World, skipped: Vec<SkippedLazyUpdate>) {
+    if skipped.is_empty() {
+        return;
+    }
+    if let Some(mut report) = world.try_fetch_mut::<LazyReport>() {
+        report.skipped.extend(skipped);
+    }
+}
+
 impl Drop for LazyUpdate {
     fn drop(&mut self) {
         // TODO: remove as soon as leak is fixed in crossbeam
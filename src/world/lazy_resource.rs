@@ -0,0 +1,148 @@
+use ahash::AHashMap as HashMap;
+use shred::{Read, Resource, ResourceId, SetupHandler, World, Write};
+
+#[cfg(feature = "parallel")]
+type Initializer = Box<dyn FnOnce(&mut World) + Send + Sync>;
+
+#[cfg(not(feature = "parallel"))]
+type Initializer = Box<dyn FnOnce(&mut World)>;
+
+/// An initializer of the resource `R`, see
+/// `WorldExt::register_lazy_resource`.
+///
+/// Implemented for all closures building `R` from the `World`, which also
+/// have to be `Send + Sync` if the `parallel` feature is enabled.
+#[cfg(feature = "parallel")]
+pub trait LazyResourceInit<R>: FnOnce(&mut World) -> R + Send + Sync + 'static {}
+
+#[cfg(feature = "parallel")]
+impl<R, F> LazyResourceInit<R> for F where F: FnOnce(&mut World) -> R + Send + Sync + 'static {}
+
+/// An initializer of the resource `R`, see
+/// `WorldExt::register_lazy_resource`.
+///
+/// Implemented for all closures building `R` from the `World`, which also
+/// have to be `Send + Sync` if the `parallel` feature is enabled.
+#[cfg(not(feature = "parallel"))]
+pub trait LazyResourceInit<R>: FnOnce(&mut World) -> R + 'static {}
+
+#[cfg(not(feature = "parallel"))]
+impl<R, F> LazyResourceInit<R> for F where F: FnOnce(&mut World) -> R + 'static {}
+
+/// A setup handler running the lazy initializer of a resource, see
+/// `WorldExt::register_lazy_resource`.
+///
+/// Use it through `ReadLazy` and `WriteLazy`, so setting up a system, e.g.
+/// with `Dispatcher::setup`, builds the resources it fetches on demand.
+/// Fetching a resource which has neither been added nor has an initializer
+/// panics, like `ReadExpect`.
+pub struct LazySetup;
+
+impl<R: Resource> SetupHandler<R> for LazySetup {
+    fn setup(world: &mut World) {
+        init_lazy_resource(world, &ResourceId::new::<R>());
+    }
+}
+
+/// Allows to fetch a resource in a system immutably, running its lazy
+/// initializer when the system is set up, see `LazySetup`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::ReadLazy};
+///
+/// struct Gravity(f32);
+///
+/// struct Fall;
+///
+/// impl<'a> System<'a> for Fall {
+///     type SystemData = ReadLazy<'a, Gravity>;
+///
+///     fn run(&mut self, gravity: Self::SystemData) {
+///         assert_eq!(gravity.0, -9.81);
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register_lazy_resource(|_| Gravity(-9.81));
+///
+/// let mut dispatcher = DispatcherBuilder::new().with(Fall, "fall", &[]).build();
+/// dispatcher.setup(&mut world);
+/// dispatcher.dispatch(&world);
+/// ```
+pub type ReadLazy<'a, R> = Read<'a, R, LazySetup>;
+
+/// Allows to fetch a resource in a system mutably, running its lazy
+/// initializer when the system is set up, see `LazySetup`.
+pub type WriteLazy<'a, R> = Write<'a, R, LazySetup>;
+
+/// Registry of resource initializers added with
+/// `WorldExt::register_lazy_resource`.
+///
+/// Initializers are removed once they ran; the stack of resources that are
+/// currently being built is used to detect dependency cycles.
+#[derive(Default)]
+pub(crate) struct LazyResources {
+    initializers: HashMap<ResourceId, (&'static str, Initializer)>,
+    building: Vec<(ResourceId, &'static str)>,
+}
+
+impl LazyResources {
+    pub(crate) fn register(&mut self, id: ResourceId, name: &'static str, init: Initializer) {
+        self.initializers.insert(id, (name, init));
+    }
+
+    /// Takes the initializer for `id` and marks it as being built.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the resource is already being built, which means its
+    /// initializer (transitively) depends on itself.
+    pub(crate) fn begin(&mut self, id: &ResourceId) -> Option<Initializer> {
+        if let Some(pos) = self.building.iter().position(|(b, _)| b == id) {
+            let cycle: Vec<_> = self.building[pos..]
+                .iter()
+                .chain(Some(&self.building[pos]))
+                .map(|(_, name)| *name)
+                .collect();
+            panic!(
+                "Cycle detected while initializing lazy resources: {}",
+                cycle.join(" -> ")
+            );
+        }
+
+        let (name, init) = self.initializers.remove(id)?;
+        self.building.push((id.clone(), name));
+
+        Some(init)
+    }
+
+    /// Marks the innermost resource as built.
+    pub(crate) fn finish(&mut self) {
+        self.building.pop();
+    }
+
+    /// Returns the ids of all resources whose initializer hasn't run yet.
+    pub(crate) fn pending(&self) -> Vec<ResourceId> {
+        self.initializers.keys().cloned().collect()
+    }
+}
+
+/// Runs the lazy initializer of the resource `id` if it hasn't been added
+/// yet.
+pub(crate) fn init_lazy_resource(world: &mut World, id: &ResourceId) {
+    if world.has_value_raw(id.clone()) {
+        return;
+    }
+
+    let init = match world.get_mut::<LazyResources>() {
+        Some(lazy) => lazy.begin(id),
+        None => return,
+    };
+
+    if let Some(init) = init {
+        init(world);
+        world.fetch_mut::<LazyResources>().finish();
+    }
+}
@@ -0,0 +1,67 @@
+use shred::World;
+
+/// The points inside `WorldExt::maintain` at which hooks registered with
+/// `WorldExt::on_maintain` run.
+///
+/// `maintain` first maintains every storage, then runs the phases in the
+/// order they are declared here. Hooks of the same phase run in the order
+/// they were registered.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::MaintainPhase};
+///
+/// #[derive(Default)]
+/// struct Log(Vec<&'static str>);
+///
+/// let mut world = World::new();
+/// world.insert(Log::default());
+/// world.on_maintain(MaintainPhase::AfterLazy, |world| {
+///     world.write_resource::<Log>().0.push("after lazy")
+/// });
+/// world.on_maintain(MaintainPhase::BeforeDeletes, |world| {
+///     world.write_resource::<Log>().0.push("before deletes")
+/// });
+///
+/// world.maintain();
+/// assert_eq!(
+///     world.read_resource::<Log>().0,
+///     ["before deletes", "after lazy"]
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MaintainPhase {
+    /// Before entities deleted with `Entities::delete` are removed. They are
+    /// still alive at this point.
+    BeforeDeletes,
+    /// After the deleted entities and their components have been removed.
+    AfterDeletes,
+    /// Before the updates queued in `LazyUpdate` are applied.
+    BeforeLazy,
+    /// After the updates queued in `LazyUpdate` have been applied, at the
+    /// end of `maintain`.
+    AfterLazy,
+}
+
+/// The hooks registered with `WorldExt::on_maintain`, by phase.
+#[derive(Default)]
+pub(crate) struct MaintainHooks([Vec<fn(&mut World)>; 4]);
+
+impl MaintainHooks {
+    pub(crate) fn add(&mut self, phase: MaintainPhase, hook: fn(&mut World)) {
+        self.0[phase as usize].push(hook);
+    }
+
+    /// Runs the hooks of `phase`, if any were registered.
+    pub(crate) fn run(world: &mut World, phase: MaintainPhase) {
+        let hooks = match world.try_fetch::<MaintainHooks>() {
+            Some(hooks) if !hooks.0[phase as usize].is_empty() => hooks.0[phase as usize].clone(),
+            _ => return,
+        };
+
+        for hook in hooks {
+            hook(world);
+        }
+    }
+}
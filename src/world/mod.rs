@@ -2,24 +2,67 @@
 
 pub use shred::World;
 
+#[cfg(feature = "tracking")]
+pub use self::events::WorldEvents;
+#[cfg(feature = "tracking")]
+pub use self::replication::{ComponentSink, MemorySink, ReplicationSink, ReplicationSystem};
 pub use self::{
-    comp::Component,
+    cache::{CacheGuard, WorldCache},
+    clone::ComponentFilter,
+    comp::{Component, ComponentGroup, SplitComponent},
     entity::{
-        CreateIterAtomic, Entities, EntitiesRes, Entity, EntityResBuilder, Generation, Index,
+        CreateIterAtomic, Entities, EntitiesRes, Entity, EntityResBuilder, EntityStream,
+        Generation, Index, WeakEntity,
     },
-    lazy::{LazyBuilder, LazyUpdate},
-    world_ext::WorldExt,
+    events::WorldEvent,
+    exclusive::{ExclusiveDispatcher, ExclusiveDispatcherBuilder, ExclusiveSystem},
+    groups::{GroupDispatcherBuilder, SystemGroup, SystemGroups},
+    history::History,
+    inspect::{Inspect, InspectVisitor, InspectVisitorMut},
+    layout::{ComponentLayout, GroupCandidate, LayoutReport, StorageSuggestion},
+    lazy::{LazyBuilder, LazyReport, LazyUpdate, SkippedLazyUpdate},
+    lazy_resource::{LazyResourceInit, LazySetup, ReadLazy, WriteLazy},
+    maintain::MaintainPhase,
+    plugin::WorldPlugin,
+    scratch::FrameScratch,
+    setup::{SetupDispatcher, SetupDispatcherBuilder},
+    singleton::Singleton,
+    tasks::{AsyncStage, AsyncSystems, TaskQueue},
+    tick::{SystemTicks, TickDispatcherBuilder, WorldTick},
+    transaction::Transaction,
+    ttl::{DeltaTime, EntityTtl, TtlSystem},
+    world_ext::{assert_distinct_system_data, WorldExt},
 };
 
 use shred::{FetchMut, SystemData};
 
-use crate::storage::WriteStorage;
+use crate::{error::WrongGeneration, storage::WriteStorage};
 
+mod cache;
+mod clone;
 mod comp;
 mod entity;
+pub(crate) mod events;
+mod exclusive;
+mod groups;
+mod history;
+mod inspect;
+mod layout;
 mod lazy;
+mod lazy_resource;
+mod maintain;
+mod plugin;
+#[cfg(feature = "tracking")]
+mod replication;
+mod scratch;
+mod setup;
+mod singleton;
 #[cfg(test)]
 mod tests;
+mod tasks;
+mod tick;
+mod transaction;
+mod ttl;
 mod world_ext;
 
 /// An iterator for entity creation.
@@ -101,6 +144,37 @@ pub trait Builder {
         }
     }
 
+    /// Appends both parts of a `SplitComponent`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parts haven't been `register()`ed in the `World`.
+    #[cfg(feature = "parallel")]
+    fn with_split<C>(self, c: C) -> Self
+    where
+        Self: Sized,
+        C: SplitComponent,
+        C::Hot: Send + Sync,
+        C::Cold: Send + Sync,
+    {
+        let (hot, cold) = c.split();
+        self.with(hot).with(cold)
+    }
+
+    /// Appends both parts of a `SplitComponent`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the parts haven't been `register()`ed in the `World`.
+    #[cfg(not(feature = "parallel"))]
+    fn with_split<C: SplitComponent>(self, c: C) -> Self
+    where
+        Self: Sized,
+    {
+        let (hot, cold) = c.split();
+        self.with(hot).with(cold)
+    }
+
     /// Finishes the building and returns the entity.
     fn build(self) -> Entity;
 }
@@ -181,6 +255,87 @@ pub struct EntityBuilder<'a> {
     /// A reference to the `World` for component insertions.
     pub world: &'a World,
     built: bool,
+    /// Whether the entity has been created by this builder, and so gets
+    /// deleted if the builder is dropped without calling `build`.
+    created: bool,
+}
+
+impl<'a> EntityBuilder<'a> {
+    /// Starts building an entity which has already been created, usually
+    /// with `WorldExt::reserve_entity`.
+    ///
+    /// Unlike with `WorldExt::create_entity`, the entity is not deleted if
+    /// the builder is dropped without calling `build`, as the builder
+    /// didn't create it.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the entity is not alive.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Partner(Entity);
+    ///
+    /// impl Component for Partner {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Partner>();
+    ///
+    /// let a = world.reserve_entity();
+    /// let b = world.reserve_entity();
+    /// EntityBuilder::for_entity(&mut world, a)
+    ///     .with(Partner(b))
+    ///     .build();
+    /// EntityBuilder::for_entity(&mut world, b)
+    ///     .with(Partner(a))
+    ///     .build();
+    ///
+    /// assert_eq!(world.read_storage::<Partner>().get(b).unwrap().0, a);
+    /// ```
+    pub fn for_entity(world: &'a mut World, entity: Entity) -> Self {
+        match Self::try_for_entity(world, entity) {
+            Ok(builder) => builder,
+            Err(e) => panic!("Cannot build entity {:?}, it is not alive", e.entity),
+        }
+    }
+
+    /// Starts building an entity which has already been created, like
+    /// `for_entity`, but returns an error instead of panicking if the entity
+    /// is not alive.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// let entity = world.reserve_entity();
+    /// world.delete_entity(entity).unwrap();
+    ///
+    /// let e = EntityBuilder::try_for_entity(&mut world, entity).err().unwrap();
+    /// assert_eq!(e.entity, entity);
+    /// ```
+    pub fn try_for_entity(world: &'a mut World, entity: Entity) -> Result<Self, WrongGeneration> {
+        if !world.is_alive(entity) {
+            return Err(WrongGeneration {
+                action: "build",
+                actual_gen: world.entities().entity(entity.id()).gen(),
+                entity,
+            });
+        }
+
+        Ok(EntityBuilder {
+            entity,
+            world,
+            built: false,
+            created: false,
+        })
+    }
 }
 
 impl<'a> Builder for EntityBuilder<'a> {
@@ -188,13 +343,19 @@ impl<'a> Builder for EntityBuilder<'a> {
     ///
     /// If a component was already associated with the entity, it will
     /// overwrite the previous component.
+    ///
+    /// Components rejected by a validator registered with
+    /// `WorldExt::register_validator` are not inserted, and a warning is
+    /// logged.
     #[inline]
     fn with<T: Component>(self, c: T) -> Self {
         {
             let mut storage: WriteStorage<T> = SystemData::fetch(self.world);
-            // This can't fail.  This is guaranteed by the lifetime 'a
-            // in the EntityBuilder.
-            storage.insert(self.entity, c).unwrap();
+            // The entity is alive, this is guaranteed by the lifetime 'a in
+            // the EntityBuilder, so this can only fail for invalid components.
+            if let Err(err) = storage.insert(self.entity, c) {
+                log::warn!("{}", err);
+            }
         }
 
         self
@@ -211,7 +372,7 @@ impl<'a> Builder for EntityBuilder<'a> {
 
 impl<'a> Drop for EntityBuilder<'a> {
     fn drop(&mut self) {
-        if !self.built {
+        if self.created && !self.built {
             self.world
                 .read_resource::<EntitiesRes>()
                 .delete(self.entity)
@@ -0,0 +1,77 @@
+use std::any::TypeId;
+
+use ahash::AHashSet as HashSet;
+use shred::{DispatcherBuilder, World};
+
+/// Installs everything a library built on Specs needs at once, like its
+/// components, resources, saveload markers and systems.
+///
+/// Plugins are installed with `WorldExt::install`, which installs each
+/// plugin type only once, so plugins can install the plugins they depend on.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, WorldPlugin};
+///
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Vel(f32);
+///
+/// impl Component for Vel {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct Movement;
+///
+/// impl<'a> System<'a> for Movement {
+///     type SystemData = (WriteStorage<'a, Pos>, ReadStorage<'a, Vel>);
+///
+///     fn run(&mut self, (mut pos, vel): Self::SystemData) {
+///         for (pos, vel) in (&mut pos, &vel).join() {
+///             pos.0 += vel.0;
+///         }
+///     }
+/// }
+///
+/// struct PhysicsPlugin;
+///
+/// impl WorldPlugin for PhysicsPlugin {
+///     fn install(&self, world: &mut World, dispatcher: &mut DispatcherBuilder) {
+///         world.register::<Pos>();
+///         world.register::<Vel>();
+///         dispatcher.add(Movement, "movement", &[]);
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let mut builder = DispatcherBuilder::new();
+/// assert!(world.install(PhysicsPlugin, &mut builder));
+/// // Installing a plugin again does nothing.
+/// assert!(!world.install(PhysicsPlugin, &mut builder));
+///
+/// let mut dispatcher = builder.build();
+/// let e = world.create_entity().with(Pos(0.0)).with(Vel(2.0)).build();
+/// dispatcher.dispatch(&world);
+/// assert_eq!(world.read_storage::<Pos>().get(e).unwrap().0, 2.0);
+/// ```
+pub trait WorldPlugin: 'static {
+    /// Installs the plugin into `world` and `dispatcher`.
+    fn install(&self, world: &mut World, dispatcher: &mut DispatcherBuilder<'_, '_>);
+}
+
+/// The types of the plugins installed with `WorldExt::install`.
+#[derive(Default)]
+pub(crate) struct InstalledPlugins(HashSet<TypeId>);
+
+impl InstalledPlugins {
+    /// Marks the plugin `P` as installed, returning `false` if it already
+    /// was.
+    pub(crate) fn insert<P: WorldPlugin>(&mut self) -> bool {
+        self.0.insert(TypeId::of::<P>())
+    }
+}
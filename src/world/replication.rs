@@ -0,0 +1,277 @@
+use std::any::{Any, TypeId};
+
+use ahash::AHashMap as HashMap;
+use shred::World;
+use shrev::ReaderId;
+
+use super::{Component, Entity, ExclusiveSystem, WorldEvent, WorldEvents, WorldExt};
+use crate::{
+    join::Join,
+    storage::{ReplicationKind, ReplicationQueue, Tracked},
+};
+
+/// A backend mirroring the entities of a `World`, driven by a
+/// `ReplicationSystem`, like a key-value store, a network protocol or a
+/// scripting VM.
+///
+/// The components it receives are declared by implementing `ComponentSink`
+/// for each of them. With the `parallel` feature, sinks have to be `Send`
+/// like all `ExclusiveSystem`s.
+#[cfg(feature = "parallel")]
+pub trait ReplicationSink: Send + 'static {
+    /// Called for each entity deleted from the `World`, instead of calling
+    /// `ComponentSink::on_remove` for each of its components.
+    fn on_entity_delete(&mut self, entity: Entity);
+}
+
+/// A backend mirroring the entities of a `World`, driven by a
+/// `ReplicationSystem`, like a key-value store, a network protocol or a
+/// scripting VM.
+///
+/// The components it receives are declared by implementing `ComponentSink`
+/// for each of them. With the `parallel` feature, sinks have to be `Send`
+/// like all `ExclusiveSystem`s.
+#[cfg(not(feature = "parallel"))]
+pub trait ReplicationSink: 'static {
+    /// Called for each entity deleted from the `World`, instead of calling
+    /// `ComponentSink::on_remove` for each of its components.
+    fn on_entity_delete(&mut self, entity: Entity);
+}
+
+/// The part of a `ReplicationSink` receiving the components of type `T`.
+pub trait ComponentSink<T>: ReplicationSink {
+    /// Called when a component is inserted or loaded.
+    fn on_insert(&mut self, entity: Entity, component: &T);
+
+    /// Called when a component has been accessed mutably.
+    fn on_modify(&mut self, entity: Entity, component: &T);
+
+    /// Called when a component is removed from an entity which is still
+    /// alive.
+    fn on_remove(&mut self, entity: Entity);
+}
+
+/// The `ReplicationQueue` of one component type, with the type erased.
+type AnyQueue = Box<dyn Any + Send + Sync>;
+
+/// Drains the `ReplicationQueue` of one component type to the sink.
+struct ComponentReplicator<S> {
+    queue: Option<AnyQueue>,
+    replicate: fn(&World, &mut Option<AnyQueue>, &mut S),
+}
+
+/// An `ExclusiveSystem` mirroring the components registered with
+/// `with_component` and the deletion of entities to a `ReplicationSink`.
+///
+/// Components are drained from a `ReplicationQueue` of their tracked storages
+/// and deleted entities read from the `WorldEvents`. When the system is set
+/// up or first runs, the components the storages already contain are sent as
+/// insertions.
+///
+/// Added to a dispatcher, the system runs after the other systems in
+/// `ExclusiveDispatcher::dispatch_exclusive`, so the sink receives the
+/// changes of a frame at once. The system can also be run directly, e.g.
+/// right after `maintain`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     world::{MemorySink, ReplicationSystem},
+/// };
+///
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = FlaggedStorage<Self>;
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// let mut replication = ReplicationSystem::new(MemorySink::default()).with_component::<Pos>();
+/// replication.setup(&mut world);
+///
+/// let e = world.create_entity().with(Pos(1.0)).build();
+/// world.maintain();
+/// replication.run(&mut world);
+/// assert_eq!(replication.sink().get::<Pos>(e), Some(&Pos(1.0)));
+///
+/// world.delete_entity(e).unwrap();
+/// world.maintain();
+/// replication.run(&mut world);
+/// assert!(replication.sink().is_empty());
+/// ```
+pub struct ReplicationSystem<S> {
+    sink: S,
+    components: Vec<ComponentReplicator<S>>,
+    deletions: Option<ReaderId<WorldEvent>>,
+}
+
+impl<S: ReplicationSink> ReplicationSystem<S> {
+    /// Creates a system replicating to `sink`, only deletions of entities
+    /// until components are added with `with_component`.
+    pub fn new(sink: S) -> Self {
+        ReplicationSystem {
+            sink,
+            components: Vec::new(),
+            deletions: None,
+        }
+    }
+
+    /// Replicates the components of type `T`.
+    pub fn with_component<T>(mut self) -> Self
+    where
+        T: Component,
+        T::Storage: Tracked,
+        S: ComponentSink<T>,
+    {
+        self.components.push(ComponentReplicator {
+            queue: None,
+            replicate: replicate::<T, S>,
+        });
+
+        self
+    }
+
+    /// Returns the sink.
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Returns the sink mutably.
+    pub fn sink_mut(&mut self) -> &mut S {
+        &mut self.sink
+    }
+
+    /// Returns the sink, consuming the system.
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    /// Forwards the changes since the last call to the sink.
+    fn replicate(&mut self, world: &World) {
+        for component in &mut self.components {
+            (component.replicate)(world, &mut component.queue, &mut self.sink);
+        }
+
+        let deletions = self
+            .deletions
+            .get_or_insert_with(|| world.write_resource::<WorldEvents>().register_reader());
+        for event in world.read_resource::<WorldEvents>().read(deletions) {
+            if let WorldEvent::EntityDeleted(entity) = *event {
+                self.sink.on_entity_delete(entity);
+            }
+        }
+    }
+}
+
+impl<S: ReplicationSink> ExclusiveSystem for ReplicationSystem<S> {
+    fn run(&mut self, world: &mut World) {
+        self.replicate(world);
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        self.replicate(world);
+    }
+}
+
+/// Forwards the dirty components of the storage of `T` to `sink`, creating
+/// the queue and sending the existing components as insertions if `queue` is
+/// `None`.
+fn replicate<T, S>(world: &World, queue: &mut Option<AnyQueue>, sink: &mut S)
+where
+    T: Component,
+    T::Storage: Tracked,
+    S: ComponentSink<T>,
+{
+    let queue = match queue {
+        Some(queue) => queue.downcast_mut::<ReplicationQueue<T>>().unwrap(),
+        None => {
+            let mut storage = world.write_storage::<T>();
+            *queue = Some(Box::new(ReplicationQueue::new(&mut storage)));
+            for (entity, component) in (&world.entities(), &storage).join() {
+                sink.on_insert(entity, component);
+            }
+
+            return;
+        }
+    };
+
+    let storage = world.read_storage::<T>();
+    let entities = storage.fetched_entities();
+    queue.drain_up_to_with(&storage, usize::MAX, |entity, kind, component| {
+        match (kind, component) {
+            (ReplicationKind::Inserted, Some(component)) => sink.on_insert(entity, component),
+            (_, Some(component)) => sink.on_modify(entity, component),
+            // Deleted entities are handled by `on_entity_delete`.
+            (_, None) if entities.is_alive(entity) => sink.on_remove(entity),
+            (_, None) => {}
+        }
+    });
+}
+
+/// A `ReplicationSink` keeping copies of the replicated components in
+/// memory, e.g. to test a `ReplicationSystem` or a replicated game.
+#[derive(Default)]
+pub struct MemorySink {
+    entities: HashMap<Entity, HashMap<TypeId, Box<dyn Any + Send + Sync>>>,
+}
+
+impl MemorySink {
+    /// Returns the copy of the component of `entity`.
+    pub fn get<T: Any>(&self, entity: Entity) -> Option<&T> {
+        self.entities
+            .get(&entity)?
+            .get(&TypeId::of::<T>())?
+            .downcast_ref()
+    }
+
+    /// Returns `true` if a component of `entity` has been replicated and not
+    /// removed since.
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains_key(&entity)
+    }
+
+    /// Returns the number of entities with replicated components.
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Returns `true` if no entity has replicated components.
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+impl ReplicationSink for MemorySink {
+    fn on_entity_delete(&mut self, entity: Entity) {
+        self.entities.remove(&entity);
+    }
+}
+
+impl<T> ComponentSink<T> for MemorySink
+where
+    T: Any + Clone + Send + Sync,
+{
+    fn on_insert(&mut self, entity: Entity, component: &T) {
+        self.entities
+            .entry(entity)
+            .or_default()
+            .insert(TypeId::of::<T>(), Box::new(component.clone()));
+    }
+
+    fn on_modify(&mut self, entity: Entity, component: &T) {
+        self.on_insert(entity, component);
+    }
+
+    fn on_remove(&mut self, entity: Entity) {
+        if let Some(components) = self.entities.get_mut(&entity) {
+            components.remove(&TypeId::of::<T>());
+            if components.is_empty() {
+                self.entities.remove(&entity);
+            }
+        }
+    }
+}
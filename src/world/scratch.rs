@@ -0,0 +1,162 @@
+use std::any::{Any, TypeId};
+
+use ahash::AHashMap as HashMap;
+
+use super::Entity;
+
+/// Temporary per-entity values that systems exchange within a frame.
+///
+/// Values are keyed by entity and type, and stored in one arena per type
+/// whose memory is reused from frame to frame. `WorldExt::maintain` clears
+/// all values at its end, so intermediate results like contact lists or
+/// steering forces don't need a registered component that is inserted and
+/// removed every frame.
+///
+/// The resource is added by `WorldExt::new`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::FrameScratch};
+///
+/// struct Damage(u32);
+///
+/// let mut world = World::new();
+/// let e = world.create_entity().build();
+///
+/// {
+///     let mut scratch = world.write_resource::<FrameScratch>();
+///     scratch.alloc(e, Damage(3));
+///     scratch.get_mut::<Damage>(e).unwrap().0 += 2;
+///     assert_eq!(scratch.get::<Damage>(e).unwrap().0, 5);
+/// }
+///
+/// world.maintain();
+/// let scratch = world.read_resource::<FrameScratch>();
+/// assert!(scratch.get::<Damage>(e).is_none());
+/// ```
+#[derive(Default)]
+pub struct FrameScratch {
+    arenas: HashMap<TypeId, Box<dyn ScratchArena>>,
+}
+
+impl FrameScratch {
+    /// Stores `value` for `entity` until the end of the next `maintain`,
+    /// replacing the value of the same type it may already have, and returns
+    /// a reference to it.
+    pub fn alloc<T>(&mut self, entity: Entity, value: T) -> &mut T
+    where
+        T: Any + Send + Sync,
+    {
+        self.arenas
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::<Arena<T>>::default())
+            .as_any_mut()
+            .downcast_mut::<Arena<T>>()
+            .unwrap()
+            .alloc(entity, value)
+    }
+
+    /// Returns the value of type `T` stored for `entity`, if any.
+    pub fn get<T>(&self, entity: Entity) -> Option<&T>
+    where
+        T: Any + Send + Sync,
+    {
+        let arena = self.arena::<T>()?;
+        arena.index.get(&entity).map(|&i| &arena.values[i].1)
+    }
+
+    /// Returns the value of type `T` stored for `entity` mutably, if any.
+    pub fn get_mut<T>(&mut self, entity: Entity) -> Option<&mut T>
+    where
+        T: Any + Send + Sync,
+    {
+        let arena = self.arena_mut::<T>()?;
+        let i = *arena.index.get(&entity)?;
+
+        Some(&mut arena.values[i].1)
+    }
+
+    /// Iterates over the entities and their values of type `T`, in the order
+    /// they were allocated.
+    pub fn iter<T>(&self) -> impl Iterator<Item = (Entity, &T)>
+    where
+        T: Any + Send + Sync,
+    {
+        self.arena::<T>()
+            .into_iter()
+            .flat_map(|arena| arena.values.iter().map(|(e, value)| (*e, value)))
+    }
+
+    /// Removes all values, keeping the memory of the arenas.
+    pub fn clear(&mut self) {
+        for arena in self.arenas.values_mut() {
+            arena.clear();
+        }
+    }
+
+    fn arena<T: Any + Send + Sync>(&self) -> Option<&Arena<T>> {
+        self.arenas
+            .get(&TypeId::of::<T>())
+            .and_then(|arena| arena.as_any().downcast_ref())
+    }
+
+    fn arena_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut Arena<T>> {
+        self.arenas
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|arena| arena.as_any_mut().downcast_mut())
+    }
+}
+
+/// The values of one type, see `FrameScratch`.
+struct Arena<T> {
+    values: Vec<(Entity, T)>,
+    index: HashMap<Entity, usize>,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena {
+            values: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    fn alloc(&mut self, entity: Entity, value: T) -> &mut T {
+        let values = &mut self.values;
+        let i = *self.index.entry(entity).or_insert_with(|| values.len());
+        if i == values.len() {
+            values.push((entity, value));
+        } else {
+            values[i].1 = value;
+        }
+
+        &mut values[i].1
+    }
+}
+
+/// Type-erased `Arena`.
+trait ScratchArena: Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    fn clear(&mut self);
+}
+
+impl<T: Any + Send + Sync> ScratchArena for Arena<T> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn clear(&mut self) {
+        self.values.clear();
+        self.index.clear();
+    }
+}
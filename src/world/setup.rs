@@ -0,0 +1,256 @@
+use std::{
+    cmp::Reverse,
+    mem,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+};
+
+use ahash::AHashSet as HashSet;
+use shred::{Dispatcher, DispatcherBuilder, RunNow, RunningTime, System, World};
+
+/// Extension trait for shred's `DispatcherBuilder` to control the order in
+/// which `System::setup` runs.
+///
+/// The setup of a system added with a setup priority is deferred until the
+/// setups of the other systems (except thread-local ones added later) have
+/// run. The deferred setups then run from the highest priority to the
+/// lowest, systems with the same priority in the order they were added.
+/// Each of them only runs once per `World`, so setting up a dispatcher again
+/// doesn't run them twice.
+///
+/// This allows setups depending on resources created by the setups of other
+/// systems, without relying on the order the systems are registered in.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::prelude::*;
+///
+/// #[derive(Default)]
+/// struct Config(u32);
+///
+/// struct Cache(u32);
+///
+/// struct UseCache;
+///
+/// impl<'a> System<'a> for UseCache {
+///     type SystemData = ReadExpect<'a, Cache>;
+///
+///     fn run(&mut self, _: Self::SystemData) {}
+///
+///     fn setup(&mut self, world: &mut World) {
+///         // Runs after `BuildCache::setup`, added with a higher priority.
+///         assert_eq!(world.read_resource::<Cache>().0, 3);
+///     }
+/// }
+///
+/// struct BuildCache;
+///
+/// impl<'a> System<'a> for BuildCache {
+///     type SystemData = Read<'a, Config>;
+///
+///     fn run(&mut self, _: Self::SystemData) {}
+///
+///     fn setup(&mut self, world: &mut World) {
+///         let size = world.read_resource::<Config>().0;
+///         world.insert(Cache(size));
+///     }
+/// }
+///
+/// struct LoadConfig;
+///
+/// impl<'a> System<'a> for LoadConfig {
+///     type SystemData = ();
+///
+///     fn run(&mut self, _: Self::SystemData) {}
+///
+///     fn setup(&mut self, world: &mut World) {
+///         world.insert(Config(3));
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with_setup_priority(UseCache, "use_cache", &[], 0)
+///     .with_setup_priority(BuildCache, "build_cache", &[], 1)
+///     .with(LoadConfig, "load_config", &[])
+///     .build();
+/// dispatcher.setup_only(&mut world);
+/// dispatcher.setup_only(&mut world);
+/// ```
+pub trait SetupDispatcherBuilder {
+    /// Adds a new system whose setup runs deferred, ordered by `priority`.
+    ///
+    /// Same as [`SetupDispatcherBuilder::add_setup_priority`], but returns
+    /// `self` to enable method chaining.
+    fn with_setup_priority<S>(self, system: S, name: &str, dep: &[&str], priority: i32) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static;
+
+    /// Adds a new system whose setup runs deferred, ordered by `priority`.
+    ///
+    /// The system is dispatched like one added with `DispatcherBuilder::add`.
+    fn add_setup_priority<S>(&mut self, system: S, name: &str, dep: &[&str], priority: i32)
+    where
+        S: for<'c> System<'c> + Send + 'static;
+}
+
+impl<'a, 'b> SetupDispatcherBuilder for DispatcherBuilder<'a, 'b> {
+    fn with_setup_priority<S>(mut self, system: S, name: &str, dep: &[&str], priority: i32) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        self.add_setup_priority(system, name, dep, priority);
+
+        self
+    }
+
+    fn add_setup_priority<S>(&mut self, system: S, name: &str, dep: &[&str], priority: i32)
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+        let system = PrioritizedSetup {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            priority,
+            system: Arc::new(Mutex::new(system)),
+        };
+        self.add(system, name, dep);
+        // Thread-local systems are set up last, the first one runs the
+        // deferred setups. It is added once per builder, marked by a system
+        // doing nothing.
+        if !self.has_system(DEFERRED_SETUPS) {
+            self.add(DeferredSetupsMarker, DEFERRED_SETUPS, &[]);
+            self.add_thread_local(DeferredSetups);
+        }
+    }
+}
+
+/// The name of the system marking that `DeferredSetups` has been added to a
+/// `DispatcherBuilder`.
+const DEFERRED_SETUPS: &str = "specs::deferred_setups";
+
+/// Extension trait for shred's `Dispatcher` to set up the `World` ahead of
+/// the first dispatch.
+pub trait SetupDispatcher {
+    /// Runs the setup of all systems, without dispatching them, to create
+    /// the resources and storages they need.
+    ///
+    /// This can be called repeatedly: the setups only run the first time a
+    /// dispatcher is set up with a `World`. The dispatcher is recognized by
+    /// its address, so it is set up again after being moved. A dispatcher
+    /// created at the address of a dropped one is taken for it, set it up
+    /// with `Dispatcher::setup` instead.
+    fn setup_only(&mut self, world: &mut World);
+}
+
+impl<'a, 'b> SetupDispatcher for Dispatcher<'a, 'b> {
+    fn setup_only(&mut self, world: &mut World) {
+        let key = self as *const Self as usize;
+        let mut set_up = world.entry::<SetUpDispatchers>().or_insert_with(Default::default);
+        if set_up.0.insert(key) {
+            drop(set_up);
+            self.setup(world);
+        }
+    }
+}
+
+/// The addresses of the dispatchers set up with `SetupDispatcher::setup_only`.
+#[derive(Default)]
+struct SetUpDispatchers(HashSet<usize>);
+
+/// A system whose setup is queued on `SetupQueue`.
+struct PrioritizedSetup<S> {
+    id: u64,
+    priority: i32,
+    system: Arc<Mutex<S>>,
+}
+
+impl<S> PrioritizedSetup<S> {
+    fn system(&self) -> MutexGuard<'_, S> {
+        self.system
+            .lock()
+            .expect("System panicked during a previous run")
+    }
+}
+
+impl<'c, S> System<'c> for PrioritizedSetup<S>
+where
+    S: for<'d> System<'d> + Send + 'static,
+{
+    type SystemData = <S as System<'c>>::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        self.system().run(data);
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system().running_time()
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        let mut queue = world.entry::<SetupQueue>().or_insert_with(Default::default);
+        if !queue.done.contains(&self.id) && queue.pending.iter().all(|p| p.1 != self.id) {
+            queue
+                .pending
+                .push((self.priority, self.id, self.system.clone()));
+        }
+    }
+}
+
+/// A setup queued by a `PrioritizedSetup`.
+trait DeferredSetup: Send + Sync {
+    fn setup(&self, world: &mut World);
+}
+
+impl<S> DeferredSetup for Mutex<S>
+where
+    S: for<'c> System<'c> + Send,
+{
+    fn setup(&self, world: &mut World) {
+        self.lock()
+            .expect("System panicked during a previous run")
+            .setup(world);
+    }
+}
+
+/// The deferred setups of a `World`, see `SetupDispatcherBuilder`.
+#[derive(Default)]
+struct SetupQueue {
+    pending: Vec<(i32, u64, Arc<dyn DeferredSetup>)>,
+    /// Ids of the systems set up already.
+    done: HashSet<u64>,
+}
+
+/// Marks that `DeferredSetups` has been added to a `DispatcherBuilder`.
+struct DeferredSetupsMarker;
+
+impl<'a> System<'a> for DeferredSetupsMarker {
+    type SystemData = ();
+
+    fn run(&mut self, _: Self::SystemData) {}
+}
+
+/// Thread-local system running the deferred setups.
+struct DeferredSetups;
+
+impl<'a> RunNow<'a> for DeferredSetups {
+    fn run_now(&mut self, _: &'a World) {}
+
+    fn setup(&mut self, world: &mut World) {
+        let mut pending = match world.try_fetch_mut::<SetupQueue>() {
+            Some(mut queue) => mem::take(&mut queue.pending),
+            None => return,
+        };
+        // The sort is stable, keeping the order of equal priorities.
+        pending.sort_by_key(|&(priority, ..)| Reverse(priority));
+
+        for (_, id, system) in pending {
+            system.setup(world);
+            world.fetch_mut::<SetupQueue>().done.insert(id);
+        }
+    }
+}
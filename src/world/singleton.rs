@@ -0,0 +1,126 @@
+use std::{
+    any::{type_name, TypeId},
+    marker::PhantomData,
+    ops::Deref,
+};
+
+use ahash::AHashMap as HashMap;
+use shred::{ResourceId, SystemData, World};
+
+use super::Entity;
+
+/// The singleton entities of a `World`, by tag type, see
+/// `WorldExt::set_singleton`.
+#[derive(Default)]
+pub(crate) struct Singletons {
+    entities: HashMap<TypeId, Entity>,
+}
+
+impl Singletons {
+    pub(crate) fn get<T: 'static>(&self) -> Option<Entity> {
+        self.entities.get(&TypeId::of::<T>()).copied()
+    }
+
+    pub(crate) fn insert<T: 'static>(&mut self, e: Entity) -> Option<Entity> {
+        self.entities.insert(TypeId::of::<T>(), e)
+    }
+
+    pub(crate) fn remove<T: 'static>(&mut self) -> Option<Entity> {
+        self.entities.remove(&TypeId::of::<T>())
+    }
+
+    /// Clears the singletons of deleted entities.
+    pub(crate) fn delete(&mut self, deleted: &[Entity]) {
+        self.entities.retain(|_, e| !deleted.contains(e));
+    }
+}
+
+/// `SystemData` fetching the singleton entity tagged with `T`, see
+/// `WorldExt::set_singleton`.
+///
+/// ## Panics
+///
+/// Panics when fetched if there is no singleton tagged with `T`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{prelude::*, world::Singleton};
+///
+/// struct Player;
+///
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// struct FollowPlayer;
+///
+/// impl<'a> System<'a> for FollowPlayer {
+///     type SystemData = (Singleton<'a, Player>, ReadStorage<'a, Pos>);
+///
+///     fn run(&mut self, (player, pos): Self::SystemData) {
+///         assert_eq!(pos.get(*player).unwrap().0, 3.0);
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// world.register::<Pos>();
+/// let player = world.create_entity().with(Pos(3.0)).build();
+/// world.set_singleton::<Player>(player).unwrap();
+/// FollowPlayer.run_now(&world);
+///
+/// world.delete_entity(player).unwrap();
+/// assert_eq!(world.singleton::<Player>(), None);
+/// ```
+pub struct Singleton<'a, T> {
+    entity: Entity,
+    phantom: PhantomData<fn() -> &'a T>,
+}
+
+impl<'a, T> Singleton<'a, T> {
+    /// Returns the singleton entity.
+    pub fn entity(&self) -> Entity {
+        self.entity
+    }
+}
+
+impl<'a, T> Deref for Singleton<'a, T> {
+    type Target = Entity;
+
+    fn deref(&self) -> &Entity {
+        &self.entity
+    }
+}
+
+impl<'a, T: 'static> SystemData<'a> for Singleton<'a, T> {
+    fn setup(world: &mut World) {
+        world.entry::<Singletons>().or_insert_with(Default::default);
+    }
+
+    fn fetch(world: &'a World) -> Self {
+        let entity = world
+            .try_fetch::<Singletons>()
+            .and_then(|singletons| singletons.get::<T>())
+            .unwrap_or_else(|| {
+                panic!(
+                    "No singleton entity tagged with `{}`, see `WorldExt::set_singleton`",
+                    type_name::<T>()
+                )
+            });
+
+        Singleton {
+            entity,
+            phantom: PhantomData,
+        }
+    }
+
+    fn reads() -> Vec<ResourceId> {
+        vec![ResourceId::new::<Singletons>()]
+    }
+
+    fn writes() -> Vec<ResourceId> {
+        vec![]
+    }
+}
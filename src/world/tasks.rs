@@ -0,0 +1,243 @@
+use std::{
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+use shred::{Read, System, World};
+
+use super::LazyUpdate;
+
+/// A change to the `World`, applied during `World::maintain`.
+type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+type PendingFuture = Pin<Box<dyn Future<Output = Command> + Send>>;
+
+/// Futures polled once per dispatch by the `AsyncStage`, bridging async code
+/// like asset loading or networking with the synchronous dispatch loop.
+///
+/// A future resolves to a closure which gets exclusive access to the
+/// `World`: it is queued on `LazyUpdate` when the future completes, and
+/// runs during the next `World::maintain`.
+///
+/// The futures are polled with a waker which does nothing, since the stage
+/// polls every pending future on each dispatch anyway.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     world::{AsyncStage, AsyncSystems},
+/// };
+///
+/// #[derive(Default)]
+/// struct Level(Option<String>);
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with(AsyncStage, "async_stage", &[])
+///     .build();
+/// dispatcher.setup(&mut world);
+/// world.insert(Level::default());
+///
+/// world.fetch::<AsyncSystems>().register(async {
+///     let level = String::from("level 1"); // e.g. read from disk
+///     move |world: &mut World| world.fetch_mut::<Level>().0 = Some(level)
+/// });
+/// dispatcher.dispatch(&world);
+/// world.maintain();
+///
+/// assert_eq!(world.fetch::<Level>().0.as_deref(), Some("level 1"));
+/// ```
+#[derive(Default)]
+pub struct AsyncSystems {
+    pending: Mutex<Vec<PendingFuture>>,
+}
+
+impl AsyncSystems {
+    /// Registers a future, whose output is run with the `World` during the
+    /// first `World::maintain` after it completed.
+    pub fn register<F, C>(&self, future: F)
+    where
+        F: Future<Output = C> + Send + 'static,
+        C: FnOnce(&mut World) + Send + 'static,
+    {
+        let future = async move { Box::new(future.await) as Command };
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Box::pin(future));
+    }
+
+    /// Returns the number of futures which didn't complete yet.
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Returns `true` if all futures completed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Polls every pending future once, returning the outputs of the ones
+    /// which completed.
+    fn poll(&self) -> Vec<Command> {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let mut completed = Vec::new();
+        pending.retain_mut(|future| match future.as_mut().poll(&mut cx) {
+            Poll::Ready(command) => {
+                completed.push(command);
+                false
+            }
+            Poll::Pending => true,
+        });
+
+        completed
+    }
+}
+
+/// Runs computations in the background, applying their results to the
+/// `World` once they are done.
+///
+/// With the `parallel` feature, jobs run on the rayon thread pool; without
+/// it, they run right away on the calling thread. Either way, the results of
+/// completed jobs are collected by the `AsyncStage` and applied during the
+/// following `World::maintain`. If a job panics, the panic is caught and
+/// logged, and its result is never applied.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     world::{AsyncStage, TaskQueue},
+/// };
+///
+/// #[derive(Default)]
+/// struct Path(Vec<u32>);
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with(AsyncStage, "async_stage", &[])
+///     .build();
+/// dispatcher.setup(&mut world);
+/// world.insert(Path::default());
+///
+/// world.fetch::<TaskQueue>().spawn(
+///     || (0..4).collect::<Vec<_>>(), // e.g. path finding
+///     |path, world| world.fetch_mut::<Path>().0 = path,
+/// );
+/// while world.fetch::<Path>().0.is_empty() {
+///     dispatcher.dispatch(&world);
+///     world.maintain();
+/// }
+///
+/// assert_eq!(world.fetch::<Path>().0, [0, 1, 2, 3]);
+/// ```
+#[derive(Default)]
+pub struct TaskQueue {
+    completed: Arc<Mutex<Vec<Command>>>,
+    running: Arc<AtomicUsize>,
+}
+
+impl TaskQueue {
+    /// Runs `job` in the background, then `apply` with its result and the
+    /// `World` during the first `World::maintain` after it completed.
+    pub fn spawn<J, R, A>(&self, job: J, apply: A)
+    where
+        J: FnOnce() -> R + Send + 'static,
+        R: Send + 'static,
+        A: FnOnce(R, &mut World) + Send + 'static,
+    {
+        let completed = self.completed.clone();
+        let running = Running(self.running.clone());
+        let task = move || {
+            // Moved in to be dropped last, even if the job panics.
+            let _running = running;
+            match panic::catch_unwind(AssertUnwindSafe(job)) {
+                Ok(result) => completed
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(Box::new(move |world| apply(result, world))),
+                Err(_) => log::warn!("A `TaskQueue` job panicked, its result is dropped"),
+            }
+        };
+
+        self.running.fetch_add(1, Ordering::AcqRel);
+        #[cfg(feature = "parallel")]
+        rayon::spawn(task);
+        #[cfg(not(feature = "parallel"))]
+        task();
+    }
+
+    /// Returns the number of jobs which are still running.
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::Acquire)
+    }
+
+    /// Takes the results of the completed jobs.
+    fn take_completed(&self) -> Vec<Command> {
+        std::mem::take(&mut *self.completed.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+/// Decrements the number of running jobs of a `TaskQueue` when dropped.
+struct Running(Arc<AtomicUsize>);
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The system polling the `AsyncSystems` and collecting the results of the
+/// `TaskQueue`, queueing them on `LazyUpdate`.
+///
+/// Add it to the dispatcher wherever async work should be picked up, e.g.
+/// at the start or, after a barrier, at the end of a frame. Its results are
+/// applied in the order they were picked up, during the next
+/// `World::maintain`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AsyncStage;
+
+impl<'a> System<'a> for AsyncStage {
+    type SystemData = (
+        Read<'a, AsyncSystems>,
+        Read<'a, TaskQueue>,
+        Read<'a, LazyUpdate>,
+    );
+
+    fn run(&mut self, (systems, tasks, lazy): Self::SystemData) {
+        for command in systems.poll().into_iter().chain(tasks.take_completed()) {
+            // `LazyUpdate` needs `Sync` closures, commands only have to be
+            // `Send`.
+            let command = Mutex::new(command);
+            lazy.exec_mut(move |world| {
+                let command = command.into_inner().unwrap_or_else(|e| e.into_inner());
+                command(world);
+            });
+        }
+    }
+}
+
+fn noop_waker() -> Waker {
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(std::ptr::null(), &VTABLE)
+    }
+
+    fn noop(_: *const ()) {}
+
+    // SAFETY: The functions of the vtable ignore the data pointer, so any
+    // pointer upholds the contract of `RawWaker`.
+    unsafe { Waker::from_raw(clone(std::ptr::null())) }
+}
@@ -71,6 +71,69 @@ fn lazy_removal() {
     assert!(world.read_storage::<Pos>().get(e).is_none());
 }
 
+#[test]
+fn lazy_component_batches() {
+    let mut world = World::new();
+    world.register::<Pos>();
+    world.register::<Vel>();
+
+    let e = world.create_entity().build();
+    {
+        let lazy = world.read_resource::<LazyUpdate>();
+        lazy.insert(e, Pos);
+        lazy.insert(e, Vel);
+        lazy.remove::<Pos>(e);
+        // Closures see the component updates requested before them.
+        lazy.exec(move |world| {
+            assert!(world.read_storage::<Pos>().get(e).is_none());
+            assert!(world.read_storage::<Vel>().get(e).is_some());
+        });
+        lazy.insert(e, Pos);
+        lazy.remove::<Vel>(e);
+    }
+
+    world.maintain();
+    assert!(world.read_storage::<Pos>().get(e).is_some());
+    assert!(world.read_storage::<Vel>().get(e).is_none());
+}
+
+#[test]
+fn lazy_update_of_dead_entity() {
+    let mut world = World::new();
+    world.register::<Pos>();
+    world.register::<Vel>();
+
+    let a = world.create_entity().with(Vel).build();
+    {
+        let lazy = world.read_resource::<LazyUpdate>();
+        world.entities().delete(a).unwrap();
+        lazy.insert(a, Pos);
+        lazy.remove::<Vel>(a);
+    }
+
+    world.maintain();
+    assert_eq!(world.read_storage::<Pos>().count(), 0);
+    let skipped: Vec<_> = world
+        .read_resource::<LazyReport>()
+        .skipped()
+        .iter()
+        .map(|s| (s.entity, s.action))
+        .collect();
+    assert_eq!(skipped, [(a, "insert"), (a, "removal")]);
+
+    // The index of `a` is reused.
+    let b = world.create_entity().build();
+    assert_eq!(b.id(), a.id());
+    world.read_resource::<LazyUpdate>().insert(a, Pos);
+
+    world.maintain();
+    assert!(world.read_storage::<Pos>().get(b).is_none());
+    assert_eq!(world.read_resource::<LazyReport>().skipped().len(), 1);
+
+    world.maintain();
+    assert!(world.read_resource::<LazyReport>().skipped().is_empty());
+}
+
 #[test]
 fn super_lazy_execution() {
     let mut world = World::new();
@@ -134,6 +197,29 @@ fn lazy_execution_order() {
     assert_eq!(&**v, &[1, 2]);
 }
 
+#[test]
+fn lazy_execution_priority() {
+    let mut world = World::new();
+    world.insert(Vec::<u32>::new());
+    {
+        let lazy = world.read_resource::<LazyUpdate>();
+        let push = |n| move |world: &mut World| world.write_resource::<Vec<u32>>().push(n);
+        lazy.execute_with_priority(-1, push(4));
+        lazy.exec(push(2));
+        lazy.execute_with_priority(1, push(1));
+        lazy.exec(push(3));
+        lazy.execute_with_priority(-1, push(5));
+        lazy.exec(move |world| {
+            world
+                .read_resource::<LazyUpdate>()
+                .execute_with_priority(1, push(6));
+        });
+    }
+    world.maintain();
+    let v = world.read_resource::<Vec<u32>>();
+    assert_eq!(&**v, &[1, 2, 3, 4, 5, 6]);
+}
+
 #[test]
 fn delete_twice() {
     let mut world = World::new();
@@ -164,3 +250,1326 @@ fn delete_and_lazy() {
 
     world.delete_all();
 }
+
+#[test]
+fn lazy_resource_dependencies() {
+    struct A(u32);
+    struct B(u32);
+
+    let mut world = World::new();
+    world.register_lazy_resource(|world| {
+        assert!(world.ensure_resource::<A>());
+        B(world.read_resource::<A>().0 + 1)
+    });
+    world.register_lazy_resource(|_| A(1));
+
+    assert!(world.ensure_resource::<B>());
+    assert_eq!(world.read_resource::<A>().0, 1);
+    assert_eq!(world.read_resource::<B>().0, 2);
+}
+
+#[test]
+fn lazy_resource_not_overwritten() {
+    struct A(u32);
+    struct Missing;
+
+    let mut world = World::new();
+    world.insert(A(5));
+    world.register_lazy_resource(|_| A(1));
+    world.init_lazy_resources();
+
+    assert_eq!(world.read_resource::<A>().0, 5);
+    assert!(!world.ensure_resource::<Missing>());
+}
+
+#[test]
+fn lazy_resource_fetches() {
+    use crate::{error::Error, storage::WriteStorage};
+    use shred::{ReadExpect, WriteExpect};
+
+    struct A(u32);
+    struct B(u32);
+
+    let mut world = World::new();
+    world.register_lazy_resource(|_| A(1));
+    world.register_lazy_resource(|_| B(2));
+
+    world.fetch_lazy_mut::<A>().unwrap().0 += 1;
+    assert_eq!(world.fetch_lazy::<A>().unwrap().0, 2);
+    assert!(matches!(
+        world.fetch_lazy::<Pos>(),
+        Err(Error::NotRegistered(_))
+    ));
+
+    assert!(world
+        .system_data_lazy::<(WriteExpect<B>, WriteStorage<Pos>)>()
+        .is_err());
+    assert!(world.has_value::<B>());
+    world.register::<Pos>();
+    let (b, a, pos) = world
+        .system_data_lazy::<(ReadExpect<B>, WriteExpect<A>, WriteStorage<Pos>)>()
+        .unwrap();
+    assert_eq!((b.0, a.0, pos.count()), (2, 2, 0));
+}
+
+#[test]
+fn lazy_resource_setup() {
+    use super::{ReadLazy, WriteLazy};
+
+    struct A(u32);
+    struct B(u32);
+
+    let mut world = World::new();
+    world.register_lazy_resource(|_| A(1));
+    world.register_lazy_resource(|world| {
+        assert!(world.ensure_resource::<A>());
+        B(world.read_resource::<A>().0 + 1)
+    });
+
+    assert_eq!(world.setup_for::<WriteLazy<B>>().len(), 1);
+    assert!(world.has_value::<A>());
+    world.exec(|(a, mut b): (ReadLazy<A>, WriteLazy<B>)| b.0 += a.0);
+    assert_eq!(world.read_resource::<B>().0, 3);
+}
+
+#[test]
+#[should_panic(expected = "Cycle detected")]
+fn lazy_resource_cycle() {
+    struct A;
+    struct B;
+
+    let mut world = World::new();
+    world.register_lazy_resource(|world| {
+        world.ensure_resource::<B>();
+        A
+    });
+    world.register_lazy_resource(|world| {
+        world.ensure_resource::<A>();
+        B
+    });
+    world.init_lazy_resources();
+}
+
+#[test]
+fn remove_components() {
+    let mut world = World::new();
+    world.register::<Pos>();
+    world.register::<Vel>();
+
+    let a = world.create_entity().with(Pos).with(Vel).build();
+    let b = world.create_entity().with(Pos).with(Vel).build();
+
+    let (pos, vel) = world.remove_components::<(Pos, Vel)>(a);
+    assert!(pos.is_some() && vel.is_some());
+    let (pos,) = world.remove_components::<(Pos,)>(a);
+    assert!(pos.is_none());
+
+    world
+        .read_resource::<LazyUpdate>()
+        .remove_components::<(Pos, Vel)>(b);
+    assert!(world.read_storage::<Vel>().get(b).is_some());
+    world.maintain();
+    assert!(world.read_storage::<Pos>().get(b).is_none());
+    assert!(world.read_storage::<Vel>().get(b).is_none());
+}
+
+#[test]
+fn reserve_entity() {
+    struct Partner(Entity);
+
+    impl Component for Partner {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register::<Partner>();
+
+    let a = world.reserve_entity();
+    let b = world.reserve_entity();
+    assert!(world.is_alive(a));
+    assert_eq!(world.entities().join().count(), 2);
+
+    EntityBuilder::for_entity(&mut world, a)
+        .with(Partner(b))
+        .build();
+    EntityBuilder::for_entity(&mut world, b)
+        .with(Partner(a))
+        .build();
+
+    let partners = world.read_storage::<Partner>();
+    assert_eq!(partners.get(a).unwrap().0, b);
+    assert_eq!(partners.get(b).unwrap().0, a);
+}
+
+#[test]
+fn reserved_entity_kept_without_build() {
+    let mut world = World::new();
+    world.register::<Pos>();
+
+    let a = world.reserve_entity();
+    drop(EntityBuilder::for_entity(&mut world, a).with(Pos));
+    world.maintain();
+    assert!(world.is_alive(a));
+    assert!(world.read_storage::<Pos>().contains(a));
+
+    drop(world.create_entity().with(Pos));
+    world.maintain();
+    assert_eq!(world.entities().join().count(), 1);
+}
+
+#[test]
+#[should_panic(expected = "not alive")]
+fn for_dead_entity() {
+    let mut world = World::new();
+
+    let a = world.reserve_entity();
+    world.delete_entity(a).unwrap();
+    let _ = EntityBuilder::for_entity(&mut world, a);
+}
+
+#[test]
+fn entities_changed_this_maintain() {
+    let mut world = World::new();
+
+    let a = world.create_entity().build();
+    let b = world.entities().create();
+    let c = world.entities().create();
+    world.delete_entity(c).unwrap();
+    world.maintain();
+    assert_eq!(world.entities().created_this_maintain(), &[a, c, b]);
+    assert_eq!(world.entities().deleted_this_maintain(), &[c]);
+
+    world.entities().delete(a).unwrap();
+    world.delete_entity(b).unwrap();
+    let d = world.create_entity().build();
+    world.maintain();
+    assert_eq!(world.entities().created_this_maintain(), &[d]);
+    assert_eq!(world.entities().deleted_this_maintain(), &[b, a]);
+    assert!(!world.is_alive(c));
+
+    world.maintain();
+    assert!(world.entities().created_this_maintain().is_empty());
+    assert!(world.entities().deleted_this_maintain().is_empty());
+}
+
+#[test]
+fn copy_components_from() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Count(u32);
+
+    impl Component for Count {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Name(&'static str);
+
+    impl Component for Name {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register_cloneable::<Count>();
+    world.register_cloneable::<Name>();
+    world.register::<Pos>();
+
+    let src = world
+        .create_entity()
+        .with(Count(5))
+        .with(Name("sword"))
+        .with(Pos)
+        .build();
+    let dst = world.create_entity().with(Name("shield")).build();
+
+    let filter = ComponentFilter::all().without::<Name>();
+    assert_eq!(world.copy_components_from(src, dst, filter), Ok(1));
+    assert_eq!(world.read_storage::<Count>().get(dst), Some(&Count(5)));
+    assert_eq!(world.read_storage::<Name>().get(dst), Some(&Name("shield")));
+    // Not cloneable.
+    assert!(!world.read_storage::<Pos>().contains(dst));
+
+    assert_eq!(
+        world.copy_components_from(src, dst, ComponentFilter::all()),
+        Ok(2)
+    );
+    assert_eq!(world.read_storage::<Name>().get(dst), Some(&Name("sword")));
+
+    world.delete_entity(dst).unwrap();
+    let err = world
+        .copy_components_from(src, dst, ComponentFilter::all())
+        .unwrap_err();
+    assert_eq!(err.entity, dst);
+}
+
+#[test]
+fn setup_for() {
+    use crate::storage::{MaskedStorage, ReadStorage, WriteStorage};
+    use shred::{Read, ReadExpect, ResourceId};
+
+    #[derive(Default)]
+    struct Frame;
+    struct Config;
+
+    let mut world = World::new();
+    world.register::<Vel>();
+
+    type Data<'a> = (
+        ReadStorage<'a, Pos>,
+        WriteStorage<'a, Vel>,
+        ReadStorage<'a, Pos>,
+        Read<'a, Frame>,
+        ReadExpect<'a, Config>,
+    );
+    // `ReadExpect` can't be set up, so `Config` isn't created.
+    assert_eq!(
+        world.setup_for::<Data>(),
+        [
+            ResourceId::new::<MaskedStorage<Pos>>(),
+            ResourceId::new::<Frame>(),
+        ]
+    );
+    assert!(!world.has_value::<Config>());
+
+    world.insert(Config);
+    assert!(world.setup_for::<Data>().is_empty());
+    world.exec(|(pos, vel, ..): Data| {
+        assert_eq!(pos.count(), 0);
+        assert_eq!(vel.count(), 0);
+    });
+}
+
+#[test]
+fn install_plugins() {
+    use shred::DispatcherBuilder;
+
+    #[derive(Default)]
+    struct Installs(Vec<&'static str>);
+
+    fn log(world: &mut World, name: &'static str) {
+        world
+            .entry::<Installs>()
+            .or_insert_with(Default::default)
+            .0
+            .push(name);
+    }
+
+    struct Base;
+
+    impl WorldPlugin for Base {
+        fn install(&self, world: &mut World, _: &mut DispatcherBuilder) {
+            world.register::<Pos>();
+            log(world, "base");
+        }
+    }
+
+    struct Physics(&'static str);
+
+    impl WorldPlugin for Physics {
+        fn install(&self, world: &mut World, dispatcher: &mut DispatcherBuilder) {
+            world.install(Base, dispatcher);
+            world.register::<Vel>();
+            log(world, self.0);
+        }
+    }
+
+    let mut world = World::new();
+    let mut dispatcher = DispatcherBuilder::new();
+    assert!(world.install(Physics("physics"), &mut dispatcher));
+    assert!(!world.install(Physics("again"), &mut dispatcher));
+    assert!(!world.install(Base, &mut dispatcher));
+
+    assert_eq!(world.fetch::<Installs>().0, ["base", "physics"]);
+    world.create_entity().with(Pos).with(Vel).build();
+}
+
+#[test]
+fn maintain_hooks() {
+    #[derive(Default)]
+    struct Seen(Vec<(MaintainPhase, usize, usize)>);
+
+    fn record(world: &mut World, phase: MaintainPhase) {
+        let entities = world.entities().join().count();
+        let pos = world.read_storage::<Pos>().join().count();
+        world
+            .write_resource::<Seen>()
+            .0
+            .push((phase, entities, pos));
+    }
+
+    let mut world = World::new();
+    world.register::<Pos>();
+    world.insert(Seen::default());
+    world.on_maintain(MaintainPhase::AfterLazy, |w| {
+        record(w, MaintainPhase::AfterLazy)
+    });
+    world.on_maintain(MaintainPhase::BeforeLazy, |w| {
+        record(w, MaintainPhase::BeforeLazy)
+    });
+    world.on_maintain(MaintainPhase::AfterDeletes, |w| {
+        record(w, MaintainPhase::AfterDeletes)
+    });
+    world.on_maintain(MaintainPhase::BeforeDeletes, |w| {
+        record(w, MaintainPhase::BeforeDeletes)
+    });
+
+    let e = world.create_entity().with(Pos).build();
+    world.entities().delete(e).unwrap();
+    let lazy = world.create_entity().build();
+    world.read_resource::<LazyUpdate>().insert(lazy, Pos);
+    world.maintain();
+
+    assert_eq!(
+        world.read_resource::<Seen>().0,
+        [
+            (MaintainPhase::BeforeDeletes, 2, 1),
+            (MaintainPhase::AfterDeletes, 1, 0),
+            (MaintainPhase::BeforeLazy, 1, 0),
+            (MaintainPhase::AfterLazy, 1, 1),
+        ]
+    );
+}
+
+#[test]
+#[cfg(feature = "tracking")]
+fn world_events() {
+    use std::any::{type_name, TypeId};
+
+    let mut world = World::new();
+    let mut reader = world.write_resource::<WorldEvents>().register_reader();
+
+    world.register::<Pos>();
+    world.register::<Pos>();
+    let a = world.create_entity().build();
+    let b = world.entities().create();
+    world.maintain();
+    world.entities().delete(a).unwrap();
+    world.delete_entity(b).unwrap();
+    world.maintain();
+
+    let events: Vec<_> = world
+        .read_resource::<WorldEvents>()
+        .read(&mut reader)
+        .cloned()
+        .collect();
+    assert_eq!(
+        events,
+        [
+            WorldEvent::ComponentRegistered {
+                type_id: TypeId::of::<Pos>(),
+                name: type_name::<Pos>(),
+            },
+            WorldEvent::EntityCreated(a),
+            WorldEvent::EntityCreated(b),
+            WorldEvent::EntityDeleted(b),
+            WorldEvent::EntityDeleted(a),
+        ]
+    );
+}
+
+#[test]
+fn frame_scratch() {
+    let mut world = World::new();
+    let a = world.create_entity().build();
+    let b = world.create_entity().build();
+
+    {
+        let mut scratch = world.write_resource::<FrameScratch>();
+        scratch.alloc(b, 1u32);
+        scratch.alloc(a, 2u32);
+        *scratch.alloc(b, 3u32) += 1;
+        scratch.alloc(a, "a");
+
+        assert_eq!(scratch.get::<u32>(b), Some(&4));
+        assert_eq!(scratch.get::<&str>(a), Some(&"a"));
+        assert_eq!(scratch.get::<&str>(b), None);
+        assert_eq!(scratch.get::<u64>(a), None);
+        assert_eq!(
+            scratch.iter::<u32>().collect::<Vec<_>>(),
+            [(b, &4), (a, &2)]
+        );
+    }
+
+    world.maintain();
+    let scratch = world.read_resource::<FrameScratch>();
+    assert_eq!(scratch.get::<u32>(a), None);
+    assert_eq!(scratch.iter::<u32>().count(), 0);
+}
+
+#[test]
+fn try_exec() {
+    use crate::{
+        error::Error,
+        storage::{MaskedStorage, ReadStorage, WriteStorage},
+    };
+    use shred::{Read, Write};
+
+    #[derive(Default)]
+    struct Frame(u32);
+
+    let mut world = World::new();
+    world.register::<Vel>();
+    let vel = std::any::type_name::<Vel>();
+    match world.try_exec(|_: (Entities, WriteStorage<Vel>, WriteStorage<Vel>)| {}) {
+        Err(Error::BorrowConflict(e)) => {
+            assert_eq!(e.resource, format!("the storage of `{}`", vel));
+            assert!(e.system_data.contains(vel));
+        }
+        r => panic!("unexpected result {:?}", r),
+    }
+    let err = world.try_exec(|_: (ReadStorage<Pos>, Write<Frame>, Read<Frame>)| {});
+    assert!(err.is_err());
+    assert!(!world.has_value::<MaskedStorage<Pos>>());
+    assert!(!world.has_value::<Frame>());
+
+    match world.try_exec(|_: ReadStorage<Pos>| {}) {
+        Err(Error::NotRegistered(_)) => {}
+        r => panic!("unexpected result {:?}", r),
+    }
+    let vel_mut = world.write_storage::<Vel>();
+    match world.try_exec(|_: ReadStorage<Vel>| {}) {
+        Err(Error::BorrowConflict(e)) => {
+            assert_eq!(e.resource, format!("the storage of `{}`", vel));
+        }
+        r => panic!("unexpected result {:?}", r),
+    }
+    drop(vel_mut);
+    assert!(world.try_exec(|_: ReadStorage<Vel>| {}).is_ok());
+
+    let pos = world.exec_mut(true, |world| {
+        world.register::<Pos>();
+        world.create_entity().with(Pos).build();
+        world.setup_for::<Write<Frame>>();
+        world.try_exec(|(pos, mut frame): (ReadStorage<Pos>, Write<Frame>)| {
+            frame.0 += 1;
+            pos.count()
+        })
+    });
+    assert_eq!(pos.unwrap(), 1);
+    assert_eq!(world.read_resource::<Frame>().0, 1);
+}
+
+#[test]
+fn assert_distinct_system_data() {
+    use crate::storage::{ReadStorage, WriteStorage};
+
+    super::assert_distinct_system_data::<(Entities, ReadStorage<Pos>, WriteStorage<Vel>)>();
+    let conflict = std::panic::catch_unwind(|| {
+        super::assert_distinct_system_data::<(ReadStorage<Pos>, WriteStorage<Pos>)>();
+    });
+    assert!(conflict.is_err());
+}
+
+#[test]
+fn singleton_cleared_at_maintain() {
+    struct Player;
+    struct Camera;
+
+    let mut world = World::new();
+    let a = world.create_entity().build();
+    let b = world.create_entity().build();
+    assert_eq!(world.set_singleton::<Player>(a), Ok(None));
+    assert_eq!(world.set_singleton::<Camera>(b), Ok(None));
+    assert_eq!(world.set_singleton::<Player>(b), Ok(Some(a)));
+    assert_eq!(world.set_singleton::<Player>(a), Ok(Some(b)));
+
+    world.entities().delete(a).unwrap();
+    assert_eq!(world.singleton::<Player>(), Some(a));
+    world.maintain();
+    assert_eq!(world.singleton::<Player>(), None);
+    assert_eq!(world.singleton::<Camera>(), Some(b));
+    assert!(world.set_singleton::<Player>(a).is_err());
+
+    assert_eq!(world.remove_singleton::<Camera>(), Some(b));
+    assert!(world.is_alive(b));
+}
+
+#[test]
+#[cfg(feature = "tracking")]
+fn replication_system() {
+    use crate::storage::FlaggedStorage;
+    use shred::DispatcherBuilder;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Health(u32);
+
+    impl Component for Health {
+        type Storage = FlaggedStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register::<Health>();
+    let a = world.create_entity().with(Health(1)).build();
+    let b = world.create_entity().with(Health(2)).build();
+
+    let mut replication = ReplicationSystem::new(MemorySink::default()).with_component::<Health>();
+    replication.setup(&mut world);
+    assert_eq!(replication.sink().get::<Health>(a), Some(&Health(1)));
+    assert_eq!(replication.sink().len(), 2);
+
+    let c = world.create_entity().with(Health(3)).build();
+    world.write_storage::<Health>().get_mut(a).unwrap().0 = 4;
+    world.write_storage::<Health>().remove(b);
+    world.delete_entity(c).unwrap();
+    world.maintain();
+    replication.run(&mut world);
+    assert_eq!(replication.sink().get::<Health>(a), Some(&Health(4)));
+    assert!(!replication.sink().contains(b));
+    assert!(!replication.sink().contains(c));
+
+    struct Log(Arc<Mutex<Vec<String>>>);
+
+    impl ReplicationSink for Log {
+        fn on_entity_delete(&mut self, entity: Entity) {
+            self.0.lock().unwrap().push(format!("delete {}", entity.id()));
+        }
+    }
+
+    impl ComponentSink<Health> for Log {
+        fn on_insert(&mut self, entity: Entity, component: &Health) {
+            let event = format!("insert {} {}", entity.id(), component.0);
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_modify(&mut self, entity: Entity, component: &Health) {
+            let event = format!("modify {} {}", entity.id(), component.0);
+            self.0.lock().unwrap().push(event);
+        }
+
+        fn on_remove(&mut self, entity: Entity) {
+            self.0.lock().unwrap().push(format!("remove {}", entity.id()));
+        }
+    }
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut dispatcher = DispatcherBuilder::new()
+        .with_exclusive(
+            ReplicationSystem::new(Log(log.clone())).with_component::<Health>(),
+            "replication",
+            &[],
+        )
+        .build();
+    dispatcher.setup(&mut world);
+    world.delete_entity(a).unwrap();
+    let d = world.create_entity().with(Health(5)).build();
+    world.maintain();
+    dispatcher.dispatch_exclusive(&mut world);
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            format!("insert {} 4", a.id()),
+            format!("insert {} 5", d.id()),
+            format!("delete {}", a.id()),
+        ]
+    );
+}
+
+#[test]
+fn transaction() {
+    use crate::error::TransactionError;
+
+    struct Unregistered;
+
+    impl Component for Unregistered {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register::<Pos>();
+    let a = world.create_entity().build();
+
+    let err = world
+        .transaction(|tx| {
+            tx.insert(a, Pos);
+            tx.insert(a, Unregistered);
+        })
+        .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::Unregistered {
+            action: "insert",
+            component: std::any::type_name::<Unregistered>(),
+        }
+    );
+    assert!(world.read_storage::<Pos>().get(a).is_none());
+
+    let err = world.transaction(|tx| {
+        tx.delete(a);
+        tx.insert(a, Pos);
+        tx.error().is_some()
+    });
+    assert_eq!(
+        err,
+        Err(TransactionError::DeadEntity {
+            action: "insert",
+            entity: a,
+        })
+    );
+    assert!(world.is_alive(a));
+
+    assert_eq!(world.transaction(|tx| tx.insert(a, Pos)), Ok(()));
+    assert!(world.read_storage::<Pos>().contains(a));
+
+    #[derive(Clone)]
+    struct Leased;
+
+    impl Component for Leased {
+        type Storage = VecStorage<Self>;
+    }
+
+    world.register::<Leased>();
+    let lease = world.read_storage::<Leased>().lease();
+    let err = world
+        .transaction(|tx| {
+            tx.remove::<Pos>(a);
+            tx.insert(a, Leased);
+        })
+        .unwrap_err();
+    assert!(matches!(err, TransactionError::LeaseConflict(_)));
+    assert!(world.read_storage::<Pos>().contains(a));
+    drop(lease);
+}
+
+#[test]
+fn ttl_system() {
+    use shred::{DispatcherBuilder, RunNow};
+
+    let mut world = World::new();
+    let mut ttl = TtlSystem;
+    ttl.setup(&mut world);
+
+    let frame = world.create_entity().with(EntityTtl(1.0)).build();
+    let timed = world.create_entity().with(EntityTtl(0.5)).build();
+    let other = world.create_entity().build();
+
+    world.insert(DeltaTime(0.25));
+    ttl.run_now(&world);
+    world.maintain();
+    assert!(world.is_alive(frame));
+    assert!(world.is_alive(timed));
+    assert_eq!(world.read_storage::<EntityTtl>().get(timed), Some(&EntityTtl(0.25)));
+
+    ttl.run_now(&world);
+    world.maintain();
+    assert!(world.is_alive(frame));
+    assert!(!world.is_alive(timed));
+
+    world.remove::<DeltaTime>();
+    let mut dispatcher = DispatcherBuilder::new().with(TtlSystem, "ttl", &[]).build();
+    dispatcher.dispatch(&world);
+    world.maintain();
+    assert!(!world.is_alive(frame));
+    assert!(world.is_alive(other));
+}
+
+#[test]
+fn async_stage() {
+    use std::{
+        future::Future,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use shred::RunNow;
+
+    /// Completes on its third poll.
+    struct Countdown(u32);
+
+    impl Future for Countdown {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut Context) -> Poll<()> {
+            self.0 -= 1;
+            if self.0 == 0 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    let mut world = World::new();
+    let mut stage = AsyncStage;
+    stage.setup(&mut world);
+    world.insert(Log::default());
+
+    world.fetch::<AsyncSystems>().register(async {
+        Countdown(3).await;
+        |world: &mut World| world.fetch_mut::<Log>().0.push("future")
+    });
+    world
+        .fetch::<TaskQueue>()
+        .spawn(|| "task", |name, world| world.fetch_mut::<Log>().0.push(name));
+    world.fetch::<TaskQueue>().spawn(
+        || -> &'static str { panic!("job failed") },
+        |name, world| world.fetch_mut::<Log>().0.push(name),
+    );
+
+    for _ in 0..2 {
+        stage.run_now(&world);
+        world.maintain();
+        assert_eq!(world.fetch::<AsyncSystems>().len(), 1);
+    }
+    stage.run_now(&world);
+    assert!(world.fetch::<AsyncSystems>().is_empty());
+    assert!(!world.fetch::<Log>().0.contains(&"future"));
+    world.maintain();
+    assert!(world.fetch::<Log>().0.contains(&"future"));
+
+    while world.fetch::<TaskQueue>().running() > 0 {
+        std::thread::yield_now();
+    }
+    stage.run_now(&world);
+    world.maintain();
+    // Without the `parallel` feature, the task completes before the future.
+    let mut log = world.fetch::<Log>().0.clone();
+    log.sort_unstable();
+    assert_eq!(log, ["future", "task"]);
+}
+
+#[test]
+fn analyze_layout() {
+    use crate::storage::{HashMapStorage, NullStorage};
+
+    #[derive(Default)]
+    struct Tag;
+
+    impl Component for Tag {
+        type Storage = NullStorage<Self>;
+    }
+
+    struct Rare(#[allow(dead_code)] u32);
+
+    impl Component for Rare {
+        type Storage = HashMapStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register::<Pos>();
+    world.register::<Vel>();
+    world.register::<Rare>();
+    world.register::<Tag>();
+    for i in 0..200 {
+        let builder = world.create_entity().with(Pos);
+        match i {
+            0 => builder.with(Rare(i)).build(),
+            1..=99 => builder.with(Vel).build(),
+            _ => builder.build(),
+        };
+    }
+
+    let report = world.analyze_layout();
+    assert_eq!(report.entities, 200);
+    assert_eq!(report.sampled, 200);
+    let components: Vec<_> = report
+        .components
+        .iter()
+        .map(|c| (c.count, c.suggestion))
+        .collect();
+    assert_eq!(
+        components,
+        [
+            (200, StorageSuggestion::Null),
+            (99, StorageSuggestion::Null),
+            (1, StorageSuggestion::HashMap),
+            (0, StorageSuggestion::Null),
+        ]
+    );
+    assert_eq!(report.groups.len(), 1);
+    assert_eq!(report.groups[0].count, 99);
+    assert!(report.groups[0].components[1].ends_with("Vel"));
+    assert!(report.to_string().contains("% of entities -> HashMapStorage"));
+}
+
+#[test]
+fn drop_order() {
+    use std::{
+        any::TypeId,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::storage::{DenseVecStorage, HashMapStorage};
+
+    type Log = Arc<Mutex<Vec<&'static str>>>;
+
+    struct Device(Log);
+
+    impl Component for Device {
+        type Storage = HashMapStorage<Self>;
+    }
+
+    impl Drop for Device {
+        fn drop(&mut self) {
+            self.0.lock().unwrap().push("device");
+        }
+    }
+
+    struct Buffer(Log);
+
+    impl Component for Buffer {
+        type Storage = DenseVecStorage<Self>;
+    }
+
+    impl Drop for Buffer {
+        fn drop(&mut self) {
+            self.0.lock().unwrap().push("buffer");
+        }
+    }
+
+    let log = Log::default();
+    let mut world = World::new();
+    world.register::<Device>();
+    world.register::<Buffer>();
+    world.set_drop_order(&[TypeId::of::<Buffer>(), TypeId::of::<Pos>(), TypeId::of::<Device>()]);
+
+    let e = world
+        .create_entity()
+        .with(Device(log.clone()))
+        .with(Buffer(log.clone()))
+        .build();
+    world.delete_entity(e).unwrap();
+    world
+        .create_entity()
+        .with(Device(log.clone()))
+        .with(Buffer(log.clone()))
+        .build();
+    world.delete_all();
+
+    assert_eq!(*log.lock().unwrap(), ["buffer", "device", "buffer", "device"]);
+}
+
+#[test]
+fn not_registered() {
+    use crate::error::{Error, NotRegistered};
+    use std::error::Error as StdError;
+
+    let mut world = World::new();
+    match world.try_write_storage::<Pos>().err().unwrap() {
+        Error::NotRegistered(e) => assert_eq!(e, NotRegistered::new::<Pos>()),
+        e => panic!("unexpected error {:?}", e),
+    }
+    let e = world.try_read_resource::<u32>().err().unwrap();
+    assert!(e.to_string().contains("`u32`"));
+    assert!(e.source().unwrap().is::<NotRegistered>());
+
+    world.register::<Pos>();
+    world.insert(5u32);
+    let e = world.create_entity().with(Pos).build();
+    assert!(world.try_read_storage::<Pos>().unwrap().contains(e));
+    *world.try_write_resource::<u32>().unwrap() += 1;
+    assert_eq!(*world.try_read_resource::<u32>().unwrap(), 6);
+}
+
+#[test]
+fn try_fetch_borrow_conflict() {
+    use crate::error::Error;
+
+    let mut world = World::new();
+    world.register::<Pos>();
+    world.insert(5u32);
+
+    let storage = world.write_storage::<Pos>();
+    match world.try_read_storage::<Pos>().err().unwrap() {
+        Error::BorrowConflict(e) => {
+            assert_eq!(
+                e.resource,
+                format!("the storage of `{}`", std::any::type_name::<Pos>())
+            );
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+    drop(storage);
+
+    let value = world.read_resource::<u32>();
+    assert!(world.try_read_resource::<u32>().is_ok());
+    match world.try_write_resource::<u32>().err().unwrap() {
+        Error::BorrowConflict(_) => {}
+        e => panic!("unexpected error {:?}", e),
+    }
+    drop(value);
+
+    let entities = world.write_resource::<EntitiesRes>();
+    assert!(world.try_write_storage::<Pos>().is_err());
+    drop(entities);
+    assert!(world.try_write_storage::<Pos>().is_ok());
+}
+
+#[test]
+fn try_for_entity() {
+    let mut world = World::new();
+    world.register::<Pos>();
+
+    let dead = world.create_entity().build();
+    world.delete_entity(dead).unwrap();
+    let e = EntityBuilder::try_for_entity(&mut world, dead).err().unwrap();
+    assert_eq!((e.action, e.entity), ("build", dead));
+
+    let alive = world.reserve_entity();
+    EntityBuilder::try_for_entity(&mut world, alive)
+        .unwrap()
+        .with(Pos)
+        .build();
+    assert!(world.read_storage::<Pos>().contains(alive));
+}
+
+#[test]
+fn history_step_back() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hp(u32);
+
+    impl Component for Hp {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Mesh(u32);
+
+    impl Component for Mesh {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register_cloneable::<Hp>();
+    world.register_cloneable::<Mesh>();
+    let a = world.create_entity().with(Hp(10)).with(Mesh(0)).build();
+    let b = world.create_entity().with(Hp(5)).build();
+
+    let mut history = History::with_filter(3, ComponentFilter::all().without::<Mesh>());
+    assert!(!history.step_back(&mut world, 0));
+    for frame in 1..=4 {
+        world.write_storage::<Hp>().get_mut(a).unwrap().0 -= 1;
+        world.write_storage::<Mesh>().insert(a, Mesh(frame)).unwrap();
+        if frame == 3 {
+            world.write_storage::<Hp>().remove(b);
+        }
+        history.capture(&world);
+    }
+    assert_eq!(history.len(), 3);
+    assert!(!history.step_back(&mut world, 3));
+
+    world.delete_entity(b).unwrap();
+    let c = world.create_entity().with(Hp(1)).build();
+    assert!(history.step_back(&mut world, 2));
+    assert_eq!(history.len(), 1);
+    let hp = world.read_storage::<Hp>();
+    assert_eq!(hp.get(a), Some(&Hp(8)));
+    assert_eq!(hp.get(b), None);
+    assert_eq!(hp.get(c), None);
+    assert_eq!(world.read_storage::<Mesh>().get(a), Some(&Mesh(4)));
+}
+
+#[test]
+#[cfg(feature = "tracking")]
+fn history_track() {
+    use crate::storage::FlaggedStorage;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hp(u32);
+
+    impl Component for Hp {
+        type Storage = FlaggedStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register_cloneable::<Hp>();
+    let a = world.create_entity().with(Hp(10)).build();
+    let b = world.create_entity().with(Hp(5)).build();
+
+    let mut history = History::new(3);
+    history.track::<Hp>(&world);
+    for frame in 1..=4 {
+        world.write_storage::<Hp>().get_mut(a).unwrap().0 -= 1;
+        if frame == 3 {
+            world.write_storage::<Hp>().remove(b);
+        }
+        history.capture(&world);
+    }
+    assert_eq!(history.len(), 3);
+
+    world.delete_entity(b).unwrap();
+    let c = world.create_entity().with(Hp(1)).build();
+    world.write_storage::<Hp>().get_mut(a).unwrap().0 = 0;
+    assert!(history.step_back(&mut world, 0));
+    assert_eq!(world.read_storage::<Hp>().get(a), Some(&Hp(6)));
+    assert_eq!(world.read_storage::<Hp>().get(c), None);
+
+    assert!(history.step_back(&mut world, 2));
+    assert_eq!(history.len(), 1);
+    assert_eq!(world.read_storage::<Hp>().get(a), Some(&Hp(8)));
+    assert_eq!(world.read_storage::<Hp>().count(), 1);
+
+    // Capturing continues from the restored snapshot.
+    world.write_storage::<Hp>().get_mut(a).unwrap().0 = 20;
+    history.capture(&world);
+    world.write_storage::<Hp>().get_mut(a).unwrap().0 = 30;
+    assert!(history.step_back(&mut world, 1));
+    assert_eq!(world.read_storage::<Hp>().get(a), Some(&Hp(8)));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn register_validator() {
+    use crate::error::{Error, InsertError, TransactionError};
+
+    struct Health(i32);
+
+    impl Component for Health {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register_validator(|hp: &Health| {
+        if hp.0 < 0 {
+            Err(format!("negative health {}", hp.0))
+        } else {
+            Ok(())
+        }
+    });
+    world.register_validator(|hp: &Health| {
+        if hp.0 > 100 {
+            Err("too much health".to_string())
+        } else {
+            Ok(())
+        }
+    });
+
+    let a = world.create_entity().with(Health(-1)).build();
+    let b = world.create_entity().with(Health(10)).build();
+    assert!(!world.read_storage::<Health>().contains(a));
+
+    match world.write_storage::<Health>().insert(b, Health(101)) {
+        Err(Error::InvalidComponent(e)) => {
+            assert_eq!(e.entity, b);
+            assert_eq!(e.reason, "too much health");
+        }
+        r => panic!("unexpected result {:?}", r.map(|_| ())),
+    }
+    assert_eq!(world.read_storage::<Health>().get(b).unwrap().0, 10);
+
+    let err = world
+        .transaction(|tx| {
+            tx.insert(a, Health(5));
+            tx.insert(b, Health(-1));
+        })
+        .unwrap_err();
+    assert!(matches!(err, TransactionError::Invalid(ref e) if e.entity == b));
+    assert!(!world.read_storage::<Health>().contains(a));
+
+    {
+        let mut health = world.write_storage::<Health>();
+        let err = health.insert_new(a, Health(-2)).unwrap_err();
+        assert!(matches!(err, InsertError::Invalid { .. }));
+        assert!(matches!(
+            health.insert_with(a, || Health(-3)),
+            Err(Error::InvalidComponent(_))
+        ));
+        assert!(!health.contains(a));
+    }
+
+    world.read_resource::<LazyUpdate>().insert(a, Health(-5));
+    world.read_resource::<LazyUpdate>().insert(b, Health(20));
+    world.maintain();
+    let health = world.read_storage::<Health>();
+    assert!(!health.contains(a));
+    assert_eq!(health.get(b).unwrap().0, 20);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "negative health")]
+fn register_validator_entry() {
+    struct Health(i32);
+
+    impl Component for Health {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register_validator(|hp: &Health| {
+        if hp.0 < 0 {
+            Err(format!("negative health {}", hp.0))
+        } else {
+            Ok(())
+        }
+    });
+
+    let a = world.create_entity().build();
+    let mut health = world.write_storage::<Health>();
+    health.entry(a).unwrap().or_insert(Health(-1));
+}
+
+#[test]
+fn system_groups() {
+    use shred::{DispatcherBuilder, System, Write};
+
+    #[derive(Default)]
+    struct Runs(Vec<&'static str>);
+
+    struct Record(&'static str);
+
+    impl<'a> System<'a> for Record {
+        type SystemData = Write<'a, Runs>;
+
+        fn run(&mut self, mut runs: Self::SystemData) {
+            runs.0.push(self.0);
+        }
+    }
+
+    let mut world = World::new();
+    let mut dispatcher = DispatcherBuilder::new()
+        .with(Record("core"), "core", &[])
+        .with_group("audio", |group| {
+            assert_eq!(group.name(), "audio");
+            group
+                .with(Record("music"), "music", &["core"])
+                .with(Record("sfx"), "sfx", &["music"]);
+        })
+        .with_group("audio", |group| {
+            group.with(Record("voice"), "voice", &["sfx"]);
+        })
+        .build();
+    dispatcher.setup(&mut world);
+
+    dispatcher.dispatch_seq(&world);
+    assert_eq!(world.read_resource::<Runs>().0, ["core", "music", "sfx", "voice"]);
+
+    let mut groups = world.write_resource::<SystemGroups>();
+    assert!(groups.disable_group("audio"));
+    assert!(!groups.disable_group("physics"));
+    assert_eq!(groups.is_group_enabled("audio"), Some(false));
+    assert_eq!(groups.names().collect::<Vec<_>>(), ["audio"]);
+    drop(groups);
+
+    world.write_resource::<Runs>().0.clear();
+    dispatcher.dispatch_seq(&world);
+    assert_eq!(world.read_resource::<Runs>().0, ["core"]);
+
+    world.write_resource::<SystemGroups>().enable_group("audio");
+    world.write_resource::<Runs>().0.clear();
+    dispatcher.dispatch_seq(&world);
+    assert_eq!(world.read_resource::<Runs>().0.len(), 4);
+}
+
+#[test]
+fn fork() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hp(u32);
+
+    impl Component for Hp {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Clone, Default)]
+    struct Seed(u64);
+
+    let mut world = World::new();
+    world.register_cloneable::<Hp>();
+    world.register::<Pos>();
+    world.register_cloneable_resource::<Seed>();
+    world.insert(Seed(7));
+    let a = world.create_entity().with(Hp(10)).with(Pos).build();
+    let dead = world.create_entity().with(Hp(1)).build();
+    world.delete_entity(dead).unwrap();
+    let pending = world.entities().create();
+
+    let mut fork = world.fork();
+    assert!(fork.entities().is_alive(a));
+    assert!(!fork.entities().is_alive(dead));
+    assert!(fork.entities().is_alive(pending));
+    assert_eq!(fork.read_storage::<Hp>().get(a), Some(&Hp(10)));
+    assert_eq!(fork.read_storage::<Hp>().count(), 1);
+    assert!(!fork.has_value::<crate::storage::MaskedStorage<Pos>>());
+    assert_eq!(fork.read_resource::<Seed>().0, 7);
+
+    fork.maintain();
+    fork.write_storage::<Hp>().insert(pending, Hp(3)).unwrap();
+    fork.delete_entity(a).unwrap();
+
+    let fork2 = fork.fork();
+    assert_eq!(fork2.read_storage::<Hp>().get(pending), Some(&Hp(3)));
+    assert_eq!(fork2.read_resource::<Seed>().0, 7);
+
+    world.maintain();
+    assert_eq!(world.read_storage::<Hp>().get(a), Some(&Hp(10)));
+    assert_eq!(world.read_storage::<Hp>().get(pending), None);
+    assert!(world.read_storage::<Pos>().contains(a));
+}
+
+#[test]
+fn fork_storage_config() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::storage::StorageHooks;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hp(u32);
+
+    impl Component for Hp {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register_cloneable::<Hp>();
+    world.register_with_ctor(|_| Hp(100));
+    world.register_validator(|hp: &Hp| match hp.0 {
+        0 => Err("dead".to_string()),
+        _ => Ok(()),
+    });
+    let inserted = Arc::new(AtomicUsize::new(0));
+    let counter = inserted.clone();
+    world.write_storage::<Hp>().observe(StorageHooks::new().on_insert(move |_, _: &Hp| {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }));
+    let e = world.create_entity().build();
+
+    let fork = world.fork();
+    let mut hp = fork.write_storage::<Hp>();
+    assert_eq!(hp.get_mut_or_construct(e).map(|hp| hp.0), Some(100));
+    if cfg!(debug_assertions) {
+        assert!(hp.insert(e, Hp(0)).is_err());
+    }
+    // Hooks stay with the original world.
+    assert_eq!(inserted.load(Ordering::Relaxed), 0);
+}
+
+#[test]
+fn register_with() {
+    use crate::storage::AutoStorage;
+    use shred::SystemData;
+
+    struct Burning;
+
+    impl Component for Burning {
+        type Storage = AutoStorage<Self>;
+    }
+
+    let mut world = World::new();
+    world.register_with::<Burning, _>(1);
+    // Neither registering again nor setting up systems replaces the storage.
+    world.register::<Burning>();
+    WriteStorage::<Burning>::setup(&mut world);
+
+    world.create_entity().with(Burning).build();
+    assert!(!world.read_storage::<Burning>().unprotected_storage().is_dense());
+    world.create_entity().with(Burning).build();
+    assert!(world.read_storage::<Burning>().unprotected_storage().is_dense());
+}
+
+#[test]
+#[should_panic(expected = "already immutably borrowed")]
+fn world_cache_blocks_writes() {
+    let mut world = World::new();
+    world.register::<Pos>();
+    world.create_entity().with(Pos).build();
+
+    let cache = WorldCache::new("ui").with_storage::<Pos>();
+    let guard = cache.fetch(&world);
+    assert_eq!(guard.read_storage::<Pos>().join().count(), 1);
+    world.write_storage::<Pos>();
+}
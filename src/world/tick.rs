@@ -0,0 +1,178 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+use ahash::AHashMap as HashMap;
+use shred::{DispatcherBuilder, RunningTime, System, World};
+
+/// The number of times `World::maintain` was called, inserted by
+/// `WorldExt::new`.
+///
+/// Every frame is usually one dispatch followed by one `maintain`, so this
+/// counts the frames. Systems added with
+/// [`TickDispatcherBuilder::with_ticked`] record the tick they last ran at in
+/// `SystemTicks`.
+#[derive(Debug, Default)]
+pub struct WorldTick(Arc<AtomicU64>);
+
+impl WorldTick {
+    /// Returns the current tick.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn advance(&mut self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Marks that a system never ran.
+const NEVER: u64 = u64::MAX;
+
+/// The ticks systems added with [`TickDispatcherBuilder::with_ticked`] last
+/// ran at, by the name of the system.
+///
+/// A system reading this during its run sees the tick of its previous run,
+/// which allows e.g. only handling changes made since then, or running only
+/// every few ticks.
+#[derive(Debug, Default)]
+pub struct SystemTicks {
+    systems: HashMap<String, Arc<AtomicU64>>,
+}
+
+impl SystemTicks {
+    /// Returns the tick the system `name` last ran at, `None` if it never
+    /// ran or isn't known.
+    pub fn last_run(&self, name: &str) -> Option<u64> {
+        let tick = self.systems.get(name)?.load(Ordering::Relaxed);
+
+        (tick != NEVER).then_some(tick)
+    }
+
+    /// Returns the number of ticks since the system `name` last ran, `None`
+    /// if it never ran or isn't known.
+    pub fn since_last_run(&self, name: &str, now: &WorldTick) -> Option<u64> {
+        self.last_run(name)
+            .map(|tick| now.get().saturating_sub(tick))
+    }
+}
+
+/// Extension trait for shred's `DispatcherBuilder` to add systems recording
+/// the tick they last ran at in `SystemTicks`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     world::{SystemTicks, WorldTick},
+/// };
+///
+/// struct Autosave;
+///
+/// impl<'a> System<'a> for Autosave {
+///     type SystemData = (Read<'a, WorldTick>, Read<'a, SystemTicks>);
+///
+///     fn run(&mut self, (now, ticks): Self::SystemData) {
+///         if ticks.since_last_run("autosave", &now).map_or(true, |n| n >= 2) {
+///             // Save the game.
+///         }
+///     }
+/// }
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with_ticked(Autosave, "autosave", &[])
+///     .build();
+/// dispatcher.setup(&mut world);
+/// assert_eq!(world.read_resource::<SystemTicks>().last_run("autosave"), None);
+///
+/// for _ in 0..3 {
+///     dispatcher.dispatch(&world);
+///     world.maintain();
+/// }
+/// assert_eq!(world.read_resource::<WorldTick>().get(), 3);
+/// assert_eq!(world.read_resource::<SystemTicks>().last_run("autosave"), Some(2));
+/// ```
+pub trait TickDispatcherBuilder {
+    /// Adds a new system recording the tick it last ran at.
+    ///
+    /// Same as [`TickDispatcherBuilder::add_ticked`], but returns `self` to
+    /// enable method chaining.
+    fn with_ticked<S>(self, system: S, name: &str, dep: &[&str]) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static;
+
+    /// Adds a new system recording the tick it last ran at.
+    ///
+    /// The system is dispatched like one added with `DispatcherBuilder::add`,
+    /// and its tick is recorded in `SystemTicks` under `name` after each run.
+    fn add_ticked<S>(&mut self, system: S, name: &str, dep: &[&str])
+    where
+        S: for<'c> System<'c> + Send + 'static;
+}
+
+impl<'a, 'b> TickDispatcherBuilder for DispatcherBuilder<'a, 'b> {
+    fn with_ticked<S>(mut self, system: S, name: &str, dep: &[&str]) -> Self
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        self.add_ticked(system, name, dep);
+
+        self
+    }
+
+    fn add_ticked<S>(&mut self, system: S, name: &str, dep: &[&str])
+    where
+        S: for<'c> System<'c> + Send + 'static,
+    {
+        let system = Ticked {
+            name: name.to_owned(),
+            tick: Default::default(),
+            last_run: Arc::new(AtomicU64::new(NEVER)),
+            system,
+        };
+        self.add(system, name, dep);
+    }
+}
+
+/// A system recording the tick it last ran at.
+struct Ticked<S> {
+    name: String,
+    /// The counter of the `WorldTick`, shared during setup.
+    tick: Arc<AtomicU64>,
+    last_run: Arc<AtomicU64>,
+    system: S,
+}
+
+impl<'c, S> System<'c> for Ticked<S>
+where
+    S: for<'d> System<'d>,
+{
+    type SystemData = <S as System<'c>>::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        self.system.run(data);
+        let tick = self.tick.load(Ordering::Relaxed);
+        self.last_run.store(tick, Ordering::Relaxed);
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        self.system.setup(world);
+        self.tick = world
+            .entry::<WorldTick>()
+            .or_insert_with(Default::default)
+            .0
+            .clone();
+        world
+            .entry::<SystemTicks>()
+            .or_insert_with(Default::default)
+            .systems
+            .insert(self.name.clone(), self.last_run.clone());
+    }
+}
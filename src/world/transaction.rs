@@ -0,0 +1,138 @@
+use std::any::type_name;
+
+use ahash::AHashSet as HashSet;
+use shred::World;
+
+use super::{Component, Entity, WorldExt};
+use crate::{error::TransactionError, storage::MaskedStorage};
+
+pub(crate) type Operation = Box<dyn FnOnce(&mut World)>;
+
+/// Stages insertions, removals and deletions to be applied together by
+/// `WorldExt::transaction`.
+///
+/// Every operation is validated when it is staged, against the `World` and
+/// the operations staged before it, including that the storages it writes
+/// aren't leased. The first invalid operation fails the whole transaction,
+/// so none of them are applied.
+pub struct Transaction<'a> {
+    world: &'a World,
+    deleted: HashSet<Entity>,
+    operations: Vec<Operation>,
+    error: Option<TransactionError>,
+}
+
+impl<'a> Transaction<'a> {
+    pub(crate) fn new(world: &'a World) -> Self {
+        Transaction {
+            world,
+            deleted: HashSet::new(),
+            operations: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Stages inserting `component` for `entity`, replacing the component it
+    /// may have.
+    pub fn insert<T: Component>(&mut self, entity: Entity, component: T) {
+        if self.check::<T>("insert", entity) && self.check_valid(entity, &component) {
+            self.operations.push(Box::new(move |world| {
+                world
+                    .write_storage::<T>()
+                    .insert(entity, component)
+                    .expect("The insertion is validated when staged");
+            }));
+        }
+    }
+
+    /// Stages removing the component `T` of `entity`, if it has one.
+    pub fn remove<T: Component>(&mut self, entity: Entity) {
+        if self.check::<T>("remove", entity) {
+            self.operations.push(Box::new(move |world| {
+                world.write_storage::<T>().remove(entity);
+            }));
+        }
+    }
+
+    /// Stages deleting `entity` and its components.
+    pub fn delete(&mut self, entity: Entity) {
+        if self.check_alive("delete", entity) {
+            self.deleted.insert(entity);
+            self.operations.push(Box::new(move |world| {
+                world
+                    .delete_entity(entity)
+                    .expect("The entity is validated to be alive");
+            }));
+        }
+    }
+
+    /// Returns the error of the first invalid operation staged so far.
+    pub fn error(&self) -> Option<&TransactionError> {
+        self.error.as_ref()
+    }
+
+    /// Returns the staged operations, to be applied in order, or the error
+    /// of the first invalid one.
+    pub(crate) fn into_operations(self) -> Result<Vec<Operation>, TransactionError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.operations),
+        }
+    }
+
+    /// Checks that `entity` is alive and the storage of `T` registered and
+    /// writable.
+    ///
+    /// `WorldExt::transaction` borrows the `World` mutably, so the storage
+    /// can't be borrowed elsewhere when the operations are applied, and no
+    /// lease can be taken until then.
+    fn check<T: Component>(&mut self, action: &'static str, entity: Entity) -> bool {
+        if !self.check_alive(action, entity) {
+            return false;
+        }
+        let leases = match self.world.try_fetch::<MaskedStorage<T>>() {
+            Some(storage) => storage.check_leases(),
+            None => {
+                self.error = Some(TransactionError::Unregistered {
+                    action,
+                    component: type_name::<T>(),
+                });
+                return false;
+            }
+        };
+        if let Err(e) = leases {
+            self.error = Some(TransactionError::LeaseConflict(e));
+            return false;
+        }
+
+        true
+    }
+
+    /// Checks that the validators registered for `T` accept `component`.
+    fn check_valid<T: Component>(&mut self, entity: Entity, component: &T) -> bool {
+        let result = self
+            .world
+            .fetch::<MaskedStorage<T>>()
+            .validate(entity, component);
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                self.error = Some(TransactionError::Invalid(e));
+                false
+            }
+        }
+    }
+
+    /// Checks that `entity` is alive and not deleted by an earlier operation.
+    fn check_alive(&mut self, action: &'static str, entity: Entity) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        if !self.world.entities().is_alive(entity) || self.deleted.contains(&entity) {
+            self.error = Some(TransactionError::DeadEntity { action, entity });
+            return false;
+        }
+
+        true
+    }
+}
@@ -0,0 +1,77 @@
+use shred::{Read, System};
+
+use super::{Component, Entities};
+use crate::{
+    join::Join,
+    storage::{DenseVecStorage, WriteStorage},
+};
+
+/// The time left before an entity is deleted by the `TtlSystem`.
+///
+/// The unit is the one of `DeltaTime`, or frames if the world has no
+/// `DeltaTime`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntityTtl(pub f32);
+
+impl Component for EntityTtl {
+    type Storage = DenseVecStorage<Self>;
+}
+
+/// The time elapsed since the previous frame, by which the `TtlSystem`
+/// decrements `EntityTtl`s.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DeltaTime(pub f32);
+
+/// A system decrementing the `EntityTtl` of entities every time it runs,
+/// deleting the entities whose time is up.
+///
+/// `EntityTtl`s are decremented by the `DeltaTime` resource, or by one if
+/// there is none, so a TTL counts frames. Entities are deleted atomically,
+/// so their components are removed, with the usual events, by the next
+/// `World::maintain`.
+///
+/// ## Examples
+///
+/// ```
+/// use specs::{
+///     prelude::*,
+///     world::{EntityTtl, TtlSystem},
+/// };
+///
+/// let mut world = World::new();
+/// let mut dispatcher = DispatcherBuilder::new()
+///     .with(TtlSystem, "ttl", &[])
+///     .build();
+/// dispatcher.setup(&mut world);
+///
+/// let spark = world.create_entity().with(EntityTtl(2.0)).build();
+/// for _ in 0..2 {
+///     assert!(world.is_alive(spark));
+///     dispatcher.dispatch(&world);
+///     world.maintain();
+/// }
+/// assert!(!world.is_alive(spark));
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TtlSystem;
+
+impl<'a> System<'a> for TtlSystem {
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, EntityTtl>,
+        Option<Read<'a, DeltaTime>>,
+    );
+
+    fn run(&mut self, (entities, mut ttls, delta): Self::SystemData) {
+        let delta = delta.map_or(1.0, |delta| delta.0);
+        for (entity, ttl) in (&entities, &mut ttls).join() {
+            ttl.0 -= delta;
+            if ttl.0 <= 0.0 {
+                entities
+                    .delete(entity)
+                    .expect("Joined entities are alive");
+            }
+        }
+    }
+}
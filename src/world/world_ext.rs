@@ -1,15 +1,38 @@
+use std::any::TypeId;
+#[cfg(feature = "serde")]
+use std::hash::Hasher;
+
 use super::{
-    comp::Component,
+    clone::{CloneRegistry, ComponentFilter},
+    comp::{Component, ComponentGroup},
     entity::{Allocator, EntitiesRes, Entity},
-    CreateIter, EntityBuilder, LazyUpdate,
+    events::{self, WorldEvent},
+    exclusive::ExclusiveQueue,
+    inspect::{Inspect, InspectRegistry, InspectVisitor, InspectVisitorMut},
+    layout::{self, LayoutReport},
+    lazy_resource::{init_lazy_resource, LazyResourceInit, LazyResources},
+    maintain::{MaintainHooks, MaintainPhase},
+    plugin::{InstalledPlugins, WorldPlugin},
+    scratch::FrameScratch,
+    singleton::Singletons,
+    tick::WorldTick,
+    transaction::Transaction,
+    CreateIter, EntityBuilder, LazyReport, LazyUpdate,
 };
 
 use crate::{
-    error::WrongGeneration,
-    storage::{AnyStorage, MaskedStorage},
+    error::{BorrowConflict, Error, NotRegistered, TransactionError, WrongGeneration},
+    storage::{
+        setup_sharded, setup_storage, AnyStorage, MaskedStorage, ShardMaintainers,
+        ShardedComponent, StorageRegistry, StorageSetup,
+    },
     ReadStorage, WriteStorage,
 };
-use shred::{Fetch, FetchMut, MetaTable, Read, Resource, SystemData, World};
+#[cfg(feature = "serde")]
+use crate::saveload::StateHashRegistry;
+use shred::{
+    DispatcherBuilder, Fetch, FetchMut, MetaTable, Read, Resource, ResourceId, SystemData, World,
+};
 
 /// This trait provides some extension methods to make working with shred's
 /// [World] easier.
@@ -105,7 +128,8 @@ pub trait WorldExt {
 
     /// Registers a new component, adding the component storage.
     ///
-    /// Calls `register_with_storage` with `Default::default()`.
+    /// Calls `register_with_storage` with `Default::default()`. Use
+    /// `WorldExt::register_with` for storages set up with `StorageSetup`.
     ///
     /// Does nothing if the component was already
     /// registered.
@@ -140,6 +164,256 @@ pub trait WorldExt {
         F: FnOnce() -> T::Storage,
         T: Component;
 
+    /// Registers a new component, creating its storage from `args` with
+    /// `StorageSetup`.
+    ///
+    /// This allows registering components whose storages need configuration,
+    /// like a capacity, before `SystemData::setup` tries to create them
+    /// without arguments. Does nothing if the component was already
+    /// registered.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if `StorageSetup::setup` fails.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{prelude::*, storage::AutoStorage};
+    ///
+    /// struct Burning;
+    ///
+    /// impl Component for Burning {
+    ///     type Storage = AutoStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// // Promote the storage once it holds more than 16 components.
+    /// world.register_with::<Burning, _>(16);
+    /// ```
+    fn register_with<T, A>(&mut self, args: A)
+    where
+        T: Component,
+        T::Storage: StorageSetup<A>;
+
+    /// Registers a `ShardedComponent`, inserting all of its shards.
+    ///
+    /// Does nothing if the component was already registered. This is done
+    /// automatically by the `setup` of `ShardedRead`, `ShardedWrite` and
+    /// `ShardedWriteAll`.
+    fn register_sharded<T: ShardedComponent>(&mut self);
+
+    /// Registers a new component like `WorldExt::register`, and allows
+    /// copying it between entities with `WorldExt::copy_components_from` and
+    /// to forks with `WorldExt::fork`.
+    fn register_cloneable<T>(&mut self)
+    where
+        T: Component + Clone,
+        T::Storage: Default;
+
+    /// Allows copying the resource `R` to forks with `WorldExt::fork`.
+    fn register_cloneable_resource<R>(&mut self)
+    where
+        R: Resource + Clone;
+
+    /// Registers a new component like `WorldExt::register`, and allows
+    /// visiting its fields with `WorldExt::inspect_entity` and
+    /// `WorldExt::inspect_entity_mut`.
+    fn register_inspectable<T>(&mut self)
+    where
+        T: Component + Inspect,
+        T::Storage: Default;
+
+    /// Registers a new component like `WorldExt::register`, together with a
+    /// constructor creating it for an entity in place of `Default::default()`.
+    ///
+    /// The constructor is used by `GenericWriteStorage::get_mut_or_default`,
+    /// `Storage::get_mut_or_construct` and `StorageEntry::or_construct`. This
+    /// allows components needing context, like asset handles captured by the
+    /// constructor, to be created by these code paths. Registering another
+    /// constructor replaces the previous one.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Sprite {
+    ///     texture: &'static str,
+    /// }
+    ///
+    /// impl Component for Sprite {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// let default_texture = "missing.png";
+    /// world.register_with_ctor(move |_| Sprite {
+    ///     texture: default_texture,
+    /// });
+    /// let e = world.create_entity().build();
+    ///
+    /// let mut sprites = world.write_storage::<Sprite>();
+    /// assert_eq!(sprites.entry(e).unwrap().or_construct().texture, "missing.png");
+    /// ```
+    fn register_with_ctor<T, F>(&mut self, ctor: F)
+    where
+        T: Component,
+        T::Storage: Default,
+        F: Fn(Entity) -> T + Send + Sync + 'static;
+
+    /// Registers a new component like `WorldExt::register`, and adds a
+    /// validator checking the components inserted in debug builds,
+    /// to catch invalid data like NaN positions where it is inserted instead
+    /// of deep in the systems using it.
+    ///
+    /// `Storage::insert` runs the validators and returns
+    /// `Error::InvalidComponent` with the reason of the first one rejecting
+    /// the component, without inserting it. `EntityBuilder::with` and the
+    /// insertions of `LazyUpdate` log a warning instead. In release builds the
+    /// validators are not run.
+    ///
+    /// ```
+    /// use specs::{error::Error, prelude::*};
+    ///
+    /// struct Pos(f32, f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_validator(|pos: &Pos| {
+    ///     if pos.0.is_nan() || pos.1.is_nan() {
+    ///         Err("position is NaN".to_string())
+    ///     } else {
+    ///         Ok(())
+    ///     }
+    /// });
+    /// let e = world.create_entity().build();
+    ///
+    /// let mut positions = world.write_storage::<Pos>();
+    /// let result = positions.insert(e, Pos(0.0, f32::NAN));
+    /// if cfg!(debug_assertions) {
+    ///     assert!(matches!(result, Err(Error::InvalidComponent(_))));
+    ///     assert!(!positions.contains(e));
+    /// }
+    /// ```
+    fn register_validator<T, F>(&mut self, validator: F)
+    where
+        T: Component,
+        T::Storage: Default,
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static;
+
+    /// Sets up the `SystemData` `S` like a `System` using it would, without
+    /// a dispatcher, e.g. to register all storages and resources needed by
+    /// `World::exec` or `World::system_data` in tests and tools.
+    ///
+    /// Returns the ids of the resources read or written by `S` that did not
+    /// exist before, reads first; resources inserted by `S::setup` which `S`
+    /// doesn't fetch are not included.
+    ///
+    /// ```
+    /// use specs::{prelude::*, shred::ResourceId, storage::MaskedStorage};
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// #[derive(Default)]
+    /// struct Frame(u64);
+    ///
+    /// let mut world = World::new();
+    /// let created = world.setup_for::<(Entities, WriteStorage<Pos>, Read<Frame>)>();
+    /// assert_eq!(
+    ///     created,
+    ///     [
+    ///         ResourceId::new::<Frame>(),
+    ///         ResourceId::new::<MaskedStorage<Pos>>()
+    ///     ]
+    /// );
+    /// assert!(world.setup_for::<WriteStorage<Pos>>().is_empty());
+    /// ```
+    fn setup_for<'a, S: SystemData<'a>>(&mut self) -> Vec<ResourceId>;
+
+    /// Like `World::exec`, calls `f` with the system data `T`, but returns an
+    /// error instead of panicking:
+    ///
+    /// * `Error::BorrowConflict` if `T` borrows a resource mutably more than
+    ///   once, or both mutably and immutably, or if one of its resources is
+    ///   still borrowed in a conflicting way, e.g. by a storage fetched
+    ///   earlier.
+    /// * `Error::NotRegistered`, naming `T`, if one of its resources is
+    ///   missing. This includes the resources of optional system data like
+    ///   `Option<Read<R>>`.
+    ///
+    /// The borrows are checked before anything is fetched, and `T` isn't set
+    /// up, so the world is left untouched on error. Call
+    /// `WorldExt::setup_for` first if the resources of `T` may be missing.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// let err = world
+    ///     .try_exec(|(_pos, _pos_mut): (ReadStorage<Pos>, WriteStorage<Pos>)| {})
+    ///     .unwrap_err();
+    /// assert!(err.to_string().contains("Pos"));
+    ///
+    /// let pos = world.write_storage::<Pos>();
+    /// assert!(world.try_exec(|_: ReadStorage<Pos>| {}).is_err());
+    /// drop(pos);
+    ///
+    /// let count = world.try_exec(|pos: ReadStorage<Pos>| pos.count());
+    /// assert_eq!(count.unwrap(), 0);
+    /// ```
+    fn try_exec<'a, T, F, R>(&'a self, f: F) -> Result<R, Error>
+    where
+        T: SystemData<'a>,
+        F: FnOnce(T) -> R;
+
+    /// Calls `f` with mutable access to the world, so it can make structural
+    /// changes like creating entities or registering components, then calls
+    /// `maintain` unless `maintain` is `false`.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// let mut world = World::new();
+    /// let e = world.exec_mut(true, |world| {
+    ///     let e = world.entities().create();
+    ///     world.entities().delete(e).unwrap();
+    ///     e
+    /// });
+    /// assert!(!world.is_alive(e));
+    /// ```
+    fn exec_mut<F, R>(&mut self, maintain: bool, f: F) -> R
+    where
+        F: FnOnce(&mut World) -> R;
+
+    /// Installs `plugin` into the world and `dispatcher`, see `WorldPlugin`.
+    ///
+    /// Returns `false` and does nothing if a plugin of the same type has
+    /// already been installed into this world.
+    fn install<P: WorldPlugin>(
+        &mut self,
+        plugin: P,
+        dispatcher: &mut DispatcherBuilder<'_, '_>,
+    ) -> bool;
+
+    /// Registers `hook` to run inside `WorldExt::maintain` at `phase`, so
+    /// structures built on top of the world like hierarchies, indexes or
+    /// caches are updated at well-defined points. See `MaintainPhase` for
+    /// the order.
+    fn on_maintain(&mut self, phase: MaintainPhase, hook: fn(&mut World));
+
     /// Adds a resource to the world.
     ///
     /// If the resource already exists it will be overwritten.
@@ -160,6 +434,145 @@ pub trait WorldExt {
     #[deprecated(since = "0.15.0", note = "use `World::insert` instead")]
     fn add_resource<T: Resource>(&mut self, res: T);
 
+    /// Registers an initializer for the resource `R`, which is run the
+    /// first time `R` is requested through `ensure_resource`,
+    /// `fetch_lazy`, `system_data_lazy` or `init_lazy_resources`, or when
+    /// system data containing a `ReadLazy<R>` or `WriteLazy<R>` is set up,
+    /// e.g. by `Dispatcher::setup`. Fetches of shred, like `World::fetch`,
+    /// `Read` and `ReadExpect`, don't run it.
+    ///
+    /// The initializer gets mutable access to the `World`, so it can in turn
+    /// request the resources it depends on with `ensure_resource`. Cyclic
+    /// dependencies between initializers are detected and result in a panic.
+    ///
+    /// If `R` has already been added, the initializer will never run.
+    /// Registering another initializer for `R` replaces the previous one.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Gravity(f32);
+    /// struct Physics {
+    ///     gravity: f32,
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_lazy_resource(|world| {
+    ///     world.ensure_resource::<Gravity>();
+    ///     Physics {
+    ///         gravity: world.read_resource::<Gravity>().0,
+    ///     }
+    /// });
+    /// world.register_lazy_resource(|_| Gravity(-9.81));
+    ///
+    /// world.init_lazy_resources();
+    /// assert_eq!(world.read_resource::<Physics>().gravity, -9.81);
+    /// ```
+    fn register_lazy_resource<R, F>(&mut self, init: F)
+    where
+        R: Resource,
+        F: LazyResourceInit<R>;
+
+
+    /// Makes sure the resource `R` exists, running its lazy initializer if
+    /// it hasn't been added yet.
+    ///
+    /// Returns `true` if `R` exists afterwards.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the initializer of `R` (transitively) depends on `R`.
+    fn ensure_resource<R: Resource>(&mut self) -> bool;
+
+    /// Fetches the resource `R` for reading, running its lazy initializer
+    /// first if it hasn't been added yet.
+    ///
+    /// Returns `Error::NotRegistered` if `R` has neither been added nor has
+    /// an initializer.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the initializer of `R` (transitively) depends on `R`.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Gravity(f32);
+    ///
+    /// let mut world = World::new();
+    /// assert!(world.fetch_lazy::<Gravity>().is_err());
+    ///
+    /// world.register_lazy_resource(|_| Gravity(-9.81));
+    /// assert_eq!(world.fetch_lazy::<Gravity>().unwrap().0, -9.81);
+    /// ```
+    fn fetch_lazy<R: Resource>(&mut self) -> Result<Fetch<'_, R>, Error>;
+
+    /// Fetches the resource `R` for writing, running its lazy initializer
+    /// first if it hasn't been added yet.
+    ///
+    /// Returns `Error::NotRegistered` if `R` has neither been added nor has
+    /// an initializer.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the initializer of `R` (transitively) depends on `R`.
+    fn fetch_lazy_mut<R: Resource>(&mut self) -> Result<FetchMut<'_, R>, Error>;
+
+    /// Fetches the system data `S`, like `World::system_data`, running the
+    /// lazy initializers of the resources it reads or writes first, so `S`
+    /// can contain `ReadExpect`s of lazy resources as well as storages.
+    ///
+    /// Returns `Error::NotRegistered`, naming `S`, instead of panicking if
+    /// one of these resources has neither been added nor has an initializer,
+    /// e.g. the storage of an unregistered component.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if an initializer (transitively) depends on its resource, or
+    /// if `S` borrows a resource conflictingly.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// struct Gravity(f32);
+    ///
+    /// struct Vel(f32);
+    ///
+    /// impl Component for Vel {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_lazy_resource(|_| Gravity(-9.81));
+    /// assert!(world
+    ///     .system_data_lazy::<(ReadExpect<Gravity>, ReadStorage<Vel>)>()
+    ///     .is_err());
+    ///
+    /// world.register::<Vel>();
+    /// let (gravity, vel) = world
+    ///     .system_data_lazy::<(ReadExpect<Gravity>, ReadStorage<Vel>)>()
+    ///     .unwrap();
+    /// assert_eq!(gravity.0, -9.81);
+    /// assert_eq!(vel.count(), 0);
+    /// ```
+    fn system_data_lazy<'a, S: SystemData<'a>>(&'a mut self) -> Result<S, Error>;
+
+    /// Runs all lazy initializers which haven't run yet.
+    ///
+    /// Call this before `Dispatcher::setup`, otherwise resources implementing
+    /// `Default` will already have been added with their default value.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if there is a dependency cycle between initializers.
+    fn init_lazy_resources(&mut self);
+
     /// Fetches a component storage for reading.
     ///
     /// ## Panics
@@ -186,16 +599,59 @@ pub trait WorldExt {
         self.read_component()
     }
 
+    /// Fetches a component storage for reading, like `read_storage`, but
+    /// returns an error instead of panicking if the component has not been
+    /// registered (`Error::NotRegistered`), or if the storage or the entities
+    /// are already borrowed mutably (`Error::BorrowConflict`).
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{error::Error, prelude::*};
+    ///
+    /// struct Pos;
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = NullStorage<Self>;
+    /// }
+    ///
+    /// impl Default for Pos {
+    ///     fn default() -> Self {
+    ///         Pos
+    ///     }
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// assert!(matches!(
+    ///     world.try_read_storage::<Pos>(),
+    ///     Err(Error::NotRegistered(_))
+    /// ));
+    ///
+    /// world.register::<Pos>();
+    /// assert!(world.try_read_storage::<Pos>().is_ok());
+    /// ```
+    fn try_read_storage<T: Component>(&self) -> Result<ReadStorage<'_, T>, Error>;
+
     /// Fetches a component storage for writing.
     ///
     /// # Panics
     ///
     /// Panics if it is already borrowed.
     /// Panics if the component has not been registered.
+    /// Panics if the storage is leased, see `Storage::lease`.
     fn write_storage<T: Component>(&self) -> WriteStorage<T> {
         self.write_component()
     }
 
+    /// Fetches a component storage for writing, like `write_storage`, but
+    /// returns an error instead of panicking if the component has not been
+    /// registered (`Error::NotRegistered`), if the storage is already
+    /// borrowed or the entities are borrowed mutably
+    /// (`Error::BorrowConflict`), or if the storage is leased
+    /// (`Error::LeaseConflict`, listing the active leases, see
+    /// `Storage::lease`).
+    fn try_write_storage<T: Component>(&self) -> Result<WriteStorage<'_, T>, Error>;
+
     /// Fetches a resource for reading.
     ///
     /// ## Panics
@@ -204,6 +660,12 @@ pub trait WorldExt {
     /// Panics if the resource has not been added.
     fn read_resource<T: Resource>(&self) -> Fetch<T>;
 
+    /// Fetches a resource for reading, like `read_resource`, but returns an
+    /// error instead of panicking if the resource has not been added
+    /// (`Error::NotRegistered`), or if it is already borrowed mutably
+    /// (`Error::BorrowConflict`).
+    fn try_read_resource<T: Resource>(&self) -> Result<Fetch<'_, T>, Error>;
+
     /// Fetches a resource for writing.
     ///
     /// # Panics
@@ -212,6 +674,12 @@ pub trait WorldExt {
     /// Panics if the resource has not been added.
     fn write_resource<T: Resource>(&self) -> FetchMut<T>;
 
+    /// Fetches a resource for writing, like `write_resource`, but returns an
+    /// error instead of panicking if the resource has not been added
+    /// (`Error::NotRegistered`), or if it is already borrowed
+    /// (`Error::BorrowConflict`).
+    fn try_write_resource<T: Resource>(&self) -> Result<FetchMut<'_, T>, Error>;
+
     /// Convenience method for fetching entities.
     ///
     /// Creation and deletion of entities with the `Entities` struct
@@ -255,6 +723,28 @@ pub trait WorldExt {
     /// ```
     fn create_iter(&mut self) -> CreateIter;
 
+    /// Creates a new entity without any components, to be built later with
+    /// `EntityBuilder::for_entity`.
+    ///
+    /// This allows handing out an entity before its components are created,
+    /// e.g. for two entities whose components reference each other.
+    fn reserve_entity(&mut self) -> Entity;
+
+    /// Makes `e` the singleton entity tagged with `T`, like the player or
+    /// the camera, returning the previous one.
+    ///
+    /// The singleton is cleared when its entity is deleted. Systems fetch it
+    /// with the `Singleton` `SystemData`.
+    fn set_singleton<T: 'static>(&mut self, e: Entity) -> Result<Option<Entity>, WrongGeneration>;
+
+    /// Returns the singleton entity tagged with `T`, see
+    /// `WorldExt::set_singleton`.
+    fn singleton<T: 'static>(&self) -> Option<Entity>;
+
+    /// Clears the singleton entity tagged with `T`, returning it. The entity
+    /// itself is kept.
+    fn remove_singleton<T: 'static>(&mut self) -> Option<Entity>;
+
     /// Deletes an entity and its components.
     fn delete_entity(&mut self, entity: Entity) -> Result<(), WrongGeneration>;
 
@@ -266,8 +756,278 @@ pub trait WorldExt {
     fn delete_entities(&mut self, delete: &[Entity]) -> Result<(), (WrongGeneration, usize)>;
 
     /// Deletes all entities and their components.
+    ///
+    /// The components are dropped in the order set with `set_drop_order`.
     fn delete_all(&mut self);
 
+    /// Sets the order in which the storages of the given component types
+    /// drop the components of deleted entities, before the other storages
+    /// drop theirs, in no particular order.
+    ///
+    /// This matters when components own handles of external resources, e.g.
+    /// GPU or physics FFI handles, which have to be destroyed in a certain
+    /// order. Type ids which aren't registered components are ignored.
+    ///
+    /// The order in which the `World` itself drops its storages can't be
+    /// controlled, so call `delete_all` before dropping it to destroy all
+    /// components in this order.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use std::any::TypeId;
+    ///
+    /// use specs::prelude::*;
+    ///
+    /// struct Device;
+    ///
+    /// impl Component for Device {
+    ///     type Storage = HashMapStorage<Self>;
+    /// }
+    ///
+    /// struct Buffer;
+    ///
+    /// impl Component for Buffer {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Device>();
+    /// world.register::<Buffer>();
+    /// // Buffers have to be released before the device they were created on.
+    /// world.set_drop_order(&[TypeId::of::<Buffer>(), TypeId::of::<Device>()]);
+    ///
+    /// world.create_entity().with(Device).with(Buffer).build();
+    /// world.delete_all();
+    /// ```
+    fn set_drop_order(&mut self, order: &[TypeId]);
+
+    /// Stages insertions, removals and deletions with `f`, then applies them
+    /// in order if they are all valid, or none of them otherwise.
+    ///
+    /// Operations are invalid if their entity is dead, or deleted by an
+    /// earlier operation, or their component is not registered. Unlike
+    /// scattered `LazyUpdate` calls, either all operations are applied or
+    /// none, and tracked storages only emit events for applied ones.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{error::TransactionError, prelude::*};
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// #[derive(Debug, PartialEq)]
+    /// struct Vel(f32);
+    ///
+    /// impl Component for Vel {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// world.register::<Vel>();
+    /// let a = world.create_entity().with(Vel(1.0)).build();
+    /// let b = world.create_entity().build();
+    ///
+    /// world
+    ///     .transaction(|tx| {
+    ///         tx.insert(a, Pos(0.0));
+    ///         tx.remove::<Vel>(a);
+    ///         tx.delete(b);
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(world.read_storage::<Pos>().get(a), Some(&Pos(0.0)));
+    /// assert!(!world.is_alive(b));
+    ///
+    /// // `b` is dead, so `a` is left untouched.
+    /// let err = world
+    ///     .transaction(|tx| {
+    ///         tx.remove::<Pos>(a);
+    ///         tx.insert(b, Pos(1.0));
+    ///     })
+    ///     .unwrap_err();
+    /// assert_eq!(
+    ///     err,
+    ///     TransactionError::DeadEntity {
+    ///         action: "insert",
+    ///         entity: b
+    ///     }
+    /// );
+    /// assert_eq!(world.read_storage::<Pos>().get(a), Some(&Pos(0.0)));
+    /// ```
+    fn transaction<F, R>(&mut self, f: F) -> Result<R, TransactionError>
+    where
+        F: FnOnce(&mut Transaction) -> R;
+
+    /// Reports which share of the entities has each registered component,
+    /// suggesting a storage for it, and which sets of components are often
+    /// found together, as candidates for joining or storing together.
+    ///
+    /// Component sets are compared on a sample of at most a few thousand
+    /// entities. The report is meant for tuning and tooling, it is not
+    /// cheap to compute.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::{prelude::*, world::StorageSuggestion};
+    ///
+    /// struct Pos(f32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = HashMapStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// for _ in 0..10 {
+    ///     world.create_entity().with(Pos(0.0)).build();
+    /// }
+    ///
+    /// let report = world.analyze_layout();
+    /// assert_eq!(report.components[0].suggestion, StorageSuggestion::Vec);
+    /// println!("{}", report);
+    /// ```
+    fn analyze_layout(&self) -> LayoutReport;
+
+    /// Removes a group of components from an entity, returning the removed
+    /// components as a tuple of `Option`s.
+    ///
+    /// Tracked storages emit a `ComponentEvent::Removed` for each component
+    /// that was present, just like removing them one by one.
+    ///
+    /// ## Examples
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// # #[derive(Debug, PartialEq)]
+    /// struct Pos(f32);
+    /// # impl Component for Pos { type Storage = VecStorage<Self>; }
+    /// # #[derive(Debug, PartialEq)]
+    /// struct Vel(f32);
+    /// # impl Component for Vel { type Storage = VecStorage<Self>; }
+    ///
+    /// let mut world = World::new();
+    /// world.register::<Pos>();
+    /// world.register::<Vel>();
+    /// let e = world.create_entity().with(Pos(1.0)).build();
+    ///
+    /// let (pos, vel) = world.remove_components::<(Pos, Vel)>(e);
+    /// assert_eq!(pos, Some(Pos(1.0)));
+    /// assert_eq!(vel, None);
+    /// ```
+    ///
+    /// ## Panics
+    ///
+    /// Panics if one of the components has not been registered.
+    fn remove_components<G: ComponentGroup>(&mut self, entity: Entity) -> G::Removed;
+
+    /// Clones the components of `src` to `dst`, e.g. to duplicate an entity.
+    ///
+    /// Only components registered with `WorldExt::register_cloneable` and
+    /// included by `filter` are copied; components `dst` already has are
+    /// overwritten. Returns the number of copied components, or an error if
+    /// one of the entities is not alive.
+    ///
+    /// ```
+    /// use specs::{prelude::*, world::ComponentFilter};
+    ///
+    /// #[derive(Clone, Debug, PartialEq)]
+    /// struct Stack(u32);
+    ///
+    /// impl Component for Stack {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// let mut world = World::new();
+    /// world.register_cloneable::<Stack>();
+    /// let a = world.create_entity().with(Stack(10)).build();
+    ///
+    /// let b = world.create_entity().build();
+    /// let copied = world
+    ///     .copy_components_from(a, b, ComponentFilter::all())
+    ///     .unwrap();
+    /// assert_eq!(copied, 1);
+    /// assert_eq!(world.read_storage::<Stack>().get(b), Some(&Stack(10)));
+    /// ```
+    fn copy_components_from(
+        &mut self,
+        src: Entity,
+        dst: Entity,
+        filter: ComponentFilter,
+    ) -> Result<usize, WrongGeneration>;
+
+    /// Creates a deep copy of the world, e.g. to run a dispatcher on it for
+    /// AI lookahead or rollback simulations without touching the original.
+    ///
+    /// The fork gets a copy of the entities, including the ones created or
+    /// deleted since the last `World::maintain`, of the components registered
+    /// with `WorldExt::register_cloneable` and of the resources registered
+    /// with `WorldExt::register_cloneable_resource`. Other components and
+    /// resources are missing from the fork, `Dispatcher::setup` adds them
+    /// with their default values. Pending `LazyUpdate`s are not copied.
+    ///
+    /// The storages of the fork are set up without arguments, after the
+    /// cloneable resources were copied, so arguments passed to
+    /// `WorldExt::register_with` are lost unless `StorageSetup` reads them
+    /// from such a resource. Constructors and validators are shared with the
+    /// fork. Storage hooks, and with them `GroupIndex`es, are not copied,
+    /// since they update state outside of the storage which would then be
+    /// shared by both worlds.
+    ///
+    /// ```
+    /// use specs::prelude::*;
+    ///
+    /// #[derive(Clone)]
+    /// struct Pos(i32);
+    ///
+    /// impl Component for Pos {
+    ///     type Storage = VecStorage<Self>;
+    /// }
+    ///
+    /// #[derive(Clone, Default)]
+    /// struct Turn(u32);
+    ///
+    /// let mut world = World::new();
+    /// world.register_cloneable::<Pos>();
+    /// world.register_cloneable_resource::<Turn>();
+    /// world.insert(Turn(3));
+    /// let e = world.create_entity().with(Pos(0)).build();
+    ///
+    /// let mut fork = world.fork();
+    /// fork.write_storage::<Pos>().get_mut(e).unwrap().0 = 5;
+    /// fork.write_resource::<Turn>().0 += 1;
+    /// let other = fork.create_entity().build();
+    ///
+    /// assert_eq!(world.read_storage::<Pos>().get(e).unwrap().0, 0);
+    /// assert_eq!(world.read_resource::<Turn>().0, 3);
+    /// assert_eq!(fork.read_resource::<Turn>().0, 4);
+    /// // Both worlds allocate the same entities from now on.
+    /// assert_eq!(world.create_entity().build(), other);
+    /// ```
+    fn fork(&self) -> World;
+
+    /// Visits the components of `entity` registered with
+    /// `WorldExt::register_inspectable`, in registration order.
+    ///
+    /// `InspectVisitor::component` is called with the type name of each
+    /// component the entity has, followed by its fields. Nothing is visited
+    /// if the entity is not alive. See `Inspect` for an example.
+    fn inspect_entity(&self, entity: Entity, visitor: &mut dyn InspectVisitor);
+
+    /// Like `WorldExt::inspect_entity`, but visits the fields mutably so
+    /// they can be edited.
+    ///
+    /// Every visited component counts as modified for flagged storages.
+    fn inspect_entity_mut(&mut self, entity: Entity, visitor: &mut dyn InspectVisitorMut);
+
     /// Checks if an entity is alive.
     /// Please note that atomically created or deleted entities
     /// (the ones created / deleted with the `Entities` struct)
@@ -289,8 +1049,35 @@ pub trait WorldExt {
     /// Also removes all the abandoned components.
     ///
     /// Additionally, `LazyUpdate` will be merged.
+    ///
+    /// With the `parallel` feature, the components of deleted entities are
+    /// dropped on the rayon thread pool, one storage per task, except for
+    /// storages which are `UnprotectedStorage::is_thread_local`. Lazy
+    /// insertions and removals of components are applied on the thread pool
+    /// too, in one batch per component type between the lazy closures, which
+    /// run in order on the calling thread because they may access any
+    /// resource. Component drops, validators and storage hooks may therefore
+    /// run on rayon threads.
+    ///
+    /// Before all of this, every storage gets maintained, see
+    /// `UnprotectedStorage::maintain`.
+    ///
+    /// Hooks registered with `on_maintain` run in between, see
+    /// `MaintainPhase`. At the end, the `FrameScratch` is cleared and the
+    /// `WorldTick` is advanced.
     fn maintain(&mut self);
 
+    /// Computes a digest of the entities and components included by
+    /// `registry`, e.g. to detect desyncs between the peers of a lockstep
+    /// multiplayer game. See `StateHashRegistry` for details.
+    ///
+    /// Fails if a component registered with
+    /// `StateHashRegistry::register_serialize` fails to serialize.
+    #[cfg(feature = "serde")]
+    fn hash_state<H>(&self, registry: &StateHashRegistry) -> Result<u64, Error>
+    where
+        H: Hasher + Default;
+
     #[doc(hidden)]
     fn delete_components(&mut self, delete: &[Entity]);
 }
@@ -300,7 +1087,14 @@ impl WorldExt for World {
         let mut world = Self::default();
         world.insert(EntitiesRes::default());
         world.insert(MetaTable::<dyn AnyStorage>::default());
+        world.insert(StorageRegistry::default());
         world.insert(LazyUpdate::default());
+        world.insert(LazyReport::default());
+        world.insert(ExclusiveQueue::default());
+        #[cfg(feature = "tracking")]
+        world.insert(super::WorldEvents::default());
+        world.insert(FrameScratch::default());
+        world.insert(WorldTick::default());
 
         world
     }
@@ -321,12 +1115,200 @@ impl WorldExt for World {
             .or_insert_with(move || MaskedStorage::<T>::new(storage()));
         self.fetch_mut::<MetaTable<dyn AnyStorage>>()
             .register::<MaskedStorage<T>>();
+        StorageRegistry::register::<T>(self);
+    }
+
+    fn register_with<T, A>(&mut self, args: A)
+    where
+        T: Component,
+        T::Storage: StorageSetup<A>,
+    {
+        setup_storage::<T, A>(self, args);
+    }
+
+    fn register_sharded<T: ShardedComponent>(&mut self) {
+        setup_sharded::<T>(self);
+    }
+
+    fn register_cloneable<T>(&mut self)
+    where
+        T: Component + Clone,
+        T::Storage: Default,
+    {
+        self.register::<T>();
+        self.entry::<CloneRegistry>()
+            .or_insert_with(Default::default)
+            .register::<T>();
+    }
+
+    fn register_cloneable_resource<R>(&mut self)
+    where
+        R: Resource + Clone,
+    {
+        self.entry::<CloneRegistry>()
+            .or_insert_with(Default::default)
+            .register_resource::<R>();
+    }
+
+    fn register_inspectable<T>(&mut self)
+    where
+        T: Component + Inspect,
+        T::Storage: Default,
+    {
+        self.register::<T>();
+        self.entry::<InspectRegistry>()
+            .or_insert_with(Default::default)
+            .register::<T>();
+    }
+
+    fn register_with_ctor<T, F>(&mut self, ctor: F)
+    where
+        T: Component,
+        T::Storage: Default,
+        F: Fn(Entity) -> T + Send + Sync + 'static,
+    {
+        self.register::<T>();
+        self.fetch_mut::<MaskedStorage<T>>().set_constructor(ctor);
+    }
+
+    fn register_validator<T, F>(&mut self, validator: F)
+    where
+        T: Component,
+        T::Storage: Default,
+        F: Fn(&T) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.register::<T>();
+        self.fetch_mut::<MaskedStorage<T>>().add_validator(validator);
+    }
+
+    fn setup_for<'a, S: SystemData<'a>>(&mut self) -> Vec<ResourceId> {
+        let mut missing = Vec::new();
+        for id in S::reads().into_iter().chain(S::writes()) {
+            if !self.has_value_raw(id.clone()) && !missing.contains(&id) {
+                missing.push(id);
+            }
+        }
+
+        S::setup(self);
+        missing.retain(|id| self.has_value_raw(id.clone()));
+
+        missing
+    }
+
+    fn try_exec<'a, T, F, R>(&'a self, f: F) -> Result<R, Error>
+    where
+        T: SystemData<'a>,
+        F: FnOnce(T) -> R,
+    {
+        borrow_conflict::<T>(self)?;
+        let reads = T::reads().into_iter().map(|id| (id, false));
+        for (id, mutable) in reads.chain(T::writes().into_iter().map(|id| (id, true))) {
+            if !self.has_value_raw(id.clone()) {
+                return Err(NotRegistered::new::<T>().into());
+            }
+            check_borrow::<T>(self, id.clone(), mutable, || resource_name(self, &id))?;
+        }
+
+        Ok(f(self.system_data()))
+    }
+
+    fn exec_mut<F, R>(&mut self, maintain: bool, f: F) -> R
+    where
+        F: FnOnce(&mut World) -> R,
+    {
+        let ret = f(self);
+        if maintain {
+            self.maintain();
+        }
+
+        ret
+    }
+
+    fn install<P: WorldPlugin>(
+        &mut self,
+        plugin: P,
+        dispatcher: &mut DispatcherBuilder<'_, '_>,
+    ) -> bool {
+        let new = self
+            .entry::<InstalledPlugins>()
+            .or_insert_with(Default::default)
+            .insert::<P>();
+        if new {
+            plugin.install(self, dispatcher);
+        }
+
+        new
+    }
+
+    fn on_maintain(&mut self, phase: MaintainPhase, hook: fn(&mut World)) {
+        self.entry::<MaintainHooks>()
+            .or_insert_with(Default::default)
+            .add(phase, hook);
     }
 
     fn add_resource<T: Resource>(&mut self, res: T) {
         self.insert(res);
     }
 
+    fn register_lazy_resource<R, F>(&mut self, init: F)
+    where
+        R: Resource,
+        F: LazyResourceInit<R>,
+    {
+        self.entry::<LazyResources>()
+            .or_insert_with(Default::default)
+            .register(
+                ResourceId::new::<R>(),
+                std::any::type_name::<R>(),
+                Box::new(move |world: &mut World| {
+                    let res = init(world);
+                    world.insert(res);
+                }),
+            );
+    }
+
+    fn ensure_resource<R: Resource>(&mut self) -> bool {
+        let id = ResourceId::new::<R>();
+        init_lazy_resource(self, &id);
+
+        self.has_value_raw(id)
+    }
+
+    fn fetch_lazy<R: Resource>(&mut self) -> Result<Fetch<'_, R>, Error> {
+        self.ensure_resource::<R>();
+
+        self.try_read_resource()
+    }
+
+    fn fetch_lazy_mut<R: Resource>(&mut self) -> Result<FetchMut<'_, R>, Error> {
+        self.ensure_resource::<R>();
+
+        self.try_write_resource()
+    }
+
+    fn system_data_lazy<'a, S: SystemData<'a>>(&'a mut self) -> Result<S, Error> {
+        for id in S::reads().into_iter().chain(S::writes()) {
+            init_lazy_resource(self, &id);
+            if !self.has_value_raw(id) {
+                return Err(NotRegistered::new::<S>().into());
+            }
+        }
+
+        let world: &'a World = self;
+        Ok(world.system_data())
+    }
+
+    fn init_lazy_resources(&mut self) {
+        let pending = match self.get_mut::<LazyResources>() {
+            Some(lazy) => lazy.pending(),
+            None => return,
+        };
+
+        for id in &pending {
+            init_lazy_resource(self, id);
+        }
+    }
+
     fn read_component<T: Component>(&self) -> ReadStorage<T> {
         self.system_data()
     }
@@ -335,14 +1317,49 @@ impl WorldExt for World {
         self.system_data()
     }
 
+    fn try_read_storage<T: Component>(&self) -> Result<ReadStorage<'_, T>, Error> {
+        check_storage_borrow::<T, ReadStorage<T>>(self, false)?;
+        let data: Fetch<MaskedStorage<T>> =
+            self.try_fetch().ok_or_else(NotRegistered::new::<T>)?;
+
+        Ok(ReadStorage::new(self.fetch(), data))
+    }
+
+    fn try_write_storage<T: Component>(&self) -> Result<WriteStorage<'_, T>, Error> {
+        check_storage_borrow::<T, WriteStorage<T>>(self, true)?;
+        let data: FetchMut<MaskedStorage<T>> =
+            self.try_fetch_mut().ok_or_else(NotRegistered::new::<T>)?;
+        data.check_leases()?;
+
+        Ok(WriteStorage::new(self.fetch(), data))
+    }
+
     fn read_resource<T: Resource>(&self) -> Fetch<T> {
         self.fetch()
     }
 
+    fn try_read_resource<T: Resource>(&self) -> Result<Fetch<'_, T>, Error> {
+        check_borrow::<Fetch<T>>(self, ResourceId::new::<T>(), false, || {
+            format!("`{}`", std::any::type_name::<T>())
+        })?;
+
+        self.try_fetch()
+            .ok_or_else(|| NotRegistered::new::<T>().into())
+    }
+
     fn write_resource<T: Resource>(&self) -> FetchMut<T> {
         self.fetch_mut()
     }
 
+    fn try_write_resource<T: Resource>(&self) -> Result<FetchMut<'_, T>, Error> {
+        check_borrow::<FetchMut<T>>(self, ResourceId::new::<T>(), true, || {
+            format!("`{}`", std::any::type_name::<T>())
+        })?;
+
+        self.try_fetch_mut()
+            .ok_or_else(|| NotRegistered::new::<T>().into())
+    }
+
     fn entities(&self) -> Read<EntitiesRes> {
         Read::fetch(self)
     }
@@ -362,6 +1379,7 @@ impl WorldExt for World {
             entity,
             world: self,
             built: false,
+            created: true,
         }
     }
 
@@ -369,6 +1387,33 @@ impl WorldExt for World {
         CreateIter(self.entities_mut())
     }
 
+    fn reserve_entity(&mut self) -> Entity {
+        self.entities_mut().alloc.allocate()
+    }
+
+    fn set_singleton<T: 'static>(&mut self, e: Entity) -> Result<Option<Entity>, WrongGeneration> {
+        if !self.is_alive(e) {
+            return Err(WrongGeneration {
+                action: "set singleton entity",
+                actual_gen: self.entities().entity(e.id()).gen(),
+                entity: e,
+            });
+        }
+
+        Ok(self
+            .entry::<Singletons>()
+            .or_insert_with(Default::default)
+            .insert::<T>(e))
+    }
+
+    fn singleton<T: 'static>(&self) -> Option<Entity> {
+        self.try_fetch::<Singletons>()?.get::<T>()
+    }
+
+    fn remove_singleton<T: 'static>(&mut self) -> Option<Entity> {
+        self.try_fetch_mut::<Singletons>()?.remove::<T>()
+    }
+
     fn delete_entity(&mut self, entity: Entity) -> Result<(), WrongGeneration> {
         self.delete_entities(&[entity])
             .map_err(|(wrong_gen, _)| wrong_gen)
@@ -396,6 +1441,75 @@ impl WorldExt for World {
         );
     }
 
+    fn set_drop_order(&mut self, order: &[TypeId]) {
+        self.insert(DropOrder(order.to_vec()));
+    }
+
+    fn transaction<F, R>(&mut self, f: F) -> Result<R, TransactionError>
+    where
+        F: FnOnce(&mut Transaction) -> R,
+    {
+        let mut transaction = Transaction::new(self);
+        let result = f(&mut transaction);
+        let operations = transaction.into_operations()?;
+        for operation in operations {
+            operation(self);
+        }
+
+        Ok(result)
+    }
+
+    fn analyze_layout(&self) -> LayoutReport {
+        layout::analyze(self)
+    }
+
+    fn remove_components<G: ComponentGroup>(&mut self, entity: Entity) -> G::Removed {
+        G::remove(self, entity)
+    }
+
+    fn copy_components_from(
+        &mut self,
+        src: Entity,
+        dst: Entity,
+        filter: ComponentFilter,
+    ) -> Result<usize, WrongGeneration> {
+        for (entity, action) in [(src, "copy components from"), (dst, "copy components to")] {
+            if !self.entities().is_alive(entity) {
+                return Err(WrongGeneration {
+                    action,
+                    actual_gen: self.entities().entity(entity.id()).gen(),
+                    entity,
+                });
+            }
+        }
+
+        Ok(self
+            .try_fetch::<CloneRegistry>()
+            .map_or(0, |registry| registry.copy(self, src, dst, &filter)))
+    }
+
+    fn fork(&self) -> World {
+        let mut fork = World::new();
+        fork.entities_mut().alloc = self.entities().alloc.fork();
+        if let Some(registry) = self.try_fetch::<CloneRegistry>() {
+            registry.fork(self, &mut fork);
+        }
+
+        fork
+    }
+
+    fn inspect_entity(&self, entity: Entity, visitor: &mut dyn InspectVisitor) {
+        if let Some(registry) = self.try_fetch::<InspectRegistry>() {
+            registry.inspect(self, entity, visitor);
+        }
+    }
+
+    fn inspect_entity_mut(&mut self, entity: Entity, visitor: &mut dyn InspectVisitorMut) {
+        if let Some(registry) = self.try_fetch::<InspectRegistry>() {
+            registry.inspect_mut(self, entity, visitor);
+        }
+    }
+
     fn is_alive(&self, e: Entity) -> bool {
         assert!(e.gen().is_alive(), "Generation is dead");
 
@@ -404,18 +1518,235 @@ impl WorldExt for World {
     }
 
     fn maintain(&mut self) {
+        for mut storage in self.fetch_mut::<MetaTable<dyn AnyStorage>>().iter_mut(self) {
+            (*storage).maintain();
+        }
+        if let Some(shards) = self.try_fetch::<ShardMaintainers>() {
+            shards.maintain(self);
+        }
+
+        MaintainHooks::run(self, MaintainPhase::BeforeDeletes);
+
         let deleted = self.entities_mut().alloc.merge();
         if !deleted.is_empty() {
             self.delete_components(&deleted);
         }
+        {
+            let entities = self.entities();
+            events::publish(
+                self,
+                entities
+                    .created_this_maintain()
+                    .iter()
+                    .map(|&e| WorldEvent::EntityCreated(e)),
+            );
+            events::publish(
+                self,
+                entities
+                    .deleted_this_maintain()
+                    .iter()
+                    .map(|&e| WorldEvent::EntityDeleted(e)),
+            );
+        }
+        MaintainHooks::run(self, MaintainPhase::AfterDeletes);
 
+        MaintainHooks::run(self, MaintainPhase::BeforeLazy);
         let lazy = self.write_resource::<LazyUpdate>().clone();
         lazy.maintain(self);
+        MaintainHooks::run(self, MaintainPhase::AfterLazy);
+
+        if let Some(mut scratch) = self.try_fetch_mut::<FrameScratch>() {
+            scratch.clear();
+        }
+        if let Some(mut tick) = self.try_fetch_mut::<WorldTick>() {
+            tick.advance();
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    fn hash_state<H>(&self, registry: &StateHashRegistry) -> Result<u64, Error>
+    where
+        H: Hasher + Default,
+    {
+        registry.hash::<H>(self)
     }
 
     fn delete_components(&mut self, delete: &[Entity]) {
-        for mut storage in self.fetch_mut::<MetaTable<dyn AnyStorage>>().iter_mut(self) {
+        // Dropping is idempotent, so the ordered storages are simply dropped
+        // from again below.
+        if let Some(order) = self.try_fetch::<DropOrder>() {
+            let registry = self.fetch::<StorageRegistry>();
+            for accessor in order.0.iter().filter_map(|&ty| registry.get(ty)) {
+                accessor.drop_components(self, delete);
+            }
+        }
+        let meta = self.fetch_mut::<MetaTable<dyn AnyStorage>>();
+        // Spawning rayon tasks costs more than dropping a few components, so
+        // small deletions like a single `delete_entity` stay on this thread.
+        #[cfg(feature = "parallel")]
+        if delete.len() >= PARALLEL_DELETE_THRESHOLD {
+            use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+            let mut storages: Vec<_> = meta.iter_mut(self).collect();
+            let (local, shared): (Vec<_>, Vec<_>) = storages
+                .iter_mut()
+                .map(|storage| &mut **storage)
+                .partition(|storage| storage.is_thread_local());
+            // Thread-local storages are never sent, they drop on this thread.
+            for storage in local {
+                storage.drop(delete);
+            }
+            shared
+                .into_iter()
+                .map(SendStorage)
+                .collect::<Vec<_>>()
+                .into_par_iter()
+                .for_each(|storage| storage.0.drop(delete));
+        } else {
+            for mut storage in meta.iter_mut(self) {
+                (*storage).drop(delete);
+            }
+        }
+        #[cfg(not(feature = "parallel"))]
+        for mut storage in meta.iter_mut(self) {
             (*storage).drop(delete);
         }
+        if let Some(shards) = self.try_fetch::<ShardMaintainers>() {
+            shards.drop(self, delete);
+        }
+        if let Some(mut singletons) = self.try_fetch_mut::<Singletons>() {
+            singletons.delete(delete);
+        }
+    }
+}
+
+/// The component types whose storages drop components first, see
+/// `WorldExt::set_drop_order`.
+struct DropOrder(Vec<TypeId>);
+
+/// The number of deleted entities from which `delete_components` drops the
+/// components of the storages in parallel.
+#[cfg(feature = "parallel")]
+const PARALLEL_DELETE_THRESHOLD: usize = 256;
+
+/// A storage of the `MetaTable`, sent to the thread pool by
+/// `delete_components`.
+#[cfg(feature = "parallel")]
+struct SendStorage<'a>(&'a mut (dyn AnyStorage + 'static));
+
+// SAFETY: The `MetaTable` only holds `MaskedStorage<T>`s, which are inserted
+// as resources and so are `Send + Sync` with the `parallel` feature. Storages
+// that implement this by hand, like `NonSendStorage`, may only drop their
+// components on the owning thread; they report `AnyStorage::is_thread_local`,
+// which `delete_components` partitions out and drops on the calling thread,
+// so a `SendStorage` never wraps one. The `&mut` borrows come from distinct
+// storages of the same `MetaTable`, so no two threads alias a storage.
+#[cfg(feature = "parallel")]
+unsafe impl Send for SendStorage<'_> {}
+
+/// Checks that the system data `T` doesn't borrow a resource mutably more
+/// than once, or both mutably and immutably, naming the storages registered
+/// in `world`.
+fn borrow_conflict<'a, T: SystemData<'a>>(world: &World) -> Result<(), BorrowConflict> {
+    let reads = T::reads();
+    let writes = T::writes();
+    for (i, id) in writes.iter().enumerate() {
+        if writes[..i].contains(id) || reads.contains(id) {
+            return Err(BorrowConflict {
+                system_data: std::any::type_name::<T>(),
+                resource: resource_name(world, id),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Names the resource `id` for errors, by its component for registered
+/// storages.
+fn resource_name(world: &World, id: &ResourceId) -> String {
+    match world.try_fetch::<StorageRegistry>() {
+        Some(registry) => match registry.get_by_storage(id) {
+            Some(accessor) => format!("the storage of `{}`", accessor.type_name()),
+            None => format!("{:?}", id),
+        },
+        None => format!("{:?}", id),
+    }
+}
+
+/// Checks that the resource `id`, fetched as `S`, can be borrowed, mutably if
+/// `mutable` is `true`, so the fallible fetches of `WorldExt` don't panic.
+///
+/// Missing resources pass the check, they are reported when fetching them.
+fn check_borrow<S>(
+    world: &World,
+    id: ResourceId,
+    mutable: bool,
+    resource: impl FnOnce() -> String,
+) -> Result<(), BorrowConflict> {
+    // SAFETY: The resource is only borrowed, not replaced.
+    let free = match unsafe { world.try_fetch_internal(id) } {
+        Some(cell) if mutable => cell.try_borrow_mut().is_ok(),
+        Some(cell) => cell.try_borrow().is_ok(),
+        None => true,
+    };
+    if free {
+        return Ok(());
+    }
+
+    Err(BorrowConflict {
+        system_data: std::any::type_name::<S>(),
+        resource: resource(),
+    })
+}
+
+/// Checks the borrows of the storage of `T` and of the entities, which are
+/// fetched as `S`, see `check_borrow`.
+fn check_storage_borrow<T: Component, S>(
+    world: &World,
+    mutable: bool,
+) -> Result<(), BorrowConflict> {
+    check_borrow::<S>(world, ResourceId::new::<EntitiesRes>(), false, || {
+        format!("`{}`", std::any::type_name::<EntitiesRes>())
+    })?;
+    check_borrow::<S>(world, ResourceId::new::<MaskedStorage<T>>(), mutable, || {
+        format!("the storage of `{}`", std::any::type_name::<T>())
+    })
+}
+
+/// Asserts that the system data `T` doesn't borrow a resource mutably more
+/// than once, or both mutably and immutably, like a system requesting both
+/// `ReadStorage<T>` and `WriteStorage<T>`.
+///
+/// Such system data panics when it is fetched. This check doesn't need a
+/// populated `World` or a dispatch, so calling it in a unit test catches
+/// conflicts at test time. Rust can't compare types at compile time, so this
+/// can't be a compile-time check.
+///
+/// ## Panics
+///
+/// Panics with a `BorrowConflict` message if `T` has conflicting borrows.
+///
+/// ## Examples
+///
+/// ```should_panic
+/// use specs::{prelude::*, world::assert_distinct_system_data};
+///
+/// struct Pos(f32);
+///
+/// impl Component for Pos {
+///     type Storage = VecStorage<Self>;
+/// }
+///
+/// assert_distinct_system_data::<(Entities, ReadStorage<Pos>)>();
+/// // Panics: "... can't borrow the storage of `Pos`, it is already borrowed".
+/// assert_distinct_system_data::<(ReadStorage<Pos>, WriteStorage<Pos>)>();
+/// ```
+#[track_caller]
+pub fn assert_distinct_system_data<'a, T: SystemData<'a>>() {
+    let mut world = World::new();
+    T::setup(&mut world);
+    if let Err(err) = borrow_conflict::<T>(&world) {
+        panic!("{}", err);
     }
 }
@@ -107,6 +107,48 @@ mod tests {
     #[derive(ConvertSaveload)]
     struct Generic<E: EntityLike>(E);
 
+    /// Converts an entity pair, which has no `ConvertSaveload` impl.
+    mod entity_pair {
+        use std::convert::Infallible;
+
+        use spocs::Entity;
+
+        pub type Data<MA> = (MA, MA);
+
+        pub fn convert_into<MA, F>(pair: &[Entity; 2], mut ids: F) -> Result<Data<MA>, Infallible>
+        where
+            F: FnMut(Entity) -> Option<MA>,
+        {
+            Ok((ids(pair[0]).unwrap(), ids(pair[1]).unwrap()))
+        }
+
+        pub fn convert_from<MA, F>(data: Data<MA>, mut ids: F) -> Result<[Entity; 2], Infallible>
+        where
+            F: FnMut(MA) -> Option<Entity>,
+        {
+            Ok([ids(data.0).unwrap(), ids(data.1).unwrap()])
+        }
+    }
+
+    #[derive(ConvertSaveload)]
+    struct NamedWith {
+        #[convert_save_load_with(entity_pair)]
+        pair: [Entity; 2],
+        e: Entity,
+    }
+
+    #[derive(ConvertSaveload)]
+    struct TupleWith(#[convert_save_load_with(entity_pair)] [Entity; 2], u32);
+
+    #[derive(ConvertSaveload)]
+    enum EnumWith {
+        A(#[convert_save_load_with(entity_pair)] [Entity; 2]),
+        B {
+            #[convert_save_load_with(entity_pair)]
+            pair: [Entity; 2],
+        },
+    }
+
     trait EntityLike {}
 
     impl EntityLike for Entity {}
@@ -154,6 +196,57 @@ mod tests {
         // so no need to test anything but unit
         black_box::<M, _>(AnEnum::Unit);
         black_box::<M, _>(Generic(entity));
+        black_box::<M, _>(NamedWith {
+            pair: [entity, entity],
+            e: entity,
+        });
+        black_box::<M, _>(TupleWith([entity, entity], 5));
+        black_box::<M, _>(EnumWith::A([entity, entity]));
+    }
+
+    #[test]
+    fn convert_with() {
+        use spocs::{
+            saveload::{MarkedBuilder, SimpleMarkerAllocator},
+            Join,
+        };
+
+        let mut world = World::new();
+        world.register::<SimpleMarker<NetworkSync>>();
+        world.insert(SimpleMarkerAllocator::<NetworkSync>::new());
+        let a = world
+            .create_entity()
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+        let b = world
+            .create_entity()
+            .marked::<SimpleMarker<NetworkSync>>()
+            .build();
+
+        let markers = world.read_storage::<SimpleMarker<NetworkSync>>();
+        let entities = world.entities();
+        let into = |e| markers.get(e).cloned();
+        let from = |m: SimpleMarker<NetworkSync>| {
+            (&entities, &markers)
+                .join()
+                .find(|(_, marker)| marker.id() == m.id())
+                .map(|(e, _)| e)
+        };
+
+        let named = NamedWith {
+            pair: [b, a],
+            e: a,
+        };
+        let data = named.convert_into(into).unwrap();
+        let named = NamedWith::convert_from(data, from).unwrap();
+        assert_eq!(named.pair, [b, a]);
+        assert_eq!(named.e, a);
+
+        let data = EnumWith::B { pair: [a, b] }.convert_into(into).unwrap();
+        match EnumWith::convert_from(data, from).unwrap() {
+            EnumWith::B { pair } => assert_eq!(pair, [a, b]),
+            EnumWith::A(_) => panic!("Expected the `B` variant"),
+        }
     }
 
     fn black_box<M, T: ConvertSaveload<M>>(_item: T) {}
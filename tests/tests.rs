@@ -552,6 +552,82 @@ fn par_join_many_entities_and_systems() {
     }
 }
 
+#[test]
+#[cfg(feature = "parallel")]
+fn par_join_try_for_each() {
+    use rayon::iter::ParallelIterator;
+
+    let mut world = create_world();
+    for i in 0..100 {
+        world.create_entity().with(CompInt(i)).build();
+    }
+    let ints = world.read_storage::<CompInt>();
+    let check = |int: &CompInt| if int.0 % 10 == 3 { Err(int.0) } else { Ok(()) };
+
+    let first = (&ints).par_join().try_for_each(check);
+    assert_eq!(first.unwrap_err() % 10, 3);
+
+    let all = (&ints).par_join().try_for_each_collect(check);
+    assert_eq!(all, Err(vec![3, 13, 23, 33, 43, 53, 63, 73, 83, 93]));
+
+    assert_eq!(
+        (&ints).par_join().try_for_each_collect(|_| Ok::<_, ()>(())),
+        Ok(())
+    );
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn par_join_reductions() {
+    let mut world = create_world();
+    let entities: Vec<_> = [4, -3, 7, -3, 0]
+        .into_iter()
+        .map(|i| world.create_entity().with(CompInt(i)).build())
+        .collect();
+    let entities_res = world.entities();
+    let ints = world.read_storage::<CompInt>();
+
+    let (count, max) = (&ints)
+        .par_join()
+        .map_reduce(|int| (1, int.0), || (0, i8::MIN), |a, b| (a.0 + b.0, a.1.max(b.1)));
+    assert_eq!((count, max), (5, 7));
+    assert_eq!((&ints).par_join().sum_by(|int| i32::from(int.0)), 5);
+
+    let (min, _) = (&entities_res, &ints)
+        .par_join()
+        .min_by_key(|(_, int)| int.0)
+        .unwrap();
+    assert_eq!(min, entities[1]);
+
+    let none = world.read_storage::<CompBool>();
+    assert_eq!((&none).par_join().sum_by(|_| 1), 0);
+    assert!((&none).par_join().min_by_key(|_| 0).is_none());
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn par_join_batches_splits_runs() {
+    let mut world = create_world();
+    let entities: Vec<_> = (0..10)
+        .map(|i| world.create_entity().with(CompInt(i)).build())
+        .collect();
+    world.delete_entity(entities[4]).unwrap();
+
+    let mut ints = world.write_storage::<CompInt>();
+    let mut batches: Vec<_> = (&mut ints)
+        .par_join_batches(3)
+        .map(|(ids, ints)| {
+            for int in ints.iter_mut() {
+                int.0 *= 2;
+            }
+            ids
+        })
+        .collect();
+    batches.sort_by_key(|ids| ids.start);
+    assert_eq!(batches, [0..3, 3..4, 5..8, 8..10]);
+    assert_eq!(ints.get(entities[9]), Some(&CompInt(18)));
+}
+
 #[test]
 fn getting_specific_entity_with_lend_join() {
     let mut world = create_world();
@@ -597,6 +673,48 @@ fn getting_specific_entity_with_lend_join() {
     );
 }
 
+#[test]
+fn getting_specific_entity_with_restricted_lend_join() {
+    let mut world = create_world();
+    let a = world.create_entity().with(CompInt(1)).build();
+    let b = world
+        .create_entity()
+        .with(CompInt(2))
+        .with(CompBool(true))
+        .build();
+
+    let entities = world.entities();
+    let bools = world.read_storage::<CompBool>();
+    let mut ints = world.write_storage::<CompInt>();
+    let mut restricted = ints.restrict_mut();
+    {
+        let mut lent = (&entities, &mut restricted).lend_join();
+        assert!(lent.contains(a, &entities));
+        while let Some((_, mut int)) = lent.next() {
+            int.get_mut().0 += 10;
+        }
+        let (_, mut int) = lent.get(a, &entities).unwrap();
+        int.get_mut().0 *= 2;
+    }
+    {
+        // The restricted storage can be joined again after the first join.
+        let mut lent = (&mut restricted, &bools).lend_join();
+        assert!(!lent.contains(a, &entities));
+        assert!(lent.contains(b, &entities));
+        let (int, _) = lent.get(b, &entities).unwrap();
+        assert_eq!(int.get(), &CompInt(12));
+    }
+    assert_eq!(ints.get(a), Some(&CompInt(22)));
+
+    drop((entities, bools, ints));
+
+    world.delete_entity(b).unwrap();
+    let mut ints = world.write_storage::<CompInt>();
+    let mut lent = (&mut ints).lend_join();
+    assert!(!lent.contains(b, &world.entities()));
+    assert!(lent.get(b, &world.entities()).is_none());
+}
+
 #[test]
 fn maintain_entity_deletion() {
     let mut world = World::new();
@@ -664,3 +782,408 @@ fn maintain_entity_deletion() {
     world.maintain();
     check.run_now(&world);
 }
+
+#[test]
+fn exclusive_systems_run_in_order_after_dispatch() {
+    struct Log(Vec<&'static str>);
+
+    struct Push(&'static str);
+
+    impl ExclusiveSystem for Push {
+        fn run(&mut self, world: &mut World) {
+            world.write_resource::<Log>().0.push(self.0);
+        }
+
+        fn setup(&mut self, world: &mut World) {
+            world.entry().or_insert_with(|| Log(Vec::new()));
+        }
+    }
+
+    struct Spawn;
+
+    impl ExclusiveSystem for Spawn {
+        fn run(&mut self, world: &mut World) {
+            world.create_entity().with(CompInt(1)).build();
+        }
+    }
+
+    let mut world = create_world();
+    let mut dispatcher = DispatcherBuilder::new()
+        .with_exclusive(Push("a"), "a", &[])
+        .with_exclusive(Spawn, "spawn", &["a"])
+        .with_exclusive(Push("b"), "b", &[])
+        .build();
+    dispatcher.setup(&mut world);
+
+    dispatcher.dispatch(&world);
+    dispatcher.dispatch(&world);
+    assert!(world.read_resource::<Log>().0.is_empty());
+
+    dispatcher.dispatch_exclusive(&mut world);
+    assert_eq!(world.read_resource::<Log>().0, vec!["a", "b"]);
+    assert_eq!(world.read_storage::<CompInt>().count(), 1);
+
+    world.maintain();
+    assert_eq!(world.read_resource::<Log>().0, vec!["a", "b"]);
+}
+
+#[test]
+fn setup_priority_orders_deferred_setups() {
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    struct Named(&'static str);
+
+    impl<'a> System<'a> for Named {
+        type SystemData = ();
+
+        fn run(&mut self, _: Self::SystemData) {}
+
+        fn setup(&mut self, world: &mut World) {
+            world.entry().or_insert_with(Log::default).0.push(self.0);
+        }
+    }
+
+    let mut world = create_world();
+    let mut dispatcher = DispatcherBuilder::new()
+        .with_setup_priority(Named("low"), "low", &[], -1)
+        .with_setup_priority(Named("high"), "high", &[], 5)
+        .with(Named("plain"), "plain", &[])
+        .with_setup_priority(Named("high2"), "high2", &[], 5)
+        .build();
+    dispatcher.setup_only(&mut world);
+    assert_eq!(
+        world.read_resource::<Log>().0,
+        vec!["plain", "high", "high2", "low"]
+    );
+
+    dispatcher.setup_only(&mut world);
+    assert_eq!(
+        world.read_resource::<Log>().0,
+        vec!["plain", "high", "high2", "low"]
+    );
+
+    dispatcher.setup(&mut world);
+    assert_eq!(
+        world.read_resource::<Log>().0,
+        vec!["plain", "high", "high2", "low", "plain"]
+    );
+}
+
+#[test]
+fn join_count_matched_and_size_hint() {
+    let mut world = create_world();
+    for i in 0..ITERATIONS {
+        let builder = world.create_entity().with(CompInt(i as i8));
+        if i % 3 == 0 {
+            builder.with(CompBool(true)).build();
+        } else {
+            builder.build();
+        }
+    }
+
+    let mut mask = BitSet::new();
+    for i in (0..ITERATIONS * 100).step_by(7) {
+        mask.add(i);
+    }
+    assert_eq!((&mask).join().count_matched(), (&mask).join().count());
+
+    let ints = world.read_storage::<CompInt>();
+    let bools = world.read_storage::<CompBool>();
+
+    let mut iter = (&ints, &bools).join();
+    let expected = ITERATIONS as usize / 3 + 1;
+    for left in (0..=expected).rev() {
+        assert_eq!(iter.size_hint(), (left, None));
+        iter.next();
+    }
+    let mut iter = (&ints, &bools).join();
+    iter.next();
+    assert_eq!(iter.count_matched(), expected - 1);
+
+    assert_eq!(
+        (&ints, !&bools).join().count_matched(),
+        ITERATIONS as usize - expected
+    );
+    assert_eq!((&ints).maybe().join().size_hint(), (0, None));
+    assert_eq!(
+        (&ints, &bools).join().take_matched(5).size_hint(),
+        (5, Some(5))
+    );
+    assert_eq!((&ints, &bools).join().take_matched(5).count(), 5);
+
+    // Creating entities while iterating only lets the hint underestimate.
+    let entities = world.entities();
+    let mut iter = entities.join();
+    let mut bounds = vec![iter.size_hint().0];
+    while let Some(e) = iter.next() {
+        if e.id() % 100 == 0 {
+            entities.create();
+        }
+        bounds.push(bounds.len() + iter.size_hint().0);
+    }
+    let yielded = bounds.len() - 1;
+    assert!(yielded > ITERATIONS as usize);
+    assert!(bounds.iter().all(|&bound| bound <= yielded));
+}
+
+#[test]
+fn inspect_entity() {
+    use specs::world::{Inspect, InspectVisitor, InspectVisitorMut};
+    use std::any::Any;
+
+    #[derive(Debug, PartialEq, specs_derive::Inspect)]
+    struct Health {
+        current: u32,
+        #[inspect(skip)]
+        regen: u32,
+    }
+
+    impl Component for Health {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Debug, PartialEq, specs_derive::Inspect)]
+    struct Label(String, u32);
+
+    impl Component for Label {
+        type Storage = VecStorage<Self>;
+    }
+
+    #[derive(Default)]
+    struct Collect(Vec<String>);
+
+    impl InspectVisitor for Collect {
+        fn component(&mut self, name: &'static str) {
+            self.0.push(name.rsplit("::").next().unwrap().to_owned());
+        }
+
+        fn field(&mut self, name: &'static str, value: &dyn Any) {
+            if let Some(value) = value.downcast_ref::<u32>() {
+                self.0.push(format!("{}={}", name, value));
+            } else if let Some(value) = value.downcast_ref::<String>() {
+                self.0.push(format!("{}={}", name, value));
+            }
+        }
+    }
+
+    struct Double;
+
+    impl InspectVisitorMut for Double {
+        fn field(&mut self, _: &'static str, value: &mut dyn Any) {
+            if let Some(value) = value.downcast_mut::<u32>() {
+                *value *= 2;
+            }
+        }
+    }
+
+    let mut world = create_world();
+    world.register_inspectable::<Health>();
+    world.register_inspectable::<Label>();
+
+    let a = world
+        .create_entity()
+        .with(Health {
+            current: 3,
+            regen: 1,
+        })
+        .with(Label("a".to_owned(), 4))
+        .with(CompInt(1))
+        .build();
+    let b = world.create_entity().with(Label("b".to_owned(), 5)).build();
+
+    let mut collect = Collect::default();
+    world.inspect_entity(a, &mut collect);
+    assert_eq!(collect.0, ["Health", "current=3", "Label", "0=a", "1=4"]);
+
+    world.inspect_entity_mut(a, &mut Double);
+    assert_eq!(
+        world.read_storage::<Health>().get(a),
+        Some(&Health {
+            current: 6,
+            regen: 1,
+        })
+    );
+    assert_eq!(
+        world.read_storage::<Label>().get(a),
+        Some(&Label("a".to_owned(), 8))
+    );
+
+    world.delete_entity(a).unwrap();
+    let mut collect = Collect::default();
+    world.inspect_entity(a, &mut collect);
+    world.inspect_entity(b, &mut collect);
+    assert_eq!(collect.0, ["Label", "0=b", "1=5"]);
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn storage_par_iter() {
+    use rayon::iter::ParallelIterator;
+
+    struct CompU32(u32);
+
+    impl Component for CompU32 {
+        type Storage = VecStorage<Self>;
+    }
+
+    let mut world = create_world();
+    world.register::<CompU32>();
+    let entities: Vec<_> = (0..ITERATIONS)
+        .map(|i| {
+            let builder = world.create_entity();
+            if i % 2 == 0 {
+                builder.with(CompU32(i)).build()
+            } else {
+                builder.build()
+            }
+        })
+        .collect();
+
+    let mut storage = world.write_storage::<CompU32>();
+    storage.par_iter_mut().for_each(|(e, c)| c.0 += e.id());
+    let mut pairs: Vec<_> = storage.par_iter().map(|(e, c)| (e, c.0)).collect();
+    pairs.sort();
+
+    let expected: Vec<_> = entities
+        .iter()
+        .step_by(2)
+        .map(|&e| (e, e.id() * 2))
+        .collect();
+    assert_eq!(pairs, expected);
+}
+
+mod chunked {
+    use specs::storage::VecStorage;
+
+    /// Stands in for a storage with const generic parameters.
+    pub type ChunkedStorage<T, const N: usize> = VecStorage<T>;
+}
+
+#[test]
+fn derive_component_storage() {
+    #[derive(Debug, PartialEq, specs_derive::Component)]
+    #[storage(crate::chunked::ChunkedStorage<Self, 4>)]
+    struct Chunked(u32);
+
+    #[derive(Debug, PartialEq, specs_derive::Component)]
+    #[storage(chunked::ChunkedStorage<Self, { 2 * 4 }>)]
+    struct Block(u32);
+
+    #[derive(Debug, PartialEq, specs_derive::Component)]
+    #[storage(HashMapStorage)]
+    struct Sparse(u32);
+
+    #[derive(Debug, PartialEq, specs_derive::Component)]
+    struct Dense(u32);
+
+    let mut world = World::new();
+    world.register::<Chunked>();
+    world.register::<Block>();
+    world.register::<Sparse>();
+    world.register::<Dense>();
+    let e = world
+        .create_entity()
+        .with(Chunked(1))
+        .with(Block(2))
+        .with(Sparse(3))
+        .with(Dense(4))
+        .build();
+
+    assert_eq!(world.read_storage::<Chunked>().get(e), Some(&Chunked(1)));
+    assert_eq!(world.read_storage::<Block>().get(e), Some(&Block(2)));
+    assert_eq!(world.read_storage::<Sparse>().get(e), Some(&Sparse(3)));
+    assert_eq!(world.read_storage::<Dense>().get(e), Some(&Dense(4)));
+}
+
+#[test]
+fn derive_soa_component() {
+    use specs::storage::{SoAColumns, SoAComponent, SoAStorage};
+
+    #[derive(Clone, Debug, PartialEq, specs_derive::SoAComponent)]
+    struct Particle {
+        pos: f32,
+        name: String,
+    }
+
+    let mut world = World::new();
+    world.register::<Particle>();
+    let particle = |pos: f32| Particle {
+        pos,
+        name: pos.to_string(),
+    };
+    let entities: Vec<_> = (0..4)
+        .map(|i| world.create_entity().with(particle(i as f32)).build())
+        .collect();
+    world.create_entity().build();
+
+    let mut particles = world.write_storage::<Particle>();
+    assert_eq!(particles.remove(entities[1]), Some(particle(1.0)));
+    assert_eq!(particles.columns().pos(), [0.0, 3.0, 2.0]);
+    let row = particles.row(entities[3]).unwrap();
+    assert_eq!((*row.pos, row.name.as_str()), (3.0, "3"));
+    assert!(particles.row(entities[1]).is_none());
+
+    particles.get_mut(entities[2]).unwrap().name.push('!');
+    for pos in particles.column_mut(|c| c.pos_mut()).join() {
+        *pos *= 2.0;
+    }
+    assert_eq!(*particles.row(entities[3]).unwrap().pos, 6.0);
+    assert_eq!(particles.row(entities[2]).unwrap().name, "2!");
+
+    let mut lent = (&mut particles).lend_join();
+    while let Some(mut particle) = lent.next() {
+        particle.pos += 1.0;
+    }
+    let sum: f32 = particles.column(|c| c.pos()).join().sum();
+    assert_eq!(sum, 1.0 + 7.0 + 5.0);
+    let names: Vec<_> = particles.rows().join().map(|row| row.name.as_str()).collect();
+    assert_eq!(names, ["0", "2!", "3"]);
+}
+
+#[test]
+fn derive_split_component() {
+    use specs::world::SplitComponent;
+
+    #[derive(Debug, PartialEq, specs_derive::SplitComponent)]
+    struct Body {
+        pos: f32,
+        #[split(hot)]
+        vel: f32,
+        #[split(cold)]
+        mesh: Vec<u8>,
+    }
+
+    let mut world = World::new();
+    world.register::<BodyHot>();
+    world.register::<BodyCold>();
+    let e = world
+        .create_entity()
+        .with_split(Body {
+            pos: 1.0,
+            vel: 2.0,
+            mesh: vec![3],
+        })
+        .build();
+
+    let (hot, cold) = (world.read_storage::<BodyHot>(), world.read_storage::<BodyCold>());
+    let bodies: Vec<_> = (&hot, &cold)
+        .join()
+        .map(|(hot, cold)| BodyRef::new(hot, cold))
+        .collect();
+    assert_eq!(bodies.len(), 1);
+    assert_eq!((*bodies[0].pos(), *bodies[0].vel()), (1.0, 2.0));
+    assert_eq!(bodies[0].mesh(), &[3]);
+    drop((hot, cold));
+
+    let hot = world.write_storage::<BodyHot>().remove(e).unwrap();
+    let cold = world.write_storage::<BodyCold>().remove(e).unwrap();
+    assert_eq!(
+        Body::unsplit(hot, cold),
+        Body {
+            pos: 1.0,
+            vel: 2.0,
+            mesh: vec![3],
+        }
+    );
+}